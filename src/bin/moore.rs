@@ -66,6 +66,8 @@ fn main() {
                     "insts",
                     "func-args",
                     "call-args",
+                    "mir-consts",
+                    "const-stats",
                 ])
                 .global(true),
         )
@@ -97,6 +99,11 @@ fn main() {
                 .long("dump-ast")
                 .help("Dump the parsed abstract syntax tree"),
         )
+        .arg(
+            Arg::with_name("dump-params")
+                .long("dump-params")
+                .help("Dump the resolved parameters of the elaborated module and its instances"),
+        )
         .arg(
             Arg::with_name("check-syntax")
                 .long("syntax")
@@ -114,8 +121,87 @@ fn main() {
                 .help("Sets optimization level applied to the output")
                 .default_value("1")
                 .takes_value(true)
+                .number_of_values(1)
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("eval")
+                .long("eval")
+                .value_name("EXPR")
+                .help("Evaluate a constant expression in the scope of the elaborated module")
+                .multiple(true)
+                .takes_value(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("param")
+                .short("G")
+                .value_name("NAME=VALUE")
+                .help("Override an integer parameter when evaluating expressions with --eval")
+                .multiple(true)
+                .takes_value(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("max-genvar-iterations")
+                .long("max-genvar-iterations")
+                .help("Sets the maximum number of iterations of a generate loop")
+                .takes_value(true)
+                .number_of_values(1)
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("max-context-notes")
+                .long("max-context-notes")
+                .help("Sets the maximum number of instantiation sites listed in a diagnostic")
+                .takes_value(true)
+                .number_of_values(1)
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("diag-format")
+                .long("diag-format")
+                .help("Format in which diagnostics are emitted")
+                .takes_value(true)
+                .possible_values(&["human", "json"]),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .help("Highlights diagnostics with colors")
+                .takes_value(true)
+                .possible_values(&["auto", "always", "never"]),
+        )
+        .arg(
+            Arg::with_name("diag-code")
+                .short("W")
+                .value_name("ACTION")
+                .help("Treats diagnostics by code: `no-<code>` suppresses, `error=<code>` upgrades")
+                .multiple(true)
+                .takes_value(true)
                 .number_of_values(1),
         )
+        .arg(
+            Arg::with_name("deny-warnings")
+                .long("deny-warnings")
+                .help("Treats all warnings as errors"),
+        )
+        .arg(
+            Arg::with_name("error-limit")
+                .long("error-limit")
+                .help("Sets the maximum number of errors shown (0 for no limit)")
+                .takes_value(true)
+                .number_of_values(1)
+                .validator(validate_count),
+        )
+        .arg(
+            Arg::with_name("warning-limit")
+                .long("warning-limit")
+                .help("Sets the maximum number of warnings shown (0 for no limit)")
+                .takes_value(true)
+                .number_of_values(1)
+                .validator(validate_count),
+        )
         .arg(
             Arg::with_name("lib")
                 .short("l")
@@ -184,13 +270,81 @@ fn main() {
             "insts" => Verbosity::INSTS,
             "func-args" => Verbosity::FUNC_ARGS,
             "call-args" => Verbosity::CALL_ARGS,
+            "mir-consts" => Verbosity::MIR_CONSTS,
+            "const-stats" => Verbosity::CONST_STATS,
             _ => unreachable!(),
         };
     }
     session.opts.opt_level = matches.value_of("opt-level").unwrap().parse().unwrap();
+    session.opts.max_genvar_iterations = matches
+        .value_of("max-genvar-iterations")
+        .map(|v| v.parse().unwrap());
+    session.opts.max_context_notes = matches
+        .value_of("max-context-notes")
+        .map(|v| v.parse().unwrap());
+    session.opts.error_limit = matches.value_of("error-limit").map(|v| v.parse().unwrap());
+    session.opts.warning_limit = matches
+        .value_of("warning-limit")
+        .map(|v| v.parse().unwrap());
+    session.opts.diag_format = match matches.value_of("diag-format") {
+        Some("json") => DiagFormat::Json,
+        _ => DiagFormat::Human,
+    };
+    session.opts.color = match matches.value_of("color") {
+        Some("always") => ColorChoice::Always,
+        Some("never") => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    };
+    session.opts.diag_policy.deny_warnings = matches.is_present("deny-warnings");
+    for v in matches.values_of("diag-code").into_iter().flatten() {
+        let (action, code) = if let Some(code) = v.strip_prefix("no-") {
+            (DiagCodeAction::Suppress, code)
+        } else if let Some(code) = v.strip_prefix("error=") {
+            (DiagCodeAction::Error, code)
+        } else {
+            session.emit(
+                DiagBuilder2::fatal(format!("invalid diagnostic option `-W {}`", v))
+                    .add_note("Use `-W no-<code>` or `-W error=<code>`."),
+            );
+            std::process::exit(1);
+        };
+        if DiagCode::is_stable_code(code) {
+            session
+                .opts
+                .diag_policy
+                .code_actions
+                .insert(code.to_string(), action);
+        } else {
+            session.emit(DiagBuilder2::fatal(format!(
+                "unknown diagnostic code `{}`",
+                code
+            )));
+            std::process::exit(1);
+        }
+    }
 
     // Invoke the compiler.
     score(&session, &matches);
+    exit(&session, 0);
+}
+
+/// Check that a command-line argument is a non-negative integer.
+///
+/// Used as a `clap` validator, such that malformed numbers are reported as a
+/// usage error instead of a panic.
+fn validate_count(value: String) -> Result<(), String> {
+    value
+        .parse::<usize>()
+        .map(|_| ())
+        .map_err(|_| format!("`{}` is not a non-negative integer", value))
+}
+
+/// Summarize the diagnostics that were not shown due to the error and warning
+/// limits, and exit. The exit code is raised to the session's exit status if
+/// that is higher.
+fn exit(sess: &Session, code: i32) -> ! {
+    sess.emit_limit_summary();
+    std::process::exit(code.max(sess.exit_status()))
 }
 
 fn score(sess: &Session, matches: &ArgMatches) {
@@ -232,18 +386,22 @@ fn score(sess: &Session, matches: &ArgMatches) {
             Some("vhd") | Some("vhdl") => Language::Vhdl,
             Some(ext) => {
                 sess.emit(
-                    DiagBuilder2::warning(format!("ignoring `{}`", filename)).add_note(format!(
-                        "Cannot determine language from extension `.{}`",
-                        ext
-                    )),
+                    DiagBuilder2::warning(format!("ignoring `{}`", filename))
+                        .code(DiagCode::IGNORED_INPUT)
+                        .add_note(format!(
+                            "Cannot determine language from extension `.{}`",
+                            ext
+                        )),
                 );
                 continue;
             }
             None => {
                 sess.emit(
-                    DiagBuilder2::warning(format!("ignoring `{}`", filename)).add_note(format!(
-                        "No file extension that can be used to guess language"
-                    )),
+                    DiagBuilder2::warning(format!("ignoring `{}`", filename))
+                        .code(DiagCode::IGNORED_INPUT)
+                        .add_note(format!(
+                            "No file extension that can be used to guess language"
+                        )),
                 );
                 continue;
             }
@@ -284,7 +442,7 @@ fn score(sess: &Session, matches: &ArgMatches) {
                 }
 
                 let lexer = svlog::lexer::Lexer::new(preproc);
-                match svlog::parser::parse(lexer, &svlog_arenas.ast) {
+                match svlog::parser::parse_with_emitter(lexer, &svlog_arenas.ast, sess) {
                     Ok(x) => asts.push(score::Ast::Svlog(x)),
                     Err(()) => failed = true,
                 }
@@ -296,7 +454,7 @@ fn score(sess: &Session, matches: &ArgMatches) {
         }
     }
     if failed || sess.failed() {
-        std::process::exit(1);
+        exit(sess, 1);
     }
     if matches.is_present("preproc") {
         return;
@@ -321,7 +479,7 @@ fn score(sess: &Session, matches: &ArgMatches) {
 
     // Stop processing if requested.
     if matches.is_present("check-syntax") {
-        std::process::exit(0);
+        exit(sess, 0);
     }
 
     // Create the scoreboard and add the initial map of libraries.
@@ -374,9 +532,14 @@ fn score(sess: &Session, matches: &ArgMatches) {
                 failed = true;
             }
         }
+
+        // Emit the constant evaluation statistics if requested.
+        if ctx.sess.has_verbosity(Verbosity::CONST_STATS) {
+            eprint!("{}", ctx.svlog.const_stats());
+        }
     }
     if failed || sess.failed() {
-        std::process::exit(1);
+        exit(sess, 1);
     }
 
     // Extract the populated LLHD modules from the scoreboards and link them
@@ -388,7 +551,7 @@ fn score(sess: &Session, matches: &ArgMatches) {
     // llhd::assembly::write_module(&mut std::io::stdout().lock(), &vhdl_module);
 
     if sess.failed() {
-        std::process::exit(1);
+        exit(sess, 1);
     }
 }
 
@@ -519,6 +682,51 @@ fn elaborate_name(
                 svlog::InstVerbosityVisitor::new(ctx.svlog).visit_node_with_id(m, false);
             }
 
+            // Dump the resolved parameters instead of generating code if
+            // requested.
+            if matches.is_present("dump-params") {
+                print!("{}", svlog::dump_params(ctx.svlog, m)?);
+                return Ok(());
+            }
+
+            // Evaluate constant expressions instead of generating code if
+            // requested.
+            if let Some(exprs) = matches.values_of("eval") {
+                let int_ty = svlog::ty::SbvType::new(
+                    svlog::ty::Domain::TwoValued,
+                    svlog::ty::Sign::Signed,
+                    32,
+                )
+                .to_unpacked(ctx.svlog);
+                let mut overrides = vec![];
+                for param in matches.values_of("param").into_iter().flatten() {
+                    let mut iter = param.splitn(2, '=');
+                    let name = iter.next().unwrap();
+                    let value = match iter.next().and_then(|v| v.parse::<num::BigInt>().ok()) {
+                        Some(v) => v,
+                        None => {
+                            ctx.sess.emit(
+                                DiagBuilder2::error(format!(
+                                    "invalid parameter override `{}`",
+                                    param
+                                ))
+                                .add_note("Overrides must be of the form `NAME=INTEGER`."),
+                            );
+                            return Err(());
+                        }
+                    };
+                    overrides.push((
+                        name::get_name_table().intern(name, true),
+                        svlog::value::make_int(int_ty, value),
+                    ));
+                }
+                for expr in exprs {
+                    let value = svlog::eval_constant_expr(ctx.svlog, m, &overrides, expr)?;
+                    println!("{} = {:#}", expr, value);
+                }
+                return Ok(());
+            }
+
             // Create an MLIR context and load the dialects we need.
             let mlir_cx = mlir::OwnedContext::new();
             mlir_cx.load_dialect(circt::func::dialect());
@@ -2,8 +2,8 @@
 
 //! Utilities to implement diagnostics and error reporting facilities.
 
-use crate::source::Span;
-use std::fmt;
+use crate::source::{get_source_manager, Location, Source, Span};
+use std::{collections::HashMap, fmt, io::IsTerminal};
 
 /// Print debug information. Omitted in release builds.
 #[macro_export]
@@ -75,12 +75,16 @@ pub struct DiagBuilder2 {
     pub severity: Severity,
     pub message: String,
     pub segments: Vec<DiagSegment>,
+    pub code: Option<DiagCode>,
 }
 
 #[derive(Clone, Debug)]
 pub enum DiagSegment {
     Span(Span),
     Note(String),
+    /// A span with a label. The first label of a diagnostic is the primary
+    /// one.
+    Label(Span, String),
 }
 
 /// A diagnostic result type. Either carries the result `T` in the Ok variant,
@@ -93,6 +97,7 @@ impl DiagBuilder2 {
             severity: severity,
             message: message.into(),
             segments: Vec::new(),
+            code: None,
         }
     }
 
@@ -133,6 +138,25 @@ impl DiagBuilder2 {
         self.segment(DiagSegment::Note(message.into()))
     }
 
+    /// Add a labelled span.
+    ///
+    /// Labels in the same file are rendered together, with each labelled
+    /// range underlined and annotated with its text. The first label is the
+    /// primary one; it should point at the offending code, with subsequent
+    /// labels pointing at related code. Labels in other files are rendered as
+    /// notes.
+    pub fn span_label<S: Into<Span>, T: Into<String>>(self, span: S, text: T) -> DiagBuilder2 {
+        self.segment(DiagSegment::Label(span.into(), text.into()))
+    }
+
+    /// Assign a stable code to the diagnostic.
+    pub fn code(self, code: DiagCode) -> DiagBuilder2 {
+        DiagBuilder2 {
+            code: Some(code),
+            ..self
+        }
+    }
+
     pub fn get_severity(&self) -> Severity {
         self.severity
     }
@@ -144,6 +168,222 @@ impl DiagBuilder2 {
     pub fn get_segments(&self) -> &[DiagSegment] {
         &self.segments
     }
+
+    /// Get the span the diagnostic primarily refers to.
+    ///
+    /// This is the span or label that precedes all notes.
+    pub fn get_primary_span(&self) -> Option<Span> {
+        match self.segments.first() {
+            Some(&DiagSegment::Span(sp)) | Some(&DiagSegment::Label(sp, _)) => Some(sp),
+            _ => None,
+        }
+    }
+
+    /// Get the notes attached to the diagnostic, together with the span that
+    /// immediately follows each note.
+    ///
+    /// Spans that follow the primary span or another note's span are reported
+    /// as notes with an empty message. Labels are reported as notes with the
+    /// label's text as message.
+    pub fn get_notes(&self) -> Vec<(&str, Option<Span>)> {
+        let mut notes: Vec<(&str, Option<Span>)> = vec![];
+        let mut primary = false;
+        for segment in &self.segments {
+            match *segment {
+                DiagSegment::Span(_) if !primary && notes.is_empty() => primary = true,
+                DiagSegment::Span(sp) => match notes.last_mut() {
+                    Some((_, span @ None)) => *span = Some(sp),
+                    _ => notes.push(("", Some(sp))),
+                },
+                DiagSegment::Note(ref message) => notes.push((message, None)),
+                DiagSegment::Label(sp, ref text) => {
+                    primary = true;
+                    notes.push((text, Some(sp)));
+                }
+            }
+        }
+        notes
+    }
+
+    pub fn get_code(&self) -> Option<DiagCode> {
+        self.code
+    }
+
+    /// Get a stable code that identifies the kind of diagnostic.
+    ///
+    /// This is the code assigned to the diagnostic, if there is one. Otherwise
+    /// the code is derived from the message, with the contents of any
+    /// `backtick-quoted` parts and numbers removed, such that diagnostics that
+    /// only differ in the names and values they mention share a code.
+    pub fn get_stable_code(&self) -> String {
+        if let Some(code) = self.code {
+            return code.as_str().to_string();
+        }
+        let mut hash: u32 = 0x811c9dc5;
+        let mut quoted = false;
+        for c in self.message.bytes() {
+            if c == b'`' {
+                quoted = !quoted;
+            } else if quoted || c.is_ascii_digit() {
+                continue;
+            }
+            hash = (hash ^ c as u32).wrapping_mul(0x01000193);
+        }
+        format!("M{:08x}", hash)
+    }
+}
+
+/// A stable code that identifies a kind of diagnostic.
+///
+/// Codes are rendered alongside the severity of a diagnostic, as in
+/// `warning[W0201]`, and allow individual kinds of diagnostics to be
+/// suppressed or upgraded to errors. Codes starting with `W` are warnings and
+/// codes starting with `E` are errors.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct DiagCode(&'static str);
+
+macro_rules! diag_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal;)*) => {
+        impl DiagCode {
+            $($(#[$meta])* pub const $name: DiagCode = DiagCode($code);)*
+
+            /// All known diagnostic codes.
+            pub const ALL: &'static [DiagCode] = &[$(DiagCode::$name),*];
+        }
+    };
+}
+
+diag_codes! {
+    /// An input file whose language cannot be determined.
+    IGNORED_INPUT = "W0001";
+    /// A positional parameter or port after a named one.
+    POSITIONAL_AFTER_NAMED = "W0101";
+    /// An unsupported construct that is ignored.
+    UNSUPPORTED_IGNORED = "W0102";
+    /// A literal that does not fit into its size.
+    LITERAL_TOO_LARGE = "W0103";
+    /// A function or task port declared after a statement.
+    PORT_AFTER_STATEMENT = "W0104";
+    /// A pattern that assigns the same field or element twice.
+    PATTERN_OVERWRITE = "W0105";
+    /// A deprecated construct, such as `defparam`.
+    DEPRECATED_CONSTRUCT = "W0106";
+    /// A superfluous trailing comma in a list.
+    TRAILING_COMMA = "W0107";
+    /// A block or generate block labelled twice with the same name.
+    REPEATED_LABEL = "W0108";
+    /// A generate block with a trailing label but no leading label.
+    MISSING_LEADING_LABEL = "W0109";
+    /// A `casex` statement, which also treats `x` in the case expression as a
+    /// wildcard.
+    CASEX_WILDCARD = "W0110";
+    /// A non-ASCII whitespace character.
+    NON_ASCII_WHITESPACE = "W0111";
+    /// A constant shift by a negative amount.
+    NEGATIVE_SHIFT = "W0201";
+    /// A constant shift by more than the width of the shifted value.
+    SHIFT_EXCEEDS_WIDTH = "W0202";
+    /// A cast ignored during constant evaluation that changes the value.
+    CAST_IGNORED = "W0203";
+    /// A `ref` port in a module with static lifetime.
+    STATIC_REF_PORT = "W0301";
+    /// A port with zero width.
+    ZERO_WIDTH_PORT = "W0302";
+    /// A packed port range against the prevailing direction.
+    REVERSED_PORT_RANGE = "W0303";
+    /// An implicit port connection made redundant by `.*`.
+    REDUNDANT_PORT_CONN = "W0304";
+    /// A port of an instance that is not connected.
+    UNCONNECTED_PORT = "W0305";
+    /// A streaming concatenation that is padded or truncated.
+    STREAM_WIDTH_MISMATCH = "W0401";
+    /// A conversion to `time` without a timescale.
+    ASSUMED_TIMESCALE = "W0402";
+    /// An unknown compiler directive.
+    UNKNOWN_DIRECTIVE = "E0203";
+}
+
+impl DiagCode {
+    /// Look up a code by its text, such as `W0201`.
+    pub fn lookup(code: &str) -> Option<DiagCode> {
+        DiagCode::ALL.iter().cloned().find(|c| c.0 == code)
+    }
+
+    /// Check whether a text is a stable code that diagnostics may have, as
+    /// returned by `DiagBuilder2::get_stable_code`.
+    ///
+    /// This accepts the known codes, as well as the codes derived from the
+    /// message of diagnostics that have no code assigned, such as `M1a2b3c4d`.
+    pub fn is_stable_code(code: &str) -> bool {
+        if DiagCode::lookup(code).is_some() {
+            return true;
+        }
+        match code.strip_prefix('M') {
+            Some(hash) => {
+                hash.len() == 8 && hash.bytes().all(|c| matches!(c, b'0'..=b'9' | b'a'..=b'f'))
+            }
+            None => false,
+        }
+    }
+
+    /// Get the text of the code.
+    pub fn as_str(self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for DiagCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How diagnostics with a specific code are treated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiagCodeAction {
+    /// Drop the diagnostic.
+    Suppress,
+    /// Upgrade the diagnostic to an error.
+    Error,
+}
+
+/// The policy by which diagnostics are suppressed or upgraded before they are
+/// emitted.
+#[derive(Clone, Default, Debug)]
+pub struct DiagPolicy {
+    /// How diagnostics with specific codes are treated, as configured by the
+    /// `-W no-<code>` and `-W error=<code>` options. Keyed by the stable code
+    /// of the diagnostics, as returned by `DiagBuilder2::get_stable_code`.
+    pub code_actions: HashMap<String, DiagCodeAction>,
+    /// Upgrade all warnings to errors, as configured by `--deny-warnings`.
+    pub deny_warnings: bool,
+}
+
+impl DiagPolicy {
+    /// Suppress or upgrade a diagnostic.
+    ///
+    /// The action for the diagnostic's code applies first, such that a
+    /// suppressed warning is not upgraded by `deny_warnings`. Returns `None` if
+    /// the diagnostic is suppressed. Fatal errors and compiler bugs are never
+    /// suppressed.
+    pub fn apply(&self, mut diag: DiagBuilder2) -> Option<DiagBuilder2> {
+        let action = if self.code_actions.is_empty() {
+            None
+        } else {
+            self.code_actions.get(&diag.get_stable_code())
+        };
+        match action {
+            Some(DiagCodeAction::Suppress) if diag.severity < Severity::Fatal => return None,
+            Some(DiagCodeAction::Error) if diag.severity < Severity::Error => {
+                diag.severity = Severity::Error;
+            }
+            _ => (),
+        }
+        if self.deny_warnings && diag.severity == Severity::Warning {
+            diag.severity = Severity::Error;
+        }
+        Some(diag)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -175,86 +415,72 @@ impl fmt::Display for Severity {
 
 impl fmt::Display for DiagBuilder2 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut colorcode = match self.get_severity() {
+        self.write_human(f, Style { color: true })
+    }
+}
+
+impl DiagBuilder2 {
+    /// Render the diagnostic as human-readable text with source excerpts.
+    fn write_human(&self, f: &mut dyn fmt::Write, style: Style) -> fmt::Result {
+        let mut colorcode = style.ansi(match self.get_severity() {
             Severity::Bug | Severity::Fatal | Severity::Error => "\x1B[31;1m",
             Severity::Warning => "\x1B[33;1m",
             Severity::Note => "\x1B[36;1m",
-        };
+        });
+        match self.get_code() {
+            Some(code) => write!(f, "{}{}[{}]:", colorcode, self.get_severity(), code)?,
+            None => write!(f, "{}{}:", colorcode, self.get_severity())?,
+        }
         write!(
             f,
-            "{}{}:\x1B[m\x1B[1m {}\x1B[m\n",
-            colorcode,
-            self.get_severity(),
-            self.get_message()
+            "{}{} {}{}\n",
+            style.reset(),
+            style.bold(),
+            self.get_message(),
+            style.reset()
         )?;
 
+        // Labels in the same file as the first label are rendered together
+        // with a single source excerpt.
+        let labels: Vec<_> = self
+            .segments
+            .iter()
+            .filter_map(|s| match *s {
+                DiagSegment::Label(sp, ref text) => Some((sp, text.as_str())),
+                _ => None,
+            })
+            .collect();
+        let label_source = labels.first().map(|(sp, _)| sp.source);
+        let mut labels_written = false;
+
         for segment in &self.segments {
             match *segment {
                 DiagSegment::Span(sp) => {
-                    let c = sp.source.get_content();
-
-                    // Look for the start of the line.
-                    let (line, col, line_offset) = sp.begin().human();
-
-                    // Print the line in question.
-                    let text: String = c
-                        .iter_from(line_offset)
-                        .map(|x| x.1)
-                        .take_while(|c| *c != '\n' && *c != '\r')
-                        .collect();
-                    write!(
-                        f,
-                        "  --> {}:{}:{}-{}:\n",
-                        sp.source.get_path(),
-                        line,
-                        col,
-                        col + sp.extract().len()
-                    )?;
-                    write!(f, "   | \n")?;
-                    write!(f, "   | ")?;
-                    for (mut i, c) in text.char_indices() {
-                        i += line_offset;
-                        if sp.begin != sp.end {
-                            if i == sp.begin {
-                                write!(f, "{}", colorcode)?;
-                            }
-                            if i == sp.end {
-                                write!(f, "\x1B[m")?;
-                            }
-                        }
-                        match c {
-                            '\t' => write!(f, "    ")?,
-                            c => write!(f, "{}", c)?,
-                        }
-                    }
-                    write!(f, "\x1B[m\n")?;
-                    write!(f, "   | ")?;
-
-                    // Print the caret markers for the line in question.
-                    let mut pd = ' ';
-                    for (mut i, c) in text.char_indices() {
-                        i += line_offset;
-                        let d = if (i >= sp.begin && i < sp.end)
-                            || (i == sp.begin && sp.begin == sp.end)
-                        {
-                            '^'
-                        } else {
-                            ' '
-                        };
-                        if d != pd {
-                            write!(f, "{}", if d == ' ' { "\x1B[m" } else { colorcode })?;
-                        }
-                        pd = d;
-                        match c {
-                            '\t' => write!(f, "{}{}{}{}", d, d, d, d)?,
-                            _ => write!(f, "{}", d)?,
-                        }
+                    write_span(f, sp, colorcode, style)?;
+                    colorcode = style.bold();
+                }
+                DiagSegment::Note(ref message) => write!(
+                    f,
+                    "   = {}note:{} {}\n",
+                    style.bold(),
+                    style.reset(),
+                    message
+                )?,
+                DiagSegment::Label(sp, _) if Some(sp.source) == label_source => {
+                    if !labels_written {
+                        let group: Vec<_> = labels
+                            .iter()
+                            .filter(|(sp, _)| Some(sp.source) == label_source)
+                            .cloned()
+                            .collect();
+                        write_labels(f, &group, colorcode, style)?;
+                        labels_written = true;
+                        colorcode = style.bold();
                     }
-                    write!(f, "\x1B[m\n")?;
-                    colorcode = "\x1B[1m";
                 }
-                DiagSegment::Note(ref message) => {
-                    write!(f, "   = \x1B[1mnote:\x1B[m {}\n", message)?
+                DiagSegment::Label(sp, ref text) => {
+                    write!(f, "   = {}note:{} {}\n", style.bold(), style.reset(), text)?;
+                    write_span(f, sp, colorcode, style)?;
                 }
             }
         }
@@ -272,3 +498,592 @@ impl fmt::Display for DiagBuilder2 {
         Ok(())
     }
 }
+
+/// The ANSI escape sequences used to render a diagnostic. All of them are
+/// empty if colors are disabled.
+#[derive(Clone, Copy)]
+struct Style {
+    color: bool,
+}
+
+impl Style {
+    fn ansi(self, code: &'static str) -> &'static str {
+        if self.color {
+            code
+        } else {
+            ""
+        }
+    }
+
+    fn bold(self) -> &'static str {
+        self.ansi("\x1B[1m")
+    }
+
+    fn reset(self) -> &'static str {
+        self.ansi("\x1B[m")
+    }
+}
+
+/// The column of the next tab stop is a multiple of this.
+const TAB_WIDTH: usize = 4;
+
+/// The maximum number of columns of a source line shown in an excerpt. Longer
+/// lines are cut down to a window around the highlighted range.
+const MAX_EXCERPT_WIDTH: usize = 100;
+
+/// A line of source text prepared for display in an excerpt.
+struct ExcerptLine {
+    /// The one-based line number.
+    line: usize,
+    /// The columns of the line, each with the byte offset of the character it
+    /// shows. Tabs are expanded to spaces up to the next tab stop.
+    cells: Vec<(usize, char)>,
+}
+
+impl ExcerptLine {
+    /// Prepare the line that contains a byte offset.
+    fn new(source: Source, offset: usize) -> ExcerptLine {
+        let (line, _, line_offset) = Location::new(source, offset).human();
+        let mut cells = vec![];
+        for (i, c) in source.get_content().iter_from(line_offset) {
+            let i = i + line_offset;
+            match c {
+                '\n' | '\r' => break,
+                '\t' => {
+                    let width = TAB_WIDTH - cells.len() % TAB_WIDTH;
+                    cells.extend(std::iter::repeat((i, ' ')).take(width));
+                }
+                c => cells.push((i, c)),
+            }
+        }
+        ExcerptLine { line, cells }
+    }
+
+    /// Get the range of columns that shows a range of byte offsets. The range
+    /// is clipped to the end of the line, but covers at least one column such
+    /// that empty ranges and ranges past the end of the line remain visible.
+    fn columns(&self, begin: usize, end: usize) -> (usize, usize) {
+        let column = |offset| {
+            self.cells
+                .iter()
+                .position(|&(i, _)| i >= offset)
+                .unwrap_or_else(|| self.cells.len())
+        };
+        let lo = column(begin);
+        (lo, column(end).max(lo + 1))
+    }
+
+    /// Choose the columns to show such that a range of columns is visible, or
+    /// as much of it as fits.
+    fn window(&self, lo: usize, hi: usize) -> (usize, usize) {
+        let len = self.cells.len() + 1;
+        if len <= MAX_EXCERPT_WIDTH {
+            return (0, len);
+        }
+        let width = (hi - lo).min(MAX_EXCERPT_WIDTH);
+        let start = lo
+            .saturating_sub((MAX_EXCERPT_WIDTH - width) / 2)
+            .min(len - MAX_EXCERPT_WIDTH);
+        (start, start + MAX_EXCERPT_WIDTH)
+    }
+
+    /// Render the columns of the line within a window, optionally
+    /// highlighting a range of them. Cut off text is marked with `...`.
+    fn write_text(
+        &self,
+        f: &mut dyn fmt::Write,
+        window: (usize, usize),
+        highlight: Option<(usize, usize, &str)>,
+        style: Style,
+    ) -> fmt::Result {
+        write!(f, "   | ")?;
+        if window.0 > 0 {
+            write!(f, "...")?;
+        }
+        let mut highlighted = false;
+        for column in window.0..window.1.min(self.cells.len()) {
+            let h = match highlight {
+                Some((lo, hi, _)) => column >= lo && column < hi,
+                None => false,
+            };
+            if h != highlighted {
+                match highlight {
+                    Some((_, _, color)) if h => write!(f, "{}", color)?,
+                    _ => write!(f, "{}", style.reset())?,
+                }
+                highlighted = h;
+            }
+            write!(f, "{}", self.cells[column].1)?;
+        }
+        if highlighted {
+            write!(f, "{}", style.reset())?;
+        }
+        if window.1 < self.cells.len() {
+            write!(f, "...")?;
+        }
+        write!(f, "\n")
+    }
+
+    /// Render a row of marks underneath a range of columns, followed by a
+    /// label.
+    fn write_marks(
+        &self,
+        f: &mut dyn fmt::Write,
+        window: (usize, usize),
+        (lo, hi): (usize, usize),
+        mark: char,
+        color: &str,
+        label: &str,
+        style: Style,
+    ) -> fmt::Result {
+        let lo = lo.max(window.0).min(window.1);
+        let hi = hi.max(lo + 1).min(window.1.max(lo + 1));
+        let indent = lo - window.0 + if window.0 > 0 { 3 } else { 0 };
+        let marks: String = std::iter::repeat(mark).take(hi - lo).collect();
+        write!(f, "   | {:indent$}{}{}", "", color, marks, indent = indent)?;
+        if !label.is_empty() {
+            write!(f, " {}", label)?;
+        }
+        write!(f, "{}\n", style.reset())
+    }
+}
+
+/// Render the source excerpt of a span, with the span underlined. Spans that
+/// cover multiple lines show their first and last line, separated by `...` if
+/// there are lines in between.
+fn write_span(f: &mut dyn fmt::Write, sp: Span, colorcode: &str, style: Style) -> fmt::Result {
+    let sm = get_source_manager();
+    let (line, col) = sm.line_col_of(sp);
+    let col = col as usize;
+    let first = ExcerptLine::new(sp.source, sp.begin);
+    let last = ExcerptLine::new(sp.source, sp.end.saturating_sub(1).max(sp.begin));
+    if last.line == first.line {
+        write!(
+            f,
+            "  --> {}:{}:{}-{}:\n",
+            sp.source.get_path(),
+            line,
+            col,
+            col + sp.with_text(|text| text.chars().count())
+        )?;
+    } else {
+        let (end_line, end_col) = sm.line_col_of(sp.end().into());
+        write!(
+            f,
+            "  --> {}:{}:{}-{}:{}:\n",
+            sp.source.get_path(),
+            line,
+            col,
+            end_line,
+            end_col
+        )?;
+    }
+    write!(f, "   | \n")?;
+
+    let (lo, hi) = first.columns(sp.begin, sp.end);
+    let window = first.window(lo, hi);
+    first.write_text(f, window, Some((lo, hi, colorcode)), style)?;
+    first.write_marks(f, window, (lo, hi), '^', colorcode, "", style)?;
+    if last.line == first.line {
+        return Ok(());
+    }
+
+    // Underline the last line from its first non-blank character.
+    if last.line > first.line + 1 {
+        write!(f, "  ...\n")?;
+    }
+    let (_, hi) = last.columns(0, sp.end);
+    let lo = last
+        .cells
+        .iter()
+        .position(|&(_, c)| c != ' ')
+        .unwrap_or(0)
+        .min(hi - 1);
+    let window = last.window(lo, hi);
+    last.write_text(f, window, Some((lo, hi, colorcode)), style)?;
+    last.write_marks(f, window, (lo, hi), '^', colorcode, "", style)
+}
+
+/// Render the source excerpt of a group of labels in the same file.
+///
+/// Each line is printed once, followed by one row per label on that line that
+/// underlines the labelled range and shows the label's text. The first label
+/// is the primary one and is underlined with `^`; the others with `-`.
+fn write_labels(
+    f: &mut dyn fmt::Write,
+    labels: &[(Span, &str)],
+    colorcode: &str,
+    style: Style,
+) -> fmt::Result {
+    let (line, col) = get_source_manager().line_col_of(labels[0].0);
+    let col = col as usize;
+    write!(
+        f,
+        "  --> {}:{}:{}-{}:\n",
+        labels[0].0.source.get_path(),
+        line,
+        col,
+        col + labels[0].0.with_text(|text| text.chars().count())
+    )?;
+    write!(f, "   | \n")?;
+
+    let mut order: Vec<_> = (0..labels.len()).collect();
+    order.sort_by_key(|&i| labels[i].0);
+    let mut prev_line = None;
+    let mut window = (0, 0);
+    for (position, &index) in order.iter().enumerate() {
+        let (sp, text) = labels[index];
+        let line = ExcerptLine::new(sp.source, sp.begin);
+        if prev_line != Some(line.line) {
+            if prev_line.map_or(false, |prev| prev + 1 < line.line) {
+                write!(f, "  ...\n")?;
+            }
+
+            // Show as much as possible of all labels on this line.
+            let (lo, hi) = order[position..]
+                .iter()
+                .map(|&i| labels[i].0)
+                .take_while(|sp| sp.begin().human_line() == line.line)
+                .map(|sp| line.columns(sp.begin, sp.end))
+                .fold((usize::MAX, 0), |(lo, hi), (l, h)| (lo.min(l), hi.max(h)));
+            window = line.window(lo, hi);
+            line.write_text(f, window, None, style)?;
+            prev_line = Some(line.line);
+        }
+
+        // Underline the labelled range, clipped to the end of the line.
+        let (mark, color) = if index == 0 {
+            ('^', colorcode)
+        } else {
+            ('-', style.ansi("\x1B[34;1m"))
+        };
+        let range = line.columns(sp.begin, sp.end);
+        line.write_marks(f, window, range, mark, color, text, style)?;
+    }
+    Ok(())
+}
+
+/// Whether diagnostics are highlighted with ANSI colors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorChoice {
+    /// Use colors if the standard error is a terminal.
+    Auto,
+    /// Always use colors.
+    Always,
+    /// Never use colors.
+    Never,
+}
+
+impl Default for ColorChoice {
+    fn default() -> Self {
+        ColorChoice::Auto
+    }
+}
+
+impl ColorChoice {
+    /// Decide whether diagnostics written to the standard error use colors.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stderr().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Renders diagnostics for output.
+pub trait DiagRenderer {
+    /// Render a diagnostic as text.
+    fn render(&self, diag: &DiagBuilder2) -> String;
+}
+
+/// The format in which diagnostics are emitted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiagFormat {
+    /// Human-readable text with source excerpts.
+    Human,
+    /// One JSON object per diagnostic and line.
+    Json,
+}
+
+impl Default for DiagFormat {
+    fn default() -> Self {
+        DiagFormat::Human
+    }
+}
+
+impl DiagFormat {
+    /// Get the renderer for this format. Human-readable text is highlighted
+    /// with ANSI colors if `color` is set.
+    pub fn renderer(self, color: bool) -> &'static dyn DiagRenderer {
+        match self {
+            DiagFormat::Human if color => &HumanRenderer { color: true },
+            DiagFormat::Human => &HumanRenderer { color: false },
+            DiagFormat::Json => &JsonRenderer,
+        }
+    }
+}
+
+/// Renders diagnostics as human-readable text with source excerpts.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct HumanRenderer {
+    /// Whether to highlight the text with ANSI colors.
+    pub color: bool,
+}
+
+impl DiagRenderer for HumanRenderer {
+    fn render(&self, diag: &DiagBuilder2) -> String {
+        let mut text = String::new();
+        diag.write_human(&mut text, Style { color: self.color })
+            .unwrap();
+        text
+    }
+}
+
+/// Renders diagnostics as JSON objects on a single line.
+///
+/// Each object carries the `severity`, `code`, and `message` of the diagnostic,
+/// its primary `span`, and a list of `notes`, each with a `message` and a
+/// `span`. Spans are objects with the `file`, the one-based `line` and
+/// `column` of their start, and the `begin` and `end` byte offsets. Missing
+/// spans are `null`.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct JsonRenderer;
+
+impl DiagRenderer for JsonRenderer {
+    fn render(&self, diag: &DiagBuilder2) -> String {
+        let mut notes = String::new();
+        for (i, (message, span)) in diag.get_notes().into_iter().enumerate() {
+            if i > 0 {
+                notes.push(',');
+            }
+            notes.push_str(&format!(
+                "{{\"message\":{},\"span\":{}}}",
+                json_string(message),
+                json_span(span)
+            ));
+        }
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"span\":{},\"notes\":[{}]}}",
+            json_string(diag.get_severity().to_str()),
+            json_string(&diag.get_stable_code()),
+            json_string(diag.get_message()),
+            json_span(diag.get_primary_span()),
+            notes
+        )
+    }
+}
+
+fn json_span(span: Option<Span>) -> String {
+    let sp = match span {
+        Some(sp) => sp,
+        None => return "null".to_string(),
+    };
+    let (line, column) = get_source_manager().line_col_of(sp);
+    format!(
+        "{{\"file\":{},\"line\":{},\"column\":{},\"begin\":{},\"end\":{}}}",
+        json_string(&sp.source.get_path()),
+        line,
+        column,
+        sp.begin,
+        sp.end
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::get_source_manager;
+
+    #[test]
+    fn json_two_notes() {
+        let source = get_source_manager().add(
+            "json_diag.sv",
+            "module foo;\n  assign x = \"a\\tb\";\nendmodule\n",
+        );
+        let diag = DiagBuilder2::error("`x` is not declared")
+            .span(Span::new(source, 21, 22))
+            .add_note("Did you mean `y`?")
+            .add_note("Module declared here:")
+            .span(Span::new(source, 7, 10));
+        assert_eq!(
+            JsonRenderer.render(&diag),
+            concat!(
+                r#"{"severity":"error","code":"M68b85790","message":"`x` is not declared","#,
+                r#""span":{"file":"json_diag.sv","line":2,"column":10,"begin":21,"end":22},"#,
+                r#""notes":[{"message":"Did you mean `y`?","span":null},"#,
+                r#"{"message":"Module declared here:","#,
+                r#""span":{"file":"json_diag.sv","line":1,"column":8,"begin":7,"end":10}}]}"#,
+            )
+        );
+    }
+
+    /// Render a diagnostic without color codes.
+    fn plain(diag: &DiagBuilder2) -> String {
+        HumanRenderer { color: false }.render(diag)
+    }
+
+    #[test]
+    fn labels_same_file() {
+        let source = get_source_manager().add(
+            "labels_same.sv",
+            "`define FOO(a, b) a + b\nmodule foo;\n  int x = `FOO(1, 2, 3);\nendmodule\n",
+        );
+        let diag = DiagBuilder2::fatal("macro expansion with 3 arguments, but `FOO` expects 2")
+            .span_label(Span::new(source, 46, 59), "3 arguments supplied here")
+            .span_label(
+                Span::new(source, 8, 11),
+                "macro declared with 2 arguments here",
+            );
+        assert_eq!(
+            plain(&diag),
+            concat!(
+                "fatal: macro expansion with 3 arguments, but `FOO` expects 2\n",
+                "  --> labels_same.sv:3:11-24:\n",
+                "   | \n",
+                "   | `define FOO(a, b) a + b\n",
+                "   |         --- macro declared with 2 arguments here\n",
+                "  ...\n",
+                "   |   int x = `FOO(1, 2, 3);\n",
+                "   |           ^^^^^^^^^^^^^ 3 arguments supplied here\n",
+            )
+        );
+    }
+
+    #[test]
+    fn labels_cross_file() {
+        let def = get_source_manager().add("labels_def.svh", "`define BAR(a) a\n");
+        let source = get_source_manager().add("labels_use.sv", "int y = `BAR(1, 2);\n");
+        let diag = DiagBuilder2::fatal("macro expansion with 2 arguments, but `BAR` expects 1")
+            .span_label(Span::new(source, 8, 18), "2 arguments supplied here")
+            .span_label(Span::new(def, 8, 11), "macro declared with 1 argument here");
+        assert_eq!(
+            plain(&diag),
+            concat!(
+                "fatal: macro expansion with 2 arguments, but `BAR` expects 1\n",
+                "  --> labels_use.sv:1:9-19:\n",
+                "   | \n",
+                "   | int y = `BAR(1, 2);\n",
+                "   |         ^^^^^^^^^^ 2 arguments supplied here\n",
+                "   = note: macro declared with 1 argument here\n",
+                "  --> labels_def.svh:1:9-12:\n",
+                "   | \n",
+                "   | `define BAR(a) a\n",
+                "   |         ^^^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn excerpt_mid_line() {
+        let source = get_source_manager().add(
+            "excerpt_mid.sv",
+            "module foo;\n  assign x = y + z;\nendmodule\n",
+        );
+        let diag = DiagBuilder2::error("`y` is not declared").span(Span::new(source, 25, 26));
+        assert_eq!(
+            plain(&diag),
+            concat!(
+                "error: `y` is not declared\n",
+                "  --> excerpt_mid.sv:2:14-15:\n",
+                "   | \n",
+                "   |   assign x = y + z;\n",
+                "   |              ^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn excerpt_end_of_line() {
+        let source =
+            get_source_manager().add("excerpt_eol.sv", "module foo;\n\tassign x = y\nendmodule\n");
+        let diag = DiagBuilder2::error("expected `;`").span(Span::new(source, 25, 25));
+        assert_eq!(
+            plain(&diag),
+            concat!(
+                "error: expected `;`\n",
+                "  --> excerpt_eol.sv:2:14-14:\n",
+                "   | \n",
+                "   |     assign x = y\n",
+                "   |                 ^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn excerpt_multi_line() {
+        let source = get_source_manager().add(
+            "excerpt_multi.sv",
+            "module foo;\n  assign x = a +\n    b +\n    c;\nendmodule\n",
+        );
+        let diag = DiagBuilder2::warning("expression is too wide").span(Span::new(source, 25, 42));
+        assert_eq!(
+            plain(&diag),
+            concat!(
+                "warning: expression is too wide\n",
+                "  --> excerpt_multi.sv:2:14-4:6:\n",
+                "   | \n",
+                "   |   assign x = a +\n",
+                "   |              ^^^\n",
+                "  ...\n",
+                "   |     c;\n",
+                "   |     ^\n",
+            )
+        );
+    }
+
+    #[test]
+    fn excerpt_long_line() {
+        let xs = "x, ".repeat(60);
+        let line = format!("int {}y, {}z;", xs, xs);
+        let source = get_source_manager().add("excerpt_long.sv", &line);
+        let diag = DiagBuilder2::error("`y` is not declared").span(Span::new(source, 184, 185));
+        let text = plain(&diag);
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[1], "  --> excerpt_long.sv:1:185-186:");
+        assert_eq!(lines[3], format!("   | ...{}...", &line[135..235]));
+        assert_eq!(lines[4], format!("   | {}^", " ".repeat(52)));
+    }
+
+    #[test]
+    fn stable_codes() {
+        let a = DiagBuilder2::error("`x` is not declared");
+        let b = DiagBuilder2::error("`foo` is not declared");
+        let c = DiagBuilder2::error("`x` is not a constant");
+        assert_eq!(a.get_stable_code(), b.get_stable_code());
+        assert_ne!(a.get_stable_code(), c.get_stable_code());
+        assert_eq!(
+            a.code(DiagCode::UNKNOWN_DIRECTIVE).get_stable_code(),
+            "E0203"
+        );
+        assert!(DiagCode::is_stable_code("W0201"));
+        assert_eq!(
+            DiagCode::lookup("W0106"),
+            Some(DiagCode::DEPRECATED_CONSTRUCT)
+        );
+        assert_eq!(DiagCode::lookup("W0203"), Some(DiagCode::CAST_IGNORED));
+        assert!(DiagCode::is_stable_code(&b.get_stable_code()));
+        assert!(!DiagCode::is_stable_code("W9999"));
+        assert!(!DiagCode::is_stable_code("M1234"));
+        assert!(!DiagCode::is_stable_code("M1234ABCD"));
+        assert_eq!(json_string("a\"b\\\n"), r#""a\"b\\\n""#);
+    }
+}
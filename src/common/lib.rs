@@ -18,13 +18,15 @@ pub mod source;
 pub mod util;
 
 pub use self::id::NodeId;
-use crate::errors::{DiagBuilder2, DiagEmitter, Severity};
+use crate::errors::{ColorChoice, DiagBuilder2, DiagEmitter, DiagFormat, DiagPolicy, Severity};
 use std::cell::Cell;
 
 pub struct Session {
     pub opts: SessionOptions,
     /// Whether any error diagnostics were produced.
     pub failed: Cell<bool>,
+    /// The number of diagnostics emitted so far.
+    counts: Cell<DiagCounts>,
 }
 
 impl Session {
@@ -33,20 +35,118 @@ impl Session {
         Session {
             opts: Default::default(),
             failed: Cell::new(false),
+            counts: Default::default(),
         }
     }
 
     pub fn failed(&self) -> bool {
         self.failed.get()
     }
+
+    /// Count a diagnostic and decide whether it should be rendered.
+    ///
+    /// Errors and warnings beyond `SessionOptions::error_limit` and
+    /// `SessionOptions::warning_limit` are counted but not rendered. Fatal
+    /// errors, compiler bugs, and notes are always rendered.
+    pub fn count_diag(&self, diag: &DiagBuilder2) -> bool {
+        let mut counts = self.counts.get();
+        *counts.get_mut(diag.severity) += 1;
+        self.counts.set(counts);
+        let limit = match diag.severity {
+            Severity::Error => self.error_limit(),
+            Severity::Warning => self.warning_limit(),
+            _ => return true,
+        };
+        limit == 0 || counts.get(diag.severity) <= limit
+    }
+
+    /// Get the number of diagnostics emitted so far, by severity.
+    ///
+    /// Diagnostics suppressed by `SessionOptions::diag_policy` are not
+    /// counted, and upgraded ones are counted with their new severity.
+    pub fn diagnostic_counts(&self) -> DiagCounts {
+        self.counts.get()
+    }
+
+    /// Get the process exit status that reflects the diagnostics emitted so
+    /// far.
+    ///
+    /// This is 2 if a compiler bug was encountered, 1 if any errors or fatal
+    /// errors were emitted, and 0 otherwise.
+    pub fn exit_status(&self) -> i32 {
+        let counts = self.diagnostic_counts();
+        if counts.bugs > 0 {
+            2
+        } else if counts.errors > 0 || counts.fatals > 0 || self.failed() {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn error_limit(&self) -> usize {
+        self.opts.error_limit.unwrap_or(DEFAULT_ERROR_LIMIT)
+    }
+
+    fn warning_limit(&self) -> usize {
+        self.opts.warning_limit.unwrap_or(DEFAULT_WARNING_LIMIT)
+    }
+
+    /// Get notes summarizing the errors and warnings that were not rendered
+    /// due to the limits.
+    pub fn limit_summary(&self) -> Vec<DiagBuilder2> {
+        let mut notes = vec![];
+        let counts = self.diagnostic_counts();
+        let hidden = |count: usize, limit: usize| match limit {
+            0 => 0,
+            _ => count.saturating_sub(limit),
+        };
+        let errors = hidden(counts.errors, self.error_limit());
+        if errors > 0 {
+            notes.push(DiagBuilder2::note(format!(
+                "{} additional error{} not shown (raise with --error-limit)",
+                errors,
+                if errors == 1 { "" } else { "s" }
+            )));
+        }
+        let warnings = hidden(counts.warnings, self.warning_limit());
+        if warnings > 0 {
+            notes.push(DiagBuilder2::note(format!(
+                "{} additional warning{} not shown (raise with --warning-limit)",
+                warnings,
+                if warnings == 1 { "" } else { "s" }
+            )));
+        }
+        notes
+    }
+
+    /// Emit the summary of the errors and warnings that were not rendered
+    /// due to the limits.
+    pub fn emit_limit_summary(&self) {
+        for note in self.limit_summary() {
+            self.emit(note);
+        }
+    }
 }
 
 impl DiagEmitter for Session {
     fn emit(&self, diag: DiagBuilder2) {
+        let diag = match self.opts.diag_policy.apply(diag) {
+            Some(diag) => diag,
+            None => return,
+        };
         if diag.severity >= Severity::Error {
             self.failed.set(true);
         }
-        eprintln!("{}", diag);
+        if self.count_diag(&diag) {
+            eprintln!(
+                "{}",
+                self.opts
+                    .diag_format
+                    .renderer(self.opts.color.enabled())
+                    .render(&diag)
+            );
+        }
     }
 }
 
@@ -75,8 +175,73 @@ pub struct SessionOptions {
     pub verbosity: Verbosity,
     /// The optimization level.
     pub opt_level: usize,
+    /// The maximum number of iterations of a generate loop. Defaults to
+    /// `DEFAULT_MAX_GENVAR_ITERATIONS` if not set.
+    pub max_genvar_iterations: Option<usize>,
+    /// The maximum number of instantiation sites listed in a diagnostic that
+    /// arises in a parametrized module. Defaults to
+    /// `DEFAULT_MAX_CONTEXT_NOTES` if not set.
+    pub max_context_notes: Option<usize>,
+    /// The format in which diagnostics are emitted.
+    pub diag_format: DiagFormat,
+    /// Whether diagnostics are highlighted with ANSI colors.
+    pub color: ColorChoice,
+    /// How diagnostics are suppressed or upgraded before they are emitted.
+    pub diag_policy: DiagPolicy,
+    /// The maximum number of errors that are rendered. Defaults to
+    /// `DEFAULT_ERROR_LIMIT` if not set. A limit of zero renders all errors.
+    pub error_limit: Option<usize>,
+    /// The maximum number of warnings that are rendered. Defaults to
+    /// `DEFAULT_WARNING_LIMIT` if not set. A limit of zero renders all
+    /// warnings.
+    pub warning_limit: Option<usize>,
+}
+
+/// The number of diagnostics emitted in a session, by severity.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct DiagCounts {
+    pub notes: usize,
+    pub warnings: usize,
+    pub errors: usize,
+    pub fatals: usize,
+    pub bugs: usize,
 }
 
+impl DiagCounts {
+    /// Get the number of diagnostics of a severity.
+    pub fn get(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::Note => self.notes,
+            Severity::Warning => self.warnings,
+            Severity::Error => self.errors,
+            Severity::Fatal => self.fatals,
+            Severity::Bug => self.bugs,
+        }
+    }
+
+    fn get_mut(&mut self, severity: Severity) -> &mut usize {
+        match severity {
+            Severity::Note => &mut self.notes,
+            Severity::Warning => &mut self.warnings,
+            Severity::Error => &mut self.errors,
+            Severity::Fatal => &mut self.fatals,
+            Severity::Bug => &mut self.bugs,
+        }
+    }
+}
+
+/// The default maximum number of iterations of a generate loop.
+pub const DEFAULT_MAX_GENVAR_ITERATIONS: usize = 1_000_000;
+
+/// The default maximum number of instantiation sites listed in a diagnostic.
+pub const DEFAULT_MAX_CONTEXT_NOTES: usize = 5;
+
+/// The default maximum number of errors that are rendered.
+pub const DEFAULT_ERROR_LIMIT: usize = 50;
+
+/// The default maximum number of warnings that are rendered.
+pub const DEFAULT_WARNING_LIMIT: usize = 200;
+
 bitflags! {
     /// A set of verbosity options for a session.
     ///
@@ -94,5 +259,112 @@ bitflags! {
         const INSTS         = 1 << 8;
         const FUNC_ARGS     = 1 << 9;
         const CALL_ARGS     = 1 << 10;
+        const MIR_CONSTS    = 1 << 11;
+        const CONST_STATS   = 1 << 12;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{DiagCode, DiagCodeAction};
+
+    #[test]
+    fn code_actions() {
+        let mut policy = DiagPolicy::default();
+        policy.code_actions.insert(
+            DiagCode::NEGATIVE_SHIFT.to_string(),
+            DiagCodeAction::Suppress,
+        );
+        policy.code_actions.insert(
+            DiagCode::SHIFT_EXCEEDS_WIDTH.to_string(),
+            DiagCodeAction::Error,
+        );
+        policy.code_actions.insert(
+            DiagCode::UNKNOWN_DIRECTIVE.to_string(),
+            DiagCodeAction::Suppress,
+        );
+        let uncoded = DiagBuilder2::warning("`x` is never read");
+        policy
+            .code_actions
+            .insert(uncoded.get_stable_code(), DiagCodeAction::Suppress);
+        let severity = |diag: DiagBuilder2| policy.apply(diag).map(|d| d.severity);
+
+        let negative = DiagBuilder2::warning("negative shift amount -1");
+        assert_eq!(severity(negative.clone()), Some(Severity::Warning));
+        assert_eq!(severity(negative.code(DiagCode::NEGATIVE_SHIFT)), None);
+        assert_eq!(
+            severity(DiagBuilder2::warning("shift amount 40").code(DiagCode::SHIFT_EXCEEDS_WIDTH)),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            severity(DiagBuilder2::fatal("unknown directive").code(DiagCode::UNKNOWN_DIRECTIVE)),
+            Some(Severity::Fatal)
+        );
+
+        // Diagnostics without a code are matched by the code derived from
+        // their message.
+        assert_eq!(severity(uncoded), None);
+        assert_eq!(severity(DiagBuilder2::warning("`y` is never read")), None);
+        assert_eq!(
+            severity(DiagBuilder2::warning("`x` is never written")),
+            Some(Severity::Warning)
+        );
+    }
+
+    #[test]
+    fn deny_warnings() {
+        let mut sess = Session::new();
+        sess.opts.diag_policy.deny_warnings = true;
+        sess.opts.diag_policy.code_actions.insert(
+            DiagCode::NEGATIVE_SHIFT.to_string(),
+            DiagCodeAction::Suppress,
+        );
+
+        // Suppression applies before the upgrade.
+        sess.emit(DiagBuilder2::warning("negative shift amount -1").code(DiagCode::NEGATIVE_SHIFT));
+        assert_eq!(sess.diagnostic_counts(), DiagCounts::default());
+        assert_eq!(sess.exit_status(), 0);
+
+        sess.emit(DiagBuilder2::note("just a note"));
+        assert_eq!(sess.exit_status(), 0);
+        sess.emit(DiagBuilder2::warning("shift amount 40").code(DiagCode::SHIFT_EXCEEDS_WIDTH));
+        assert_eq!(
+            sess.diagnostic_counts(),
+            DiagCounts {
+                notes: 1,
+                errors: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(sess.exit_status(), 1);
+        sess.emit(DiagBuilder2::bug("something broke"));
+        assert_eq!(sess.diagnostic_counts().get(Severity::Bug), 1);
+        assert_eq!(sess.exit_status(), 2);
+    }
+
+    #[test]
+    fn error_limit() {
+        let mut sess = Session::new();
+        sess.opts.error_limit = Some(2);
+        sess.opts.warning_limit = Some(0);
+        let rendered = (0..5)
+            .filter(|i| sess.count_diag(&DiagBuilder2::error(format!("error {}", i))))
+            .count();
+        assert_eq!(rendered, 2);
+        assert!(sess.count_diag(&DiagBuilder2::fatal("fatal")));
+        assert!(sess.count_diag(&DiagBuilder2::warning("warning")));
+        let summary: Vec<_> = sess
+            .limit_summary()
+            .into_iter()
+            .map(|d| (d.severity, d.message))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(
+                Severity::Note,
+                "3 additional errors not shown (raise with --error-limit)".to_string()
+            )]
+        );
     }
 }
@@ -8,16 +8,22 @@ use crate::name::RcStr;
 use memmap2::Mmap;
 use once_cell::sync::OnceCell;
 use std;
-use std::borrow::Borrow;
-use std::cell::RefCell;
+use std::borrow::{Borrow, Cow};
+use std::cell::{Cell, RefCell};
 use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::fmt;
 use std::fs::File;
 use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use std::rc::Rc;
 
+/// Files on disk at least this many bytes large are memory-mapped instead of
+/// being read into memory, unless changed with
+/// `SourceManager::set_mmap_threshold`.
+pub const DEFAULT_MMAP_THRESHOLD: usize = 1 << 20;
+
 pub const INVALID_SOURCE: Source = Source(0);
 pub const INVALID_LOCATION: Location = Location {
     source: INVALID_SOURCE,
@@ -129,6 +135,13 @@ pub trait SourceContent {
     /// more efficient than copying the extract into a String.
     fn extract_iter(&self, begin: usize, end: usize) -> Box<CharIter>;
 
+    /// Borrow a range of the source content as a string slice, without copying
+    /// it. Invalid UTF-8 byte sequences are replaced by U+FFFD, in which case
+    /// the range is copied.
+    fn extract_str(&self, begin: usize, end: usize) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.bytes()[begin..end])
+    }
+
     /// Obtain a slice voer all bytes within the source file. This is the
     /// fastest way of getting at the file's contents, since no parsing or
     /// character encoding is performed or assumed.
@@ -142,6 +155,10 @@ pub trait SourceContent {
 pub struct SourceManager {
     map: RefCell<HashMap<RcStr, Source>>,
     vect: RefCell<Vec<Box<dyn SourceFile>>>,
+    /// The in-memory files that shadow files of the same name.
+    overlays: RefCell<HashMap<RcStr, Source>>,
+    /// The size from which files on disk are memory-mapped.
+    mmap_threshold: Cell<usize>,
 }
 
 impl SourceManager {
@@ -149,9 +166,17 @@ impl SourceManager {
         SourceManager {
             map: RefCell::new(HashMap::new()),
             vect: RefCell::new(Vec::new()),
+            overlays: RefCell::new(HashMap::new()),
+            mmap_threshold: Cell::new(DEFAULT_MMAP_THRESHOLD),
         }
     }
 
+    /// Set the size in bytes from which files opened in the future are
+    /// memory-mapped instead of read into memory.
+    pub fn set_mmap_threshold(&self, bytes: usize) {
+        self.mmap_threshold.set(bytes);
+    }
+
     /// Obtain the source file for a given source ID.
     pub fn with<F, R>(&self, id: Source, f: F) -> R
     where
@@ -173,10 +198,18 @@ impl SourceManager {
         RcStr: Borrow<Q>,
         Q: Eq + Hash,
     {
+        if let Some(&id) = self.overlays.borrow().get(filename) {
+            return Some(id);
+        }
         (*self.map.borrow()).get(filename).map(|v| *v)
     }
 
     pub fn open(&self, filename: &str) -> Option<Source> {
+        // Overlays shadow the files on disk.
+        if let Some(&id) = self.overlays.borrow().get(filename) {
+            return Some(id);
+        }
+
         // Check if the file has already been opened and return its pointer.
         let mut map = self.map.borrow_mut();
         if let Some(&id) = map.get(filename) {
@@ -193,6 +226,7 @@ impl SourceManager {
                 id: new_id,
                 filename: v,
                 content: RefCell::new(None),
+                mmap_threshold: self.mmap_threshold.get(),
             }));
             Some(new_id)
         } else {
@@ -222,11 +256,62 @@ impl SourceManager {
         new_id
     }
 
+    /// Register an in-memory file that shadows the file at `filename`.
+    ///
+    /// Future calls to `open()` with the given filename yield the provided
+    /// contents instead of the file on disk, or the file previously added
+    /// under that name. Registering another overlay for the same filename
+    /// replaces this one, but the returned `Source` keeps referring to these
+    /// contents.
+    pub fn add_overlay(&self, filename: &str, content: &str) -> Source {
+        let mut vect = self.vect.borrow_mut();
+        let new_id = Source(vect.len() as u32 + 1);
+        let v = RcStr::new(filename);
+        self.overlays.borrow_mut().insert(v.clone(), new_id);
+        vect.push(Box::new(VirtualSourceFile {
+            id: new_id,
+            filename: v,
+            content: Rc::new(VirtualSourceContent(content.to_string(), OnceCell::new())),
+        }));
+        new_id
+    }
+
+    /// Remove the overlay registered for `filename`, such that future calls to
+    /// `open()` yield the file on disk again. Returns whether there was an
+    /// overlay.
+    pub fn remove_overlay(&self, filename: &str) -> bool {
+        self.overlays.borrow_mut().remove(filename).is_some()
+    }
+
+    /// Determine the one-based line and column at which a span begins.
+    ///
+    /// Uses the index of line starts of the span's source, which is built once
+    /// and then searched, such that repeated lookups do not rescan the file.
+    /// Columns count characters rather than bytes.
+    pub fn line_col_of(&self, span: Span) -> (u32, u32) {
+        let content = self.with(span.source, |file| file.get_content());
+        let lines = content.lines();
+        let index = lines.partition_point(|&x| x <= span.begin) - 1;
+        let column = column_of(content.bytes(), lines[index], span.begin);
+        (index as u32 + 1, column as u32)
+    }
+
     /// Create a virtual file from the contents of a string and add it to the
     /// source manager. The file can only be used with the returned `Source`,
     /// since there is no name associated with it by which it could be referred
     /// to.
     pub fn add_anonymous<S>(&self, content: S) -> Source
+    where
+        S: Into<String>,
+    {
+        self.add_named_virtual("<anonymous>", content)
+    }
+
+    /// Create a virtual file like `add_anonymous()`, but with a descriptive
+    /// name that diagnostics show in place of a path, such as `<define FOO from
+    /// command line>`. The name is not registered for `open()` or `find()`, and
+    /// the file is never read from disk.
+    pub fn add_named_virtual<S>(&self, name: &str, content: S) -> Source
     where
         S: Into<String>,
     {
@@ -234,7 +319,7 @@ impl SourceManager {
         let new_id = Source(vect.len() as u32 + 1);
         vect.push(Box::new(VirtualSourceFile {
             id: new_id,
-            filename: RcStr::new("<anonymous>"),
+            filename: RcStr::new(name),
             content: Rc::new(VirtualSourceContent(content.into(), OnceCell::new())),
         }));
         new_id
@@ -249,10 +334,17 @@ pub fn get_source_manager() -> Rc<SourceManager> {
     MNGR.with(|x| x.clone())
 }
 
-fn line_starts(iter: impl Iterator<Item = (usize, char)>) -> impl Iterator<Item = usize> {
-    Some(0)
-        .into_iter()
-        .chain(iter.filter(|(_i, c)| *c == '\n').map(|(i, _c)| i + 1))
+/// Find the byte offsets at which the lines of a file start. A `\r\n` line
+/// break is treated like a `\n`, with the `\r` counting towards the end of the
+/// line it terminates.
+fn line_starts(bytes: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    Some(0).into_iter().chain(
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c == b'\n')
+            .map(|(i, _)| i + 1),
+    )
 }
 
 /// A virtual source file that has no correspondence in the file system. Useful
@@ -301,19 +393,58 @@ impl SourceContent for VirtualSourceContent {
     }
 
     fn lines(&self) -> &[usize] {
-        self.1.get_or_init(|| line_starts(self.iter()).collect())
+        self.1.get_or_init(|| line_starts(self.bytes()).collect())
     }
 }
 
-/// A source file on disk.
+/// A source file on disk. Its content is loaded when first requested.
 struct DiskSourceFile {
     id: Source,
     filename: RcStr,
-    content: RefCell<Option<Rc<DiskSourceContent>>>,
+    content: RefCell<Option<Rc<dyn SourceContent>>>,
+    mmap_threshold: usize,
 }
 
+/// A memory-mapped source file on disk.
+///
+/// The mapping is only read while the file keeps the length and modification
+/// time it had when it was mapped. This is checked whenever the content is
+/// accessed, and at every `MAP_CHECK_INTERVAL` boundary while iterating.
+/// Once a modification is noticed, the file is read again and the content is
+/// served from that copy instead. Spans created before the modification then
+/// refer to the new text, and offsets beyond its end yield no characters.
 #[derive(Debug)]
-struct DiskSourceContent(pub Mmap, OnceCell<Vec<usize>>);
+struct DiskSourceContent {
+    map: Mmap,
+    file: File,
+    stamp: FileStamp,
+    reread: OnceCell<Vec<u8>>,
+    lines: OnceCell<Vec<usize>>,
+}
+
+/// The alignment of the chunks in which a mapped file is read between checks
+/// for modifications. This is the smallest page size in common use, such that
+/// an iterator reads from at most two pages before checking again. A file
+/// truncated between two such checks can still fault on the pages it no
+/// longer backs.
+const MAP_CHECK_INTERVAL: usize = 4096;
+
+/// The length and modification time of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl FileStamp {
+    fn of(file: &File) -> Option<FileStamp> {
+        let meta = file.metadata().ok()?;
+        Some(FileStamp {
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
 
 impl SourceFile for DiskSourceFile {
     fn get_id(&self) -> Source {
@@ -325,48 +456,253 @@ impl SourceFile for DiskSourceFile {
     }
 
     fn get_content(&self) -> Rc<dyn SourceContent> {
-        let is_none = self.content.borrow().is_none();
-        if is_none {
-            let c = Rc::new(DiskSourceContent(
-                unsafe { Mmap::map(&File::open(&*self.filename).unwrap()).unwrap() },
-                OnceCell::new(),
-            ));
-            *self.content.borrow_mut() = Some(c.clone());
-            c
-        } else {
-            self.content.borrow().clone().unwrap()
+        if let Some(content) = self.content.borrow().clone() {
+            return content;
         }
+        let content = load_file(&self.filename, self.mmap_threshold);
+        *self.content.borrow_mut() = Some(content.clone());
+        content
+    }
+}
+
+/// Load the content of a file on disk.
+///
+/// Files of at least `mmap_threshold` bytes are memory-mapped, such that they
+/// are paged in on demand rather than copied. Smaller files, and files that
+/// cannot be mapped, are read into memory. Invalid UTF-8 byte sequences are
+/// replaced by U+FFFD in either case, which the lexer reports.
+///
+/// A mapped file that is modified while the compiler runs is read again, such
+/// that spans may refer to text that differs from what was preprocessed. Files
+/// that are being edited should be provided through
+/// `SourceManager::add_overlay` instead.
+fn load_file(filename: &str, mmap_threshold: usize) -> Rc<dyn SourceContent> {
+    let mut file = File::open(filename).unwrap();
+    let len = file.metadata().map(|m| m.len() as usize).unwrap_or(0);
+    if len >= mmap_threshold {
+        if let Some(stamp) = FileStamp::of(&file) {
+            if let Ok(map) = unsafe { Mmap::map(&file) } {
+                return Rc::new(DiskSourceContent {
+                    map,
+                    file,
+                    stamp,
+                    reread: OnceCell::new(),
+                    lines: OnceCell::new(),
+                });
+            }
+        }
+    }
+    let mut bytes = Vec::with_capacity(len);
+    file.read_to_end(&mut bytes).unwrap();
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => String::from_utf8_lossy(err.as_bytes()).into_owned(),
+    };
+    Rc::new(VirtualSourceContent(text, OnceCell::new()))
+}
+
+impl DiskSourceContent {
+    /// The bytes of the file, either from the mapping if the file has not been
+    /// modified since it was mapped, or from a copy read afterwards.
+    fn current(&self) -> &[u8] {
+        if self.reread.get().is_none() && FileStamp::of(&self.file) == Some(self.stamp) {
+            return &self.map[..];
+        }
+        self.reread.get_or_init(|| {
+            let mut bytes = vec![];
+            let mut file = &self.file;
+            if file.seek(SeekFrom::Start(0)).is_ok() {
+                let _ = file.read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    }
+
+    /// Create an iterator over the characters within a range of the file.
+    fn chars(&self, begin: usize, end: usize) -> Box<CharIter<'_>> {
+        let end = min(end, self.current().len());
+        Box::new(MappedChars {
+            content: self,
+            bytes: &[],
+            front: min(begin, end),
+            back: end,
+            front_checked: 0,
+            back_checked: usize::MAX,
+        })
     }
 }
 
 impl SourceContent for DiskSourceContent {
     fn iter(&self) -> Box<CharIter> {
-        use std::str;
-        Box::new(str::from_utf8(&self.0[..]).unwrap().char_indices())
+        self.chars(0, usize::MAX)
     }
 
     fn iter_from(&self, offset: usize) -> Box<CharIter> {
-        use std::str;
-        Box::new(str::from_utf8(&self.0[offset..]).unwrap().char_indices())
+        self.chars(offset, usize::MAX)
     }
 
     fn extract(&self, begin: usize, end: usize) -> String {
-        use std::str;
-        str::from_utf8(&self.0[begin..end]).unwrap().to_string()
+        let bytes = self.current();
+        let end = min(end, bytes.len());
+        let begin = min(begin, end);
+        String::from_utf8_lossy(&bytes[begin..end]).into_owned()
     }
 
     fn extract_iter(&self, begin: usize, end: usize) -> Box<CharIter> {
-        use std::str;
-        Box::new(str::from_utf8(&self.0[begin..end]).unwrap().char_indices())
+        self.chars(begin, end)
+    }
+
+    fn extract_str(&self, begin: usize, end: usize) -> Cow<'_, str> {
+        let bytes = self.current();
+        let end = min(end, bytes.len());
+        let begin = min(begin, end);
+        String::from_utf8_lossy(&bytes[begin..end])
     }
 
     fn bytes(&self) -> &[u8] {
-        &self.0[..]
+        self.current()
     }
 
     fn lines(&self) -> &[usize] {
-        self.1.get_or_init(|| line_starts(self.iter()).collect())
+        self.lines
+            .get_or_init(|| line_starts(self.bytes()).collect())
+    }
+}
+
+/// An iterator over the characters of a memory-mapped file.
+///
+/// The file is checked for modifications before the first character and again
+/// whenever a `MAP_CHECK_INTERVAL` boundary is crossed. Characters read before
+/// a modification is noticed may still come from the mapping. Invalid UTF-8
+/// byte sequences are decoded as U+FFFD.
+struct MappedChars<'a> {
+    content: &'a DiskSourceContent,
+    bytes: &'a [u8],
+    front: usize,
+    back: usize,
+    front_checked: usize,
+    back_checked: usize,
+}
+
+impl MappedChars<'_> {
+    fn refresh(&mut self) {
+        self.bytes = self.content.current();
+        self.back = min(self.back, self.bytes.len());
+        self.front = min(self.front, self.back);
+        // Leave room for the up to four bytes a character is decoded from.
+        self.front_checked = ((self.front + 4) / MAP_CHECK_INTERVAL + 1) * MAP_CHECK_INTERVAL;
+        self.back_checked = self.back.saturating_sub(4) / MAP_CHECK_INTERVAL * MAP_CHECK_INTERVAL;
+    }
+}
+
+impl Iterator for MappedChars<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        if self.front + 4 > self.front_checked {
+            self.refresh();
+        }
+        let rest = &self.bytes[self.front..self.back];
+        if rest.is_empty() {
+            return None;
+        }
+        let (c, len) = decode_first_char(rest);
+        let offset = self.front;
+        self.front += len;
+        Some((offset, c))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // The file may shrink if it is modified, so only the upper bound holds.
+        (0, Some(self.back - self.front))
+    }
+}
+
+impl DoubleEndedIterator for MappedChars<'_> {
+    fn next_back(&mut self) -> Option<(usize, char)> {
+        if self.back.saturating_sub(4) < self.back_checked {
+            self.refresh();
+        }
+        let rest = &self.bytes[self.front..self.back];
+        if rest.is_empty() {
+            return None;
+        }
+        let (c, len) = decode_last_char(rest);
+        self.back -= len;
+        Some((self.back, c))
+    }
+}
+
+/// Decode the first character of a byte sequence, returning the character and
+/// its length in bytes. Invalid sequences are decoded as U+FFFD, with the same
+/// lengths as `String::from_utf8_lossy` uses.
+fn decode_first_char(bytes: &[u8]) -> (char, usize) {
+    let head = &bytes[..min(bytes.len(), 4)];
+    let err = match std::str::from_utf8(head) {
+        Ok(text) => return first_char(text),
+        Err(err) => err,
+    };
+    match std::str::from_utf8(&head[..err.valid_up_to()]) {
+        Ok(text) if !text.is_empty() => first_char(text),
+        _ => (
+            std::char::REPLACEMENT_CHARACTER,
+            err.error_len().unwrap_or(head.len()),
+        ),
+    }
+}
+
+/// Decode the last character of a byte sequence, returning the character and
+/// its length in bytes. Invalid bytes are decoded as U+FFFD one at a time.
+fn decode_last_char(bytes: &[u8]) -> (char, usize) {
+    let len = bytes.len();
+    for start in (len.saturating_sub(4)..len).rev() {
+        if bytes[start] & 0xC0 == 0x80 {
+            continue;
+        }
+        if let Ok(text) = std::str::from_utf8(&bytes[start..]) {
+            if let Some(c) = text.chars().next() {
+                if c.len_utf8() == len - start {
+                    return (c, len - start);
+                }
+            }
+        }
+        break;
     }
+    (std::char::REPLACEMENT_CHARACTER, 1)
+}
+
+fn first_char(text: &str) -> (char, usize) {
+    let c = text
+        .chars()
+        .next()
+        .unwrap_or(std::char::REPLACEMENT_CHARACTER);
+    (c, c.len_utf8())
+}
+
+/// Determine the one-based column of a byte offset within the line starting at
+/// `line_offset`, counting characters rather than bytes.
+fn column_of(bytes: &[u8], line_offset: usize, offset: usize) -> usize {
+    let offset = min(offset, bytes.len());
+    bytes[min(line_offset, offset)..offset]
+        .iter()
+        .filter(|&&b| b & 0xC0 != 0x80)
+        .count()
+        + 1
+}
+
+/// Determine the byte offset of a one-based column within the line starting at
+/// `line_offset`. This is the inverse of `column_of`.
+fn offset_of_column(bytes: &[u8], line_offset: usize, column: usize) -> usize {
+    let mut offset = min(line_offset, bytes.len());
+    let mut chars = 1;
+    while chars < column && offset < bytes.len() && bytes[offset] != b'\n' {
+        offset += 1;
+        while offset < bytes.len() && bytes[offset] & 0xC0 == 0x80 {
+            offset += 1;
+        }
+        chars += 1;
+    }
+    offset
 }
 
 /// An iterator that yields the characters from an input file together with the
@@ -390,11 +726,14 @@ impl Location {
     }
 
     /// Create a new location given a human-readable line and column.
+    ///
+    /// The column counts characters, as returned by `human_column`. Columns
+    /// beyond the end of the line refer to its end.
     pub fn with_line_and_column(source: Source, line: usize, column: usize) -> Location {
         let c = source.get_content();
         let lines = c.lines();
         if line > 0 && line <= lines.len() {
-            Location::new(source, lines[line - 1] + column - 1)
+            Location::new(source, offset_of_column(c.bytes(), lines[line - 1], column))
         } else {
             Location::new(source, 0)
         }
@@ -407,7 +746,8 @@ impl Location {
 
     /// Determine the line and column information at this location.
     ///
-    /// Returns a tuple `(line, column, line_offset)`.
+    /// Returns a tuple `(line, column, line_offset)`. Columns count characters
+    /// rather than bytes, as in `SourceManager::line_col_of`.
     pub fn human(self) -> (usize, usize, usize) {
         let c = self.source.get_content();
         let lines = c.lines();
@@ -415,7 +755,7 @@ impl Location {
         let line = index + 1;
         let line_offset = lines[index];
         assert!(line_offset <= self.offset);
-        let col = self.offset - line_offset + 1;
+        let col = column_of(c.bytes(), line_offset, self.offset);
         (line, col, line_offset)
     }
 
@@ -449,7 +789,13 @@ impl From<Location> for Span {
 
 /// A span of locations within a source file, expressed as a half-open interval
 /// of bytes `[begin,end)`.
-#[derive(Copy, Clone, PartialOrd, Ord, PartialEq, Eq, Hash)]
+///
+/// Spans are totally ordered by their source, then their begin offset, then
+/// their end offset. Within a file this is the order in which the spans appear
+/// in the text, with shorter spans first among those that begin at the same
+/// location. Across files it is the order in which the files were added to the
+/// source manager. Comparing spans does not touch the source content.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Span {
     pub source: Source,
     pub begin: usize,
@@ -494,6 +840,20 @@ impl Span {
         self
     }
 
+    /// Check whether this span covers all of `other`. An empty span is
+    /// contained in any span of the same source that it lies within or at the
+    /// boundary of. Spans in different sources never contain each other.
+    pub fn contains(&self, other: Span) -> bool {
+        self.source == other.source && self.begin <= other.begin && other.end <= self.end
+    }
+
+    /// Check whether this span and `other` have at least one byte in common.
+    /// Spans that merely touch, empty spans, and spans in different sources do
+    /// not overlap.
+    pub fn overlaps(&self, other: Span) -> bool {
+        self.source == other.source && max(self.begin, other.begin) < min(self.end, other.end)
+    }
+
     /// Return the location just before the first character in this span.
     pub fn begin(&self) -> Location {
         Location::new(self.source, self.begin)
@@ -509,6 +869,37 @@ impl Span {
         self.source.get_content().extract(self.begin, self.end)
     }
 
+    /// Borrow the portion of the source file in this span from the file's
+    /// content, without copying it unless it contains invalid UTF-8.
+    ///
+    /// The returned slice lives as long as the content handle:
+    ///
+    /// ```
+    /// # use moore_common::source::*;
+    /// let source = get_source_manager().add_anonymous("wire 老虎;");
+    /// let content = source.get_content();
+    /// assert_eq!(Span::new(source, 5, 11).extract_str(&content), "老虎");
+    /// ```
+    ///
+    /// ```compile_fail
+    /// # use moore_common::source::*;
+    /// let source = get_source_manager().add_anonymous("wire x;");
+    /// let text = {
+    ///     let content = source.get_content();
+    ///     Span::new(source, 5, 6).extract_str(&content)
+    /// };
+    /// ```
+    pub fn extract_str<'a>(self, content: &'a Rc<dyn SourceContent>) -> Cow<'a, str> {
+        content.extract_str(self.begin, self.end)
+    }
+
+    /// Call a function with the portion of the source file in this span,
+    /// without copying it into an owned string.
+    pub fn with_text<R>(self, f: impl FnOnce(&str) -> R) -> R {
+        let content = self.source.get_content();
+        f(&content.extract_str(self.begin, self.end))
+    }
+
     /// Obtain an iterator over the extract of the source file describe by this
     /// span.
     pub fn iter<'a>(self, content: &'a Rc<dyn SourceContent>) -> Box<CharIter<'a>> {
@@ -516,6 +907,18 @@ impl Span {
     }
 }
 
+impl Ord for Span {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.source, self.begin, self.end).cmp(&(other.source, other.begin, other.end))
+    }
+}
+
+impl PartialOrd for Span {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl fmt::Debug for Span {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}:{}-{}", self.source, self.begin, self.end)
@@ -629,6 +1032,82 @@ mod tests {
         assert_eq!(sm.open("/this/path/points/nowhere"), None);
     }
 
+    #[test]
+    fn overlay() {
+        let path = "/tmp/moore-test-overlay";
+        std::fs::write(path, "on disk\n").unwrap();
+        let sm = get_source_manager();
+        let first = sm.add_overlay(path, "first\n");
+        assert_eq!(sm.open(path), Some(first));
+        let second = sm.add_overlay(path, "second\n");
+        assert_eq!(sm.open(path), Some(second));
+        assert_eq!(first.extract(0, 5), "first");
+        assert_eq!(second.extract(0, 6), "second");
+        assert_eq!(first.get_path(), second.get_path());
+
+        assert!(sm.remove_overlay(path));
+        assert!(!sm.remove_overlay(path));
+        let disk = sm.open(path).expect("file should exist");
+        assert_eq!(disk.extract(0, 7), "on disk");
+    }
+
+    #[test]
+    fn line_col() {
+        let sm = get_source_manager();
+        let source = sm.add("line_col.sv", "ab\r\ncd\nef");
+        let line_col = |offset| sm.line_col_of(Span::new(source, offset, offset));
+        assert_eq!(line_col(0), (1, 1));
+        assert_eq!(line_col(2), (1, 3));
+        assert_eq!(line_col(4), (2, 1));
+        assert_eq!(line_col(5), (2, 2));
+        assert_eq!(line_col(7), (3, 1));
+        assert_eq!(line_col(9), (3, 3));
+
+        let source = sm.add("line_col_utf8.sv", "a\u{a0}b\n\u{201c}c");
+        let line_col = |offset| sm.line_col_of(Span::new(source, offset, offset));
+        assert_eq!(line_col(3), (1, 3));
+        assert_eq!(line_col(8), (2, 2));
+
+        // Locations agree with the source manager on the column, and map it
+        // back to the same offset.
+        for &(offset, line, column) in &[(0, 1, 1), (3, 1, 3), (4, 1, 4), (8, 2, 2)] {
+            let loc = Location::new(source, offset);
+            assert_eq!((loc.human_line(), loc.human_column()), (line, column));
+            assert_eq!(Location::with_line_and_column(source, line, column), loc);
+        }
+        assert_eq!(Location::with_line_and_column(source, 1, 10).offset, 4);
+    }
+
+    #[test]
+    fn line_col_cached() {
+        let sm = get_source_manager();
+        let source = sm.add_anonymous("logic [7:0] x;\n".repeat(100_000));
+        let lines = source.get_content().lines().as_ptr();
+        let start = std::time::Instant::now();
+        for line in 0..100_000 {
+            let offset = line * 15 + 6;
+            assert_eq!(
+                sm.line_col_of(Span::new(source, offset, offset)),
+                (line as u32 + 1, 7)
+            );
+        }
+        // Rescanning the 1.5 MB file for every lookup would take minutes.
+        assert!(start.elapsed().as_secs() < 10);
+        assert_eq!(source.get_content().lines().as_ptr(), lines);
+    }
+
+    #[test]
+    fn extract_multi_byte() {
+        let sm = get_source_manager();
+        let source = sm.add("extract_multi_byte.sv", "Löwe 老虎 Léopard");
+        let span = Span::new(source, 6, 12);
+        let content = source.get_content();
+        assert_eq!(span.extract_str(&content), "老虎");
+        assert_eq!(span.with_text(|s| s.chars().count()), 2);
+        assert_eq!(span.extract(), "老虎");
+        assert_eq!(Span::new(source, 13, 21).extract_str(&content), "Léopard");
+    }
+
     #[test]
     fn chars() {
         let sm = get_source_manager();
@@ -659,4 +1138,129 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    /// Get a path in the temporary directory that is unique to a test and
+    /// the process running it.
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("moore-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn mapped_file_invalid_utf8() {
+        use std::fs::File;
+        use std::io::Write;
+
+        let path = &temp_path("mapped-invalid");
+        let data = b"a\xffb\xe8\x80c\xe8\x80\x81";
+        File::create(path).unwrap().write_all(data).unwrap();
+
+        let sm = SourceManager::new();
+        sm.set_mmap_threshold(0);
+        let source = sm.open(path).expect("file should exist");
+        let content = sm.with(source, |f| f.get_content());
+        let expected: Vec<_> = String::from_utf8_lossy(data).chars().collect();
+        let actual: Vec<_> = content.iter().map(|(_, c)| c).collect();
+        assert_eq!(actual, expected);
+        assert_eq!(content.extract(0, 4), "a\u{fffd}b\u{fffd}");
+        let offsets: Vec<_> = content.iter().rev().map(|(i, _)| i).collect();
+        assert_eq!(offsets, vec![6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(content.extract_str(1, 3), "\u{fffd}b");
+        assert_eq!(content.extract_str(3, 20), "\u{fffd}c老");
+        assert_eq!(Span::new(source, 0, 1).extract_str(&content), "a");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn mapped_file_invalid_utf8_diagnostic() {
+        use crate::errors::{DiagBuilder2, DiagRenderer, HumanRenderer};
+
+        let path = &temp_path("mapped-invalid-diag");
+        std::fs::write(path, b"module \xff;\n").unwrap();
+        let sm = get_source_manager();
+        sm.set_mmap_threshold(0);
+        let source = sm.open(path).expect("file should exist");
+        sm.set_mmap_threshold(DEFAULT_MMAP_THRESHOLD);
+        let diag = DiagBuilder2::error("invalid UTF-8 byte sequence").span(Span::new(source, 7, 8));
+        let text = HumanRenderer { color: false }.render(&diag);
+        assert!(text.contains("   | module \u{fffd};\n"), "{}", text);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn mapped_file_modified() {
+        use std::fs::{File, OpenOptions};
+        use std::io::Write;
+
+        let path = &temp_path("mapped-modified");
+        File::create(path)
+            .unwrap()
+            .write_all("module foo; endmodule\n".repeat(8192).as_bytes())
+            .unwrap();
+
+        let sm = SourceManager::new();
+        sm.set_mmap_threshold(0);
+        let source = sm.open(path).expect("file should exist");
+        let content = sm.with(source, |f| f.get_content());
+        let mut iter = content.iter();
+        assert_eq!(iter.next(), Some((0, 'm')));
+
+        let file = OpenOptions::new().write(true).open(path).unwrap();
+        file.set_len(4).unwrap();
+        let modified = std::time::SystemTime::now() + std::time::Duration::from_secs(1);
+        file.set_modified(modified).unwrap();
+
+        assert!(iter.count() < MAP_CHECK_INTERVAL);
+        assert_eq!(content.iter().count(), 4);
+        assert_eq!(content.extract(2, 100), "du");
+        assert_eq!(content.bytes(), b"modu");
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn span_order() {
+        let sm = get_source_manager();
+        let a = sm.add("span_order_a.sv", "module foo; endmodule");
+        let b = sm.add("span_order_b.sv", "module bar; endmodule");
+        let mut spans = vec![
+            Span::new(b, 0, 6),
+            Span::new(a, 7, 10),
+            Span::new(a, 0, 6),
+            Span::new(a, 0, 21),
+            Span::new(a, 7, 7),
+        ];
+        spans.sort();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(a, 0, 6),
+                Span::new(a, 0, 21),
+                Span::new(a, 7, 7),
+                Span::new(a, 7, 10),
+                Span::new(b, 0, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn span_contains_overlaps() {
+        let sm = get_source_manager();
+        let a = sm.add("span_contains_a.sv", "module foo; endmodule");
+        let b = sm.add("span_contains_b.sv", "module bar; endmodule");
+        let outer = Span::new(a, 2, 8);
+        assert!(outer.contains(outer));
+        assert!(outer.contains(Span::new(a, 3, 5)));
+        assert!(outer.contains(Span::new(a, 8, 8)));
+        assert!(!outer.contains(Span::new(a, 1, 5)));
+        assert!(!outer.contains(Span::new(b, 3, 5)));
+
+        assert!(outer.overlaps(Span::new(a, 7, 10)));
+        assert!(outer.overlaps(Span::new(a, 0, 21)));
+        assert!(!outer.overlaps(Span::new(a, 8, 10)));
+        assert!(!outer.overlaps(Span::new(a, 0, 2)));
+        assert!(!outer.overlaps(Span::new(a, 4, 4)));
+        assert!(!outer.overlaps(Span::new(b, 2, 8)));
+    }
 }
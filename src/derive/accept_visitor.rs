@@ -18,7 +18,7 @@ pub(crate) fn accept_visitor(input: TokenStream, with_foreach: bool) -> TokenStr
     let dont_visit = has_dont_visit(&input.attrs);
     match &input.data {
         syn::Data::Struct(input) => {
-            let (visit, each) = visit_fields(&input.fields, dont_visit);
+            let (visit, each, _) = visit_fields(&input.fields, dont_visit);
             visits.push(quote! {
                 #name #visit
             });
@@ -30,7 +30,7 @@ pub(crate) fn accept_visitor(input: TokenStream, with_foreach: bool) -> TokenStr
             for variant in &input.variants {
                 let dont_visit = dont_visit || has_dont_visit(&variant.attrs);
                 let variant_name = &variant.ident;
-                let (visit, each) = visit_fields(&variant.fields, dont_visit);
+                let (visit, each, _) = visit_fields(&variant.fields, dont_visit);
                 visits.push(quote! {
                     #name::#variant_name #visit
                 });
@@ -77,16 +77,70 @@ pub(crate) fn accept_visitor(input: TokenStream, with_foreach: bool) -> TokenStr
     output.into()
 }
 
+pub(crate) fn accept_visitor_mut(input: TokenStream) -> TokenStream {
+    // Parse the input.
+    let input = syn::parse_macro_input!(input as DeriveInput);
+
+    let name = &input.ident;
+    let generics = &input.generics;
+
+    // Generate the match that visits the relevant fields of the input.
+    let mut visits = vec![];
+    let dont_visit = has_dont_visit(&input.attrs);
+    match &input.data {
+        syn::Data::Struct(input) => {
+            let (_, _, visit) = visit_fields(&input.fields, dont_visit);
+            visits.push(quote! {
+                #name #visit
+            });
+        }
+        syn::Data::Enum(input) => {
+            for variant in &input.variants {
+                let dont_visit = dont_visit || has_dont_visit(&variant.attrs);
+                let variant_name = &variant.ident;
+                let (_, _, visit) = visit_fields(&variant.fields, dont_visit);
+                visits.push(quote! {
+                    #name::#variant_name #visit
+                });
+            }
+        }
+        _ => panic!("unsupported item for AcceptVisitorMut"),
+    };
+
+    // Determine the impl generics, which may add another lifetime.
+    let mut impl_generics = generics.clone();
+    let lt = crate::first_lifetime(&mut impl_generics);
+
+    // Generate the implementation of the `AcceptVisitorMut` trait.
+    let output = quote! {
+        impl #impl_generics AcceptVisitorMut<#lt> for #name #generics {
+            fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<#lt>) {
+                match self {
+                    #(#visits,)*
+                }
+            }
+        }
+    };
+    output.into()
+}
+
 /// Check if a field has the `#[dont_visit]` attribute.
 fn has_dont_visit(attrs: &[syn::Attribute]) -> bool {
     attrs.iter().any(|attr| attr.path.is_ident("dont_visit"))
 }
 
 /// Generate the code to visit fields in a struct-like item.
+///
+/// Returns the match arms that walk a `Visitor`, call a function for each
+/// node, and walk a `VisitorMut` over the fields, respectively.
 fn visit_fields(
     fields: &syn::Fields,
     dont_visit: bool,
-) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+) -> (
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+    proc_macro2::TokenStream,
+) {
     // Generate a destructuring pattern that assigns predictable names to all
     // fields.
     let mut names = vec![];
@@ -133,5 +187,10 @@ fn visit_fields(
                 #(#names.for_each_node(each);)*
             }
         },
+        quote! {
+            #pat => {
+                #(#names.walk_mut(visitor);)*
+            }
+        },
     )
 }
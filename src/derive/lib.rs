@@ -12,6 +12,7 @@ mod arena;
 mod node;
 mod node_data;
 mod query;
+mod spanless_eq;
 mod visitor;
 mod walk_visitor;
 
@@ -44,6 +45,18 @@ pub fn accept_visitor_and_foreach(input: TokenStream) -> TokenStream {
     accept_visitor::accept_visitor(input, true)
 }
 
+/// Generate an `AcceptVisitorMut` implementation.
+#[proc_macro_derive(AcceptVisitorMut, attributes(dont_visit))]
+pub fn accept_visitor_mut(input: TokenStream) -> TokenStream {
+    accept_visitor::accept_visitor_mut(input)
+}
+
+/// Generate a `SpanlessEq` implementation.
+#[proc_macro_derive(SpanlessEq)]
+pub fn spanless_eq(input: TokenStream) -> TokenStream {
+    spanless_eq::spanless_eq(input)
+}
+
 /// Wrap a struct or enum in a `Node`.
 #[proc_macro_attribute]
 pub fn node(args: TokenStream, input: TokenStream) -> TokenStream {
@@ -62,13 +75,25 @@ pub fn walk_visitor(args: TokenStream, input: TokenStream) -> TokenStream {
     walk_visitor::walk_visitor(args, input)
 }
 
-/// Convenience macro to derive `AcceptVisitorAndForeach` and `walk_visitor`.
+/// Generate corresponding `*_visit_*` functions in a mutable visitor.
+#[proc_macro_attribute]
+pub fn walk_visitor_mut(_args: TokenStream, input: TokenStream) -> TokenStream {
+    walk_visitor::walk_visitor_mut(input)
+}
+
+/// Convenience macro to derive `AcceptVisitorAndForeach`, `AcceptVisitorMut`,
+/// `SpanlessEq`, `walk_visitor`, and `walk_visitor_mut`.
 #[proc_macro_attribute]
 pub fn visit(_args: TokenStream, input: TokenStream) -> TokenStream {
     let input = proc_macro2::TokenStream::from(input);
     TokenStream::from(quote! {
         #[moore_derive::walk_visitor]
-        #[derive(moore_derive::AcceptVisitorAndForeach)]
+        #[moore_derive::walk_visitor_mut]
+        #[derive(
+            moore_derive::AcceptVisitorAndForeach,
+            moore_derive::AcceptVisitorMut,
+            moore_derive::SpanlessEq
+        )]
         #input
     })
 }
@@ -96,7 +121,8 @@ pub fn all_node(args: TokenStream, input: TokenStream) -> TokenStream {
     all_node::mark_all_node(args, input)
 }
 
-/// Generate a `Visitor` trait.
+/// Generate a `Visitor` trait, and a `VisitorMut` trait if any nodes accept
+/// one.
 #[proc_macro]
 pub fn derive_visitor(input: TokenStream) -> TokenStream {
     visitor::visitor(input)
@@ -0,0 +1,84 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::DeriveInput;
+
+pub(crate) fn spanless_eq(input: TokenStream) -> TokenStream {
+    // Parse the input.
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Generate one match arm per struct or enum variant, comparing all fields
+    // of `self` against the corresponding fields of `other`.
+    let mut arms = vec![];
+    match &input.data {
+        syn::Data::Struct(input) => {
+            arms.push(compare_fields(quote! { #name }, &input.fields));
+        }
+        syn::Data::Enum(input) => {
+            for variant in &input.variants {
+                let variant_name = &variant.ident;
+                arms.push(compare_fields(
+                    quote! { #name::#variant_name },
+                    &variant.fields,
+                ));
+            }
+        }
+        _ => panic!("unsupported item for SpanlessEq"),
+    }
+
+    let output = quote! {
+        impl #impl_generics SpanlessEq for #name #ty_generics #where_clause {
+            fn spanless_eq(&self, other: &Self) -> bool {
+                #[allow(unreachable_patterns)]
+                match (self, other) {
+                    #(#arms,)*
+                    _ => false,
+                }
+            }
+        }
+    };
+    output.into()
+}
+
+/// Generate a match arm that destructures two instances of a struct-like item
+/// and compares their fields pairwise.
+fn compare_fields(
+    path: proc_macro2::TokenStream,
+    fields: &syn::Fields,
+) -> proc_macro2::TokenStream {
+    let mut lhs = vec![];
+    let mut rhs = vec![];
+    let (lhs_pat, rhs_pat) = match fields {
+        syn::Fields::Named(ref fields) => {
+            let mut lhs_mapping = vec![];
+            let mut rhs_mapping = vec![];
+            for (i, field) in fields.named.iter().enumerate() {
+                let field_name = &field.ident;
+                let a = format_ident!("a{}", i);
+                let b = format_ident!("b{}", i);
+                lhs_mapping.push(quote! { #field_name: #a });
+                rhs_mapping.push(quote! { #field_name: #b });
+                lhs.push(a);
+                rhs.push(b);
+            }
+            (
+                quote! { {#(#lhs_mapping),*} },
+                quote! { {#(#rhs_mapping),*} },
+            )
+        }
+        syn::Fields::Unnamed(ref fields) => {
+            for i in 0..fields.unnamed.len() {
+                lhs.push(format_ident!("a{}", i));
+                rhs.push(format_ident!("b{}", i));
+            }
+            (quote! { (#(#lhs),*) }, quote! { (#(#rhs),*) })
+        }
+        syn::Fields::Unit => (quote! {}, quote! {}),
+    };
+    quote! {
+        (#path #lhs_pat, #path #rhs_pat) => true #(&& #lhs.spanless_eq(#rhs))*
+    }
+}
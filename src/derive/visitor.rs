@@ -12,6 +12,7 @@ use syn::Generics;
 // minute. Better have a robust CI.
 thread_local! {
     static CALLS: RefCell<Vec<Call>> = Default::default();
+    static MUT_CALLS: RefCell<Vec<Call>> = Default::default();
 }
 
 struct Call {
@@ -29,6 +30,14 @@ pub(crate) fn add_call(name: &Ident, generics: &Generics) {
     CALLS.with(|c| c.borrow_mut().push(call));
 }
 
+pub(crate) fn add_mut_call(name: &Ident, generics: &Generics) {
+    let call = Call {
+        name: name.to_string(),
+        generics: generics.to_token_stream().to_string(),
+    };
+    MUT_CALLS.with(|c| c.borrow_mut().push(call));
+}
+
 pub(crate) fn visitor(input: TokenStream) -> TokenStream {
     let input = proc_macro2::TokenStream::from(input);
 
@@ -96,7 +105,7 @@ pub(crate) fn visitor(input: TokenStream) -> TokenStream {
         });
     }
 
-    let output = quote! {
+    let mut output = quote! {
         #[doc = #doc]
         pub trait Visitor<#lt> {
             #input
@@ -104,5 +113,67 @@ pub(crate) fn visitor(input: TokenStream) -> TokenStream {
             #(#post_calls)*
         }
     };
+
+    // Generate the mutable visitor if any nodes accept one.
+    let mut_calls = MUT_CALLS.with(|c| std::mem::replace(&mut *c.borrow_mut(), Default::default()));
+    if !mut_calls.is_empty() {
+        output.extend(visitor_mut(&lt, mut_calls));
+    }
     output.into()
 }
+
+/// Generate a `VisitorMut` trait with `pre_visit_*` and `post_visit_*`
+/// functions for the given nodes.
+fn visitor_mut(lt: &syn::Lifetime, calls: Vec<Call>) -> proc_macro2::TokenStream {
+    let mut doc = format!(
+        "A visitor that may modify the nodes it visits.\n\nUse the `walk_mut()` function \
+         to start visiting nodes. Nodes that are referred to through a shared reference, \
+         such as the ones allocated in an arena, cannot be modified and are not visited.\n\n"
+    );
+    doc.push_str("Implements the visitor pattern over the following nodes:\n\n");
+    for call in &calls {
+        doc.push_str(&format!("- `{}`\n", call.name));
+    }
+
+    let mut emitted = HashSet::new();
+    let mut pre_calls = vec![];
+    let mut post_calls = vec![];
+    for call in calls {
+        if !emitted.insert(call.name.clone()) {
+            continue;
+        }
+        let pre_visit_fn = format_ident!("pre_visit_{}", call.name.to_snake_case());
+        let post_visit_fn = format_ident!("post_visit_{}", call.name.to_snake_case());
+        let name = format_ident!("{}", call.name);
+        let generics: syn::Generics = syn::parse_str(&call.generics).unwrap();
+
+        let pre_doc = format!(
+            "Called for every `{}` node before visiting its children.\n\nReturn `false` from this \
+             function to not visit the node's children.",
+            name
+        );
+        let post_doc = format!(
+            "Called for every `{}` node after visiting its children.",
+            name
+        );
+        pre_calls.push(quote! {
+            #[doc = #pre_doc]
+            fn #pre_visit_fn (&mut self, node: &mut #name #generics) -> bool {
+                true
+            }
+        });
+        post_calls.push(quote! {
+            #[doc = #post_doc]
+            fn #post_visit_fn (&mut self, node: &mut #name #generics) {
+            }
+        });
+    }
+
+    quote! {
+        #[doc = #doc]
+        pub trait VisitorMut<#lt> {
+            #(#pre_calls)*
+            #(#post_calls)*
+        }
+    }
+}
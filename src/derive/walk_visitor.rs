@@ -89,3 +89,60 @@ pub(crate) fn walk_visitor(args: TokenStream, raw_input: TokenStream) -> TokenSt
     };
     output.into()
 }
+
+pub(crate) fn walk_visitor_mut(raw_input: TokenStream) -> TokenStream {
+    // Parse the input.
+    let input = syn::parse_macro_input!(raw_input as Item);
+    let (name, generics) = match &input {
+        Item::Enum(item) => (&item.ident, &item.generics),
+        Item::Struct(item) => (&item.ident, &item.generics),
+        Item::Type(item) => (&item.ident, &item.generics),
+        _ => panic!("unsupported item to derive WalkVisitorMut for"),
+    };
+
+    // Determine the name of the visit functions corresponding to us.
+    let pre_visit_fn = format_ident!(
+        "pre_visit_{}",
+        name.to_string().to_snake_case(),
+        span = name.span()
+    );
+    let post_visit_fn = format_ident!(
+        "post_visit_{}",
+        name.to_string().to_snake_case(),
+        span = name.span()
+    );
+    crate::visitor::add_mut_call(&name, &generics);
+
+    // Determine the impl generics, which may add another lifetime.
+    let mut impl_generics = generics.clone();
+    let lt = crate::first_lifetime(&mut impl_generics);
+
+    // Generate some documentation.
+    let doc = format!(
+        r#"
+        Walk a mutable visitor over `self`.
+
+        Calling this function is equivalent to calling:
+        - `visitor.{}(self)`
+        - `self.accept_mut(visitor)`
+        - `visitor.{}(self);`
+        "#,
+        pre_visit_fn, post_visit_fn,
+    );
+
+    // Generate the implementation of the `WalkVisitorMut` trait.
+    let output = quote! {
+        #input
+
+        impl #impl_generics WalkVisitorMut<#lt> for #name #generics {
+            #[doc = #doc]
+            fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<#lt>) {
+                if visitor.#pre_visit_fn(self) {
+                    self.accept_mut(visitor);
+                }
+                visitor.#post_visit_fn(self);
+            }
+        }
+    };
+    output.into()
+}
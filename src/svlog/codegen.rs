@@ -4,6 +4,7 @@
 #![allow(unreachable_code)]
 
 use crate::{
+    common::DEFAULT_MAX_GENVAR_ITERATIONS,
     crate_prelude::*,
     hir::{AccessedNode, HirNode},
     port_list::PortList,
@@ -248,6 +249,7 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
                                 }
                             }
                         }
+                        ast::ModportPortData::Tf { .. } => (),
                     }
                 }
                 trace!("    Modport-derived directions: {:?}", dirs);
@@ -703,6 +705,51 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
         }
     }
 
+    /// Determine the genvar assigned by the initialization step of a generate
+    /// loop.
+    fn genvar_of_init(&self, id: NodeId, env: ParamEnv) -> Option<NodeId> {
+        match self.hir_of(id).ok()? {
+            HirNode::GenvarDecl(decl) => Some(decl.id),
+            HirNode::Stmt(stmt) => match stmt.kind {
+                hir::StmtKind::Assign { lhs, .. } => self.resolve_node(lhs, env).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Report a generate loop which exceeded the maximum number of iterations.
+    fn emit_genvar_overflow(
+        &self,
+        hir: &hir::Gen,
+        genvar: Option<NodeId>,
+        step: NodeId,
+        iterations: usize,
+        recent: &[Value<'gcx>],
+    ) {
+        let genvar = genvar.and_then(|id| self.hir_of(id).ok());
+        let mut diag = DiagBuilder2::error(format!(
+            "generate loop did not terminate after {} iterations",
+            iterations
+        ));
+        diag = match genvar {
+            Some(genvar) => diag.span(genvar.human_span()).add_note(format!(
+                "Last values of {} were {}",
+                genvar.desc_full(),
+                recent
+                    .iter()
+                    .map(|v| format!("{}", v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            None => diag.span(hir.span()),
+        };
+        self.emit(
+            diag.add_note("The loop step does not make progress towards the loop condition:")
+                .span(self.span(step)),
+        );
+    }
+
     /// Execute the iteration step of a generate loop.
     fn execute_genvar_step(&mut self, id: NodeId, env: ParamEnv) -> Result<ParamEnv> {
         let hir = self.hir_of(id)?;
@@ -733,7 +780,7 @@ impl<'a, 'gcx, C: Context<'gcx>> CodeGenerator<'gcx, &'a C> {
                                 mir::LvalueKind::Genvar(id) => id,
                                 _ => unreachable!(),
                             };
-                            let next_value = self.const_mir_rvalue(Ref(rvalue));
+                            let next_value = self.const_mir_rvalue(Ref(rvalue))?;
                             Some((target_id, next_value))
                         }
                         _ => unreachable!(),
@@ -1213,7 +1260,28 @@ where
                     for &i in init {
                         local_env = self.execute_genvar_init(i, local_env)?;
                     }
+                    let genvar = init.first().and_then(|&i| self.genvar_of_init(i, env));
+                    let max_iterations = self
+                        .sess()
+                        .opts
+                        .max_genvar_iterations
+                        .unwrap_or(DEFAULT_MAX_GENVAR_ITERATIONS);
+                    let mut iterations = 0;
+                    let mut recent = vec![];
                     while self.constant_value_of(cond, local_env).is_true() {
+                        // Keep track of the last few values of the genvar, and
+                        // abort if the loop does not terminate.
+                        if let Some(genvar) = genvar {
+                            if recent.len() == 4 {
+                                recent.remove(0);
+                            }
+                            recent.push(self.constant_value_of(genvar, local_env));
+                        }
+                        if iterations == max_iterations {
+                            self.emit_genvar_overflow(hir, genvar, step, iterations, &recent);
+                            return Err(());
+                        }
+                        iterations += 1;
                         self.emit_module_block(id, local_env, body, name_prefix)?;
                         local_env = self.execute_genvar_step(step, local_env)?;
                     }
@@ -1666,7 +1734,7 @@ where
 
         // If the value is a constant, emit the fully folded constant value.
         if mir.is_const() {
-            let value = self.const_mir_rvalue(mir.into());
+            let value = self.const_mir_rvalue(mir.into())?;
             return self
                 .emit_const(value, mir.env, mir.span)
                 .map(|v| (v, Mode::Value));
@@ -1768,6 +1836,10 @@ where
             }
 
             mir::RvalueKind::Const(k) => self.emit_const(k, mir.env, mir.span)?,
+            mir::RvalueKind::Fill(bit) => {
+                let k = self.intern_value(value::make_fill(mir.ty, bit));
+                self.emit_const(k, mir.env, mir.span)?
+            }
 
             mir::RvalueKind::Index {
                 value,
@@ -1784,6 +1856,21 @@ where
                 value
             }
 
+            mir::RvalueKind::UpdateMember {
+                value,
+                field: offset,
+                new_value,
+            }
+            | mir::RvalueKind::UpdateIndex {
+                value,
+                index: offset,
+                new_value,
+            } => {
+                let target = self.emit_mir_rvalue(value)?;
+                let new_value = self.emit_mir_rvalue(new_value)?;
+                self.mk_ins_field(target, new_value, offset)
+            }
+
             mir::RvalueKind::UnaryBitwise { op, arg } => {
                 let arg = self.emit_mir_rvalue(arg)?;
                 match op {
@@ -1909,7 +1996,11 @@ where
                 );
                 let mut result = self.emit_zero_for_type_both(llty);
                 for value in values.iter().rev() {
+                    // Skip zero-width values such as zero replications.
                     let width = value.ty.simple_bit_vector(self.cx, value.span).size;
+                    if width == 0 {
+                        continue;
+                    }
                     let llval = self.emit_mir_rvalue(value)?;
                     trace!(
                         " - Value has width {}, type `{}`, in LLHD `{}`",
@@ -1917,15 +2008,64 @@ where
                         value.ty,
                         self.llhd_type(llval.0)
                     );
-                    if width > 0 {
-                        result = self.mk_ins_slice(result, llval, offset, width);
-                        offset += width;
-                    }
+                    result = self.mk_ins_slice(result, llval, offset, width);
+                    offset += width;
                 }
                 self.builder.set_name(result.0, "concat".to_string());
                 result
             }
 
+            mir::RvalueKind::StreamConcat {
+                dir,
+                slice,
+                ref values,
+            } => {
+                // Concatenate the values into a stream of bits.
+                let width: usize = values
+                    .iter()
+                    .map(|v| v.ty.simple_bit_vector(self.cx, v.span).size)
+                    .sum();
+                let domain = mir.ty.simple_bit_vector(self.cx, mir.span).domain;
+                let stream_ty = ty::SbvType::new(domain, ty::Sign::Unsigned, width);
+                let stream_llty = self.emit_type_both(stream_ty.to_unpacked(self.cx))?;
+                let mut stream = self.emit_zero_for_type_both(stream_llty.clone());
+                let mut offset = 0;
+                for value in values.iter().rev() {
+                    let value_width = value.ty.simple_bit_vector(self.cx, value.span).size;
+                    let llval = self.emit_mir_rvalue(value)?;
+                    if value_width > 0 {
+                        stream = self.mk_ins_slice(stream, llval, offset, value_width);
+                        offset += value_width;
+                    }
+                }
+
+                // Reverse the order of the slices for right-to-left streams.
+                if dir == ast::StreamDir::Out {
+                    let mut reversed = self.emit_zero_for_type_both(stream_llty);
+                    let mut offset = 0;
+                    while offset < width {
+                        let chunk = std::cmp::min(slice, width - offset);
+                        let llval = self.mk_ext_slice(stream, offset, chunk);
+                        reversed =
+                            self.mk_ins_slice(reversed, llval, width - offset - chunk, chunk);
+                        offset += chunk;
+                    }
+                    stream = reversed;
+                }
+
+                // Left-align the stream in the result.
+                let result_width = mir.ty.simple_bit_vector(self.cx, mir.span).size;
+                let result = if result_width > width {
+                    let llty = self.emit_type_both(mir.ty)?;
+                    let zero = self.emit_zero_for_type_both(llty);
+                    self.mk_ins_slice(zero, stream, result_width - width, width)
+                } else {
+                    self.mk_ext_slice(stream, width - result_width, result_width)
+                };
+                self.builder.set_name(result.0, "stream".to_string());
+                result
+            }
+
             mir::RvalueKind::Repeat(times, value) => {
                 let width = value.ty.simple_bit_vector(self.cx, value.span).size;
                 let value = self.emit_mir_rvalue(value)?;
@@ -1961,6 +2101,42 @@ where
                 }
             }
 
+            mir::RvalueKind::Inside {
+                value,
+                ref members,
+                sign,
+            } => {
+                let value = self.emit_mir_rvalue(value)?;
+                let signed = sign.is_signed();
+                let mut result = self.mk_const_int(1, &BigInt::zero());
+                for member in members {
+                    let check = match *member {
+                        mir::InsideMember::Single(single) => {
+                            let single = self.emit_mir_rvalue(single)?;
+                            self.mk_cmp(CmpPred::Eq, value, single)
+                        }
+                        mir::InsideMember::Range(lo, hi) => {
+                            let mut check = self.mk_const_int(1, &BigInt::one());
+                            if let Some(lo) = lo {
+                                let lo = self.emit_mir_rvalue(lo)?;
+                                let pred = if signed { CmpPred::Sge } else { CmpPred::Uge };
+                                let lo_check = self.mk_cmp(pred, value, lo);
+                                check = self.mk_and(check, lo_check);
+                            }
+                            if let Some(hi) = hi {
+                                let hi = self.emit_mir_rvalue(hi)?;
+                                let pred = if signed { CmpPred::Sle } else { CmpPred::Ule };
+                                let hi_check = self.mk_cmp(pred, value, hi);
+                                check = self.mk_and(check, hi_check);
+                            }
+                            check
+                        }
+                    };
+                    result = self.mk_or(result, check);
+                }
+                result
+            }
+
             mir::RvalueKind::Ternary {
                 cond,
                 true_value,
@@ -2681,19 +2857,21 @@ where
                     for &way_expr in way_exprs {
                         // Determine the constant value of the label.
                         let way_const = self.constant_value_of(way_expr, env);
-                        let (_, special_bits, x_bits) = match &way_const.kind {
-                            ValueKind::Int(v, s, x) => (v, s, x),
+                        let xz = match &way_const.kind {
+                            ValueKind::Int(_, xz) => xz,
                             _ => panic!("case constant evaluates to non-integer"),
                         };
                         let way_expr = self.emit_const(way_const, env, self.span(way_expr))?;
                         let way_width = self.llhd_type(way_expr.0).unwrap_int();
+                        let special_bits = xz.special_bits(way_width);
+                        let x_bits = xz.x_bits(way_width);
 
                         // Generate the comparison mask based on the case kind.
                         let mask = match kind {
                             ast::CaseKind::Normal => None,
                             ast::CaseKind::DontCareZ => {
                                 let mut mask = special_bits.clone();
-                                mask.difference(x_bits);
+                                mask.difference(&x_bits);
                                 mask.negate();
                                 Some(mask)
                             }
@@ -2705,7 +2883,7 @@ where
                         };
                         let mask = mask.map(|bits| {
                             let mut mask = BigInt::zero();
-                            for b in &bits {
+                            for b in bits.iter().rev() {
                                 mask <<= 1;
                                 if b {
                                     mask |= BigInt::one();
@@ -3275,7 +3453,6 @@ where
         (self.builder.ins().ext_field(arg.0, offset), mlir)
     }
 
-    #[allow(dead_code)]
     fn mk_ins_field(
         &mut self,
         into: HybridValue,
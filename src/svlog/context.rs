@@ -29,11 +29,12 @@ use crate::{
     hir::{self, HirNode},
     port_list::PortList,
     resolver::Scope,
-    value::{Value, ValueData, ValueKind},
+    value::{ConstEvalError, ConstStats, ConstTrace, Value, ValueData, ValueKind},
     ParamEnv, ParamEnvData, QueryDatabase, QueryStorage,
 };
+use num::{BigInt, ToPrimitive};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{BTreeSet, HashMap, HashSet},
 };
 
@@ -65,6 +66,8 @@ pub struct GlobalContext<'gcx> {
     node_id_to_span: RefCell<HashMap<NodeId, Span>>,
     /// The tables.
     tables: GlobalTables<'gcx>,
+    /// The sink for constant evaluation results.
+    const_tracer: Cell<Option<&'gcx dyn ConstTrace<'gcx>>>,
 }
 
 impl<'gcx> GlobalContext<'gcx> {
@@ -83,9 +86,69 @@ impl<'gcx> GlobalContext<'gcx> {
             imports: Default::default(),
             node_id_to_span: Default::default(),
             tables: Default::default(),
+            const_tracer: Default::default(),
         }
     }
 
+    /// Install a tracer that observes all constant evaluations.
+    ///
+    /// This replaces the default behaviour of printing the results to stderr
+    /// when `Verbosity::CONSTS` is set.
+    pub fn set_const_tracer(&self, tracer: &'gcx dyn ConstTrace<'gcx>) {
+        self.const_tracer.set(Some(tracer));
+    }
+
+    /// Get the statistics about constant evaluation collected so far.
+    ///
+    /// ```
+    /// # use moore_common::{source::get_source_manager, Session};
+    /// # use moore_svlog::{ast, lexer::Lexer, parser, preproc::Preprocessor};
+    /// # use moore_svlog::{Context, GlobalArenas, GlobalContext};
+    /// let sess = Session::new();
+    /// let arena = GlobalArenas::default();
+    /// let source = get_source_manager().add(
+    ///     "const_stats.sv",
+    ///     "module foo; localparam int a = 2 + 3; endmodule",
+    /// );
+    /// let preproc = Preprocessor::new(source, &[], &[]);
+    /// let file = parser::parse(Lexer::new(preproc), &arena.ast).unwrap();
+    /// let gcx = GlobalContext::new(&sess, &arena);
+    /// gcx.add_files(std::iter::once(&file));
+    /// # let module = match file.items[0].data {
+    /// #     ast::ItemData::ModuleDecl(ref m) => m,
+    /// #     _ => unreachable!(),
+    /// # };
+    /// # let expr = match module.items[0].data {
+    /// #     ast::ItemData::ParamDecl(ref p) => match p.kind {
+    /// #         ast::ParamKind::Value(ref decls) => ast::AnyNode::id(decls[0].expr.as_ref().unwrap()),
+    /// #         _ => unreachable!(),
+    /// #     },
+    /// #     _ => unreachable!(),
+    /// # };
+    /// let env = gcx.default_param_env();
+    ///
+    /// // The first evaluation computes `2 + 3` and its operands.
+    /// gcx.constant_value_of(expr, env);
+    /// let first = gcx.const_stats();
+    /// assert_eq!(first.constant_value_of, 1);
+    /// assert_eq!(first.cache_hits, 0);
+    /// assert_eq!(first.const_node, 1);
+    /// assert_eq!(first.const_mir_rvalue, 3);
+    /// assert_eq!(first.tombstones, 0);
+    /// assert_eq!(first.top_modules(10), vec![("foo".into(), 1)]);
+    ///
+    /// // The second evaluation is answered from the cache.
+    /// gcx.constant_value_of(expr, env);
+    /// let second = gcx.const_stats();
+    /// assert_eq!(second.constant_value_of, 2);
+    /// assert_eq!(second.cache_hits, 1);
+    /// assert_eq!(second.const_node, 1);
+    /// assert_eq!(second.const_mir_rvalue, 3);
+    /// ```
+    pub fn const_stats(&self) -> ConstStats {
+        self.tables.const_stats.borrow().clone()
+    }
+
     /// Add an AST root to the context for processing.
     ///
     /// Use the `find_global_item` function afterwards to look up the id of
@@ -115,6 +178,9 @@ impl<'gcx> GlobalContext<'gcx> {
         debug!("Checking names");
         self.nameck(root);
 
+        // Check module definitions against their extern declarations.
+        self.check_extern_modules(root);
+
         // Keep track of some names for now.
         for file in &root.files {
             for item in &file.items {
@@ -145,6 +211,27 @@ impl<'gcx> GlobalContext<'gcx> {
         debug!("{:?} added", root);
     }
 
+    /// Check every module definition against the extern module declaration
+    /// of the same name, if there is one.
+    fn check_extern_modules(&self, root: &'gcx ast::Root<'gcx>) {
+        let items = || root.files.iter().flat_map(|file| file.items.iter());
+        let mut externs = HashMap::new();
+        for item in items() {
+            if let ast::ItemData::ExternModuleDecl(ref n) = item.data {
+                externs.insert(n.name.value, n);
+            }
+        }
+        for item in items() {
+            if let ast::ItemData::ModuleDecl(ref n) = item.data {
+                if let Some(ext) = externs.get(&n.name.value) {
+                    for diag in syntax::extern_check::check_extern_module(ext, n) {
+                        self.emit(diag);
+                    }
+                }
+            }
+        }
+    }
+
     /// Add an AST root with a series of source files to the context for
     /// processing.
     pub fn add_files(&self, files: impl Iterator<Item = &'gcx ast::SourceFile<'gcx>>) {
@@ -364,6 +451,9 @@ where
     }
 }
 
+/// The number of small integers cached per type by `Context::intern_int`.
+const SMALL_INT_CACHE_SIZE: usize = 257;
+
 /// The lookup tables for a global context.
 ///
 /// Use this struct whenever you need to keep track of some mapping.
@@ -374,6 +464,9 @@ pub struct GlobalTables<'t> {
     param_env_contexts: RefCell<HashMap<ParamEnv, BTreeSet<NodeId>>>,
     node_id_to_parent_node_id: RefCell<HashMap<NodeId, NodeId>>,
     interned_values: RefCell<HashSet<Value<'t>>>,
+    small_int_values: RefCell<HashMap<*const ty::UnpackedType<'t>, Vec<Option<Value<'t>>>>>,
+    pub(crate) const_eval_stack: RefCell<Vec<(NodeId, ParamEnv)>>,
+    pub(crate) const_stats: RefCell<ConstStats>,
     lowering_hints: RefCell<HashMap<NodeId, hir::Hint>>,
     interned_hir: RefCell<HashMap<NodeId, HirNode<'t>>>,
 }
@@ -397,6 +490,11 @@ pub trait Context<'gcx>: DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<
         self.gcx().arena
     }
 
+    /// Get the tracer installed with `GlobalContext::set_const_tracer`, if any.
+    fn const_tracer(&self) -> Option<&'gcx dyn ConstTrace<'gcx>> {
+        self.gcx().const_tracer.get()
+    }
+
     /// Access the tables.
     fn tables(&self) -> &GlobalTables<'gcx> {
         &self.gcx().tables
@@ -558,9 +656,52 @@ pub trait Context<'gcx>: DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<
         }
         let value = self.arena().values.alloc(value);
         self.tables().interned_values.borrow_mut().insert(value);
+        self.tables().const_stats.borrow_mut().interned_values += 1;
         value
     }
 
+    /// Internalize an integer value.
+    ///
+    /// Small non-negative integers are looked up in a cache keyed by the type
+    /// and the value, which avoids building and hashing a full `ValueData` for
+    /// the loop indices, widths, and comparison results that make up the bulk
+    /// of the values created during elaboration.
+    ///
+    /// ```
+    /// # use moore_common::Session;
+    /// # use moore_svlog::{ty::UnpackedType, Context, GlobalArenas, GlobalContext};
+    /// # let sess = Session::new();
+    /// # let arena = GlobalArenas::default();
+    /// # let gcx = GlobalContext::new(&sess, &arena);
+    /// let ty = UnpackedType::make_logic();
+    /// let a = gcx.intern_int(ty, 1.into());
+    /// let b = gcx.intern_int(ty, 1.into());
+    /// assert!(std::ptr::eq(a, b));
+    /// ```
+    fn intern_int(&self, ty: &'gcx ty::UnpackedType<'gcx>, value: BigInt) -> Value<'gcx> {
+        let index = match value.to_usize() {
+            Some(index) if index < SMALL_INT_CACHE_SIZE => index,
+            _ => return self.intern_value(value::make_int(ty, value)),
+        };
+        let key = ty as *const _;
+        if let Some(&Some(x)) = self
+            .tables()
+            .small_int_values
+            .borrow()
+            .get(&key)
+            .and_then(|cache| cache.get(index))
+        {
+            return x;
+        }
+        let x = self.intern_value(value::make_int(ty, value));
+        self.tables()
+            .small_int_values
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| vec![None; SMALL_INT_CACHE_SIZE])[index] = Some(x);
+        x
+    }
+
     /// Internalize a parameter environment.
     fn intern_param_env(&self, env: ParamEnvData<'gcx>) -> ParamEnv {
         if let Some(&x) = self.tables().interned_param_envs.borrow().get(&env) {
@@ -653,6 +794,20 @@ pub trait Context<'gcx>: DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<
         }
     }
 
+    /// Find the module that encloses a node.
+    ///
+    /// Returns `None` if the node is not nested within a module, for example
+    /// because it is part of a package.
+    fn enclosing_module(&self, node_id: NodeId) -> Option<&'gcx ast::Module<'gcx>> {
+        let mut id = node_id;
+        loop {
+            if let Some(AstNode::Module(module)) = self.gcx().ast_map.get(id) {
+                return Some(module);
+            }
+            id = self.parent_node_id(id)?;
+        }
+    }
+
     /// Resolve a name upwards or emit a diagnostic if nothing is found.
     fn resolve_upwards_or_error(&self, name: Spanned<Name>, start_at: NodeId) -> Result<NodeId> {
         match self.gcx().resolve_upwards(name.value, start_at)? {
@@ -697,9 +852,159 @@ pub trait Context<'gcx>: DiagEmitter + QueryDatabase<'gcx> + ty::HasTypeStorage<
         self.tables().lowering_hints.borrow().get(&node_id).cloned()
     }
 
+    /// Compute the constant value of a node.
+    ///
+    /// Results are memoized per node and parameter environment by the
+    /// `const_node` query. If the node has no constant value, a tombstone is
+    /// returned; use `try_constant_value_of` to learn why. If the node depends
+    /// on its own value, a circular dependency error is emitted, which then
+    /// propagates to all constants that participate in the cycle.
+    ///
+    /// ```
+    /// # use moore_common::{source::get_source_manager, Session};
+    /// # use moore_svlog::{ast, lexer::Lexer, parser, preproc::Preprocessor};
+    /// # use moore_svlog::{Context, GlobalArenas, GlobalContext};
+    /// # use moore_svlog::value::{ConstTrace, ConstTraceKind, ConstTraceRecord};
+    /// # use std::cell::RefCell;
+    /// // Count how often `2 + 3` is evaluated.
+    /// struct Counter(RefCell<usize>);
+    ///
+    /// impl<'a> ConstTrace<'a> for Counter {
+    ///     fn trace(&self, record: &ConstTraceRecord<'a>) {
+    ///         if record.kind == ConstTraceKind::Node && record.input_text == "2 + 3" {
+    ///             *self.0.borrow_mut() += 1;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let sess = Session::new();
+    /// let arena = GlobalArenas::default();
+    /// let counter = Counter(Default::default());
+    /// let source = get_source_manager().add(
+    ///     "const_memo.sv",
+    ///     "module foo;
+    ///         localparam int s = 2 + 3;
+    ///         localparam int a = s + 1;
+    ///         localparam int b = s * 2;
+    ///     endmodule",
+    /// );
+    /// let preproc = Preprocessor::new(source, &[], &[]);
+    /// let file = parser::parse(Lexer::new(preproc), &arena.ast).unwrap();
+    /// let gcx = GlobalContext::new(&sess, &arena);
+    /// gcx.set_const_tracer(&counter);
+    /// gcx.add_files(std::iter::once(&file));
+    /// # let module = match file.items[0].data {
+    /// #     ast::ItemData::ModuleDecl(ref m) => m,
+    /// #     _ => unreachable!(),
+    /// # };
+    /// # let param = |index: usize| match module.items[index].data {
+    /// #     ast::ItemData::ParamDecl(ref p) => match p.kind {
+    /// #         ast::ParamKind::Value(ref decls) => ast::AnyNode::id(decls[0].expr.as_ref().unwrap()),
+    /// #         _ => unreachable!(),
+    /// #     },
+    /// #     _ => unreachable!(),
+    /// # };
+    /// let env = gcx.default_param_env();
+    ///
+    /// // Both `s + 1` and `s * 2` depend on `s`, which is only evaluated once.
+    /// assert_eq!(format!("{:x}", gcx.constant_value_of(param(1), env)), "32'sh6");
+    /// assert_eq!(format!("{:x}", gcx.constant_value_of(param(2), env)), "32'sha");
+    /// assert_eq!(*counter.0.borrow(), 1);
+    /// ```
+    fn constant_value_of(&self, node_id: NodeId, env: ParamEnv) -> Value<'gcx> {
+        match self.try_constant_value_of(node_id, env) {
+            Ok(value) => value,
+            Err(_) => self.intern_value(value::make_error(ty::UnpackedType::make_error())),
+        }
+    }
+
+    /// Compute the constant value of a node, or the reason why it has none.
+    ///
+    /// This emits the same diagnostics as `constant_value_of`, but reports the
+    /// `ConstEvalError` that caused the evaluation to fail instead of a
+    /// tombstone. This allows callers to tell an unbound parameter apart from
+    /// a reference to a variable, for example.
+    ///
+    /// ```
+    /// # use moore_common::{source::get_source_manager, Session};
+    /// # use moore_svlog::{ast, lexer::Lexer, parser, preproc::Preprocessor};
+    /// # use moore_svlog::{Context, GlobalArenas, GlobalContext};
+    /// use moore_svlog::value::ConstEvalError;
+    ///
+    /// let sess = Session::new();
+    /// let arena = GlobalArenas::default();
+    /// let source = get_source_manager().add(
+    ///     "const_errors.sv",
+    ///     "module foo #(parameter int P);
+    ///         int v;
+    ///         localparam int a = P + 1;
+    ///         localparam int b = v;
+    ///     endmodule",
+    /// );
+    /// let preproc = Preprocessor::new(source, &[], &[]);
+    /// let file = parser::parse(Lexer::new(preproc), &arena.ast).unwrap();
+    /// let gcx = GlobalContext::new(&sess, &arena);
+    /// gcx.add_files(std::iter::once(&file));
+    /// # let module = match file.items[0].data {
+    /// #     ast::ItemData::ModuleDecl(ref m) => m,
+    /// #     _ => unreachable!(),
+    /// # };
+    /// # let param = |index: usize| match module.items[index].data {
+    /// #     ast::ItemData::ParamDecl(ref p) => match p.kind {
+    /// #         ast::ParamKind::Value(ref decls) => ast::AnyNode::id(decls[0].expr.as_ref().unwrap()),
+    /// #         _ => unreachable!(),
+    /// #     },
+    /// #     _ => unreachable!(),
+    /// # };
+    /// let env = gcx.default_param_env();
+    ///
+    /// match gcx.try_constant_value_of(param(1), env) {
+    ///     Err(ConstEvalError::UnboundParameter { env: e, .. }) => assert_eq!(e, env),
+    ///     x => panic!("unexpected {:?}", x),
+    /// }
+    /// match gcx.try_constant_value_of(param(2), env) {
+    ///     Err(ConstEvalError::NotConstant { span }) => assert_eq!(span.extract(), "v"),
+    ///     x => panic!("unexpected {:?}", x),
+    /// }
+    /// ```
+    fn try_constant_value_of(
+        &self,
+        node_id: NodeId,
+        env: ParamEnv,
+    ) -> std::result::Result<Value<'gcx>, ConstEvalError> {
+        let key = (node_id, env);
+        let stack = &self.tables().const_eval_stack;
+        let cycle_start = stack.borrow().iter().position(|&x| x == key);
+        if let Some(start) = cycle_start {
+            let cycle = stack.borrow()[start..].to_vec();
+            value::report_const_cycle(self.gcx(), &cycle);
+            return Err(ConstEvalError::Circular { chain: cycle });
+        }
+        stack.borrow_mut().push(key);
+        let evaluated = self.tables().const_stats.borrow().const_node;
+        let value = self.const_node(node_id, env);
+        stack.borrow_mut().pop();
+        {
+            let mut stats = self.tables().const_stats.borrow_mut();
+            stats.constant_value_of += 1;
+            stats.cache_hits += (stats.const_node == evaluated) as usize;
+        }
+
+        // Dump the MIR of expressions that are not evaluated as part of an
+        // enclosing expression if requested.
+        if self.sess().has_verbosity(Verbosity::MIR_CONSTS) {
+            let is_expr = |id| matches!(self.gcx().hir_of(id), Ok(HirNode::Expr(_)));
+            if is_expr(node_id) && !stack.borrow().iter().any(|&(id, _)| is_expr(id)) {
+                let mir = self.gcx().mir_rvalue(node_id, env);
+                eprint!("{}", mir.dump(self.gcx(), true));
+            }
+        }
+        value
+    }
+
     /// Compute the constant value of a node and make sure it is an integer.
     fn constant_int_value_of(&self, node_id: NodeId, env: ParamEnv) -> Result<&'gcx num::BigInt> {
-        match self.gcx().constant_value_of(node_id, env).kind {
+        match self.constant_value_of(node_id, env).kind {
             ValueKind::Int(ref x, ..) => Ok(x),
             ValueKind::Error => Err(()),
             _ => {
@@ -0,0 +1,123 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Evaluation of constant expressions given as source text.
+//!
+//! This is intended for tools built on top of the compiler, which want to
+//! evaluate an expression in the context of a module without setting up the
+//! queries themselves.
+
+use crate::crate_prelude::*;
+use crate::{
+    ast_map::AstNode,
+    common::{arenas::Alloc, source::get_source_manager},
+    hir::HirNode,
+    syntax::{lexer::Lexer, parser::parse_standalone_expr, preproc::Preprocessor},
+    value::{make_int, Value, ValueData},
+    ParamEnvData,
+};
+
+/// Evaluate a constant expression in the scope of a module.
+///
+/// The expression in `expr_src` is parsed as an anonymous source and resolved
+/// as if it appeared at the end of `module`, such that it may refer to the
+/// module's parameters and localparams. The parameters named in `overrides`
+/// are bound to the given values; all other parameters assume their default.
+///
+/// Errors are reported as regular diagnostics, with spans pointing into the
+/// anonymous source.
+pub fn eval_constant_expr<'a>(
+    cx: &impl Context<'a>,
+    module: NodeId,
+    overrides: &[(Name, ValueData<'a>)],
+    expr_src: &str,
+) -> Result<Value<'a>> {
+    let module = match cx.hir_of(module)? {
+        HirNode::Module(x) => x,
+        x => bug_span!(x.span(), cx, "{} is not a module", x.desc_full()),
+    };
+
+    // Bind the overridden parameters.
+    let mut env_data = ParamEnvData::new_for_module(module.id());
+    let mut bound = vec![];
+    for (name, value) in overrides {
+        let param = module
+            .params
+            .iter()
+            .chain(module.block.params.iter())
+            .flat_map(|&id| match cx.hir_of(id) {
+                Ok(HirNode::ValueParam(p)) if !p.local => Some(p),
+                _ => None,
+            })
+            .find(|p| p.name.value == *name);
+        match param {
+            Some(param) => {
+                env_data.set_value(param.id, cx.intern_value(value.clone()));
+                bound.push((param, value));
+            }
+            None => {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "no parameter `{}` in {}",
+                        name,
+                        module.desc_full()
+                    ))
+                    .span(module.human_span()),
+                );
+                return Err(());
+            }
+        }
+    }
+
+    // Convert the overrides to the type of their parameter, which may itself
+    // depend on the other overrides.
+    let uncast_env = cx.intern_param_env(env_data.clone());
+    for (param, value) in bound {
+        let ty = cx.type_of(param.id, uncast_env)?;
+        let value = cast_override(cx, param, value, ty)?;
+        env_data.set_value(param.id, cx.intern_value(value));
+    }
+    let env = cx.intern_param_env(env_data);
+
+    // Parse the expression and hook it into the module's scope.
+    let source = get_source_manager().add_anonymous(expr_src);
+    let preproc = Preprocessor::new(source, &[], &[]);
+    let expr = parse_standalone_expr(Lexer::new(preproc), &cx.arena().ast)?;
+    let ast = cx.arena().alloc(expr);
+    ast.link_attach(module.ast, usize::max_value());
+    cx.register_ast(ast);
+    cx.map_ast_with_parent(AstNode::Expr(ast), module.id());
+    let hir = cx.hir_of_expr(Ref(ast))?;
+
+    // Evaluate the expression.
+    let value = cx.constant_value_of(hir.id, env);
+    if value.is_error() {
+        Err(())
+    } else {
+        Ok(value)
+    }
+}
+
+/// Convert the value of a parameter override to the type of the parameter.
+/// Integers are sign-extended or truncated to the width of the type.
+fn cast_override<'a>(
+    cx: &impl Context<'a>,
+    param: &hir::ValueParam,
+    value: &ValueData<'a>,
+    ty: &'a ty::UnpackedType<'a>,
+) -> Result<ValueData<'a>> {
+    match (value.get_int_signed(), ty.get_simple_bit_vector()) {
+        (Some(int), Some(_)) => Ok(make_int(ty, int)),
+        _ => {
+            cx.emit(
+                DiagBuilder2::error(format!(
+                    "cannot override {} of type `{}` with `{}`",
+                    param.desc_full(),
+                    ty,
+                    value
+                ))
+                .span(param.human_span()),
+            );
+            Err(())
+        }
+    }
+}
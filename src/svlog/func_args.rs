@@ -208,6 +208,7 @@ fn gather_from_items<'a>(
             if !non_port_reported {
                 cx.emit(
                     DiagBuilder2::warning(format!("port after statement"))
+                        .code(DiagCode::PORT_AFTER_STATEMENT)
                         .span(port.span())
                         .add_note("Port declaration appears after this statement:")
                         .span(non_port.span())
@@ -51,6 +51,7 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
                     if !is_pos {
                         cx.emit(
                             DiagBuilder2::warning("positional parameters must appear before named")
+                                .code(DiagCode::POSITIONAL_AFTER_NAMED)
                                 .span(param.span)
                                 .add_note(format!(
                                     "assuming this refers to argument #{}",
@@ -98,6 +99,7 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
                         if !is_pos {
                             cx.emit(
                                 DiagBuilder2::warning("positional port must appear before named")
+                                    .code(DiagCode::POSITIONAL_AFTER_NAMED)
                                     .span(port.span)
                                     .add_note(format!(
                                         "assuming this refers to argument #{}",
@@ -156,6 +158,7 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
                     .init
                     .as_ref()
                     .map(|expr| cx.map_ast_with_parent(AstNode::Expr(expr), node_id)),
+                konst: decl.konst,
                 kind: ast::VarKind::Var,
             };
             Ok(HirNode::VarDecl(cx.arena().alloc_hir(hir)))
@@ -171,6 +174,7 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
                     .init
                     .as_ref()
                     .map(|expr| cx.map_ast_with_parent(AstNode::Expr(expr), node_id)),
+                konst: false,
                 kind: ast::VarKind::Net {
                     ty: decl.net_type,
                     kind: decl.kind,
@@ -179,6 +183,7 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
             Ok(HirNode::VarDecl(cx.arena().alloc_hir(hir)))
         }
         AstNode::Proc(prok) => {
+            check_procedure_timing(cx, prok)?;
             let hir = hir::Proc {
                 id: node_id,
                 span: prok.span,
@@ -283,12 +288,23 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
                     .init
                     .as_ref()
                     .map(|expr| cx.map_ast_with_parent(AstNode::Expr(expr), ty)),
+                konst: false,
                 kind: ast::VarKind::Var,
             };
             Ok(HirNode::VarDecl(cx.arena().alloc_hir(hir)))
         }
         AstNode::Package(p) => lower_package(cx, node_id, p),
         AstNode::EnumVariant(var, decl, index) => {
+            if var.range.is_some() {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "unsupported: enum name range in `{}`",
+                        var.span.extract()
+                    ))
+                    .span(var.span),
+                );
+                return Err(());
+            }
             let hir = hir::EnumVariant {
                 id: node_id,
                 name: var.name,
@@ -323,6 +339,52 @@ pub(crate) fn hir_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<HirNo
     }
 }
 
+/// Check the timing controls of an `always_ff`, `always_comb`, or
+/// `always_latch` procedure.
+///
+/// An `always_ff` procedure must begin with an event control, whereas the
+/// other two are sensitive to their inputs implicitly and cannot have one. See
+/// §9.2.2.
+fn check_procedure_timing<'a>(cx: &impl Context<'a>, prok: &'a ast::Procedure<'a>) -> Result<()> {
+    // Find the timing control the procedure begins with, looking into a
+    // leading `begin ... end` block.
+    let first = match prok.stmt.kind {
+        ast::SequentialBlock(ref stmts) => stmts.first(),
+        _ => Some(&prok.stmt),
+    };
+    let timing = first.and_then(|stmt| match stmt.kind {
+        ast::TimedStmt(ref tc, _) => Some(tc),
+        _ => None,
+    });
+    match (prok.kind, timing) {
+        (ast::ProcedureKind::AlwaysFf, Some(ast::TimingControl::Event(_))) => Ok(()),
+        (ast::ProcedureKind::AlwaysFf, _) => {
+            cx.emit(
+                DiagBuilder2::error("`always_ff` procedure must begin with an event control")
+                    .span(prok.span)
+                    .add_note("Add a sensitivity list, e.g. `always_ff @(posedge clk)`"),
+            );
+            Err(())
+        }
+        (ast::ProcedureKind::AlwaysComb, Some(_)) | (ast::ProcedureKind::AlwaysLatch, Some(_)) => {
+            let name = match prok.kind {
+                ast::ProcedureKind::AlwaysComb => "always_comb",
+                _ => "always_latch",
+            };
+            cx.emit(
+                DiagBuilder2::error(format!("`{}` procedure cannot have a timing control", name))
+                    .span(prok.span)
+                    .add_note(format!(
+                        "`{}` is implicitly sensitive to the signals it reads",
+                        name
+                    )),
+            );
+            Err(())
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Lower a module to HIR.
 #[moore_derive::query]
 pub(crate) fn hir_of_module<'a>(
@@ -350,11 +412,13 @@ pub(crate) fn hir_of_module<'a>(
 
     // Lower the module body.
     let block = lower_module_block(cx, next_rib, &ast.items, true, false)?;
+    let timeunit = lower_timeunit(cx, &ast.items)?;
 
     // Create the HIR module.
     let hir = hir::Module {
         ast,
         ports_new,
+        timeunit,
         params: cx.arena().alloc_ids(params),
         last_rib: block.last_rib,
         block,
@@ -434,6 +498,9 @@ fn lower_module_block_into<'gcx>(
                 into.last_rib = id;
                 into.procs.push(id);
             }
+            // Extern declarations are checked against their module definition
+            // when the AST is added to the context.
+            ast::ItemData::ExternModuleDecl(..) => (),
             ast::ItemData::PackageDecl(ref decl) => {
                 let id = cx.map_ast_with_parent(AstNode::Package(decl), into.last_rib);
                 into.last_rib = id;
@@ -445,16 +512,26 @@ fn lower_module_block_into<'gcx>(
                 // into.procs.push(id);
                 cx.emit(
                     DiagBuilder2::warning("unsupported: interface declaration; ignored")
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
                         .span(decl.span),
                 );
             }
-            ast::ItemData::ProgramDecl(ref _decl) => {
+            ast::ItemData::ProgramDecl(ref decl) => {
                 // let id = cx.map_ast_with_parent(AstNode::Program(decl), into.last_rib);
                 // into.last_rib = id;
                 // into.procs.push(id);
-                cx.emit(DiagBuilder2::warning(
-                    "unsupported: program declaration; ignored",
-                ));
+                cx.emit(
+                    DiagBuilder2::warning("unsupported: program declaration; ignored")
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
+                        .span(decl.span),
+                );
+            }
+            ast::ItemData::CheckerDecl(ref decl) => {
+                cx.emit(
+                    DiagBuilder2::warning("unsupported: checker declaration; ignored")
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
+                        .span(decl.span),
+                );
             }
             ast::ItemData::Inst(ref inst) => {
                 let target_id = cx.map_ast_with_parent(AstNode::InstTarget(inst), into.last_rib);
@@ -512,6 +589,32 @@ fn lower_module_block_into<'gcx>(
                     into.assigns.push(id);
                 }
             }
+            ast::ItemData::Defparam(ref decl) => {
+                for (name, _) in &decl.assignments {
+                    if name.segments.len() == 1 {
+                        cx.emit(
+                            DiagBuilder2::error(format!(
+                                "defparam cannot override `{}` in its own scope",
+                                name
+                            ))
+                            .span(name.span)
+                            .add_note(
+                                "Declare the parameter as a `localparam` or change its value \
+                                 directly.",
+                            ),
+                        );
+                    } else {
+                        cx.emit(
+                            DiagBuilder2::warning(format!(
+                                "unsupported: defparam override of `{}`; ignored",
+                                name
+                            ))
+                            .code(DiagCode::UNSUPPORTED_IGNORED)
+                            .span(name.span),
+                        );
+                    }
+                }
+            }
             ast::ItemData::ImportDecl(ref decl) => {
                 for item in &decl.items {
                     let id = cx.map_ast_with_parent(AstNode::Import(item), into.last_rib);
@@ -540,6 +643,7 @@ fn lower_module_block_into<'gcx>(
             ast::ItemData::ClassDecl(ref decl) => {
                 cx.emit(
                     DiagBuilder2::warning("unsupported: class declaration; ignored")
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
                         .span(decl.span),
                 );
             }
@@ -550,12 +654,18 @@ fn lower_module_block_into<'gcx>(
             ast::ItemData::Assertion(ref assert) => {
                 cx.emit(
                     DiagBuilder2::warning("unsupported: concurrent assertion; ignored")
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
                         .span(assert.span),
                 );
             }
 
             // The remaining items don't need an HIR representation.
             ast::ItemData::DpiDecl(..)
+            | ast::ItemData::PropertyDecl(..)
+            | ast::ItemData::SequenceDecl(..)
+            | ast::ItemData::LetDecl(..)
+            | ast::ItemData::ClockingDecl(..)
+            | ast::ItemData::DefaultClocking(..)
             | ast::ItemData::GenvarDecl(..)
             | ast::ItemData::Timeunit(..) => (),
         }
@@ -799,6 +909,7 @@ fn lower_expr_inner<'gcx>(
             if size_needed > size {
                 cx.emit(
                     DiagBuilder2::warning(format!("`{}` is too large", value,))
+                        .code(DiagCode::LITERAL_TOO_LARGE)
                         .span(expr.span)
                         .add_note(format!(
                             "constant is {} bits wide, but the value `{}{}` needs {} bits to not \
@@ -808,31 +919,35 @@ fn lower_expr_inner<'gcx>(
                 );
             }
 
-            // Identify the special bits (x and z) in the input.
-            // TODO(fschuiki): Decimal literals are not handled properly.
-            let bit_iter = value_str.chars().flat_map(|c| {
-                std::iter::repeat(c).take(match base {
-                    'h' => 4,
-                    'o' => 3,
-                    'b' => 1,
-                    _ => 0,
-                })
-            });
-            let special_bits: BitVec = bit_iter
-                .clone()
-                .map(|c| match c {
-                    'x' | 'X' | 'z' | 'Z' | '?' => true,
-                    _ => false,
-                })
-                .collect();
-            let x_bits: BitVec = bit_iter
-                .clone()
-                .map(|c| match c {
-                    'x' | 'X' => true,
-                    _ => false,
-                })
+            // Identify the special bits (x and z) in the input. The bits are
+            // collected LSB first. Decimal literals may only consist of a
+            // single x or z digit, which then applies to all bits.
+            let digits: Vec<char> = value_str.chars().filter(|&c| c != '_').collect();
+            let digit_bits = match base {
+                'h' => 4,
+                'o' => 3,
+                'b' => 1,
+                _ => size,
+            };
+            let classify = |c: char| match c {
+                'x' | 'X' => Some(true),
+                'z' | 'Z' | '?' => Some(false),
+                _ => None,
+            };
+            let mut bits: Vec<Option<bool>> = digits
+                .iter()
+                .rev()
+                .flat_map(|&c| std::iter::repeat(classify(c)).take(digit_bits))
                 .collect();
 
+            // A literal with fewer digits than its size is padded with zeros,
+            // unless the leftmost digit is x or z, in which case that digit is
+            // replicated.
+            let pad = digits.first().and_then(|&c| classify(c));
+            bits.resize(size, pad);
+            let special_bits: BitVec = bits.iter().map(|b| b.is_some()).collect();
+            let x_bits: BitVec = bits.iter().map(|&b| b == Some(true)).collect();
+
             // Assemble the HIR node.
             hir::ExprKind::IntConst {
                 width: size,
@@ -844,20 +959,7 @@ fn lower_expr_inner<'gcx>(
         }
 
         ast::LiteralExpr(Lit::Time(int, frac, unit)) => {
-            use syntax::token::TimeUnit;
-            let mut value = parse_fixed_point_number(cx, expr.span, int, frac)?;
-            let magnitude = match unit {
-                TimeUnit::Second => 0,
-                TimeUnit::MilliSecond => 1,
-                TimeUnit::MicroSecond => 2,
-                TimeUnit::NanoSecond => 3,
-                TimeUnit::PicoSecond => 4,
-                TimeUnit::FemtoSecond => 5,
-            };
-            for _ in 0..magnitude {
-                value = value / num::BigInt::from(1000);
-            }
-            hir::ExprKind::TimeConst(value)
+            hir::ExprKind::TimeConst(lower_time_literal(cx, expr.span, int, frac, unit)?)
         }
 
         ast::LiteralExpr(Lit::Str(value)) => {
@@ -1096,6 +1198,41 @@ fn lower_expr_inner<'gcx>(
                 .map(|expr| cx.map_ast_with_parent(AstNode::Expr(expr), node_id))
                 .collect(),
         ),
+        ast::StreamConcatExpr {
+            dir,
+            ref slice,
+            ref exprs,
+        } => {
+            let slice = slice.as_ref().map(|slice| match *slice {
+                ast::StreamConcatSlice::Expr(ref expr) => {
+                    hir::StreamSlice::Expr(cx.map_ast_with_parent(AstNode::Expr(expr), node_id))
+                }
+                ast::StreamConcatSlice::Type(ref ty) => {
+                    hir::StreamSlice::Type(cx.map_ast_with_parent(AstNode::Type(ty), node_id))
+                }
+            });
+            let mut failed = false;
+            for expr in exprs {
+                if let Some(ref range) = expr.range {
+                    cx.emit(
+                        DiagBuilder2::error("unsupported: `with` in streaming concatenation")
+                            .span(range.span),
+                    );
+                    failed = true;
+                }
+            }
+            if failed {
+                return Err(());
+            }
+            hir::ExprKind::StreamConcat {
+                dir,
+                slice,
+                exprs: exprs
+                    .iter()
+                    .map(|expr| cx.map_ast_with_parent(AstNode::Expr(&expr.expr), node_id))
+                    .collect(),
+            }
+        }
         ast::CastExpr(ref ty, ref expr) => {
             // Catch the corner case where a size cast looks like a type cast.
             if let ast::NamedType(n) = ty.kind.data {
@@ -1150,13 +1287,15 @@ fn lower_expr_inner<'gcx>(
                         ),
                         expr.span,
                     ),
-                    ast::ValueRange::Range { lo, hi, span } => Spanned::new(
-                        hir::InsideRange::Range(
-                            cx.map_ast_with_parent(AstNode::Expr(lo), node_id),
-                            cx.map_ast_with_parent(AstNode::Expr(hi), node_id),
-                        ),
-                        *span,
-                    ),
+                    ast::ValueRange::Range { lo, hi, span } => {
+                        // Open range bounds `$` extend to the extremes of
+                        // the operand type.
+                        let bound = |expr: &'gcx ast::Expr<'gcx>| match expr.data {
+                            ast::DollarExpr => None,
+                            _ => Some(cx.map_ast_with_parent(AstNode::Expr(expr), node_id)),
+                        };
+                        Spanned::new(hir::InsideRange::Range(bound(lo), bound(hi)), *span)
+                    }
                 })
                 .collect(),
         ),
@@ -1335,6 +1474,7 @@ fn lower_stmt_inner<'a>(
                             .collect(),
                         cx.map_ast_with_parent(AstNode::Stmt(stmt), node_id),
                     )),
+                    ast::CaseItem::Inside(..) => unreachable!("inside item in a normal case"),
                 }
             }
             hir::StmtKind::Case {
@@ -1347,6 +1487,7 @@ fn lower_stmt_inner<'a>(
         ast::AssertionStmt { .. } => {
             cx.emit(
                 DiagBuilder2::warning("unsupported: immediate assertion; ignored")
+                    .code(DiagCode::UNSUPPORTED_IGNORED)
                     .span(stmt.human_span()),
             );
             hir::StmtKind::Null
@@ -1355,6 +1496,111 @@ fn lower_stmt_inner<'a>(
     })
 }
 
+/// Convert a time literal to seconds.
+fn lower_time_literal<'gcx>(
+    cx: &impl Context<'gcx>,
+    span: Span,
+    int: Name,
+    frac: Option<Name>,
+    unit: syntax::token::TimeUnit,
+) -> Result<num::BigRational> {
+    use syntax::token::TimeUnit;
+    let mut value = parse_fixed_point_number(cx, span, int, frac)?;
+    let magnitude = match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::MilliSecond => 1,
+        TimeUnit::MicroSecond => 2,
+        TimeUnit::NanoSecond => 3,
+        TimeUnit::PicoSecond => 4,
+        TimeUnit::FemtoSecond => 5,
+    };
+    for _ in 0..magnitude {
+        value = value / num::BigInt::from(1000);
+    }
+    Ok(value)
+}
+
+/// Determine the time unit declared in a list of items, in seconds.
+///
+/// The last `timeunit` declaration wins. Returns `None` if the items do not
+/// declare a time unit.
+fn lower_timeunit<'gcx>(
+    cx: &impl Context<'gcx>,
+    items: &[ast::Item<'gcx>],
+) -> Result<Option<num::BigRational>> {
+    let mut timeunit = None;
+    let mut timeprec = None;
+    let mut first_other = None;
+    for item in items {
+        match item.data {
+            ast::ItemData::Timeunit(ref tu) => {
+                if let Some(ref unit) = tu.unit {
+                    check_time_decl(cx, "timeunit", unit, &mut timeunit, first_other)?;
+                }
+                if let Some(ref prec) = tu.prec {
+                    check_time_decl(cx, "timeprecision", prec, &mut timeprec, first_other)?;
+                }
+            }
+            ast::ItemData::Dummy => (),
+            _ => first_other = first_other.or(Some(item.span)),
+        }
+    }
+    Ok(timeunit.map(|(value, _)| value))
+}
+
+/// Check a `timeunit` or `timeprecision` declaration against the previous
+/// declarations in the same scope.
+///
+/// The first declaration must precede all other items. Later declarations
+/// are allowed anywhere, but must repeat the value of the first. See §3.14.2.2
+/// of IEEE 1800-2017.
+fn check_time_decl<'gcx>(
+    cx: &impl Context<'gcx>,
+    what: &str,
+    lit: &Spanned<syntax::token::Lit>,
+    prev: &mut Option<(num::BigRational, Span)>,
+    first_other: Option<Span>,
+) -> Result<()> {
+    let value = match lit.value {
+        syntax::token::Lit::Time(int, frac, u) => lower_time_literal(cx, lit.span, int, frac, u)?,
+        _ => unreachable!("{} must be a time literal", what),
+    };
+    match *prev {
+        Some((ref prev_value, prev_span)) => {
+            if *prev_value != value {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "`{}` of {} does not match the previous declaration",
+                        what,
+                        lit.span.extract()
+                    ))
+                    .span(lit.span)
+                    .add_note(format!(
+                        "Previous `{}` was declared as {}:",
+                        what,
+                        prev_span.extract()
+                    ))
+                    .span(prev_span),
+                );
+                return Err(());
+            }
+        }
+        None => {
+            if let Some(other) = first_other {
+                cx.emit(
+                    DiagBuilder2::error(format!("`{}` must precede all other items", what))
+                        .span(lit.span)
+                        .add_note("First other item was here:")
+                        .span(other),
+                );
+                return Err(());
+            }
+            *prev = Some((value, lit.span));
+        }
+    }
+    Ok(())
+}
+
 /// Parse a fixed point number into a [`BigRational`].
 ///
 /// The fractional part of the number is optional, such that this function may
@@ -1470,6 +1716,9 @@ fn alloc_param_decl<'gcx>(
     match param.kind {
         ast::ParamKind::Type(ref decls) => {
             for decl in decls {
+                if param.local && decl.ty.is_none() {
+                    emit_localparam_without_value(cx, decl.name, decl.span);
+                }
                 let id = cx.map_ast(AstNode::TypeParam(param, decl));
                 cx.set_parent(id, next_rib);
                 next_rib = id;
@@ -1478,6 +1727,9 @@ fn alloc_param_decl<'gcx>(
         }
         ast::ParamKind::Value(ref decls) => {
             for decl in decls {
+                if param.local && decl.expr.is_none() {
+                    emit_localparam_without_value(cx, decl.name, decl.span);
+                }
                 let id = cx.map_ast(AstNode::ValueParam(param, decl));
                 cx.set_parent(id, next_rib);
                 next_rib = id;
@@ -1488,6 +1740,18 @@ fn alloc_param_decl<'gcx>(
     next_rib
 }
 
+/// Emit an error for a `localparam` that lacks a value.
+///
+/// Unlike parameters, localparams cannot be overridden from the outside, so a
+/// declaration without a value can never be assigned one.
+fn emit_localparam_without_value<'gcx>(cx: &impl Context<'gcx>, name: Spanned<Name>, span: Span) {
+    cx.emit(
+        DiagBuilder2::error(format!("localparam `{}` must have a value", name))
+            .span(span)
+            .add_note("Localparams cannot be overridden and therefore require a value."),
+    );
+}
+
 /// Allocate node IDs for a variable declaration.
 fn alloc_var_decl<'gcx>(
     cx: &impl Context<'gcx>,
@@ -1637,6 +1901,9 @@ fn lower_call<'a>(
             };
             let map_unary_id =
                 || Ok(cx.map_ast_with_parent(AstNode::Expr(map_unary()?), expr.id()));
+            let type_or_expr = |arg: &'a ast::Expr<'a>| -> &'a ast::TypeOrExpr<'a> {
+                cx.arena().alloc(ast::TypeOrExpr::Expr(arg))
+            };
             let map_array_dim = |func| match args {
                 [ast::CallArg {
                     data:
@@ -1645,7 +1912,7 @@ fn lower_call<'a>(
                             ..
                         },
                     ..
-                }] => Ok(hir::BuiltinCall::ArrayDim(func, arg, None)),
+                }] => Ok(hir::BuiltinCall::ArrayDim(func, type_or_expr(arg), None)),
                 [ast::CallArg {
                     data:
                         ast::CallArgData {
@@ -1660,7 +1927,11 @@ fn lower_call<'a>(
                             ..
                         },
                     ..
-                }] => Ok(hir::BuiltinCall::ArrayDim(func, arg, Some(dim))),
+                }] => Ok(hir::BuiltinCall::ArrayDim(
+                    func,
+                    type_or_expr(arg),
+                    Some(dim),
+                )),
                 _ => {
                     cx.emit(
                         DiagBuilder2::error(format!("`{}` takes one or two arguments", ident))
@@ -1683,12 +1954,23 @@ fn lower_call<'a>(
                 "high" => map_array_dim(hir::ArrayDim::High)?,
                 "increment" => map_array_dim(hir::ArrayDim::Increment)?,
                 "size" => map_array_dim(hir::ArrayDim::Size)?,
+                "dimensions" => hir::BuiltinCall::ArrayDim(
+                    hir::ArrayDim::Dimensions,
+                    type_or_expr(map_unary()?),
+                    None,
+                ),
+                "unpacked_dimensions" => hir::BuiltinCall::ArrayDim(
+                    hir::ArrayDim::UnpackedDimensions,
+                    type_or_expr(map_unary()?),
+                    None,
+                ),
                 _ => {
                     cx.emit(
                         DiagBuilder2::warning(format!(
                             "unsupported: system task `${}`; ignored",
                             ident
                         ))
+                        .code(DiagCode::UNSUPPORTED_IGNORED)
                         .span(expr.human_span()),
                     );
                     hir::BuiltinCall::Unsupported
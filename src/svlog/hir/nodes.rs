@@ -162,6 +162,8 @@ pub struct Module<'a> {
     pub block: ModuleBlock,
     /// The bottom of the name scope tree.
     pub last_rib: NodeId,
+    /// The time unit declared in the module, in seconds.
+    pub timeunit: Option<BigRational>,
 }
 
 impl<'a> Deref for Module<'a> {
@@ -670,6 +672,12 @@ pub enum ExprKind<'a> {
     RepeatPattern(NodeId, Vec<NodeId>),
     /// A concatenation such as `{a,b}` or `{4{a,b}}`.
     Concat(Option<NodeId>, Vec<NodeId>),
+    /// A streaming concatenation such as `{<<8{a,b}}`.
+    StreamConcat {
+        dir: ast::StreamDir,
+        slice: Option<StreamSlice>,
+        exprs: Vec<NodeId>,
+    },
     /// A cast `(ty, expr)` such as `foo'(bar)`.
     Cast(NodeId, NodeId),
     /// A sign cast such as `unsigned'(foo)`.
@@ -858,8 +866,9 @@ pub enum BuiltinCall<'a> {
     OneHot0(&'a ast::Expr<'a>),
     /// A call to the `$isunknown(x)` function.
     IsUnknown(&'a ast::Expr<'a>),
-    /// A call to one of the array dimension functions.
-    ArrayDim(ArrayDim, &'a ast::Expr<'a>, Option<&'a ast::Expr<'a>>),
+    /// A call to one of the array query functions. The argument may either be
+    /// a type or an expression.
+    ArrayDim(ArrayDim, &'a ast::TypeOrExpr<'a>, Option<&'a ast::Expr<'a>>),
 }
 
 /// The different builtin array dimension function calls that are supported.
@@ -877,6 +886,25 @@ pub enum ArrayDim {
     Increment,
     /// The `$size` function.
     Size,
+    /// The `$dimensions` function.
+    Dimensions,
+    /// The `$unpacked_dimensions` function.
+    UnpackedDimensions,
+}
+
+impl std::fmt::Display for ArrayDim {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Left => write!(f, "$left"),
+            Self::Right => write!(f, "$right"),
+            Self::Low => write!(f, "$low"),
+            Self::High => write!(f, "$high"),
+            Self::Increment => write!(f, "$increment"),
+            Self::Size => write!(f, "$size"),
+            Self::Dimensions => write!(f, "$dimensions"),
+            Self::UnpackedDimensions => write!(f, "$unpacked_dimensions"),
+        }
+    }
 }
 
 /// A variable or net declaration.
@@ -889,6 +917,8 @@ pub struct VarDecl {
     pub ty: NodeId,
     /// Initial value
     pub init: Option<NodeId>,
+    /// Whether the variable is declared `const`
+    pub konst: bool,
     /// Variable or net-specific data
     pub kind: ast::VarKind,
 }
@@ -1294,9 +1324,20 @@ pub enum PatternMapping {
     Default,
 }
 
+/// The slice size of a streaming concatenation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamSlice {
+    /// A constant slice size in bits, such as `{<<8{...}}`.
+    Expr(NodeId),
+    /// A type whose width is the slice size, such as `{<<byte{...}}`.
+    Type(NodeId),
+}
+
 /// Single values or value ranges admissible in `inside` sets.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InsideRange {
     Single(NodeId),
-    Range(NodeId, NodeId),
+    /// An inclusive range `[lo:hi]`. An open bound `$` is represented as
+    /// `None`.
+    Range(Option<NodeId>, Option<NodeId>),
 }
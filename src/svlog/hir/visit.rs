@@ -307,6 +307,19 @@ pub fn walk_expr<'a>(visitor: &mut impl Visitor<'a>, expr: &'a Expr, lvalue: boo
                 visitor.visit_node_with_id(expr, lvalue);
             }
         }
+        ExprKind::StreamConcat {
+            slice, ref exprs, ..
+        } => {
+            match slice {
+                Some(StreamSlice::Expr(id)) | Some(StreamSlice::Type(id)) => {
+                    visitor.visit_node_with_id(id, false)
+                }
+                None => (),
+            }
+            for &expr in exprs {
+                visitor.visit_node_with_id(expr, lvalue);
+            }
+        }
         ExprKind::Cast(ty, expr) => {
             visitor.visit_node_with_id(ty, false);
             visitor.visit_node_with_id(expr, false);
@@ -324,8 +337,9 @@ pub fn walk_expr<'a>(visitor: &mut impl Visitor<'a>, expr: &'a Expr, lvalue: boo
                 match r.value {
                     InsideRange::Single(expr) => visitor.visit_node_with_id(expr, false),
                     InsideRange::Range(lo, hi) => {
-                        visitor.visit_node_with_id(lo, false);
-                        visitor.visit_node_with_id(hi, false);
+                        for bound in lo.into_iter().chain(hi) {
+                            visitor.visit_node_with_id(bound, false);
+                        }
                     }
                 }
             }
@@ -7,7 +7,9 @@ use crate::{
     crate_prelude::*,
     hir::{self, HirNode},
     port_list::AsPortedNode,
+    resolved_params, resolved_params_text,
     resolver::InstTarget,
+    syntax::hier::{HierPath, PathBuilder},
     Context, ParamEnv, ParamEnvData, ParamEnvSource, PortMapping,
 };
 use std::sync::Arc;
@@ -126,6 +128,7 @@ pub(crate) fn inst_details<'a>(
     } else {
         target.inner_env
     };
+    cx.add_param_env_context(inner_env, inst.ast.id());
 
     // Wrap everything up.
     Ok(Arc::new(InstDetails {
@@ -214,3 +217,69 @@ impl<'a, 'gcx> hir::Visitor<'gcx> for InstVerbosityVisitor<'a, 'gcx> {
         .visit_node_with_id(details.target.kind.as_any().id(), false);
     }
 }
+
+/// Render the resolved parameters of a module and every instance below it.
+///
+/// Each parameter is printed on a line of its own, prefixed with the
+/// hierarchical path of the instance, as in `top.u0.N = 32'sd8 (default)`.
+pub fn dump_params<'gcx>(cx: &GlobalContext<'gcx>, module: NodeId) -> Result<String> {
+    let name = match cx.hir_of(module)? {
+        HirNode::Module(x) => x.name.value,
+        x => bug_span!(x.span(), cx, "{} is not a module", x.desc_full()),
+    };
+    let mut visitor = ParamDumpVisitor {
+        cx,
+        env: cx.default_param_env(),
+        path: PathBuilder::new().instance(name).build(),
+        output: String::new(),
+    };
+    visitor.dump(module)?;
+    Ok(visitor.output)
+}
+
+/// A visitor that renders the resolved parameters of module instances.
+struct ParamDumpVisitor<'a, 'gcx> {
+    cx: &'a GlobalContext<'gcx>,
+    env: ParamEnv,
+    path: HierPath,
+    output: String,
+}
+
+impl<'a, 'gcx> ParamDumpVisitor<'a, 'gcx> {
+    fn dump(&mut self, module: NodeId) -> Result<()> {
+        let params = resolved_params(self.cx, module, self.env)?;
+        self.output += &resolved_params_text(&params, &format!("{}.", self.path));
+        self.visit_node_with_id(module, false);
+        Ok(())
+    }
+}
+
+impl<'a, 'gcx> hir::Visitor<'gcx> for ParamDumpVisitor<'a, 'gcx> {
+    type Context = GlobalContext<'gcx>;
+
+    fn context(&self) -> &Self::Context {
+        self.cx
+    }
+
+    fn visit_inst(&mut self, hir: &'gcx hir::Inst<'gcx>) {
+        let details = match self.cx.inst_details(Ref(hir), self.env) {
+            Ok(x) => x,
+            Err(()) => return,
+        };
+        let module = match details.target.kind {
+            InstTarget::Module(m) => m.id(),
+            InstTarget::Interface(_) => return,
+        };
+        let mut inner = Self {
+            cx: self.cx,
+            env: details.inner_env,
+            path: PathBuilder::within(&self.path)
+                .instance(hir.name.value)
+                .build(),
+            output: String::new(),
+        };
+        if inner.dump(module).is_ok() {
+            self.output += &inner.output;
+        }
+    }
+}
@@ -110,6 +110,7 @@ mod ast_map;
 pub mod call_mapping;
 mod codegen;
 mod context;
+mod eval;
 #[warn(missing_docs)]
 pub mod func_args;
 pub mod hir;
@@ -139,9 +140,12 @@ pub type Result<T> = std::result::Result<T, ()>;
 pub use crate::{
     codegen::CodeGenerator,
     context::*,
-    inst_details::{InstDetails, InstTargetDetails, InstVerbosityVisitor},
+    eval::eval_constant_expr,
+    inst_details::{dump_params, InstDetails, InstTargetDetails, InstVerbosityVisitor},
     param_env::{
-        IntoNodeEnvId, NodeEnvId, ParamEnv, ParamEnvBinding, ParamEnvData, ParamEnvSource,
+        param_env_diff, param_env_text, resolved_params, resolved_params_text, IntoNodeEnvId,
+        NodeEnvId, ParamDiff, ParamEnv, ParamEnvBinding, ParamEnvData, ParamEnvSource, ParamOrigin,
+        ResolvedParam,
     },
     port_mapping::{PortMapping, PortMappingSource},
     // resolver::*,
@@ -0,0 +1,172 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! A tree-shaped dump of MIR rvalues.
+//!
+//! Unlike the `Print` implementation, which numbers every node and lists it on
+//! a line of its own, the dump renders an rvalue as an indented tree. This is
+//! mainly useful to debug constant evaluation, since each node can be
+//! annotated with the value `const_mir_rvalue` produces for it.
+
+use super::*;
+use crate::crate_prelude::*;
+use std::fmt::Write;
+
+impl<'a> Rvalue<'a> {
+    /// Render the rvalue and its operands as an indented tree.
+    ///
+    /// Each node is printed on a line of its own, showing its kind, type, and
+    /// source text. If `values` is set, constant nodes are annotated with the
+    /// value they evaluate to.
+    pub fn dump(&'a self, cx: &impl Context<'a>, values: bool) -> String {
+        let mut output = String::new();
+        self.dump_into(cx, values, 0, &mut output);
+        output
+    }
+
+    fn dump_into(&'a self, cx: &impl Context<'a>, values: bool, depth: usize, f: &mut String) {
+        write!(
+            f,
+            "{:indent$}{} : {} `{}`",
+            "",
+            self.kind.label(),
+            self.ty,
+            self.span.extract(),
+            indent = depth * 2
+        )
+        .unwrap();
+        if values && self.is_const() {
+            match cx.const_mir_rvalue(Ref(self)) {
+                Ok(value) => write!(f, " = {:#}", value).unwrap(),
+                Err(_) => write!(f, " = <error>").unwrap(),
+            }
+        }
+        writeln!(f).unwrap();
+        for operand in self.kind.operands() {
+            operand.dump_into(cx, values, depth + 1, f);
+        }
+    }
+}
+
+impl<'a> RvalueKind<'a> {
+    /// A short description of the node, without its operands.
+    fn label(&self) -> String {
+        match *self {
+            RvalueKind::CastValueDomain { from, to, .. } => {
+                format!("CastValueDomain {:?} -> {:?}", from, to)
+            }
+            RvalueKind::Transmute(..) => "Transmute".to_string(),
+            RvalueKind::CastSign(sign, _) => format!("CastSign {}", sign),
+            RvalueKind::CastToBool(..) => "CastToBool".to_string(),
+            RvalueKind::Truncate(size, _) => format!("Truncate {}", size),
+            RvalueKind::ZeroExtend(size, _) => format!("ZeroExtend {}", size),
+            RvalueKind::SignExtend(size, _) => format!("SignExtend {}", size),
+            RvalueKind::ConstructArray(..) => "ConstructArray".to_string(),
+            RvalueKind::ConstructStruct(..) => "ConstructStruct".to_string(),
+            RvalueKind::Const(value) => format!("Const {:#}", value),
+            RvalueKind::Fill(bit) => format!("Fill {}", bit),
+            RvalueKind::UnaryBitwise { op, .. } => format!("UnaryBitwise {:?}", op),
+            RvalueKind::BinaryBitwise { op, .. } => format!("BinaryBitwise {:?}", op),
+            RvalueKind::IntUnaryArith { op, .. } => format!("IntUnaryArith {:?}", op),
+            RvalueKind::IntBinaryArith { op, .. } => format!("IntBinaryArith {:?}", op),
+            RvalueKind::IntComp { op, .. } => format!("IntComp {:?}", op),
+            RvalueKind::Concat(..) => "Concat".to_string(),
+            RvalueKind::StreamConcat { dir, slice, .. } => {
+                format!("StreamConcat {:?} {}", dir, slice)
+            }
+            RvalueKind::Repeat(count, _) => format!("Repeat {}", count),
+            RvalueKind::Var(id) => format!("Var {:?}", id),
+            RvalueKind::Port(id) => format!("Port {:?}", id),
+            RvalueKind::Arg(id) => format!("Arg {:?}", id),
+            RvalueKind::Intf(id) => format!("Intf {:?}", id),
+            RvalueKind::IntfSignal(_, id) => format!("IntfSignal {:?}", id),
+            RvalueKind::Index { length: 0, .. } => "Index".to_string(),
+            RvalueKind::Index { length, .. } => format!("Index +:{}", length),
+            RvalueKind::Member { field, .. } => format!("Member {}", field),
+            RvalueKind::UpdateMember { field, .. } => format!("UpdateMember {}", field),
+            RvalueKind::UpdateIndex { index, .. } => format!("UpdateIndex {}", index),
+            RvalueKind::Inside { .. } => "Inside".to_string(),
+            RvalueKind::Ternary { .. } => "Ternary".to_string(),
+            RvalueKind::Shift { op, arith, .. } => {
+                format!("Shift {:?} {}", op, if arith { "arith" } else { "logic" })
+            }
+            RvalueKind::Reduction { op, .. } => format!("Reduction {:?}", op),
+            RvalueKind::Assignment { .. } => "Assignment".to_string(),
+            RvalueKind::PackString(..) => "PackString".to_string(),
+            RvalueKind::UnpackString(..) => "UnpackString".to_string(),
+            RvalueKind::StringComp { op, .. } => format!("StringComp {:?}", op),
+            RvalueKind::ApplyTimescale(_, ref scale) => format!("ApplyTimescale {}", scale),
+            RvalueKind::Call { target, .. } => format!("Call {}", target.prototype.name),
+            RvalueKind::Error => "Error".to_string(),
+        }
+    }
+
+    /// The rvalue operands of the node, in source order.
+    fn operands(&self) -> Vec<&'a Rvalue<'a>> {
+        match *self {
+            RvalueKind::CastValueDomain { value, .. }
+            | RvalueKind::Transmute(value)
+            | RvalueKind::CastSign(_, value)
+            | RvalueKind::CastToBool(value)
+            | RvalueKind::Truncate(_, value)
+            | RvalueKind::ZeroExtend(_, value)
+            | RvalueKind::SignExtend(_, value)
+            | RvalueKind::Repeat(_, value)
+            | RvalueKind::IntfSignal(value, _)
+            | RvalueKind::Member { value, .. }
+            | RvalueKind::PackString(value)
+            | RvalueKind::UnpackString(value)
+            | RvalueKind::ApplyTimescale(value, _)
+            | RvalueKind::UnaryBitwise { arg: value, .. }
+            | RvalueKind::IntUnaryArith { arg: value, .. }
+            | RvalueKind::Reduction { arg: value, .. } => vec![value],
+            RvalueKind::ConstructArray(ref values) => {
+                let mut values: Vec<_> = values.iter().collect();
+                values.sort_by_key(|(index, _)| **index);
+                values.into_iter().map(|(_, &v)| v).collect()
+            }
+            RvalueKind::ConstructStruct(ref values)
+            | RvalueKind::Concat(ref values)
+            | RvalueKind::StreamConcat { ref values, .. } => values.clone(),
+            RvalueKind::BinaryBitwise { lhs, rhs, .. }
+            | RvalueKind::IntBinaryArith { lhs, rhs, .. }
+            | RvalueKind::IntComp { lhs, rhs, .. }
+            | RvalueKind::StringComp { lhs, rhs, .. } => vec![lhs, rhs],
+            RvalueKind::Index { value, base, .. } => vec![value, base],
+            RvalueKind::UpdateMember {
+                value, new_value, ..
+            }
+            | RvalueKind::UpdateIndex {
+                value, new_value, ..
+            } => vec![value, new_value],
+            RvalueKind::Inside {
+                value, ref members, ..
+            } => std::iter::once(value)
+                .chain(members.iter().flat_map(|m| match *m {
+                    InsideMember::Single(v) => vec![v],
+                    InsideMember::Range(lo, hi) => lo.into_iter().chain(hi).collect(),
+                }))
+                .collect(),
+            RvalueKind::Ternary {
+                cond,
+                true_value,
+                false_value,
+            } => vec![cond, true_value, false_value],
+            RvalueKind::Shift { value, amount, .. } => vec![value, amount],
+            RvalueKind::Assignment { rvalue, .. } => vec![rvalue],
+            RvalueKind::Call { ref args, .. } => args
+                .iter()
+                .flat_map(|arg| match *arg {
+                    CallArg::Input(v) | CallArg::Inout(v, _) => Some(v),
+                    _ => None,
+                })
+                .collect(),
+            RvalueKind::Const(_)
+            | RvalueKind::Fill(_)
+            | RvalueKind::Var(_)
+            | RvalueKind::Port(_)
+            | RvalueKind::Arg(_)
+            | RvalueKind::Intf(_)
+            | RvalueKind::Error => vec![],
+        }
+    }
+}
@@ -153,10 +153,10 @@ fn lower_expr_inner<'gcx>(
             special_bits.clone(),
             x_bits.clone(),
         ))),
-        hir::ExprKind::UnsizedConst('0') => Ok(builder.constant(value::make_int(ty, num::zero()))),
-        hir::ExprKind::UnsizedConst('1') => Ok(builder.constant(value::make_int(ty, num::one()))),
-        hir::ExprKind::UnsizedConst('x') => Ok(builder.constant(value::make_int(ty, num::one()))),
-        hir::ExprKind::UnsizedConst('z') => Ok(builder.constant(value::make_int(ty, num::one()))),
+        hir::ExprKind::UnsizedConst('0') => Ok(builder.build(ty, RvalueKind::Fill(FillBit::Zero))),
+        hir::ExprKind::UnsizedConst('1') => Ok(builder.build(ty, RvalueKind::Fill(FillBit::One))),
+        hir::ExprKind::UnsizedConst('x') => Ok(builder.build(ty, RvalueKind::Fill(FillBit::X))),
+        hir::ExprKind::UnsizedConst('z') => Ok(builder.build(ty, RvalueKind::Fill(FillBit::Z))),
         hir::ExprKind::UnsizedConst(c) => {
             bug_span!(span, cx, "unsized const with weird '{}' char", c)
         }
@@ -231,6 +231,44 @@ fn lower_expr_inner<'gcx>(
             Ok(builder.constant(value::make_int(ty, num::zero())))
         }
         hir::ExprKind::Builtin(hir::BuiltinCall::ArrayDim(func, arg, dim)) => {
+            // Get the fully resolved type of the argument, which may either be
+            // a type or an expression.
+            let arg_ty = match cx.disamb_type_or_expr(Ref(arg))? {
+                &ast::TypeOrExpr::Type(x) => cx.map_to_type_or_error(Ref(x), env),
+                &ast::TypeOrExpr::Expr(x) => cx.type_of_expr(Ref(cx.hir_of_expr(Ref(x))?), env),
+            };
+            if arg_ty.is_error() {
+                return Err(());
+            }
+
+            // Handle the functions which count dimensions. Multi-bit integral
+            // element types, such as `int` or packed structs, count as one
+            // additional packed dimension.
+            match func {
+                hir::ArrayDim::Dimensions => {
+                    let mut count = arg_ty.dims().count();
+                    let mut elem_ty = arg_ty;
+                    while let Some(inner) = elem_ty.pop_dim(cx) {
+                        elem_ty = inner;
+                    }
+                    if elem_ty.get_simple_bit_vector().map(|sbv| sbv.size > 1) == Some(true) {
+                        count += 1;
+                    }
+                    return Ok(builder.constant(value::make_int(ty, count.into())));
+                }
+                hir::ArrayDim::UnpackedDimensions => {
+                    let count = arg_ty
+                        .dims()
+                        .filter(|dim| match dim {
+                            ty::Dim::Unpacked(_) => true,
+                            _ => false,
+                        })
+                        .count();
+                    return Ok(builder.constant(value::make_int(ty, count.into())));
+                }
+                _ => (),
+            }
+
             // Decide which dimension to inspect.
             let dim = match dim {
                 Some(dim) => match cx.constant_value_of(dim.id(), env).kind {
@@ -241,12 +279,6 @@ fn lower_expr_inner<'gcx>(
                 None => 1,
             };
 
-            // Get the fully resolved type of the argument.
-            let arg_ty = cx.type_of_expr(Ref(cx.hir_of_expr(Ref(arg))?), env);
-            if arg_ty.is_error() {
-                return Err(());
-            }
-
             // Extract the dimension of interest.
             let ty_dim = match arg_ty.dims().nth(dim - 1) {
                 Some(x) => x,
@@ -268,22 +300,43 @@ fn lower_expr_inner<'gcx>(
                 }
             };
 
-            // Extract the information requested by the array dim function.
-            let value = match ty_dim {
+            // Determine the range of the dimension. Dynamic dimensions have no
+            // constant range.
+            let range = match ty_dim {
+                ty::Dim::Packed(ty::PackedDim::Range(r))
+                | ty::Dim::Unpacked(ty::UnpackedDim::Range(r)) => r,
+                // An array dimension `[N]` is equivalent to `[0:N-1]`.
+                ty::Dim::Unpacked(ty::UnpackedDim::Array(size)) => ty::Range {
+                    size,
+                    dir: ty::RangeDir::Up,
+                    offset: 0,
+                },
                 ty::Dim::Packed(ty::PackedDim::Unsized)
                 | ty::Dim::Unpacked(ty::UnpackedDim::Unsized)
-                | ty::Dim::Unpacked(ty::UnpackedDim::Array(_))
                 | ty::Dim::Unpacked(ty::UnpackedDim::Assoc(_))
-                | ty::Dim::Unpacked(ty::UnpackedDim::Queue(_)) => 0,
-                ty::Dim::Packed(ty::PackedDim::Range(r))
-                | ty::Dim::Unpacked(ty::UnpackedDim::Range(r)) => match func {
-                    hir::ArrayDim::Left => r.left(),
-                    hir::ArrayDim::Right => r.right(),
-                    hir::ArrayDim::Low => r.low(),
-                    hir::ArrayDim::High => r.high(),
-                    hir::ArrayDim::Increment => r.increment(),
-                    hir::ArrayDim::Size => r.size as isize,
-                },
+                | ty::Dim::Unpacked(ty::UnpackedDim::Queue(_)) => {
+                    cx.emit(
+                        DiagBuilder2::error(format!(
+                            "`{}` of dynamic dimension `{}` is not constant",
+                            func, ty_dim
+                        ))
+                        .span(hir.span())
+                        .add_note(format!("Argument type is `{}`", arg_ty.resolve_full()))
+                        .span(arg.span()),
+                    );
+                    return Err(());
+                }
+            };
+
+            // Extract the information requested by the array dim function.
+            let value = match func {
+                hir::ArrayDim::Left => range.left(),
+                hir::ArrayDim::Right => range.right(),
+                hir::ArrayDim::Low => range.low(),
+                hir::ArrayDim::High => range.high(),
+                hir::ArrayDim::Increment => range.increment(),
+                hir::ArrayDim::Size => range.size as isize,
+                hir::ArrayDim::Dimensions | hir::ArrayDim::UnpackedDimensions => unreachable!(),
             };
 
             Ok(builder.constant(value::make_int(ty, value.into())))
@@ -292,6 +345,10 @@ fn lower_expr_inner<'gcx>(
         hir::ExprKind::Ident(..) | hir::ExprKind::Scope(..) => {
             let binding = builder.cx.resolve_node(expr_id, env)?;
             match builder.cx.hir_of(binding)? {
+                HirNode::VarDecl(decl) if decl.konst && builder.cx.is_constant(binding)? => {
+                    let k = builder.cx.constant_value_of(binding, env);
+                    Ok(builder.build(ty, RvalueKind::Const(k)))
+                }
                 HirNode::VarDecl(decl) => Ok(builder.build(ty, RvalueKind::Var(decl.id))),
                 HirNode::IntPort(port) if ty.resolve_full().core.get_interface().is_some() => {
                     Ok(builder.build(ty, RvalueKind::Intf(port.id)))
@@ -371,11 +428,21 @@ fn lower_expr_inner<'gcx>(
 
             // If a repetition is present, apply that.
             let repeat = if let Some(repeat) = repeat {
-                let count = builder
-                    .cx
-                    .constant_int_value_of(repeat, env)?
-                    .to_usize()
-                    .unwrap();
+                let count = builder.cx.replication_count(repeat, env)?;
+
+                // A zero replication yields no bits at all, which is only
+                // allowed as part of an enclosing concatenation. See §11.4.12.1
+                // "Replication operator".
+                if count == 0 && !is_within_concat(builder.cx, hir.id) {
+                    builder.cx.emit(
+                        DiagBuilder2::error(
+                            "zero replication is only allowed within a concatenation",
+                        )
+                        .span(span)
+                        .add_note("replicating a value zero times yields no bits"),
+                    );
+                    return Err(());
+                }
                 builder.build(final_ty, RvalueKind::Repeat(count, concat))
             } else {
                 concat
@@ -383,6 +450,100 @@ fn lower_expr_inner<'gcx>(
             Ok(repeat)
         }
 
+        hir::ExprKind::StreamConcat {
+            dir,
+            slice,
+            ref exprs,
+        } => {
+            // Lower each expression to MIR, implicitly casting to its SBVT as
+            // for regular concatenations.
+            let values: Vec<_> = exprs
+                .iter()
+                .map(|&expr| builder.cx.mir_rvalue(expr, env))
+                .collect();
+            if values.iter().any(|v| v.is_error()) {
+                return Err(());
+            }
+            for value in &values {
+                assert_span!(value.ty.coalesces_to_llhd_scalar(), value.span, builder.cx);
+            }
+
+            // Determine the slice size, which defaults to a single bit.
+            let slice = match slice {
+                None => 1,
+                Some(hir::StreamSlice::Expr(id)) => {
+                    let size = builder.cx.constant_int_value_of(id, env)?;
+                    match size.to_usize() {
+                        Some(size) if size > 0 => size,
+                        _ => {
+                            builder.cx.emit(
+                                DiagBuilder2::error(format!(
+                                    "slice size of streaming concatenation must be positive, \
+                                     but is {}",
+                                    size
+                                ))
+                                .span(builder.cx.span(id)),
+                            );
+                            return Err(());
+                        }
+                    }
+                }
+                Some(hir::StreamSlice::Type(id)) => {
+                    let slice_ty = builder.cx.packed_type_from_ast(
+                        Ref(builder.cx.ast_for_id(id).as_all().get_type().unwrap()),
+                        env,
+                        None,
+                    );
+                    match slice_ty.get_bit_size() {
+                        Some(size) if size > 0 => size,
+                        _ => {
+                            builder.cx.emit(
+                                DiagBuilder2::error(format!(
+                                    "`{}` cannot be used as slice size of a streaming \
+                                     concatenation",
+                                    slice_ty
+                                ))
+                                .span(builder.cx.span(id)),
+                            );
+                            return Err(());
+                        }
+                    }
+                }
+            };
+
+            // The streamed bits are fit into the result type, which is
+            // inferred from the context.
+            let width = match ty.get_simple_bit_vector() {
+                Some(sbvt) => sbvt.size,
+                None => {
+                    builder.cx.emit(
+                        DiagBuilder2::error(format!("cannot stream into a value of type `{}`", ty))
+                            .span(span),
+                    );
+                    return Err(());
+                }
+            };
+            let stream_width: usize = values.iter().map(|v| v.ty.get_bit_size().unwrap()).sum();
+            if width != stream_width {
+                builder.cx.emit(
+                    DiagBuilder2::warning(format!(
+                        "streaming concatenation of {} bits {} to {} bits",
+                        stream_width,
+                        if width > stream_width {
+                            "zero-filled"
+                        } else {
+                            "truncated"
+                        },
+                        width
+                    ))
+                    .code(DiagCode::STREAM_WIDTH_MISMATCH)
+                    .span(span),
+                );
+            }
+
+            Ok(builder.build(ty, RvalueKind::StreamConcat { dir, slice, values }))
+        }
+
         hir::ExprKind::Index(target, mode) => {
             let (base, length) = compute_indexing(cx, builder.expr, env, mode)?;
 
@@ -441,68 +602,34 @@ fn lower_expr_inner<'gcx>(
         | hir::ExprKind::Builtin(hir::BuiltinCall::Unsigned(expr)) => Ok(cx.mir_rvalue(expr, env)),
 
         hir::ExprKind::Inside(expr, ref ranges) => {
-            // By default nothing matches.
-            let mut check = builder.build(
-                ty,
-                RvalueKind::Const(cx.intern_value(value::make_int(ty, Zero::zero()))),
-            );
-
-            // Determine the intermediate type for the comparisons.
+            // Determine the intermediate type for the comparisons. The operand
+            // and all set members are cast to this type.
             let comp_ty = cx.need_operation_type(expr_id, env);
+            let sbvt = comp_ty.simple_bit_vector(cx, span);
 
-            // Determine the result type for the comparison.
-            let out_ty = cx.cast_type(expr_id, env).unwrap().init;
-
-            // Compare the LHS against all ranges.
-            let lhs = cx.mir_rvalue(expr, env);
-            for r in ranges {
-                let arg = match r.value {
+            // Lower the operand and the set members.
+            let value = cx.mir_rvalue(expr, env);
+            let members = ranges
+                .iter()
+                .map(|r| match r.value {
                     hir::InsideRange::Single(expr) => {
-                        // Check if the value matches the LHS.
-                        let expr_rv = cx.mir_rvalue(expr, env);
-                        make_int_comparison(
-                            &builder.with(expr),
-                            out_ty,
-                            comp_ty,
-                            IntCompOp::Eq,
-                            lhs,
-                            expr_rv,
-                        )
-                    }
-                    hir::InsideRange::Range(lo, hi) => {
-                        // Check if the LHS is within [lo:hi], inclusive.
-                        let lo_rv = cx.mir_rvalue(lo, env);
-                        let hi_rv = cx.mir_rvalue(hi, env);
-                        let lo_chk = make_int_comparison(
-                            &builder.with(lo),
-                            out_ty,
-                            comp_ty,
-                            IntCompOp::Geq,
-                            lhs,
-                            lo_rv,
-                        );
-                        let hi_chk = make_int_comparison(
-                            &builder.with(hi),
-                            out_ty,
-                            comp_ty,
-                            IntCompOp::Leq,
-                            lhs,
-                            hi_rv,
-                        );
-                        make_binary_bitwise(
-                            builder,
-                            ty,
-                            BinaryBitwiseOp::And,
-                            false,
-                            lo_chk,
-                            hi_chk,
-                        )
+                        InsideMember::Single(cx.mir_rvalue(expr, env))
                     }
-                };
-                check = make_binary_bitwise(builder, ty, BinaryBitwiseOp::Or, false, check, arg);
-            }
+                    hir::InsideRange::Range(lo, hi) => InsideMember::Range(
+                        lo.map(|lo| cx.mir_rvalue(lo, env)),
+                        hi.map(|hi| cx.mir_rvalue(hi, env)),
+                    ),
+                })
+                .collect();
 
-            Ok(check)
+            Ok(builder.build(
+                ty,
+                RvalueKind::Inside {
+                    value,
+                    members,
+                    sign: sbvt.sign,
+                },
+            ))
         }
 
         hir::ExprKind::FunctionCall(target, args) => {
@@ -517,6 +644,21 @@ fn lower_expr_inner<'gcx>(
     }
 }
 
+/// Check whether an expression is a direct operand of a concatenation.
+fn is_within_concat<'a>(cx: &impl Context<'a>, node_id: NodeId) -> bool {
+    let parent = match cx.parent_node_id(node_id) {
+        Some(parent) => parent,
+        None => return false,
+    };
+    match cx.hir_of(parent) {
+        Ok(HirNode::Expr(expr)) => match expr.kind {
+            hir::ExprKind::Concat(..) | hir::ExprKind::StreamConcat { .. } => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 /// Compute the base and length of an indexing operation.
 ///
 /// Determine the index of the LSB and the width of the selection. Note that
@@ -680,6 +822,12 @@ fn lower_cast<'gcx>(
             CastOp::Range(range, signed) => {
                 assert_span!(value.ty.is_simple_bit_vector(), value.span, builder.cx);
                 assert_span!(to.is_simple_bit_vector(), value.span, builder.cx);
+                // Unbased unsized literals fill the entire target type, rather
+                // than being extended or truncated.
+                if let RvalueKind::Fill(bit) = value.kind {
+                    value = builder.build(to, RvalueKind::Fill(bit));
+                    continue;
+                }
                 let kind = if value.ty.simple_bit_vector(builder.cx, value.span).size < range.size {
                     match signed {
                         true => RvalueKind::SignExtend(range.size, value),
@@ -780,9 +928,15 @@ fn pack_struct<'a>(
     strukt: &'a ty::StructType<'a>,
     to: &'a UnpackedType<'a>,
 ) -> &'a Rvalue<'a> {
-    // Pack each of the fields.
+    // Pack each of the fields. The members of a union all alias the same
+    // bits, so packing the first one suffices.
     let mut packed_fields = vec![];
-    for (i, field) in strukt.members.iter().enumerate() {
+    let num_fields = if strukt.is_union() {
+        1
+    } else {
+        strukt.members.len()
+    };
+    for (i, field) in strukt.members.iter().enumerate().take(num_fields) {
         let field_value = builder.build(field.ty, RvalueKind::Member { value, field: i });
         let field_value = pack_simple_bit_vector(builder, field_value);
         packed_fields.push(field_value);
@@ -846,6 +1000,14 @@ fn pack_array<'a>(
     builder.build(to, RvalueKind::Concat(packed_elements))
 }
 
+/// Determine the time unit in effect for a node, in seconds.
+///
+/// This is the unit declared via `timeunit` in the enclosing module, if any.
+pub fn timeunit_of<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Option<BigRational> {
+    let module = cx.enclosing_module(node_id)?;
+    cx.hir_of_module(module).ok()?.timeunit.clone()
+}
+
 /// Generate the nodes necessary to unpack a value from its corresponding simple
 /// bit vector type.
 fn unpack_simple_bit_vector<'a>(
@@ -859,17 +1021,21 @@ fn unpack_simple_bit_vector<'a>(
     if to.coalesces_to_llhd_scalar() {
         builder.build(to, RvalueKind::Transmute(value))
     } else if to.get_packed().map(|ty| ty.is_time()).unwrap_or(false) {
-        builder.cx.emit(
-            DiagBuilder2::warning(format!(
-                "assuming `1ns` timescale for conversion from `{}` to `time`",
-                value.ty
-            ))
-            .span(value.span),
-        );
-        builder.build(
-            to,
-            RvalueKind::ApplyTimescale(value, BigRational::new(1.into(), 1_000_000_000.into())),
-        )
+        let scale = match timeunit_of(builder.cx, builder.expr) {
+            Some(scale) => scale,
+            None => {
+                builder.cx.emit(
+                    DiagBuilder2::warning(format!(
+                        "assuming `1ns` timescale for conversion from `{}` to `time`",
+                        value.ty
+                    ))
+                    .code(DiagCode::ASSUMED_TIMESCALE)
+                    .span(value.span),
+                );
+                BigRational::new(1.into(), 1_000_000_000.into())
+            }
+        };
+        builder.build(to, RvalueKind::ApplyTimescale(value, scale))
     } else if let Some(dim) = to.outermost_dim() {
         unpack_array(builder, value, to, dim)
     } else if let Some(strukt) = to.get_struct() {
@@ -908,7 +1074,9 @@ fn unpack_struct<'a>(
         );
         let value = unpack_simple_bit_vector(builder, value, field.ty);
         unpacked_fields.push(value);
-        offset += w;
+        if !strukt.is_union() {
+            offset += w;
+        }
     }
 
     // Construct the struct.
@@ -994,27 +1162,71 @@ fn lower_pattern<'a>(
 
     // Construct the correct output value.
     if ty.coalesces_to_llhd_scalar() {
-        if values.len() == 1 {
+        return if values.len() == 1 {
             values[0]
         } else {
             builder.build(ty, RvalueKind::Concat(values))
-        }
-    } else if ty.outermost_dim().is_some() {
-        builder.build(
-            ty,
-            // TODO: This should rather be a Vec<>.
-            RvalueKind::ConstructArray(values.into_iter().enumerate().collect()),
-        )
-    } else if ty.get_struct().is_some() {
-        builder.build(ty, RvalueKind::ConstructStruct(values))
-    } else {
+        };
+    }
+    let is_array = ty.outermost_dim().is_some();
+    if !is_array && ty.get_struct().is_none() {
         bug_span!(
             builder.span,
             builder.cx,
             "positional pattern with invalid type `{}`",
             ty
+        );
+    }
+
+    // If the pattern has a `default:` or type key, build the aggregate with
+    // the defaults and the type's default value in the explicitly mapped
+    // fields, and then apply the explicit fields as updates on top.
+    let has_default = map.explicit.iter().any(|&explicit| !explicit);
+    let base_values: Vec<_> = values
+        .iter()
+        .zip(&map.explicit)
+        .map(|(&value, &explicit)| {
+            if has_default && explicit {
+                builder.build(
+                    value.ty,
+                    RvalueKind::Const(builder.cx.type_default_value(value.ty)),
+                )
+            } else {
+                value
+            }
+        })
+        .collect();
+    let mut result = if is_array {
+        builder.build(
+            ty,
+            // TODO: This should rather be a Vec<>.
+            RvalueKind::ConstructArray(base_values.into_iter().enumerate().collect()),
         )
+    } else {
+        builder.build(ty, RvalueKind::ConstructStruct(base_values))
+    };
+    if has_default {
+        for (index, (&new_value, &explicit)) in values.iter().zip(&map.explicit).enumerate() {
+            if !explicit {
+                continue;
+            }
+            let kind = if is_array {
+                RvalueKind::UpdateIndex {
+                    value: result,
+                    index,
+                    new_value,
+                }
+            } else {
+                RvalueKind::UpdateMember {
+                    value: result,
+                    field: index,
+                    new_value,
+                }
+            };
+            result = builder.build(ty, kind);
+        }
     }
+    result
 }
 
 /// Map a unary operator to MIR.
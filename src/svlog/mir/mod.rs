@@ -8,6 +8,7 @@
 #![deny(missing_docs)]
 
 mod assign;
+mod dump;
 pub mod lower;
 mod lvalue;
 mod rvalue;
@@ -117,6 +117,7 @@ impl<'a> Print for Rvalue<'a> {
                 ctx.print_comma_separated(outer, args),
             )?,
             RvalueKind::Const(arg) => write!(inner, "{}", arg)?,
+            RvalueKind::Fill(bit) => write!(inner, "Fill({})", bit)?,
             RvalueKind::UnaryBitwise { op, arg } => {
                 write!(inner, "UnaryBitwise {:?} {}", op, ctx.print(outer, arg))?
             }
@@ -173,6 +174,17 @@ impl<'a> Print for Rvalue<'a> {
             RvalueKind::Concat(ref args) => {
                 write!(inner, "Concat({})", ctx.print_comma_separated(outer, args))?
             }
+            RvalueKind::StreamConcat {
+                dir,
+                slice,
+                ref values,
+            } => write!(
+                inner,
+                "StreamConcat {:?} {} ({})",
+                dir,
+                slice,
+                ctx.print_comma_separated(outer, values)
+            )?,
             RvalueKind::Repeat(num, arg) => {
                 write!(inner, "Repeat({} x {})", num, ctx.print(outer, arg))?
             }
@@ -208,6 +220,39 @@ impl<'a> Print for Rvalue<'a> {
             RvalueKind::Member { value, field } => {
                 write!(inner, "{}.{}", ctx.print(outer, value), field)?
             }
+            RvalueKind::UpdateMember {
+                value,
+                field,
+                new_value,
+            } => write!(
+                inner,
+                "UpdateMember {} with .{} = {}",
+                ctx.print(outer, value),
+                field,
+                ctx.print(outer, new_value)
+            )?,
+            RvalueKind::UpdateIndex {
+                value,
+                index,
+                new_value,
+            } => write!(
+                inner,
+                "UpdateIndex {} with [{}] = {}",
+                ctx.print(outer, value),
+                index,
+                ctx.print(outer, new_value)
+            )?,
+            RvalueKind::Inside {
+                value,
+                ref members,
+                sign,
+            } => write!(
+                inner,
+                "{} inside {{{}}} ({:?})",
+                ctx.print(outer, value),
+                ctx.print_comma_separated(outer, members),
+                sign
+            )?,
             RvalueKind::Ternary {
                 cond,
                 true_value,
@@ -311,6 +356,9 @@ pub enum RvalueKind<'a> {
     ConstructStruct(Vec<&'a Rvalue<'a>>),
     /// A constant value.
     Const(value::Value<'a>),
+    /// An unbased unsized literal such as `'1`, which sets every bit of the
+    /// rvalue's type to the same value.
+    Fill(FillBit),
     /// A unary bitwise operator.
     UnaryBitwise {
         op: UnaryBitwiseOp,
@@ -363,6 +411,17 @@ pub enum RvalueKind<'a> {
     /// left-most item in the concatenation, which is at the MSB end of the
     /// final packed bit vector.
     Concat(Vec<&'a Rvalue<'a>>),
+    /// A streaming concatenation, e.g. `{<<8{a, b}}`.
+    ///
+    /// The values are concatenated as for `Concat`. A right-to-left stream
+    /// (`<<`) then reverses the order of the `slice`-bit chunks of the result,
+    /// taking chunks starting at the LSB. The streamed bits are left-aligned
+    /// in the result type, and zero-filled or truncated on the right.
+    StreamConcat {
+        dir: ast::StreamDir,
+        slice: usize,
+        values: Vec<&'a Rvalue<'a>>,
+    },
     /// Repeat a value multiple times.
     ///
     /// The value is cast to and treated as a packed bit vector, and the result
@@ -388,6 +447,29 @@ pub enum RvalueKind<'a> {
     },
     /// A struct field access.
     Member { value: &'a Rvalue<'a>, field: usize },
+    /// A copy of a struct with one field replaced.
+    UpdateMember {
+        value: &'a Rvalue<'a>,
+        field: usize,
+        new_value: &'a Rvalue<'a>,
+    },
+    /// A copy of an array with one element replaced.
+    UpdateIndex {
+        value: &'a Rvalue<'a>,
+        /// Index of the element, counted from zero regardless of the range.
+        index: usize,
+        new_value: &'a Rvalue<'a>,
+    },
+    /// A set membership check, e.g. `a inside {b, [c:d]}`.
+    ///
+    /// The operand and all members have the same type. Single values are
+    /// compared using wildcard equality, such that x and z bits in the member
+    /// match any bit of the operand.
+    Inside {
+        value: &'a Rvalue<'a>,
+        members: Vec<InsideMember<'a>>,
+        sign: Sign,
+    },
     /// The ternary operator.
     Ternary {
         cond: &'a Rvalue<'a>,
@@ -465,7 +547,7 @@ impl<'a> RvalueKind<'a> {
             | RvalueKind::ApplyTimescale(value, _) => value.is_const(),
             RvalueKind::ConstructArray(values) => values.values().all(|v| v.is_const()),
             RvalueKind::ConstructStruct(values) => values.iter().all(|v| v.is_const()),
-            RvalueKind::Const(_) => true,
+            RvalueKind::Const(_) | RvalueKind::Fill(_) => true,
             RvalueKind::UnaryBitwise { arg, .. }
             | RvalueKind::IntUnaryArith { arg, .. }
             | RvalueKind::Reduction { arg, .. } => arg.is_const(),
@@ -473,7 +555,9 @@ impl<'a> RvalueKind<'a> {
             | RvalueKind::IntBinaryArith { lhs, rhs, .. }
             | RvalueKind::IntComp { lhs, rhs, .. }
             | RvalueKind::StringComp { lhs, rhs, .. } => lhs.is_const() && rhs.is_const(),
-            RvalueKind::Concat(values) => values.iter().all(|v| v.is_const()),
+            RvalueKind::Concat(values) | RvalueKind::StreamConcat { values, .. } => {
+                values.iter().all(|v| v.is_const())
+            }
             RvalueKind::Var(_) => false,
             RvalueKind::Port(_) => false,
             RvalueKind::Arg(_) => false,
@@ -481,12 +565,27 @@ impl<'a> RvalueKind<'a> {
             RvalueKind::IntfSignal(..) => false,
             RvalueKind::Index { .. } => false, // TODO(fschuiki): reactivate once impl
             // RvalueKind::Index { value, base, .. } => value.is_const() && base.is_const(),
+            RvalueKind::Inside { value, members, .. } => {
+                value.is_const()
+                    && members.iter().all(|m| match *m {
+                        InsideMember::Single(v) => v.is_const(),
+                        InsideMember::Range(lo, hi) => {
+                            lo.into_iter().chain(hi).all(|v| v.is_const())
+                        }
+                    })
+            }
             RvalueKind::Ternary {
                 cond,
                 true_value,
                 false_value,
             } => cond.is_const() && true_value.is_const() && false_value.is_const(),
             RvalueKind::Shift { value, amount, .. } => value.is_const() && amount.is_const(),
+            RvalueKind::UpdateMember {
+                value, new_value, ..
+            }
+            | RvalueKind::UpdateIndex {
+                value, new_value, ..
+            } => value.is_const() && new_value.is_const(),
             RvalueKind::Assignment { .. } => false,
             // TODO(fschuiki): This is wrong; function calls *may* be constant
             // under certain circumstances.
@@ -566,6 +665,28 @@ pub enum ShiftOp {
     Right,
 }
 
+/// The bit value an unbased unsized literal fills its type with.
+#[moore_derive::visit_without_foreach]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum FillBit {
+    Zero,
+    One,
+    X,
+    Z,
+}
+
+impl std::fmt::Display for FillBit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Zero => write!(f, "'0"),
+            Self::One => write!(f, "'1"),
+            Self::X => write!(f, "'x"),
+            Self::Z => write!(f, "'z"),
+        }
+    }
+}
+
 /// A call input argument.
 #[moore_derive::visit_without_foreach]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -590,6 +711,42 @@ impl<'a> Print for CallInput<'a> {
     }
 }
 
+/// A member of an `inside` set.
+#[moore_derive::visit_without_foreach]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsideMember<'a> {
+    /// A single value.
+    Single(&'a Rvalue<'a>),
+    /// An inclusive range `[lo:hi]`. Open bounds `$` are `None`.
+    Range(Option<&'a Rvalue<'a>>, Option<&'a Rvalue<'a>>),
+}
+
+impl<'a> Print for InsideMember<'a> {
+    fn print_context(
+        &self,
+        outer: &mut impl Write,
+        inner: &mut impl Write,
+        ctx: &mut Context,
+    ) -> std::fmt::Result {
+        match self {
+            Self::Single(x) => x.print_context(outer, inner, ctx),
+            Self::Range(lo, hi) => {
+                write!(inner, "[")?;
+                match lo {
+                    Some(lo) => lo.print_context(outer, inner, ctx)?,
+                    None => write!(inner, "$")?,
+                }
+                write!(inner, ":")?;
+                match hi {
+                    Some(hi) => hi.print_context(outer, inner, ctx)?,
+                    None => write!(inner, "$")?,
+                }
+                write!(inner, "]")
+            }
+        }
+    }
+}
+
 /// A call argument.
 #[moore_derive::visit_without_foreach]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
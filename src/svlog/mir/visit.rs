@@ -6,6 +6,7 @@ use super::*;
 use crate::{
     common::{source::Span, NodeId},
     param_env::ParamEnv,
+    syntax::ast,
     ty, value,
 };
 use std::collections::HashMap;
@@ -30,6 +31,7 @@ impl<'a> WalkVisitor<'a> for Span {}
 impl<'a> WalkVisitor<'a> for ty::UnpackedType<'a> {}
 impl<'a> WalkVisitor<'a> for ty::Sign {}
 impl<'a> WalkVisitor<'a> for ty::Domain {}
+impl<'a> WalkVisitor<'a> for ast::StreamDir {}
 impl<'a> WalkVisitor<'a> for value::Value<'_> {}
 impl<'a> WalkVisitor<'a> for num::BigRational {}
 
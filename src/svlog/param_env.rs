@@ -4,6 +4,7 @@
 
 use crate::{
     ast_map::AstNode,
+    common::DEFAULT_MAX_CONTEXT_NOTES,
     crate_prelude::*,
     hir::{NamedParam, PosParam},
     ty::UnpackedType,
@@ -84,6 +85,14 @@ pub struct ParamEnvData<'t> {
 }
 
 impl<'t> ParamEnvData<'t> {
+    /// Create an empty parameter environment for a module.
+    pub fn new_for_module(module: NodeId) -> Self {
+        ParamEnvData {
+            module: Some(module),
+            ..Default::default()
+        }
+    }
+
     /// Find the value assigned to a node.
     pub fn find_value(&self, node_id: NodeId) -> Option<ParamEnvBinding<Value<'t>>> {
         self.values
@@ -108,6 +117,18 @@ impl<'t> ParamEnvData<'t> {
             .map(|&(_, id)| id)
     }
 
+    /// Get the values assigned to nodes.
+    pub fn values(&self) -> impl Iterator<Item = (NodeId, ParamEnvBinding<Value<'t>>)> + '_ {
+        self.values.iter().cloned()
+    }
+
+    /// Get the types assigned to nodes.
+    pub fn types(
+        &self,
+    ) -> impl Iterator<Item = (NodeId, ParamEnvBinding<&'t UnpackedType<'t>>)> + '_ {
+        self.types.iter().cloned()
+    }
+
     /// Find the node assigned to a value parameter.
     pub fn reverse_find_value(&self, node_id: NodeId) -> Option<NodeId> {
         self.values
@@ -215,6 +236,20 @@ fn param_env_from_instance<'a>(
     pos: &[PosParam],
     named: &[NamedParam],
 ) -> Result<ParamEnv> {
+    // Positional and named assignments cannot be mixed. See §23.10.2.
+    if let (Some(&(pos_span, _)), Some(&(named_span, _, _))) = (pos.first(), named.first()) {
+        cx.emit(
+            DiagBuilder2::error(format!(
+                "cannot mix positional and named parameter assignments to {}",
+                node
+            ))
+            .span(named_span)
+            .add_note("First positional assignment is here:")
+            .span(pos_span),
+        );
+        return Err(());
+    }
+
     // Associate the positional and named assignments with the actual
     // parameters of the module.
     let param_iter = pos
@@ -298,3 +333,236 @@ fn param_env_from_instance<'a>(
     cx.add_param_env_context(env, node.id());
     Ok(env)
 }
+
+/// A parameter that is bound differently in two parameter environments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamDiff {
+    /// The parameter.
+    pub param: NodeId,
+    /// The name of the parameter.
+    pub name: Name,
+    /// The binding in the first environment, if any.
+    pub a: Option<String>,
+    /// The binding in the second environment, if any.
+    pub b: Option<String>,
+}
+
+/// Render the bindings of a parameter environment as text.
+///
+/// Produces a list such as `N = 8, T = logic [7:0]`, ordered by the position
+/// of the parameter declarations. Directly bound values and types are
+/// printed as such, indirect bindings as the source text of the assigned
+/// expression.
+pub fn param_env_text<'a>(cx: &impl Context<'a>, env: ParamEnv) -> String {
+    param_env_bindings(cx, env)
+        .into_iter()
+        .map(|(_, name, text)| format!("{} = {}", name, text))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Determine the parameters that are bound differently in two parameter
+/// environments.
+///
+/// Bindings are compared by their textual form as produced by
+/// `param_env_text`. Parameters bound in only one of the environments are
+/// reported as well.
+pub fn param_env_diff<'a>(cx: &impl Context<'a>, a: ParamEnv, b: ParamEnv) -> Vec<ParamDiff> {
+    let a = param_env_bindings(cx, a);
+    let mut b = param_env_bindings(cx, b);
+    let mut diffs = vec![];
+    for (param, name, text) in a {
+        let other = b
+            .iter()
+            .position(|&(id, _, _)| id == param)
+            .map(|i| b.remove(i).2);
+        if other.as_ref() != Some(&text) {
+            diffs.push(ParamDiff {
+                param,
+                name,
+                a: Some(text),
+                b: other,
+            });
+        }
+    }
+    diffs.extend(b.into_iter().map(|(param, name, text)| ParamDiff {
+        param,
+        name,
+        a: None,
+        b: Some(text),
+    }));
+    diffs.sort_by_key(|d| cx.span(d.param));
+    diffs
+}
+
+/// Collect the name and textual form of every binding in a parameter
+/// environment, in declaration order.
+fn param_env_bindings<'a>(cx: &impl Context<'a>, env: ParamEnv) -> Vec<(NodeId, Name, String)> {
+    let env_data = cx.param_env_data(env);
+    let values = env_data.values().map(|(id, binding)| {
+        let text = match binding {
+            ParamEnvBinding::Direct(value) => format!("{}", value),
+            ParamEnvBinding::Indirect(assigned_id) => cx.span(assigned_id.id()).extract(),
+        };
+        (id, text)
+    });
+    let types = env_data.types().map(|(id, binding)| {
+        let text = match binding {
+            ParamEnvBinding::Direct(ty) => format!("{}", ty),
+            ParamEnvBinding::Indirect(assigned_id) => cx.span(assigned_id.id()).extract(),
+        };
+        (id, text)
+    });
+    let mut bindings: Vec<_> = values
+        .chain(types)
+        .flat_map(|(id, text)| {
+            let name = match cx.hir_of(id) {
+                Ok(HirNode::ValueParam(param)) => param.name.value,
+                Ok(HirNode::TypeParam(param)) => param.name.value,
+                Ok(HirNode::GenvarDecl(decl)) => decl.name.value,
+                _ => return None,
+            };
+            Some((id, name, text))
+        })
+        .collect();
+    bindings.sort_by_key(|&(id, _, _)| cx.span(id));
+    bindings
+}
+
+/// Where the final value of a parameter comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamOrigin {
+    /// The default value given in the parameter declaration.
+    Default,
+    /// A parameter assignment at the instantiation.
+    InstanceOverride(Span),
+    /// A `defparam` statement.
+    Defparam(Span),
+    /// A value bound directly in the environment, such as a parameter
+    /// overridden on the command line.
+    Direct,
+    /// The default value of the parameter's type, since the parameter has
+    /// neither a default nor an assignment.
+    TypeDefault,
+}
+
+impl std::fmt::Display for ParamOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ParamOrigin::Default => write!(f, "default"),
+            ParamOrigin::InstanceOverride(span) => {
+                write!(f, "instance override `{}`", span.extract())
+            }
+            ParamOrigin::Defparam(span) => write!(f, "defparam `{}`", span.extract()),
+            ParamOrigin::Direct => write!(f, "override"),
+            ParamOrigin::TypeDefault => write!(f, "type default"),
+        }
+    }
+}
+
+/// The final binding of a value parameter in a parameter environment.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolvedParam<'a> {
+    /// The parameter.
+    pub param: NodeId,
+    /// The name of the parameter.
+    pub name: Name,
+    /// The final value of the parameter.
+    pub value: Value<'a>,
+    /// Where the value comes from.
+    pub origin: ParamOrigin,
+}
+
+/// Resolve the final value of every value parameter of a module.
+///
+/// The bindings in `param_env_data(env)` take precedence. Parameters without a
+/// binding fall back to the default value in their declaration, or the default
+/// value of their type if they have none. The parameters are returned in
+/// declaration order, including localparams.
+///
+/// `defparam` statements are not applied during elaboration, such that
+/// `ParamOrigin::Defparam` is currently never produced.
+pub fn resolved_params<'a>(
+    cx: &impl Context<'a>,
+    module: NodeId,
+    env: ParamEnv,
+) -> Result<Vec<ResolvedParam<'a>>> {
+    let module = match cx.hir_of(module)? {
+        HirNode::Module(x) => x,
+        x => bug_span!(x.span(), cx, "{} is not a module", x.desc_full()),
+    };
+    let env_data = cx.param_env_data(env);
+    let mut resolved = vec![];
+    for &id in module.params.iter().chain(module.block.params.iter()) {
+        let param = match cx.hir_of(id)? {
+            HirNode::ValueParam(p) => p,
+            _ => continue,
+        };
+        let (value, origin) = match (env_data.find_value(id), param.default) {
+            (Some(ParamEnvBinding::Indirect(assigned_id)), _) => (
+                cx.constant_value_of(assigned_id.id(), assigned_id.env()),
+                ParamOrigin::InstanceOverride(cx.span(assigned_id.id())),
+            ),
+            (Some(ParamEnvBinding::Direct(value)), _) => (value, ParamOrigin::Direct),
+            (None, Some(default)) => (cx.constant_value_of(default, env), ParamOrigin::Default),
+            (None, None) => (
+                cx.type_default_value(cx.type_of(id, env)?),
+                ParamOrigin::TypeDefault,
+            ),
+        };
+        resolved.push(ResolvedParam {
+            param: id,
+            name: param.name.value,
+            value,
+            origin,
+        });
+    }
+    Ok(resolved)
+}
+
+/// Render resolved parameters as text, one per line.
+///
+/// Produces lines such as `N = 32'sd8 (instance override `8`)`, each prefixed
+/// with `prefix`.
+pub fn resolved_params_text(params: &[ResolvedParam], prefix: &str) -> String {
+    params
+        .iter()
+        .map(|p| format!("{}{} = {:#} ({})\n", prefix, p.name, p.value, p.origin))
+        .collect()
+}
+
+/// Emit a diagnostic that arises in a parameter environment.
+///
+/// The diagnostic is emitted once, no matter how many instantiations share the
+/// environment. The `note` function produces a note and span for each
+/// instantiation site, or `None` to omit it. Only the first few sites are
+/// listed, as configured by the session's `max_context_notes`; the remaining
+/// ones are summarized in a final note.
+pub(crate) fn emit_in_env_contexts<'a>(
+    cx: &impl Context<'a>,
+    diag: DiagBuilder2,
+    env: ParamEnv,
+    note: impl Fn(NodeId) -> Option<(String, Span)>,
+) {
+    let max = cx
+        .sess()
+        .opts
+        .max_context_notes
+        .unwrap_or(DEFAULT_MAX_CONTEXT_NOTES);
+    let notes: Vec<_> = cx
+        .param_env_contexts(env)
+        .into_iter()
+        .flat_map(|context| note(context))
+        .collect();
+    let mut diag = diag;
+    for (msg, span) in notes.iter().take(max) {
+        diag = diag.add_note(msg.clone()).span(*span);
+    }
+    if notes.len() > max {
+        diag = diag.add_note(format!(
+            "... and {} more instantiation sites",
+            notes.len() - max
+        ));
+    }
+    cx.emit(diag);
+}
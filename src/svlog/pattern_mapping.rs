@@ -18,6 +18,9 @@ pub struct PatternMapping<'a> {
     /// The mapped expression for each field. The fields are in type order.
     /// Multiple fields may be assigned the same expression.
     pub fields: Vec<(PatternField<'a>, &'a hir::Expr<'a>)>,
+    /// Whether each field was assigned explicitly, as opposed to being filled
+    /// in by a `default:` or type key.
+    pub explicit: Vec<bool>,
 }
 
 /// A field correspondence in a mapped pattern.
@@ -61,7 +64,7 @@ pub(crate) fn map_pattern<'a>(
     let ty = ty.ty();
 
     // Then handle the different pattern styles.
-    let fields = match expr.kind {
+    let (fields, explicit) = match expr.kind {
         hir::ExprKind::PositionalPattern(ref mapping) => {
             map_positional_pattern(cx, mapping, 1, ty, expr.span)?
         }
@@ -107,6 +110,7 @@ pub(crate) fn map_pattern<'a>(
         hir: expr,
         ty,
         fields,
+        explicit,
     }))
 }
 
@@ -127,7 +131,7 @@ fn map_named_array_pattern<'a>(
     dim: ty::Dim<'a>,
     span: Span,
     env: ParamEnv,
-) -> Result<Vec<(PatternField<'a>, &'a hir::Expr<'a>)>> {
+) -> Result<(Vec<(PatternField<'a>, &'a hir::Expr<'a>)>, Vec<bool>)> {
     // Determine the length of the array and the offset of the indexes.
     let (length, offset) = match dim
         .get_range()
@@ -149,6 +153,7 @@ fn map_named_array_pattern<'a>(
     // Map things.
     let mut failed = false;
     let mut default: Option<&hir::Expr> = None;
+    let mut type_default: Option<&hir::Expr> = None;
     let mut values = HashMap::<usize, (PatternField, &hir::Expr)>::new();
 
     for &(map, to) in mapping {
@@ -161,10 +166,42 @@ fn map_named_array_pattern<'a>(
         };
         match map {
             hir::PatternMapping::Type(type_id) => {
-                cx.emit(
-                    DiagBuilder2::error("types cannot index into an array").span(cx.span(type_id)),
+                // A type key applies to all elements of a matching type.
+                let key_ty = cx.packed_type_from_ast(
+                    Ref(cx.ast_for_id(type_id).as_all().get_type().unwrap()),
+                    env,
+                    None,
                 );
-                continue;
+                if key_ty.is_error() {
+                    failed = true;
+                    continue;
+                }
+                if !key_ty.is_strictly_identical(elem_ty) {
+                    cx.emit(
+                        DiagBuilder2::error(format!(
+                            "type key `{}` does not match the element type `{}` of the array",
+                            cx.span(type_id).extract(),
+                            elem_ty
+                        ))
+                        .span(cx.span(type_id)),
+                    );
+                    failed = true;
+                    continue;
+                }
+                if let Some(prev) = type_default.replace(to) {
+                    cx.emit(
+                        DiagBuilder2::warning(format!(
+                            "`{}` overwrites previous value `{}` for type `{}`",
+                            to.span.extract(),
+                            prev.span.extract(),
+                            cx.span(type_id).extract()
+                        ))
+                        .code(DiagCode::PATTERN_OVERWRITE)
+                        .span(to.span)
+                        .add_note("Previous value was here:")
+                        .span(prev.span),
+                    );
+                }
             }
             hir::PatternMapping::Member(member_id) => {
                 // Determine the index for the mapping.
@@ -210,6 +247,7 @@ fn map_named_array_pattern<'a>(
                             prev.span.extract(),
                             index
                         ))
+                        .code(DiagCode::PATTERN_OVERWRITE)
                         .span(to.span)
                         .add_note("Previous value was here:")
                         .span(prev.span),
@@ -235,9 +273,10 @@ fn map_named_array_pattern<'a>(
     }
 
     // In case the list of indices provided by the user is incomplete, use the
-    // default to fill in the other elements.
+    // type key or the default to fill in the other elements.
+    let explicit = (0..length).map(|i| values.contains_key(&i)).collect();
     let values: Vec<_> = if values.len() != length {
-        let default = if let Some(default) = default {
+        let default = if let Some(default) = type_default.or(default) {
             default
         } else {
             cx.emit(
@@ -262,7 +301,7 @@ fn map_named_array_pattern<'a>(
     if failed {
         Err(())
     } else {
-        Ok(values)
+        Ok((values, explicit))
     }
 }
 
@@ -274,7 +313,7 @@ fn map_named_struct_pattern<'a>(
     strukt: &'a ty::StructType<'a>,
     span: Span,
     env: ParamEnv,
-) -> Result<Vec<(PatternField<'a>, &'a hir::Expr<'a>)>> {
+) -> Result<(Vec<(PatternField<'a>, &'a hir::Expr<'a>)>, Vec<bool>)> {
     // Determine the field names and types for the struct to be assembled.
     let name_lookup: HashMap<Name, usize> = strukt
         .members
@@ -308,7 +347,20 @@ fn map_named_struct_pattern<'a>(
                     failed = true;
                     continue;
                 }
-                type_defaults.insert(ty.resolve_full(), to);
+                if let Some(prev) = type_defaults.insert(ty.resolve_full(), to) {
+                    cx.emit(
+                        DiagBuilder2::warning(format!(
+                            "`{}` overwrites previous value `{}` for type `{}`",
+                            to.span.extract(),
+                            prev.span.extract(),
+                            cx.span(type_id).extract()
+                        ))
+                        .code(DiagCode::PATTERN_OVERWRITE)
+                        .span(to.span)
+                        .add_note("Previous value was here:")
+                        .span(prev.span),
+                    );
+                }
             }
             hir::PatternMapping::Member(member_id) => match cx.hir_of(member_id) {
                 Ok(HirNode::Expr(&hir::Expr {
@@ -340,6 +392,7 @@ fn map_named_struct_pattern<'a>(
                                 prev.span.extract(),
                                 name
                             ))
+                            .code(DiagCode::PATTERN_OVERWRITE)
                             .span(to.span)
                             .add_note("Previous value was here:")
                             .span(prev.span),
@@ -388,6 +441,9 @@ fn map_named_struct_pattern<'a>(
 
     // In case the list of members provided by the user is incomplete, use the
     // defaults to fill in the other members.
+    let explicit = (0..strukt.members.len())
+        .map(|i| values.contains_key(&i))
+        .collect();
     for (index, field) in strukt.members.iter().enumerate() {
         if values.contains_key(&index) {
             continue;
@@ -438,7 +494,7 @@ fn map_named_struct_pattern<'a>(
     if failed {
         Err(())
     } else {
-        Ok((0..values.len()).map(|i| values[&i]).collect())
+        Ok(((0..values.len()).map(|i| values[&i]).collect(), explicit))
     }
 }
 
@@ -449,7 +505,7 @@ fn map_positional_pattern<'a>(
     repeat: usize,
     ty: &'a ty::UnpackedType<'a>,
     span: Span,
-) -> Result<Vec<(PatternField<'a>, &'a hir::Expr<'a>)>> {
+) -> Result<(Vec<(PatternField<'a>, &'a hir::Expr<'a>)>, Vec<bool>)> {
     // Lower each of the values to HIR, and abort on errors.
     let values: Result<Vec<_>> = mapping.iter().map(|&id| hir_of_expr(cx, id)).collect();
     let values = values?;
@@ -518,5 +574,5 @@ fn map_positional_pattern<'a>(
         return Err(());
     }
 
-    Ok(result)
+    Ok((result, vec![true; len]))
 }
@@ -170,6 +170,11 @@ pub trait PortedNode<'a>: ast::AnyNode<'a> + resolver::ScopedNode<'a> {
 
     /// Get the items of the node.
     fn items(&self) -> &[ast::Item<'a>];
+
+    /// Get the net type of implicitly declared nets in the node.
+    ///
+    /// This is `None` if the node was declared under `default_nettype none`.
+    fn default_nettype(&self) -> Option<ast::NetType>;
 }
 
 impl<'a> PortedNode<'a> for ast::Module<'a> {
@@ -179,6 +184,9 @@ impl<'a> PortedNode<'a> for ast::Module<'a> {
     fn items(&self) -> &[ast::Item<'a>] {
         &self.items
     }
+    fn default_nettype(&self) -> Option<ast::NetType> {
+        self.default_nettype
+    }
 }
 
 impl<'a> PortedNode<'a> for ast::Interface<'a> {
@@ -188,6 +196,9 @@ impl<'a> PortedNode<'a> for ast::Interface<'a> {
     fn items(&self) -> &[ast::Item<'a>] {
         &self.items
     }
+    fn default_nettype(&self) -> Option<ast::NetType> {
+        self.default_nettype
+    }
 }
 
 // Compare and hash `PortedNode` by reference for use in the query system.
@@ -247,12 +258,21 @@ pub(crate) fn canonicalize_ports<'a>(
         let first = match ast_ports.first() {
             Some(p) => p,
             None => {
+                // Port declarations in the body require the ports to also be
+                // listed in the header.
+                for item in ast_items {
+                    if let ast::ItemData::PortDecl(pd) = &item.data {
+                        for name in &pd.names {
+                            emit_port_not_listed(cx, name.name, name.name_span, node);
+                        }
+                    }
+                }
                 return cx.gcx().arena.alloc_port_list(PortList {
                     int: Default::default(),
                     ext_pos: Default::default(),
                     ext_named: Default::default(),
                     tail_rib: next_rib,
-                })
+                });
             }
         };
         let nonansi = match first.data {
@@ -292,29 +312,39 @@ pub(crate) fn canonicalize_ports<'a>(
     // Extend the internal port with default sign, port kind, and data type
     // where necessary in order to arrive at a final internal port list.
     let mut ports = vec![];
-    let default_net_type = ast::NetType::Wire; // TODO: Make changeable by directive
+    let default_net_type = node.default_nettype();
 
     for port in partial_ports.int {
         let port_id = port.ast.id();
 
         // Determine the port kind.
-        let kind = port.kind.unwrap_or_else(|| match port.dir {
-            ast::PortDir::Input | ast::PortDir::Inout => ast::VarKind::Net {
-                ty: default_net_type,
-                kind: ast::NetKind::None,
-            },
-            ast::PortDir::Output => {
-                if port.ty.data == ast::ImplicitType {
-                    ast::VarKind::Net {
-                        ty: default_net_type,
-                        kind: ast::NetKind::None,
-                    }
-                } else {
-                    ast::VarKind::Var
+        let implicit_net = match port.dir {
+            ast::PortDir::Input | ast::PortDir::Inout => true,
+            ast::PortDir::Output => port.ty.data == ast::ImplicitType,
+            ast::PortDir::Ref => false,
+        };
+        let kind = match port.kind {
+            Some(kind) => kind,
+            None if implicit_net => {
+                // Implicit nets are illegal under `default_nettype none`. See
+                // §22.8.
+                if default_net_type.is_none() {
+                    cx.emit(
+                        DiagBuilder2::error(format!(
+                            "port `{}` is an implicit net, but `default_nettype` is `none`",
+                            port.name
+                        ))
+                        .span(port.name.span)
+                        .add_note("Declare the port with an explicit net type or `var`."),
+                    );
+                }
+                ast::VarKind::Net {
+                    ty: default_net_type.unwrap_or(ast::NetType::Wire),
+                    kind: ast::NetKind::None,
                 }
             }
-            ast::PortDir::Ref => ast::VarKind::Var,
-        });
+            None => ast::VarKind::Var,
+        };
 
         // Verify that `inout` ports are of net kind, and `ref` ports are of var
         // kind.
@@ -336,6 +366,29 @@ pub(crate) fn canonicalize_ports<'a>(
             _ => (),
         }
 
+        // Verify that a default value is only given for input ports, or as the
+        // initial value of output variables. See §23.2.2.3 and §23.2.2.4.
+        if let Some(default) = port.default {
+            let allowed = match (port.dir, kind) {
+                (ast::PortDir::Input, _) => true,
+                (ast::PortDir::Output, ast::VarKind::Var) => true,
+                _ => false,
+            };
+            if !allowed {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "{} port `{}` cannot have a default value",
+                        port.dir, port.name
+                    ))
+                    .span(default.span)
+                    .add_note(
+                        "Only input ports may have a default value, and only output variables \
+                         may have an initial value.",
+                    ),
+                );
+            }
+        }
+
         // Hook things up in the hierarchy.
         cx.set_ast(port_id, AstNode::Port(port.span));
         cx.set_parent(port_id, next_rib);
@@ -608,6 +661,18 @@ fn lower_node_ports_ansi<'a>(
                 }
             }
 
+            // "interface" ["." ident] ident {dimension}
+            ast::PortData::Intf { name, .. } => {
+                cx.emit(
+                    DiagBuilder2::error(format!("unsupported: generic interface port `{}`", name))
+                        .span(port.span())
+                        .add_note(
+                            "Use the name of the interface instead of the `interface` keyword.",
+                        ),
+                );
+                continue;
+            }
+
             _ => {
                 cx.emit(
                     DiagBuilder2::error("non-ANSI port in ANSI port list")
@@ -1030,6 +1095,16 @@ fn lower_node_ports_nonansi<'a>(
         ext_pos.push(port);
     }
 
+    // Ensure that every port declared in the body also appears in the port
+    // list.
+    let mut listed = vec![false; decl_order.len()];
+    for expr in ext_pos.iter().flat_map(|p| p.exprs.iter()) {
+        listed[expr.port] = true;
+    }
+    for (port, _) in decl_order.iter().zip(listed).filter(|(_, l)| !l) {
+        emit_port_not_listed(cx, port.name.value, port.name.span, node);
+    }
+
     PartialPortList {
         int: decl_order,
         ext_pos,
@@ -1037,6 +1112,27 @@ fn lower_node_ports_nonansi<'a>(
     }
 }
 
+/// Emit an error for a port that is declared in the body of a node but does
+/// not appear in its port list.
+fn emit_port_not_listed<'a>(
+    cx: &impl Context<'a>,
+    name: Name,
+    span: Span,
+    node: &'a dyn PortedNode<'a>,
+) {
+    cx.emit(
+        DiagBuilder2::error(format!(
+            "port `{}` declared in {:#} body but not listed in its port list",
+            name, node
+        ))
+        .span(span)
+        .add_note(format!(
+            "Add `{}` to the port list of {}, or remove the port declaration.",
+            name, node
+        )),
+    );
+}
+
 /// Lower an AST expression as a port expression.
 ///
 /// ```plain
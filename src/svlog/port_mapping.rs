@@ -80,6 +80,20 @@ pub(crate) fn port_mapping<'a>(
     );
     let port_list = cx.canonicalize_ports(node);
 
+    // Positional and named connections cannot be mixed. See §23.3.2.
+    if let (Some(&(pos_span, _)), Some(&(named_span, _, _))) = (pos.first(), named.first()) {
+        cx.emit(
+            DiagBuilder2::error(format!(
+                "cannot mix positional and named port connections to {}",
+                node
+            ))
+            .span(named_span)
+            .add_note("First positional connection is here:")
+            .span(pos_span),
+        );
+        return Err(());
+    }
+
     // Associate the positional assignments with external ports.
     let pos_iter = pos.iter().enumerate().map(|(index, &(span, assign_id))| {
         match port_list.ext_pos.get(index) {
@@ -770,6 +770,14 @@ impl<'a, 'c, C: Context<'a>> ScopeGenerator<'a, 'c, C> {
                 }
             }
 
+            // The alternative blocks of an if-generate may share a label, since
+            // at most one of them is instantiated. See §27.5.
+            if let (DefNode::Ast(existing), DefNode::Ast(node)) = (existing.node, def.node) {
+                if are_generate_alternatives(existing, node) {
+                    return;
+                }
+            }
+
             if !def.may_override {
                 let d = DiagBuilder2::error(format!("`{}` is defined multiple times", def.name))
                     .span(def.name.span)
@@ -785,6 +793,19 @@ impl<'a, 'c, C: Context<'a>> ScopeGenerator<'a, 'c, C> {
     }
 }
 
+/// Check whether two nodes are the main and else blocks of the same
+/// if-generate.
+fn are_generate_alternatives<'a>(a: &'a dyn ast::AnyNode<'a>, b: &'a dyn ast::AnyNode<'a>) -> bool {
+    let parent_if = |node: &'a dyn ast::AnyNode<'a>| {
+        node.as_all().get_generate_block()?;
+        node.get_parent()?.as_all().get_generate_if()
+    };
+    match (parent_if(a), parent_if(b)) {
+        (Some(a), Some(b)) => a.id() == b.id(),
+        _ => false,
+    }
+}
+
 impl<'a, C: Context<'a>> ast::Visitor<'a> for ScopeGenerator<'a, '_, C> {
     // We return `false` in the pre-visit functions when the visited node
     // generates a subscope, to avoid gobbling up its local definitions.
@@ -823,6 +844,68 @@ impl<'a, C: Context<'a>> ast::Visitor<'a> for ScopeGenerator<'a, '_, C> {
         false
     }
 
+    // The header of an extern module is checked against the module
+    // definition, and defines no names of its own.
+    fn pre_visit_extern_module(&mut self, _: &'a ast::ExternModule<'a>) -> bool {
+        false
+    }
+
+    // Programs and checkers are not supported yet. Skip them entirely, such
+    // that their definitions do not leak into the enclosing scope.
+    fn pre_visit_program(&mut self, _: &'a ast::Program<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_checker(&mut self, _: &'a ast::Checker<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_property_decl(&mut self, node: &'a ast::PropertyDecl<'a>) -> bool {
+        self.add_def(Def {
+            node: DefNode::Ast(node),
+            name: node.name,
+            vis: DefVis::LOCAL | DefVis::NAMESPACE | DefVis::HIERARCHICAL,
+            may_override: false,
+            ordered: false,
+        });
+        false
+    }
+
+    fn pre_visit_sequence_decl(&mut self, node: &'a ast::SequenceDecl<'a>) -> bool {
+        self.add_def(Def {
+            node: DefNode::Ast(node),
+            name: node.name,
+            vis: DefVis::LOCAL | DefVis::NAMESPACE | DefVis::HIERARCHICAL,
+            may_override: false,
+            ordered: false,
+        });
+        false
+    }
+
+    fn pre_visit_let_decl(&mut self, node: &'a ast::LetDecl<'a>) -> bool {
+        self.add_def(Def {
+            node: DefNode::Ast(node),
+            name: node.name,
+            vis: DefVis::LOCAL | DefVis::NAMESPACE | DefVis::HIERARCHICAL,
+            may_override: false,
+            ordered: false,
+        });
+        false
+    }
+
+    fn pre_visit_clocking_decl(&mut self, node: &'a ast::ClockingDecl<'a>) -> bool {
+        if let Some(name) = node.name {
+            self.add_def(Def {
+                node: DefNode::Ast(node),
+                name,
+                vis: DefVis::LOCAL | DefVis::NAMESPACE | DefVis::HIERARCHICAL,
+                may_override: false,
+                ordered: false,
+            });
+        }
+        false
+    }
+
     fn pre_visit_modport_name(&mut self, node: &'a ast::ModportName<'a>) -> bool {
         self.add_def(Def {
             node: DefNode::Ast(node),
@@ -1393,6 +1476,38 @@ impl<'cx, C> ResolutionVisitor<'cx, C> {
     }
 }
 
+impl<'a, 'cx, C> ResolutionVisitor<'cx, C>
+where
+    C: Context<'a>,
+    'a: 'cx,
+{
+    /// Check that a reference to a genvar occurs within a loop generate
+    /// construct. See §27.4 of IEEE 1800-2017.
+    fn check_genvar_use(&mut self, node: &'a ast::Expr<'a>, ident: Spanned<Name>, def: &Def<'a>) {
+        let decl = match def.node {
+            DefNode::Ast(decl) if decl.as_all().is_genvar_decl() => decl,
+            _ => return,
+        };
+        let mut parent = node.get_parent();
+        while let Some(p) = parent {
+            if p.as_all().is_generate_for() {
+                return;
+            }
+            parent = p.get_parent();
+        }
+        self.cx.emit(
+            DiagBuilder2::error(format!(
+                "genvar `{}` used outside of a generate loop",
+                ident.value
+            ))
+            .span(ident.span)
+            .add_note("Genvar declared here:")
+            .span(decl.span()),
+        );
+        self.failed = true;
+    }
+}
+
 impl<'a, 'cx, C> ast::Visitor<'a> for ResolutionVisitor<'cx, C>
 where
     C: Context<'a>,
@@ -1426,16 +1541,45 @@ where
 
         match node.data {
             ast::IdentExpr(ident) => {
-                self.failed |= self
+                match self
                     .cx
                     .resolve_local_or_error(ident, self.cx.scope_location(node), false)
-                    .is_err();
+                {
+                    Ok(def) => self.check_genvar_use(node, ident, def),
+                    Err(()) => self.failed = true,
+                }
                 false
             }
             _ => true,
         }
     }
 
+    fn pre_visit_extern_module(&mut self, _: &'a ast::ExternModule<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_program(&mut self, _: &'a ast::Program<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_checker(&mut self, _: &'a ast::Checker<'a>) -> bool {
+        false
+    }
+
+    // The bodies of property, sequence, and let declarations refer to their
+    // formal arguments, which are not part of any scope.
+    fn pre_visit_property_decl(&mut self, _: &'a ast::PropertyDecl<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_sequence_decl(&mut self, _: &'a ast::SequenceDecl<'a>) -> bool {
+        false
+    }
+
+    fn pre_visit_let_decl(&mut self, _: &'a ast::LetDecl<'a>) -> bool {
+        false
+    }
+
     fn pre_visit_type(&mut self, node: &'a ast::Type<'a>) -> bool {
         match node.kind.data {
             ast::NamedType(ident) => {
@@ -4,8 +4,9 @@
 
 #![allow(unused_variables)]
 
-use crate::token::{Lit, Op};
+use crate::token::{Lit, Op, Precedence};
 use moore_common::{
+    errors::DiagBuilder2,
     id::NodeId,
     name::Name,
     source::{Span, Spanned},
@@ -254,6 +255,8 @@ pub struct Node<'a, T> {
     pub order: Cell<usize>,
     /// Per-node data.
     pub data: T,
+    /// Attribute instances attached to the node, such as `(* keep *)`.
+    pub attrs: Vec<AttrSpec<'a>>,
 }
 
 impl<'a, T> Node<'a, T> {
@@ -265,8 +268,32 @@ impl<'a, T> Node<'a, T> {
             data,
             parent: Default::default(),
             order: Default::default(),
+            attrs: Default::default(),
         }
     }
+
+    /// Find the attribute with the given name.
+    ///
+    /// The lookup is case-sensitive, and returns the last of multiple
+    /// attributes with the same name.
+    pub fn attr(&self, name: &str) -> Option<&AttrSpec<'a>> {
+        self.attrs
+            .iter()
+            .rev()
+            .find(|attr| &*attr.name.value.as_str() == name)
+    }
+}
+
+/// A single attribute specification, such as `keep` or `ram_style = "block"`,
+/// within an attribute instance `(* ... *)`.
+#[derive(Debug, Clone)]
+pub struct AttrSpec<'a> {
+    /// The name of the attribute.
+    pub name: Spanned<Name>,
+    /// The optional value assigned to the attribute.
+    pub value: Option<Expr<'a>>,
+    /// The span of the entire specification.
+    pub span: Span,
 }
 
 // The following are needed due to the `Cell`s in `Node`. It is safe to share
@@ -559,6 +586,85 @@ impl<'a> WalkVisitor<'a> for usize {
     fn walk(&'a self, visitor: &mut dyn Visitor<'a>) {}
 }
 
+impl<'a, 'b: 'a, T> AcceptVisitorMut<'a> for Node<'b, T>
+where
+    T: AcceptVisitorMut<'a>,
+{
+    fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        self.data.accept_mut(visitor)
+    }
+}
+
+/// A node that accepts `VisitorMut`s.
+pub trait AcceptVisitorMut<'a> {
+    /// Walk a mutable visitor over the contents of `self`.
+    fn accept_mut(&mut self, visitor: &mut dyn VisitorMut<'a>);
+}
+
+/// A node that walks a `VisitorMut` over itself.
+pub trait WalkVisitorMut<'a> {
+    /// Walk a mutable visitor over `self`.
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>);
+}
+
+/// Nodes behind a shared reference are allocated in an arena and cannot be
+/// modified. They are skipped.
+impl<'a, T> WalkVisitorMut<'a> for &'_ T {
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {}
+}
+
+impl<'a, T> WalkVisitorMut<'a> for Vec<T>
+where
+    T: WalkVisitorMut<'a>,
+{
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        for c in self {
+            c.walk_mut(visitor);
+        }
+    }
+}
+
+impl<'a, T> WalkVisitorMut<'a> for Option<T>
+where
+    T: WalkVisitorMut<'a>,
+{
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        if let Some(c) = self {
+            c.walk_mut(visitor);
+        }
+    }
+}
+
+impl<'a, T> WalkVisitorMut<'a> for Spanned<T>
+where
+    T: WalkVisitorMut<'a>,
+{
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        self.value.walk_mut(visitor);
+    }
+}
+
+impl<'a, T> WalkVisitorMut<'a> for Box<T>
+where
+    T: WalkVisitorMut<'a>,
+{
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        self.as_mut().walk_mut(visitor);
+    }
+}
+
+macro_rules! leaf_walk_mut_impls {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a> WalkVisitorMut<'a> for $ty {
+                fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {}
+            }
+        )*
+    };
+}
+
+leaf_walk_mut_impls!(Span, Name, Identifier, Lit, Op, bool, usize);
+
 macro_rules! tuple_impls {
     ($($idx:tt => $args:ident),*) => {
         impl<'a $(, $args: AcceptVisitor<'a>)*> AcceptVisitor<'a> for ($($args),*) {
@@ -578,6 +684,12 @@ macro_rules! tuple_impls {
                 $(self.$idx.for_each_node(each);)*
             }
         }
+
+        impl<'a $(, $args: WalkVisitorMut<'a>)*> WalkVisitorMut<'a> for ($($args),*) {
+            fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+                $(self.$idx.walk_mut(visitor);)*
+            }
+        }
     };
 }
 
@@ -586,6 +698,112 @@ tuple_impls!(0 => T0, 1 => T1);
 tuple_impls!(0 => T0, 1 => T1, 2 => T2);
 tuple_impls!(0 => T0, 1 => T1, 2 => T2, 3 => T3);
 
+/// Structural equality that ignores source locations.
+///
+/// Node equality via `PartialEq` compares node identity. This trait instead
+/// compares two trees field by field, skipping spans, node IDs, and parent
+/// links, such that trees parsed from different sources compare equal if they
+/// describe the same construct.
+pub trait SpanlessEq {
+    /// Check if `self` and `other` are equal, ignoring spans.
+    fn spanless_eq(&self, other: &Self) -> bool;
+}
+
+impl<'a, T> SpanlessEq for Node<'a, T>
+where
+    T: SpanlessEq,
+{
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.data.spanless_eq(&other.data)
+    }
+}
+
+impl<T: SpanlessEq + ?Sized> SpanlessEq for &'_ T {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        (**self).spanless_eq(*other)
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Box<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.as_ref().spanless_eq(other.as_ref())
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Vec<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.spanless_eq(b))
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Option<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.spanless_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<T: SpanlessEq> SpanlessEq for Spanned<T> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.value.spanless_eq(&other.value)
+    }
+}
+
+impl<R: SpanlessEq> SpanlessEq for Ambiguous<R> {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unique(a), Self::Unique(b)) => a.spanless_eq(b),
+            (Self::Ambiguous(a), Self::Ambiguous(b)) => a.spanless_eq(b),
+            _ => false,
+        }
+    }
+}
+
+impl SpanlessEq for Span {
+    fn spanless_eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+macro_rules! spanless_eq_by_value {
+    ($($ty:ty),*) => {
+        $(
+            impl SpanlessEq for $ty {
+                fn spanless_eq(&self, other: &Self) -> bool {
+                    self == other
+                }
+            }
+        )*
+    };
+}
+
+spanless_eq_by_value!(Name, Lit, Op, bool, usize);
+
+impl SpanlessEq for Identifier {
+    fn spanless_eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+macro_rules! spanless_eq_tuple {
+    ($($idx:tt => $args:ident),*) => {
+        impl<$($args: SpanlessEq),*> SpanlessEq for ($($args,)*) {
+            #[allow(unused_variables)]
+            fn spanless_eq(&self, other: &Self) -> bool {
+                true $(&& self.$idx.spanless_eq(&other.$idx))*
+            }
+        }
+    };
+}
+
+spanless_eq_tuple!();
+spanless_eq_tuple!(0 => T0, 1 => T1);
+spanless_eq_tuple!(0 => T0, 1 => T1, 2 => T2);
+spanless_eq_tuple!(0 => T0, 1 => T1, 2 => T2, 3 => T3);
+
 pub use self::ExprData::*;
 pub use self::StmtKind::*;
 pub use self::TypeKindData::*;
@@ -619,6 +837,18 @@ where
     }
 }
 
+impl<'a, R> WalkVisitorMut<'a> for Ambiguous<R>
+where
+    R: WalkVisitorMut<'a>,
+{
+    fn walk_mut(&mut self, visitor: &mut dyn VisitorMut<'a>) {
+        match self {
+            Self::Unique(x) => x.walk_mut(visitor),
+            Self::Ambiguous(x) => x.walk_mut(visitor),
+        }
+    }
+}
+
 impl<'a, R> ForEachNode<'a> for Ambiguous<R>
 where
     R: ForEachNode<'a>,
@@ -634,7 +864,7 @@ where
 /// All things being compiled.
 #[moore_derive::node]
 #[indefinite("root")]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Root<'a> {
     pub files: Vec<&'a SourceFile<'a>>,
 }
@@ -642,7 +872,7 @@ pub struct Root<'a> {
 /// An entire source file.
 #[moore_derive::node]
 #[indefinite("source file")]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SourceFile<'a> {
     pub timeunits: Timeunit,
     pub items: Vec<Item<'a>>,
@@ -655,6 +885,8 @@ pub struct SourceFile<'a> {
 /// - modules
 /// - interfaces
 /// - packages
+/// - programs
+/// - checkers
 /// - classes
 /// - generates
 #[moore_derive::node]
@@ -664,10 +896,12 @@ pub enum Item<'a> {
     #[indefinite("dummy item")]
     Dummy,
     ModuleDecl(#[forward] Module<'a>),
+    ExternModuleDecl(#[forward] ExternModule<'a>),
     InterfaceDecl(#[forward] Interface<'a>),
     PackageDecl(#[forward] Package<'a>),
     ClassDecl(#[forward] ClassDecl<'a>),
-    ProgramDecl(()),
+    ProgramDecl(#[forward] Program<'a>),
+    CheckerDecl(#[forward] Checker<'a>),
     ImportDecl(#[forward] ImportDecl<'a>),
     DpiDecl(#[forward] DpiDecl<'a>),
     ParamDecl(#[forward] ParamDecl<'a>),
@@ -677,12 +911,19 @@ pub enum Item<'a> {
     Procedure(#[forward] Procedure<'a>),
     SubroutineDecl(#[forward] SubroutineDecl<'a>),
     ContAssign(#[forward] ContAssign<'a>),
+    Defparam(#[forward] Defparam<'a>),
     GenvarDecl(Vec<GenvarDecl<'a>>),
     GenerateRegion(Span, Vec<Item<'a>>),
     GenerateFor(#[forward] GenerateFor<'a>),
     GenerateIf(#[forward] GenerateIf<'a>),
     GenerateCase(#[forward] GenerateCase<'a>),
     Assertion(Assertion<'a>),
+    PropertyDecl(#[forward] PropertyDecl<'a>),
+    SequenceDecl(#[forward] SequenceDecl<'a>),
+    LetDecl(#[forward] LetDecl<'a>),
+    ClockingDecl(#[forward] ClockingDecl<'a>),
+    /// A `default clocking foo;` marker naming the default clocking block.
+    DefaultClocking(Spanned<Name>),
     NetDecl(NetDecl<'a>),
     VarDecl(#[forward] VarDecl<'a>),
     Inst(Inst<'a>),
@@ -702,6 +943,25 @@ pub struct Module<'a> {
     pub params: Vec<ParamDecl<'a>>,
     pub ports: Vec<Port<'a>>,
     pub items: Vec<Item<'a>>,
+    /// The `default_nettype` in effect at the `module` keyword, or `None` if
+    /// it is `none` and implicit nets are an error.
+    pub default_nettype: Option<NetType>,
+}
+
+/// An extern module declaration.
+///
+/// Declares the header of a module whose definition follows separately.
+#[moore_derive::node]
+#[indefinite("extern module")]
+#[definite("extern module `{}`", name)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternModule<'a> {
+    pub lifetime: Lifetime, // default static
+    #[name]
+    pub name: Spanned<Name>,
+    pub imports: Vec<ImportDecl<'a>>,
+    pub params: Vec<ParamDecl<'a>>,
+    pub ports: Vec<Port<'a>>,
 }
 
 /// An interface.
@@ -716,6 +976,36 @@ pub struct Interface<'a> {
     pub params: Vec<ParamDecl<'a>>,
     pub ports: Vec<Port<'a>>,
     pub items: Vec<Item<'a>>,
+    /// The `default_nettype` in effect at the `interface` keyword, or `None`
+    /// if it is `none` and implicit nets are an error.
+    pub default_nettype: Option<NetType>,
+}
+
+/// A program.
+#[moore_derive::node]
+#[indefinite("program")]
+#[definite("program `{}`", name)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Program<'a> {
+    pub lifetime: Lifetime, // default static
+    #[name]
+    pub name: Spanned<Name>,
+    pub imports: Vec<ImportDecl<'a>>,
+    pub params: Vec<ParamDecl<'a>>,
+    pub ports: Vec<Port<'a>>,
+    pub items: Vec<Item<'a>>,
+}
+
+/// A checker.
+#[moore_derive::node]
+#[indefinite("checker")]
+#[definite("checker `{}`", name)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checker<'a> {
+    #[name]
+    pub name: Spanned<Name>,
+    pub ports: Vec<Port<'a>>,
+    pub items: Vec<Item<'a>>,
 }
 
 /// A package.
@@ -895,7 +1185,8 @@ pub struct Enum<'a> {
 pub struct EnumName<'a> {
     #[name]
     pub name: Spanned<Name>,
-    pub range: Option<Expr<'a>>,
+    /// The optional `[N]` or `[N:M]` range to generate multiple names.
+    pub range: Option<TypeDim<'a>>,
     pub value: Option<Expr<'a>>,
 }
 
@@ -1242,6 +1533,9 @@ pub enum CaseMode {
 pub enum CaseItem<'a> {
     Default(Box<Stmt<'a>>),
     Expr(Vec<Expr<'a>>, Box<Stmt<'a>>),
+    /// An item of a `case ... inside` statement, matching a set of values and
+    /// ranges.
+    Inside(Vec<ValueRange<'a>>, Box<Stmt<'a>>),
 }
 
 #[moore_derive::visit]
@@ -1251,6 +1545,21 @@ pub struct DelayControl<'a> {
     pub expr: Expr<'a>,
 }
 
+/// A rise, fall, and turn-off delay of a net declaration.
+///
+/// ```text
+/// "#" delay_value
+/// "#" "(" mintypmax ["," mintypmax ["," mintypmax]] ")"
+/// ```
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delay3<'a> {
+    pub span: Span,
+    pub rise: Expr<'a>,
+    pub fall: Option<Expr<'a>>,
+    pub turnoff: Option<Expr<'a>>,
+}
+
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EventControl<'a> {
@@ -1390,6 +1699,7 @@ pub enum Expr<'a> {
     ArrayNewExpr(Box<Expr<'a>>, Option<Box<Expr<'a>>>),
     EmptyQueueExpr,
     StreamConcatExpr {
+        dir: StreamDir,
         slice: Option<StreamConcatSlice<'a>>,
         exprs: Vec<StreamExpr<'a>>,
     },
@@ -1424,6 +1734,26 @@ pub enum Expr<'a> {
     },
 }
 
+impl<'a> ExprData<'a> {
+    /// The precedence of the outermost operator of the expression.
+    ///
+    /// Primary expressions such as literals, identifiers, concatenations, and
+    /// casts have the maximum precedence.
+    pub fn precedence(&self) -> Precedence {
+        match *self {
+            BinaryExpr { op, .. } => op.get_precedence(),
+            UnaryExpr { .. } => Precedence::Unary,
+            TernaryExpr { .. } => Precedence::Ternary,
+            AssignExpr { .. } => Precedence::Assignment,
+            InsideExpr(..) => Precedence::Relational,
+            RangeExpr { .. } => Precedence::Min,
+            IndexExpr { .. } | CallExpr(..) | CastSizeExpr(..) => Precedence::Postfix,
+            ScopeExpr(..) | MemberExpr { .. } => Precedence::Scope,
+            _ => Precedence::Max,
+        }
+    }
+}
+
 /// An ambiguous node that can either be a type or and expression.
 ///
 /// Use the `disamb_type_or_expr` query to disambiguate based on name
@@ -1557,6 +1887,16 @@ pub struct CallArg<'a> {
     pub expr: Option<Expr<'a>>,
 }
 
+/// The direction of a streaming concatenation.
+#[moore_derive::visit]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamDir {
+    /// A `>>` stream, which streams data left-to-right.
+    In,
+    /// A `<<` stream, which streams data right-to-left.
+    Out,
+}
+
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StreamConcatSlice<'a> {
@@ -1655,15 +1995,20 @@ pub enum ClassItemQualifier {
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ClassItemData<'a> {
-    Property,
+    /// A class property, as in `rand logic [7:0] a, b = 0;`.
+    Property {
+        ty: Type<'a>,
+        names: Vec<VarDeclName<'a>>,
+    },
     Typedef(Typedef<'a>),
     SubroutineDecl(SubroutineDecl<'a>),
     ExternSubroutine(SubroutinePrototype<'a>),
     Constraint(Constraint<'a>),
-    ClassDecl,
-    CovergroupDecl,
     ParamDecl(ParamDecl<'a>),
     Null,
+    /// A class item that is skipped by the parser, such as a covergroup or a
+    /// nested class.
+    Unsupported(Span),
 }
 
 #[moore_derive::visit]
@@ -1839,7 +2184,7 @@ impl std::fmt::Display for SubroutinePortDir {
 
 /// A net declaration.
 ///
-/// For example `wire x, y, z`.
+/// For example `wire x, y, z` or `trireg (large) #(1, 2, 3) c`.
 #[moore_derive::node]
 #[indefinite("net declaration")]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1848,7 +2193,7 @@ pub struct NetDecl<'a> {
     pub strength: Option<NetStrength>,
     pub kind: NetKind,
     pub ty: Type<'a>,
-    pub delay: Option<DelayControl<'a>>,
+    pub delay: Option<Delay3<'a>>,
     pub names: Vec<VarDeclName<'a>>,
 }
 
@@ -1959,12 +2304,12 @@ pub enum BlockingAssertion<'a> {
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ConcurrentAssertion<'a> {
-    AssertProperty(PropSpec, AssertionActionBlock<'a>),
-    AssumeProperty(PropSpec, AssertionActionBlock<'a>),
-    CoverProperty(PropSpec, Stmt<'a>),
+    AssertProperty(PropSpec<'a>, AssertionActionBlock<'a>),
+    AssumeProperty(PropSpec<'a>, AssertionActionBlock<'a>),
+    CoverProperty(PropSpec<'a>, Stmt<'a>),
     CoverSequence,
-    ExpectProperty(PropSpec, AssertionActionBlock<'a>),
-    RestrictProperty(PropSpec),
+    ExpectProperty(PropSpec<'a>, AssertionActionBlock<'a>),
+    RestrictProperty(PropSpec<'a>),
 }
 
 #[moore_derive::visit]
@@ -1975,6 +2320,135 @@ pub enum AssertionActionBlock<'a> {
     Both(Stmt<'a>, Stmt<'a>),
 }
 
+/// A let declaration.
+///
+/// For example `let max(int a, b = 0) = (a > b) ? a : b;`.
+#[moore_derive::node]
+#[indefinite("let declaration")]
+#[definite("let `{}`", name)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LetDecl<'a> {
+    #[name]
+    pub name: Spanned<Name>,
+    pub ports: Vec<LetPort<'a>>,
+    pub expr: Expr<'a>,
+}
+
+/// A formal argument of a let declaration.
+///
+/// This is the `int a = 0` part in `let foo(int a = 0) = a;`. The type is
+/// `None` for `untyped` arguments and arguments without a type.
+#[moore_derive::node]
+#[indefinite("let argument")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LetPort<'a> {
+    pub ty: Option<Type<'a>>,
+    #[name]
+    pub name: Spanned<Name>,
+    pub default: Option<Expr<'a>>,
+}
+
+/// A clocking block declaration.
+///
+/// For example `clocking cb @(posedge clk); input #1step data; endclocking`.
+/// See §14.3 of IEEE 1800-2017.
+#[moore_derive::node]
+#[indefinite("clocking block")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockingDecl<'a> {
+    /// Whether this is the `default clocking` of the enclosing scope.
+    pub default: bool,
+    pub name: Option<Spanned<Name>>,
+    pub event: EventExpr<'a>,
+    /// The skew given in a `default input ...` item.
+    pub default_input: Option<ClockingSkew<'a>>,
+    /// The skew given in a `default output ...` item.
+    pub default_output: Option<ClockingSkew<'a>>,
+    pub items: Vec<ClockingItem<'a>>,
+}
+
+/// A direction-qualified list of signals in a clocking block.
+///
+/// For example `input #1step a, b = top.c;`.
+#[moore_derive::node]
+#[indefinite("clocking item")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockingItem<'a> {
+    pub dir: ClockingDir<'a>,
+    pub signals: Vec<ClockingSignal<'a>>,
+}
+
+/// The direction of signals in a clocking block, with optional skews.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClockingDir<'a> {
+    Input(Option<ClockingSkew<'a>>),
+    Output(Option<ClockingSkew<'a>>),
+    InputOutput(Option<ClockingSkew<'a>>, Option<ClockingSkew<'a>>),
+    Inout,
+}
+
+/// A signal in a clocking block, optionally assigned a hierarchical
+/// expression, as in `a = top.b`.
+#[moore_derive::node]
+#[indefinite("clocking signal")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockingSignal<'a> {
+    #[name]
+    pub name: Spanned<Name>,
+    pub expr: Option<Expr<'a>>,
+}
+
+/// The skew of a clocking block signal.
+///
+/// Consists of an optional edge and an optional delay, as in `posedge #1`. At
+/// least one of the two is present.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockingSkew<'a> {
+    pub span: Span,
+    pub edge: EdgeIdent,
+    pub delay: Option<ClockingDelay<'a>>,
+}
+
+/// The delay of a clocking skew.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClockingDelay<'a> {
+    /// A `#1step` delay.
+    Step(Span),
+    /// A delay expression, as in `#1` or `#(2ns)`.
+    Expr(Expr<'a>),
+}
+
+/// A named property declaration.
+///
+/// For example `property p(a, b); @(posedge clk) a |=> b; endproperty`.
+#[moore_derive::node]
+#[indefinite("property declaration")]
+#[definite("property `{}`", name)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyDecl<'a> {
+    #[name]
+    pub name: Spanned<Name>,
+    pub ports: Vec<Spanned<Name>>,
+    pub spec: PropSpec<'a>,
+}
+
+/// A named sequence declaration.
+///
+/// For example `sequence s(a, b); a ##1 b; endsequence`.
+#[moore_derive::node]
+#[indefinite("sequence declaration")]
+#[definite("sequence `{}`", name)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceDecl<'a> {
+    #[name]
+    pub name: Spanned<Name>,
+    pub ports: Vec<Spanned<Name>>,
+    pub seq: SeqExpr<'a>,
+}
+
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SeqExpr<'a> {
@@ -1989,6 +2463,24 @@ pub enum SeqExprData<'a> {
     BinOp(SeqBinOp, Box<SeqExpr<'a>>, Box<SeqExpr<'a>>),
     Throughout(Expr<'a>, Box<SeqExpr<'a>>),
     Clocked(EventExpr<'a>, Box<SeqExpr<'a>>),
+    /// A cycle delay concatenation, e.g. `a ##1 b` or `##[1:3] b`.
+    Delay(Option<Box<SeqExpr<'a>>>, SeqDelay<'a>, Box<SeqExpr<'a>>),
+    /// A sequence construct the parser does not represent yet.
+    Unsupported(Span),
+}
+
+/// The cycle delay in a sequence concatenation.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeqDelay<'a> {
+    /// `##N`
+    Exact(Expr<'a>),
+    /// `##[N:M]` and `##[N:$]`
+    Range(Expr<'a>, Expr<'a>),
+    /// `##[*]`
+    Star,
+    /// `##[+]`
+    Plus,
 }
 
 #[moore_derive::visit]
@@ -2010,9 +2502,18 @@ pub enum SeqBinOp {
     Within,
 }
 
+/// A property specification, as it appears in a concurrent assertion or a
+/// property declaration.
+///
+/// For example `@(posedge clk) disable iff (rst) req |-> gnt`.
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct PropSpec;
+pub struct PropSpec<'a> {
+    pub span: Span,
+    pub clock: Option<EventExpr<'a>>,
+    pub disable: Option<Expr<'a>>,
+    pub prop: PropExpr<'a>,
+}
 
 #[moore_derive::visit]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -2029,6 +2530,8 @@ pub enum PropExprData<'a> {
     Not(Box<PropExpr<'a>>),
     BinOp(PropBinOp, Box<PropExpr<'a>>, Box<PropExpr<'a>>),
     Clocked(EventExpr<'a>, Box<PropExpr<'a>>),
+    /// A property construct the parser does not represent yet.
+    Unsupported(Span),
 }
 
 #[moore_derive::visit]
@@ -2148,6 +2651,11 @@ pub enum ModportPort<'a> {
         dir: Spanned<PortDir>,
         port: Vec<&'a ModportSimplePort<'a>>,
     },
+    /// A task or function port, for example `import a, task b()`.
+    Tf {
+        kind: Spanned<ModportTfKind>,
+        port: Vec<&'a ModportTfPort<'a>>,
+    },
 }
 
 /// A single simple modport port.
@@ -2164,6 +2672,37 @@ pub struct ModportSimplePort<'a> {
     pub expr: Option<&'a Expr<'a>>,
 }
 
+/// Whether a modport task or function port is imported or exported.
+#[moore_derive::visit]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModportTfKind {
+    Import,
+    Export,
+}
+
+impl std::fmt::Display for ModportTfKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ModportTfKind::Import => write!(f, "import"),
+            ModportTfKind::Export => write!(f, "export"),
+        }
+    }
+}
+
+/// A single task or function modport port.
+///
+/// For example the `a` or `task b()` in `import a, task b()`.
+#[moore_derive::node]
+#[indefinite("modport task or function port")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModportTfPort<'a> {
+    /// The name of the task or function.
+    #[name]
+    pub name: Spanned<Name>,
+    /// The optional prototype of the task or function.
+    pub prototype: Option<SubroutinePrototype<'a>>,
+}
+
 /// A parameter or localparam declaration.
 ///
 /// ```text
@@ -2233,6 +2772,149 @@ pub struct ContAssign<'a> {
     pub assignments: Vec<(Expr<'a>, Expr<'a>)>,
 }
 
+/// A `defparam` statement.
+///
+/// ```text
+/// "defparam" hierarchical_name "=" expr {"," hierarchical_name "=" expr} ";"
+/// ```
+#[moore_derive::node]
+#[indefinite("defparam")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Defparam<'a> {
+    pub assignments: Vec<(HierName<'a>, Expr<'a>)>,
+}
+
+/// A hierarchical or scoped name.
+///
+/// For example `top.u0[1].WIDTH`, `pkg::PARAM`, or `$unit::x`.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierName<'a> {
+    pub span: Span,
+    pub segments: Vec<HierNameSegment<'a>>,
+}
+
+/// A single segment of a hierarchical name.
+///
+/// For example the `u0[1]` in `top.u0[1].WIDTH`.
+#[moore_derive::visit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierNameSegment<'a> {
+    pub span: Span,
+    /// Whether the segment follows a `::` rather than a `.`.
+    pub scoped: bool,
+    pub name: Spanned<Name>,
+    pub indices: Vec<Expr<'a>>,
+}
+
+impl<'a> HierName<'a> {
+    /// Interpret an expression as a hierarchical name.
+    ///
+    /// Accepts identifiers, `$unit` and `$root`, followed by any combination
+    /// of `::`, `.`, and index steps. Returns `None` for any other expression.
+    pub fn from_expr(expr: &Expr<'a>) -> Option<Self> {
+        let mut segments = vec![];
+        let mut indices = vec![];
+        let mut current = expr;
+        // The end of the segment currently being collected, including its
+        // indices.
+        let mut end = expr.span.end;
+        loop {
+            let span = Span::new(current.span.source, current.span.begin, end);
+            match current.data {
+                IndexExpr {
+                    ref indexee,
+                    ref index,
+                } => {
+                    indices.insert(0, index.as_ref().clone());
+                    current = indexee;
+                }
+                MemberExpr { ref expr, name } | ScopeExpr(ref expr, name) => {
+                    segments.push(HierNameSegment {
+                        span: Span::new(name.span.source, name.span.begin, end),
+                        scoped: match current.data {
+                            ScopeExpr(..) => true,
+                            _ => false,
+                        },
+                        name,
+                        indices: std::mem::replace(&mut indices, vec![]),
+                    });
+                    end = expr.span.end;
+                    current = expr;
+                }
+                IdentExpr(name) => {
+                    segments.push(HierNameSegment {
+                        span,
+                        scoped: false,
+                        name,
+                        indices,
+                    });
+                    break;
+                }
+                SysIdentExpr(name)
+                    if &*name.value.as_str() == "unit" || &*name.value.as_str() == "root" =>
+                {
+                    let name = format!("${}", name.value);
+                    let table = moore_common::name::get_name_table();
+                    segments.push(HierNameSegment {
+                        span,
+                        scoped: false,
+                        name: Spanned::new(table.intern(&name, true), span),
+                        indices,
+                    });
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        segments.reverse();
+        Some(HierName {
+            span: expr.span,
+            segments,
+        })
+    }
+
+    /// Check that no `::` step follows a `.` step.
+    ///
+    /// Scope resolution selects an item of a package or class, which cannot
+    /// be reached through a hierarchical reference. See §23.6 of IEEE
+    /// 1800-2017.
+    pub fn validate(&self) -> Result<(), DiagBuilder2> {
+        let mut dotted = None;
+        for segment in self.segments.iter().skip(1) {
+            if !segment.scoped {
+                dotted = dotted.or(Some(segment));
+            } else if let Some(dotted) = dotted {
+                return Err(DiagBuilder2::error(format!(
+                    "`::` cannot follow a `.` in hierarchical name `{}`",
+                    self
+                ))
+                .span(segment.span)
+                .add_note("Hierarchical reference starts here:")
+                .span(dotted.name.span));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for HierName<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if segment.scoped {
+                write!(f, "::")?;
+            } else if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", segment.name)?;
+            for index in &segment.indices {
+                write!(f, "[{}]", index.span.extract())?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// A `for` generate statement.
 #[moore_derive::node]
 #[indefinite("for-generate statement")]
@@ -2580,3 +3262,354 @@ moore_derive::derive_visitor!(
 );
 moore_derive::derive_all_node!();
 moore_derive::derive_arena!();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_traits<T: std::fmt::Debug + Clone + PartialEq + Eq>() {}
+
+    macro_rules! assert_traits {
+        ($($ty:ty),* $(,)?) => { $(assert_traits::<$ty>();)* };
+    }
+
+    /// All AST nodes derive the same set of traits.
+    #[test]
+    fn uniform_derives() {
+        assert_traits!(
+            Root<'static>,
+            SourceFile<'static>,
+            Item<'static>,
+            Module<'static>,
+            ExternModule<'static>,
+            Interface<'static>,
+            Package<'static>,
+            Program<'static>,
+            Checker<'static>,
+            Lifetime,
+            Timeunit,
+            Type<'static>,
+            TypeKind<'static>,
+            TypeSign,
+            TypeDim<'static>,
+            Enum<'static>,
+            EnumName<'static>,
+            Struct<'static>,
+            StructKind,
+            StructMember<'static>,
+            Port<'static>,
+            PortDecl<'static>,
+            VarKind,
+            PortDir,
+            NetType,
+            Procedure<'static>,
+            ProcedureKind,
+            Stmt<'static>,
+            StmtKind<'static>,
+            JoinKind,
+            UniquePriority,
+            CaseKind,
+            CaseMode,
+            CaseItem<'static>,
+            DelayControl<'static>,
+            Delay3<'static>,
+            EventControl<'static>,
+            EventControlData<'static>,
+            CycleDelay,
+            TimingControl<'static>,
+            AssignOp,
+            VarDecl<'static>,
+            VarDeclName<'static>,
+            GenvarDecl<'static>,
+            ForeachIndex,
+            Expr<'static>,
+            TypeOrExpr<'static>,
+            ValueRange<'static>,
+            RangeMode,
+            Identifier,
+            CallArg<'static>,
+            StreamDir,
+            StreamConcatSlice<'static>,
+            StreamExpr<'static>,
+            EventExpr<'static>,
+            EdgeIdent,
+            ClassDecl<'static>,
+            ClassItem<'static>,
+            ClassItemQualifier,
+            ClassItemData<'static>,
+            RandomQualifier,
+            Typedef<'static>,
+            Constraint<'static>,
+            ConstraintKind,
+            ConstraintItem<'static>,
+            ConstraintItemData<'static>,
+            SubroutineDecl<'static>,
+            SubroutinePrototype<'static>,
+            SubroutineKind,
+            SubroutinePort<'static>,
+            SubroutinePortName<'static>,
+            SubroutineItem<'static>,
+            SubroutinePortDecl<'static>,
+            SubroutinePortDir,
+            NetDecl<'static>,
+            NetKind,
+            NetStrength,
+            DriveStrength,
+            ChargeStrength,
+            PatternField<'static>,
+            ImportDecl<'static>,
+            ImportItem,
+            Assertion<'static>,
+            AssertionData<'static>,
+            AssertionDeferred,
+            BlockingAssertion<'static>,
+            ConcurrentAssertion<'static>,
+            AssertionActionBlock<'static>,
+            PropertyDecl<'static>,
+            SequenceDecl<'static>,
+            LetDecl<'static>,
+            LetPort<'static>,
+            ClockingDecl<'static>,
+            ClockingItem<'static>,
+            ClockingDir<'static>,
+            ClockingSignal<'static>,
+            ClockingSkew<'static>,
+            ClockingDelay<'static>,
+            SeqExpr<'static>,
+            SeqExprData<'static>,
+            SeqDelay<'static>,
+            SeqRep<'static>,
+            SeqBinOp,
+            PropSpec<'static>,
+            PropExpr<'static>,
+            PropExprData<'static>,
+            PropSeqOp,
+            PropSeqBinOp,
+            PropBinOp,
+            Inst<'static>,
+            InstName<'static>,
+            Modport<'static>,
+            ModportName<'static>,
+            ModportPort<'static>,
+            ModportSimplePort<'static>,
+            ModportTfKind,
+            ModportTfPort<'static>,
+            ParamDecl<'static>,
+            ParamKind<'static>,
+            ParamTypeDecl<'static>,
+            ParamValueDecl<'static>,
+            ContAssign<'static>,
+            Defparam<'static>,
+            HierName<'static>,
+            HierNameSegment<'static>,
+            GenerateFor<'static>,
+            GenerateIf<'static>,
+            GenerateCase,
+            GenerateBlock<'static>,
+            ParamAssignment<'static>,
+            PortConn<'static>,
+            PortConnMode<'static>,
+            DpiDecl<'static>,
+            DpiProperty,
+            DataType<'static>,
+            IntType,
+            RealType,
+            ImplicitDataType<'static>,
+            DataTypeOrImplicit<'static>,
+            VarDim<'static>,
+            PackedDim<'static>,
+            UnpackedDim<'static>,
+            PathSegment<'static>,
+        );
+    }
+
+    /// Modules nested two deep are visited once each, with parent links to
+    /// their enclosing module.
+    #[test]
+    fn nested_modules() {
+        use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+        use moore_common::source::get_source_manager;
+
+        struct ModuleCounter<'a>(Vec<&'a Module<'a>>);
+        impl<'a> Visitor<'a> for ModuleCounter<'a> {
+            fn pre_visit_module(&mut self, node: &'a Module<'a>) -> bool {
+                self.0.push(node);
+                true
+            }
+        }
+
+        let input = "module a; module b; module c; endmodule endmodule endmodule";
+        let source = get_source_manager().add("nested_modules.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        file.link(None, &mut 0);
+
+        let mut counter = ModuleCounter(vec![]);
+        file.accept(&mut counter);
+        let names: Vec<_> = counter.0.iter().map(|m| m.name.value.to_string()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        for pair in counter.0.windows(2) {
+            let parent = pair[1].get_parent().and_then(|p| p.get_parent()).unwrap();
+            assert_eq!(parent.id(), pair[0].id());
+        }
+    }
+
+    /// A mutable visitor rewrites every implicit type to `logic`, and its
+    /// counterpart counts the ports and type dimensions afterwards.
+    #[test]
+    fn rewrite_implicit_types() {
+        use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+        use moore_common::source::get_source_manager;
+
+        struct MakeLogic(usize);
+        impl<'a> VisitorMut<'a> for MakeLogic {
+            fn pre_visit_type_kind(&mut self, node: &mut TypeKind<'a>) -> bool {
+                if node.data == ImplicitType {
+                    node.data = LogicType;
+                    self.0 += 1;
+                }
+                true
+            }
+        }
+
+        #[derive(Default)]
+        struct Counter {
+            ports: usize,
+            dims: usize,
+            implicit: usize,
+            logic: usize,
+        }
+        impl<'a> Visitor<'a> for Counter {
+            fn pre_visit_port(&mut self, _: &'a Port<'a>) -> bool {
+                self.ports += 1;
+                true
+            }
+            fn pre_visit_type_dim(&mut self, _: &'a TypeDim<'a>) -> bool {
+                self.dims += 1;
+                true
+            }
+            fn pre_visit_type_kind(&mut self, node: &'a TypeKind<'a>) -> bool {
+                match node.data {
+                    ImplicitType => self.implicit += 1,
+                    LogicType => self.logic += 1,
+                    _ => (),
+                }
+                true
+            }
+        }
+
+        let input = "module a (input [3:0] x, output logic [1:0][7:0] y, input z);
+            wire [2:0] w;
+        endmodule";
+        let source = get_source_manager().add("rewrite_implicit_types.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let mut file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+
+        let mut make_logic = MakeLogic(0);
+        file.walk_mut(&mut make_logic);
+        assert!(make_logic.0 > 0);
+
+        let mut counter = Counter::default();
+        file.accept(&mut counter);
+        assert_eq!(counter.ports, 3);
+        assert_eq!(counter.dims, 4);
+        assert_eq!(counter.implicit, 0);
+        assert_eq!(counter.logic, make_logic.0 + 1);
+    }
+
+    /// A mixed scoped and hierarchical path is split into segments.
+    #[test]
+    fn hier_name_segments() {
+        use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+        use moore_common::source::get_source_manager;
+
+        let parse = |name: &str, input: &str| {
+            let source = get_source_manager().add(name, input);
+            let preproc = Preprocessor::new(source, &[], &[]);
+            let arena = Arena::default();
+            parser::parse(Lexer::new(preproc), &arena).map(|file| {
+                let module = match file.items[0].data {
+                    ItemData::ModuleDecl(ref m) => m,
+                    _ => panic!("expected module"),
+                };
+                let expr = match module.items[0].data {
+                    ItemData::ContAssign(ref a) => &a.assignments[0].1,
+                    _ => panic!("expected continuous assignment"),
+                };
+                HierName::from_expr(expr).map(|name| {
+                    let segments: Vec<_> = name
+                        .segments
+                        .iter()
+                        .map(|s| (s.scoped, s.name.to_string(), s.span.extract()))
+                        .collect();
+                    (name.to_string(), segments)
+                })
+            })
+        };
+
+        let (text, segments) = parse(
+            "hier_name.sv",
+            "module m; assign x = pkg::cfg.entries[3].valid; endmodule",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(text, "pkg::cfg.entries[3].valid");
+        let seg = |scoped, name: &str, text: &str| (scoped, name.to_string(), text.to_string());
+        assert_eq!(
+            segments,
+            vec![
+                seg(false, "pkg", "pkg"),
+                seg(true, "cfg", "cfg"),
+                seg(false, "entries", "entries[3]"),
+                seg(false, "valid", "valid"),
+            ]
+        );
+
+        let (text, _) = parse(
+            "hier_name_unit.sv",
+            "module m; assign x = $unit::y; endmodule",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(text, "$unit::y");
+
+        // A `::` step cannot follow a `.` step.
+        assert!(parse(
+            "hier_name_fail.sv",
+            "module m; assign x = a.b::c; endmodule"
+        )
+        .is_err());
+    }
+
+    /// Trees that differ only in their source locations compare equal.
+    #[test]
+    fn spanless_eq() {
+        use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+        use moore_common::source::get_source_manager;
+
+        let arena = Arena::default();
+        let parse = |name: &str, input: &str| {
+            let source = get_source_manager().add(name, input);
+            let preproc = Preprocessor::new(source, &[], &[]);
+            parser::parse(Lexer::new(preproc), &arena).unwrap()
+        };
+        let a = parse(
+            "spanless_a.sv",
+            "module foo (input a); assign x = a + 1; endmodule",
+        );
+        let b = parse(
+            "spanless_b.sv",
+            "// comment\nmodule foo (\n  input a\n);\n  assign x = a+1;\nendmodule\n",
+        );
+        let c = parse(
+            "spanless_c.sv",
+            "module foo (input a); assign x = a + 2; endmodule",
+        );
+        assert!(a.spanless_eq(&b));
+        assert!(a != b);
+        assert!(!a.spanless_eq(&c));
+        assert!(!b.spanless_eq(&c));
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Builders for constructing AST nodes programmatically.
+//!
+//! Tools and tests that synthesize an AST rather than parsing it can use these
+//! builders instead of filling in every field by hand. Fields that are not set
+//! explicitly default to an empty list, an implicit type, or no value, and all
+//! spans default to `INVALID_SPAN`. Every call to `build()` creates a fresh
+//! node with its own `NodeId`.
+//!
+//! ```
+//! # use moore_svlog_syntax::{ast::*, builder::*};
+//! let module = ModDeclBuilder::new("foo")
+//!     .port(PortBuilder::new("clk").dir(PortDir::Input).build())
+//!     .build();
+//! assert_eq!(module.ports.len(), 1);
+//! ```
+
+use crate::ast::*;
+use moore_common::{
+    name::{get_name_table, Name},
+    source::{Span, Spanned, INVALID_SPAN},
+};
+
+fn spanned_name(name: &str) -> Spanned<Name> {
+    Spanned::new(get_name_table().intern(name, true), INVALID_SPAN)
+}
+
+/// A builder for module declarations.
+pub struct ModDeclBuilder<'a> {
+    span: Span,
+    lifetime: Lifetime,
+    name: Spanned<Name>,
+    imports: Vec<ImportDecl<'a>>,
+    params: Vec<ParamDecl<'a>>,
+    ports: Vec<Port<'a>>,
+    items: Vec<Item<'a>>,
+}
+
+impl<'a> ModDeclBuilder<'a> {
+    /// Start building a module with a given name.
+    pub fn new(name: &str) -> Self {
+        ModDeclBuilder {
+            span: INVALID_SPAN,
+            lifetime: Lifetime::Static,
+            name: spanned_name(name),
+            imports: vec![],
+            params: vec![],
+            ports: vec![],
+            items: vec![],
+        }
+    }
+
+    /// Set the span of the module.
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Set the span of the module name.
+    pub fn name_span(mut self, span: Span) -> Self {
+        self.name.span = span;
+        self
+    }
+
+    /// Set the default lifetime of the module.
+    pub fn lifetime(mut self, lifetime: Lifetime) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Add an import to the module header.
+    pub fn import(mut self, import: ImportDecl<'a>) -> Self {
+        self.imports.push(import);
+        self
+    }
+
+    /// Add a parameter declaration to the module header.
+    pub fn param(mut self, param: ParamDecl<'a>) -> Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Add a port to the module header.
+    pub fn port(mut self, port: Port<'a>) -> Self {
+        self.ports.push(port);
+        self
+    }
+
+    /// Add an item to the module body.
+    pub fn item(mut self, item: Item<'a>) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// Create the module.
+    pub fn build(self) -> Module<'a> {
+        Module::new(
+            self.span,
+            ModuleData {
+                lifetime: self.lifetime,
+                name: self.name,
+                imports: self.imports,
+                params: self.params,
+                ports: self.ports,
+                items: self.items,
+                default_nettype: Some(NetType::Wire),
+            },
+        )
+    }
+
+    /// Create the module, wrapped in an item.
+    pub fn build_item(self) -> Item<'a> {
+        let span = self.span;
+        Item::new(span, ItemData::ModuleDecl(self.build()))
+    }
+}
+
+/// A builder for named ports in a module or interface header.
+///
+/// Produces ports of the form `input logic [7:0] foo [2] = 0`.
+pub struct PortBuilder<'a> {
+    span: Span,
+    dir: Option<PortDir>,
+    kind: Option<VarKind>,
+    ty: Option<Type<'a>>,
+    name: Spanned<Name>,
+    dims: Vec<TypeDim<'a>>,
+    expr: Option<Expr<'a>>,
+}
+
+impl<'a> PortBuilder<'a> {
+    /// Start building a port with a given name.
+    pub fn new(name: &str) -> Self {
+        PortBuilder {
+            span: INVALID_SPAN,
+            dir: None,
+            kind: None,
+            ty: None,
+            name: spanned_name(name),
+            dims: vec![],
+            expr: None,
+        }
+    }
+
+    /// Set the span of the port.
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Set the span of the port name.
+    pub fn name_span(mut self, span: Span) -> Self {
+        self.name.span = span;
+        self
+    }
+
+    /// Set the direction of the port.
+    pub fn dir(mut self, dir: PortDir) -> Self {
+        self.dir = Some(dir);
+        self
+    }
+
+    /// Set the net or variable kind of the port.
+    pub fn kind(mut self, kind: VarKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set the type of the port. Defaults to an implicit type.
+    pub fn ty(mut self, ty: Type<'a>) -> Self {
+        self.ty = Some(ty);
+        self
+    }
+
+    /// Add an unpacked dimension to the port.
+    pub fn dim(mut self, dim: TypeDim<'a>) -> Self {
+        self.dims.push(dim);
+        self
+    }
+
+    /// Set the default value of the port.
+    pub fn default(mut self, expr: Expr<'a>) -> Self {
+        self.expr = Some(expr);
+        self
+    }
+
+    /// Create the port.
+    pub fn build(self) -> Port<'a> {
+        let span = self.span;
+        Port::new(
+            span,
+            PortData::Named {
+                dir: self.dir,
+                kind: self.kind,
+                ty: self
+                    .ty
+                    .unwrap_or_else(|| TypeBuilder::new(ImplicitType).span(span).build()),
+                name: self.name,
+                dims: self.dims,
+                expr: self.expr,
+            },
+        )
+    }
+}
+
+/// A builder for types.
+///
+/// Produces types of the form `logic signed [7:0]`.
+pub struct TypeBuilder<'a> {
+    span: Span,
+    kind: TypeKindData<'a>,
+    sign: TypeSign,
+    dims: Vec<TypeDim<'a>>,
+}
+
+impl<'a> TypeBuilder<'a> {
+    /// Start building a type of a given kind.
+    pub fn new(kind: TypeKindData<'a>) -> Self {
+        TypeBuilder {
+            span: INVALID_SPAN,
+            kind,
+            sign: TypeSign::None,
+            dims: vec![],
+        }
+    }
+
+    /// Start building a type that refers to a type by name.
+    pub fn named(name: &str) -> Self {
+        Self::new(NamedType(spanned_name(name)))
+    }
+
+    /// Set the span of the type.
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Set the sign of the type.
+    pub fn sign(mut self, sign: TypeSign) -> Self {
+        self.sign = sign;
+        self
+    }
+
+    /// Add a packed dimension to the type.
+    pub fn dim(mut self, dim: TypeDim<'a>) -> Self {
+        self.dims.push(dim);
+        self
+    }
+
+    /// Create the type.
+    pub fn build(self) -> Type<'a> {
+        Type::new(
+            self.span,
+            TypeData {
+                kind: TypeKind::new(self.span, self.kind),
+                sign: self.sign,
+                dims: self.dims,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pretty::Printer;
+
+    #[test]
+    fn module_with_two_ports() {
+        let module = ModDeclBuilder::new("foo")
+            .port(
+                PortBuilder::new("a")
+                    .dir(PortDir::Input)
+                    .ty(TypeBuilder::new(LogicType).build())
+                    .build(),
+            )
+            .port(PortBuilder::new("b").dir(PortDir::Output).build())
+            .build();
+        assert_eq!(module.name.value.to_string(), "foo");
+        assert_eq!(module.ports.len(), 2);
+        assert_ne!(module.ports[0].id(), module.ports[1].id());
+
+        let mut printer = Printer::new();
+        printer.print_module(&module);
+        assert_eq!(
+            printer.finish(),
+            "module foo (input logic a, output b);\nendmodule\n"
+        );
+    }
+
+    #[test]
+    fn type_defaults() {
+        let ty = TypeBuilder::new(LogicType).build();
+        assert_eq!(ty.kind.data, LogicType);
+        assert_eq!(ty.sign, TypeSign::None);
+        assert!(ty.dims.is_empty());
+
+        let ty = TypeBuilder::named("my_t").sign(TypeSign::Signed).build();
+        assert_eq!(ty.kind.data, NamedType(spanned_name("my_t")));
+        assert_eq!(ty.sign, TypeSign::Signed);
+        assert!(ty.dims.is_empty());
+    }
+}
@@ -23,6 +23,15 @@ pub struct Cat<'a> {
     last: usize,
     chars: (Option<char>, Option<char>),
     indices: (usize, usize),
+    /// Whether the last token was inside a string literal, where `//` and `/*`
+    /// do not start a comment.
+    in_string: bool,
+    /// Whether the last token was a backslash that escapes the next character
+    /// of a string literal.
+    in_escape: bool,
+    /// Whether the last token was part of an escaped identifier, where `"`
+    /// does not start a string literal.
+    in_escaped_ident: bool,
 }
 
 impl<'a> Cat<'a> {
@@ -42,6 +51,9 @@ impl<'a> Cat<'a> {
                 c0.map(|x| x.0).unwrap_or(last),
                 c1.map(|x| x.0).unwrap_or(last),
             ),
+            in_string: false,
+            in_escape: false,
+            in_escaped_ident: false,
         }
     }
 
@@ -57,7 +69,7 @@ impl<'a> Iterator for Cat<'a> {
     type Item = CatToken;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.chars {
+        let token = match self.chars {
             (None, _) => None,
 
             // Newlines
@@ -81,7 +93,7 @@ impl<'a> Iterator for Cat<'a> {
 
             // IEEE 1800-2009 5.4 Comments
             // Consume single-line comments initiated by "//".
-            (Some('/'), Some('/')) => {
+            (Some('/'), Some('/')) if !self.in_string => {
                 let p0 = self.indices.0;
                 while let (Some(c), _) = self.chars {
                     if c == '\n' {
@@ -93,7 +105,7 @@ impl<'a> Iterator for Cat<'a> {
             }
 
             // Consume multi-line comments inititated by "/*".
-            (Some('/'), Some('*')) => {
+            (Some('/'), Some('*')) if !self.in_string => {
                 let p0 = self.indices.0;
                 while let (Some(c0), Some(c1)) = self.chars {
                     if c0 == '*' && c1 == '/' {
@@ -106,6 +118,16 @@ impl<'a> Iterator for Cat<'a> {
                 Some(CatToken(Comment, p0, self.indices.0))
             }
 
+            // Invalid UTF-8 byte sequences have been replaced with U+FFFD when
+            // the source was loaded. Consume each run of them as one token.
+            (Some('\u{FFFD}'), _) => {
+                let p0 = self.indices.0;
+                while let (Some('\u{FFFD}'), _) = self.chars {
+                    self.bump();
+                }
+                Some(CatToken(Invalid, p0, self.indices.0))
+            }
+
             // Consume symbols.
             // IEEE 1800-2009 5.5 Operators & 11.3 Operators
             (Some(c), _) if is_symbol(c) => {
@@ -130,21 +152,42 @@ impl<'a> Iterator for Cat<'a> {
             (Some(_), _) => {
                 let p0 = self.indices.0;
                 while let (Some(c), _) = self.chars {
-                    if c == '\n' || is_whitespace(c) || is_symbol(c) {
+                    if c == '\n' || is_whitespace(c) || is_symbol(c) || c == '\u{FFFD}' {
                         break;
                     }
                     self.bump();
                 }
                 Some(CatToken(Text, p0, self.indices.0))
             }
+        };
+
+        // Keep track of string literals and escaped identifiers.
+        let kind = token.map(|t| t.0);
+        let escaped = self.in_escape;
+        self.in_escape = false;
+        match kind {
+            Some(Symbol('"')) if !escaped && !self.in_escaped_ident => {
+                self.in_string = !self.in_string
+            }
+            Some(Symbol('\\')) if self.in_string => self.in_escape = !escaped,
+            Some(Symbol('\\')) => self.in_escaped_ident = true,
+            Some(Newline) if !escaped => {
+                self.in_string = false;
+                self.in_escaped_ident = false;
+            }
+            Some(Whitespace) => self.in_escaped_ident = false,
+            _ => (),
         }
+        token
     }
 }
 
 /// Check whether the given character is considered a whitespace in
-/// SystemVerilog.
+/// SystemVerilog. Besides spaces, tabs, and carriage returns, this accepts
+/// Unicode whitespace such as the non-breaking space U+00A0, which the lexer
+/// warns about.
 fn is_whitespace(c: char) -> bool {
-    c == ' ' || c == '\t' || c == '\r' || c == (0xA0 as char)
+    c == ' ' || c == '\t' || c == '\r' || (c != '\n' && c.is_whitespace())
 }
 
 /// Check whether the given character is a digit.
@@ -176,6 +219,8 @@ pub enum CatTokenKind {
     Symbol(char),
     Text,
     Digits,
+    /// A run of invalid UTF-8 byte sequences.
+    Invalid,
     Eof,
 }
 
@@ -207,4 +252,40 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn unicode_whitespace_and_invalid() {
+        assert_eq!(
+            lex("a\u{A0}b\u{2003}c\u{FFFD}\u{FFFD}d"),
+            vec![
+                CatToken(Text, 0, 1),
+                CatToken(Whitespace, 1, 3),
+                CatToken(Text, 3, 4),
+                CatToken(Whitespace, 4, 7),
+                CatToken(Text, 7, 8),
+                CatToken(Invalid, 8, 14),
+                CatToken(Text, 14, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_in_strings() {
+        assert_eq!(
+            lex("\"a//\\\"/*\" //c"),
+            vec![
+                CatToken(Symbol('"'), 0, 1),
+                CatToken(Text, 1, 2),
+                CatToken(Symbol('/'), 2, 3),
+                CatToken(Symbol('/'), 3, 4),
+                CatToken(Symbol('\\'), 4, 5),
+                CatToken(Symbol('"'), 5, 6),
+                CatToken(Symbol('/'), 6, 7),
+                CatToken(Symbol('*'), 7, 8),
+                CatToken(Symbol('"'), 8, 9),
+                CatToken(Whitespace, 9, 10),
+                CatToken(Comment, 10, 13),
+            ]
+        );
+    }
 }
@@ -0,0 +1,221 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Consistency checks between extern module declarations and their
+//! definitions.
+//!
+//! An extern module declaration fixes the parameter and port lists of a module
+//! whose definition is provided separately. The definition must repeat that
+//! header exactly. See §23.2.1 of IEEE 1800-2017.
+
+use crate::{
+    ast::*,
+    pretty::{render_param_decl, Printer},
+};
+use moore_common::{errors::DiagBuilder2, source::Span};
+
+/// Check that a module definition matches its extern declaration.
+///
+/// Parameters and ports are compared by name, position, and their rendered
+/// declaration, with any direction, kind, or type inherited from a preceding
+/// port made explicit. Returns one diagnostic per mismatch.
+pub fn check_extern_module(ext: &ExternModule, module: &Module) -> Vec<DiagBuilder2> {
+    let mut diags = vec![];
+    compare(
+        &mut diags,
+        "parameter",
+        ext,
+        module,
+        &param_entries(&ext.params),
+        &param_entries(&module.params),
+    );
+    compare(
+        &mut diags,
+        "port",
+        ext,
+        module,
+        &port_entries(&ext.ports),
+        &port_entries(&module.ports),
+    );
+    diags
+}
+
+/// A named parameter or port in a module header.
+struct Entry {
+    name: String,
+    span: Span,
+    text: String,
+}
+
+fn param_entries(params: &[ParamDecl]) -> Vec<Entry> {
+    let mut entries = vec![];
+    for decl in params {
+        let names: Vec<_> = match decl.kind {
+            ParamKind::Type(ref decls) => decls.iter().map(|d| (d.name, d.span)).collect(),
+            ParamKind::Value(ref decls) => decls.iter().map(|d| (d.name, d.span)).collect(),
+        };
+        for ((name, span), text) in names.into_iter().zip(render_param_decl(decl)) {
+            entries.push(Entry {
+                name: name.value.to_string(),
+                span,
+                text,
+            });
+        }
+    }
+    entries
+}
+
+fn port_entries(ports: &[Port]) -> Vec<Entry> {
+    let texts = Printer::new().explicit_ports(true).render_ports(ports);
+    ports
+        .iter()
+        .zip(texts)
+        .map(|(port, text)| {
+            let name = match port.data {
+                PortData::Intf { name, .. }
+                | PortData::Explicit { name, .. }
+                | PortData::Named { name, .. } => name.value.to_string(),
+                PortData::Implicit(ref expr) => expr.span.extract(),
+            };
+            Entry {
+                name,
+                span: port.span,
+                text,
+            }
+        })
+        .collect()
+}
+
+fn compare(
+    diags: &mut Vec<DiagBuilder2>,
+    what: &str,
+    ext: &ExternModule,
+    module: &Module,
+    ext_entries: &[Entry],
+    mod_entries: &[Entry],
+) {
+    for (index, decl) in ext_entries.iter().enumerate() {
+        let def_index = match mod_entries.iter().position(|e| e.name == decl.name) {
+            Some(i) => i,
+            None => {
+                diags.push(
+                    DiagBuilder2::error(format!(
+                        "{} `{}` of {} missing from its definition",
+                        what,
+                        decl.name,
+                        ext.to_definite_string()
+                    ))
+                    .span(module.name.span)
+                    .add_note(format!("`{}` declared here:", decl.name))
+                    .span(decl.span),
+                );
+                continue;
+            }
+        };
+        let def = &mod_entries[def_index];
+        if def.text != decl.text {
+            diags.push(
+                DiagBuilder2::error(format!(
+                    "{} `{}` of {} does not match its extern declaration",
+                    what,
+                    def.name,
+                    module.to_definite_string()
+                ))
+                .span(def.span)
+                .add_note(format!(
+                    "Defined as `{}`, but declared as `{}`:",
+                    def.text, decl.text
+                ))
+                .span(decl.span),
+            );
+        } else if def_index != index {
+            diags.push(
+                DiagBuilder2::error(format!(
+                    "{} `{}` of {} is out of order with its extern declaration",
+                    what,
+                    def.name,
+                    module.to_definite_string()
+                ))
+                .span(def.span)
+                .add_note(format!("Declared as {} {} here:", what, index + 1))
+                .span(decl.span),
+            );
+        }
+    }
+    for def in mod_entries {
+        if !ext_entries.iter().any(|e| e.name == def.name) {
+            diags.push(
+                DiagBuilder2::error(format!(
+                    "{} `{}` of {} not declared in its extern declaration",
+                    what,
+                    def.name,
+                    module.to_definite_string()
+                ))
+                .span(def.span)
+                .add_note("Extern declaration is here:")
+                .span(ext.name.span),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    fn check(name: &str, input: &str) -> Vec<String> {
+        let source = get_source_manager().add(name, input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let (ext, module) = match (&file.items[0].data, &file.items[1].data) {
+            (ItemData::ExternModuleDecl(ext), ItemData::ModuleDecl(module)) => (ext, module),
+            _ => panic!("expected extern module followed by module"),
+        };
+        check_extern_module(ext, module)
+            .into_iter()
+            .map(|d| d.get_message().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn matching_header() {
+        let diags = check(
+            "extern_match.sv",
+            "extern module foo #(parameter N = 8) (input logic [N-1:0] a, b, output c);\n\
+             module foo #(parameter N = 8) (input logic [N-1:0] a, input logic [N-1:0] b, output c);\n\
+             endmodule\n",
+        );
+        assert_eq!(diags, Vec::<String>::new());
+    }
+
+    #[test]
+    fn direction_mismatch() {
+        let diags = check(
+            "extern_dir.sv",
+            "extern module foo (input logic a, output logic b);\n\
+             module foo (input logic a, input logic b);\nendmodule\n",
+        );
+        assert_eq!(
+            diags,
+            vec!["port `b` of module `foo` does not match its extern declaration"]
+        );
+    }
+
+    #[test]
+    fn missing_port() {
+        let diags = check(
+            "extern_missing.sv",
+            "extern module foo #(parameter N = 1) (input a, input b);\n\
+             module foo #(parameter N = 1, parameter M = 2) (input a);\nendmodule\n",
+        );
+        assert_eq!(
+            diags,
+            vec![
+                "parameter `M` of module `foo` not declared in its extern declaration",
+                "port `b` of extern module `foo` missing from its definition",
+            ]
+        );
+    }
+}
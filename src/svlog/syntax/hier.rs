@@ -0,0 +1,250 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Canonical hierarchical paths into the elaborated design.
+//!
+//! Generate loops, generate blocks, and instances introduce levels of
+//! hierarchy, such that an instance may end up with a path like
+//! `top.gen_lanes[3].u_lane`. A `HierPath` is the canonical representation of
+//! such a path. It renders to and parses from the bracketed text form, and can
+//! be used as a key in maps. Names that are not simple identifiers are
+//! rendered as escaped identifiers, such as `\weird.name `.
+
+use moore_common::{errors::DiagBuilder2, name::Name};
+use std::{cmp::Ordering, fmt};
+
+/// A segment of a hierarchical path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HierSegment {
+    /// A block label or instance name.
+    Name(Name),
+    /// The index of a generate loop iteration.
+    Index(i64),
+}
+
+/// A hierarchical path, such as `top.gen_lanes[3].u_lane`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct HierPath {
+    /// The segments of the path, outermost first.
+    pub segments: Vec<HierSegment>,
+}
+
+impl HierPath {
+    /// Parse the canonical text form of a path.
+    ///
+    /// Accepts names separated by `.`, each optionally followed by one or more
+    /// `[<index>]`. Escaped identifiers are terminated by a single space.
+    pub fn parse(text: &str) -> Result<HierPath, DiagBuilder2> {
+        let err = |msg: &str| {
+            DiagBuilder2::error(format!("invalid hierarchical path `{}`: {}", text, msg))
+        };
+        let mut segments = vec![];
+        let mut rest = text;
+        loop {
+            // Parse a simple or escaped name.
+            let name = if let Some(esc) = rest.strip_prefix('\\') {
+                let end = esc
+                    .find(char::is_whitespace)
+                    .ok_or_else(|| err("escaped identifier not terminated by a space"))?;
+                rest = &esc[end + 1..];
+                &esc[..end]
+            } else {
+                let end = rest
+                    .find(|c: char| !is_ident_char(c))
+                    .unwrap_or_else(|| rest.len());
+                let name = &rest[..end];
+                rest = &rest[end..];
+                name
+            };
+            if name.is_empty() {
+                return Err(err("expected a name"));
+            }
+            segments.push(HierSegment::Name(Name::from(name)));
+
+            // Parse the loop indices.
+            while let Some(index) = rest.strip_prefix('[') {
+                let end = index.find(']').ok_or_else(|| err("missing `]`"))?;
+                let value = index[..end]
+                    .parse()
+                    .map_err(|_| err("expected an integer index"))?;
+                segments.push(HierSegment::Index(value));
+                rest = &index[end + 1..];
+            }
+
+            if rest.is_empty() {
+                break;
+            }
+            rest = rest
+                .strip_prefix('.')
+                .ok_or_else(|| err("expected `.` or `[` after name"))?;
+        }
+        Ok(HierPath { segments })
+    }
+
+    /// Get the path that contains this path, or `None` for a path with a
+    /// single segment.
+    pub fn parent(&self) -> Option<HierPath> {
+        if self.segments.len() <= 1 {
+            return None;
+        }
+        Some(HierPath {
+            segments: self.segments[..self.segments.len() - 1].to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for HierPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match *segment {
+                HierSegment::Name(name) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    let name = name.as_str();
+                    let simple = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                        && name.chars().all(is_ident_char);
+                    if simple {
+                        write!(f, "{}", name)?;
+                    } else {
+                        write!(f, "\\{} ", name)?;
+                    }
+                }
+                HierSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Ord for HierSegment {
+    /// Names are ordered lexicographically and before indices, which are
+    /// ordered numerically.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (*self, *other) {
+            (HierSegment::Name(a), HierSegment::Name(b)) => a.as_str().cmp(&b.as_str()),
+            (HierSegment::Name(_), HierSegment::Index(_)) => Ordering::Less,
+            (HierSegment::Index(_), HierSegment::Name(_)) => Ordering::Greater,
+            (HierSegment::Index(a), HierSegment::Index(b)) => a.cmp(&b),
+        }
+    }
+}
+
+impl PartialOrd for HierSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HierPath {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.segments.cmp(&other.segments)
+    }
+}
+
+impl PartialOrd for HierPath {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// A builder for hierarchical paths.
+///
+/// ```
+/// # use moore_svlog_syntax::hier::*;
+/// let path = PathBuilder::new()
+///     .instance("top".into())
+///     .block("gen_lanes".into())
+///     .index(3)
+///     .instance("u_lane".into())
+///     .build();
+/// assert_eq!(path.to_string(), "top.gen_lanes[3].u_lane");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PathBuilder {
+    path: HierPath,
+}
+
+impl PathBuilder {
+    /// Start building an empty path.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Start building a path below an existing one.
+    pub fn within(path: &HierPath) -> Self {
+        PathBuilder { path: path.clone() }
+    }
+
+    /// Add the label of a generate or named block.
+    pub fn block(mut self, label: Name) -> Self {
+        self.path.segments.push(HierSegment::Name(label));
+        self
+    }
+
+    /// Add the name of an instance.
+    pub fn instance(mut self, name: Name) -> Self {
+        self.path.segments.push(HierSegment::Name(name));
+        self
+    }
+
+    /// Add the index of a generate loop iteration, which is the constant value
+    /// of the loop's genvar.
+    pub fn index(mut self, index: i64) -> Self {
+        self.path.segments.push(HierSegment::Index(index));
+        self
+    }
+
+    /// Create the path.
+    pub fn build(self) -> HierPath {
+        self.path
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn round_trip() {
+        let path = PathBuilder::new()
+            .instance("top".into())
+            .block("gen_lanes".into())
+            .index(3)
+            .instance("weird.name".into())
+            .block("b".into())
+            .build();
+        let text = path.to_string();
+        assert_eq!(text, "top.gen_lanes[3].\\weird.name .b");
+        let parsed = HierPath::parse(&text).unwrap();
+        assert_eq!(parsed, path);
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(
+            parsed.parent().unwrap(),
+            HierPath::parse("top.gen_lanes[3].\\weird.name ").unwrap()
+        );
+    }
+
+    #[test]
+    fn ordering() {
+        let mut map = BTreeMap::new();
+        for text in &["top.g[10].u", "top.g[2].u", "top.a", "top"] {
+            map.insert(HierPath::parse(text).unwrap(), *text);
+        }
+        assert_eq!(
+            map.values().cloned().collect::<Vec<_>>(),
+            vec!["top", "top.a", "top.g[2].u", "top.g[10].u"]
+        );
+    }
+
+    #[test]
+    fn invalid_paths() {
+        assert!(HierPath::parse("top..u").is_err());
+        assert!(HierPath::parse("top.g[x]").is_err());
+        assert!(HierPath::parse("top.\\esc").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Validation of the port connections of a module instantiation.
+//!
+//! Ports can be connected by position, by name as in `.a(x)`, implicitly by
+//! name as in `.a`, or all at once through the `.*` wildcard. Named
+//! connections must refer to ports of the instantiated module, and each port
+//! may be connected at most once. See §23.3.2 of IEEE 1800-2017.
+
+use crate::ast::*;
+use moore_common::{
+    errors::{DiagBuilder2, DiagCode},
+    name::Name,
+    source::Span,
+};
+
+/// Check the port connections of an instantiation against the instantiated
+/// module.
+///
+/// Each instance in `inst` is checked separately. The `has_net` function
+/// reports whether a net or variable of a given name is visible at the
+/// instantiation; it decides whether implicit `.name` and `.*` connections
+/// can be made. Diagnostics carry the span of the offending connection and
+/// the span of the target port's declaration, if there is one.
+pub fn check_instantiation(
+    inst: &Inst,
+    target: &Module,
+    has_net: &dyn Fn(Name) -> bool,
+) -> Vec<DiagBuilder2> {
+    let ports: Vec<_> = target.ports.iter().map(TargetPort::new).collect();
+    let mut diags = vec![];
+    for name in &inst.names {
+        check_instance(&mut diags, name, target, &ports, has_net);
+    }
+    diags
+}
+
+/// A port of the instantiated module.
+struct TargetPort {
+    name: Option<Name>,
+    span: Span,
+    has_default: bool,
+}
+
+impl TargetPort {
+    fn new(port: &Port) -> Self {
+        let (name, has_default) = match port.data {
+            PortData::Intf { name, ref expr, .. } | PortData::Named { name, ref expr, .. } => {
+                (Some(name.value), expr.is_some())
+            }
+            PortData::Explicit { name, .. } => (Some(name.value), false),
+            PortData::Implicit(ref expr) => match expr.data {
+                IdentExpr(name) => (Some(name.value), false),
+                _ => (None, false),
+            },
+        };
+        TargetPort {
+            name,
+            span: port.span,
+            has_default,
+        }
+    }
+}
+
+fn check_instance(
+    diags: &mut Vec<DiagBuilder2>,
+    inst: &InstName,
+    target: &Module,
+    ports: &[TargetPort],
+    has_net: &dyn Fn(Name) -> bool,
+) {
+    // Assign the connections to the ports.
+    let mut conns: Vec<Option<&PortConn>> = vec![None; ports.len()];
+    let mut wildcard: Option<&PortConn> = None;
+    let mut positional = 0;
+    for conn in &inst.conns {
+        let index = match conn.data {
+            PortConnData::Auto => {
+                if let Some(prev) = wildcard {
+                    diags.push(
+                        DiagBuilder2::error("`.*` appears multiple times in port connections")
+                            .span(conn.span)
+                            .add_note("Previous `.*` here:")
+                            .span(prev.span),
+                    );
+                }
+                wildcard = Some(conn);
+                continue;
+            }
+            PortConnData::Named(name, _) => {
+                match ports.iter().position(|p| p.name == Some(name.value)) {
+                    Some(index) => index,
+                    None => {
+                        diags.push(
+                            DiagBuilder2::error(format!(
+                                "{} has no port `{}`",
+                                target.to_definite_string(),
+                                name
+                            ))
+                            .span(conn.span)
+                            .add_note(format!("{} declared here:", target.to_definite_string()))
+                            .span(target.name.span),
+                        );
+                        continue;
+                    }
+                }
+            }
+            PortConnData::Positional(_) => {
+                positional += 1;
+                if positional > ports.len() {
+                    diags.push(
+                        DiagBuilder2::error(format!(
+                            "too many port connections for {}",
+                            target.to_definite_string()
+                        ))
+                        .span(conn.span)
+                        .add_note(format!(
+                            "{} has {} ports:",
+                            target.to_definite_string(),
+                            ports.len()
+                        ))
+                        .span(target.name.span),
+                    );
+                    continue;
+                }
+                positional - 1
+            }
+        };
+        if let Some(prev) = conns[index] {
+            diags.push(
+                DiagBuilder2::error(format!(
+                    "{} connected multiple times",
+                    port_desc(&ports[index])
+                ))
+                .span(conn.span)
+                .add_note("Previous connection here:")
+                .span(prev.span)
+                .add_note("Port declared here:")
+                .span(ports[index].span),
+            );
+            continue;
+        }
+        conns[index] = Some(conn);
+    }
+
+    // Check the connections that rely on an identically named net in scope,
+    // and the ports that remain unconnected.
+    for (port, conn) in ports.iter().zip(conns) {
+        let name = match port.name {
+            Some(name) => name,
+            None => continue,
+        };
+        match conn {
+            Some(conn) => {
+                match conn.data {
+                    PortConnData::Named(_, PortConnMode::Auto) => (),
+                    _ => continue,
+                }
+                if let Some(wildcard) = wildcard {
+                    diags.push(
+                        DiagBuilder2::warning(format!(
+                            "implicit connection of {} is redundant with `.*`",
+                            port_desc(port)
+                        ))
+                        .code(DiagCode::REDUNDANT_PORT_CONN)
+                        .span(conn.span)
+                        .add_note("`.*` here:")
+                        .span(wildcard.span)
+                        .add_note("Port declared here:")
+                        .span(port.span),
+                    );
+                }
+                if !has_net(name) {
+                    diags.push(
+                        DiagBuilder2::error(format!(
+                            "no net `{}` in scope for implicit connection of {}",
+                            name,
+                            port_desc(port)
+                        ))
+                        .span(conn.span)
+                        .add_note("Port declared here:")
+                        .span(port.span),
+                    );
+                }
+            }
+            None if port.has_default => (),
+            None => match wildcard {
+                Some(wildcard) => {
+                    if !has_net(name) {
+                        diags.push(
+                            DiagBuilder2::error(format!(
+                                "no net `{}` in scope for `.*` connection of {}",
+                                name,
+                                port_desc(port)
+                            ))
+                            .span(wildcard.span)
+                            .add_note("Port declared here:")
+                            .span(port.span),
+                        );
+                    }
+                }
+                None => {
+                    diags.push(
+                        DiagBuilder2::warning(format!(
+                            "{} of {} is not connected",
+                            port_desc(port),
+                            inst.to_definite_string()
+                        ))
+                        .code(DiagCode::UNCONNECTED_PORT)
+                        .span(inst.name.span)
+                        .add_note("Port declared here:")
+                        .span(port.span),
+                    );
+                }
+            },
+        }
+    }
+}
+
+fn port_desc(port: &TargetPort) -> String {
+    match port.name {
+        Some(name) => format!("port `{}`", name),
+        None => "port".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::{errors::DiagSegment, source::get_source_manager};
+
+    /// Parse a module followed by a module that instantiates it, and check the
+    /// first instantiation. Nets named `n*` are considered to be in scope.
+    fn check(name: &str, input: &str) -> Vec<(String, usize)> {
+        let source = get_source_manager().add(name, input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let (target, parent) = match (&file.items[0].data, &file.items[1].data) {
+            (ItemData::ModuleDecl(target), ItemData::ModuleDecl(parent)) => (target, parent),
+            _ => panic!("expected two modules"),
+        };
+        let inst = parent
+            .items
+            .iter()
+            .find_map(|item| match item.data {
+                ItemData::Inst(ref inst) => Some(inst),
+                _ => None,
+            })
+            .expect("expected instantiation");
+        let has_net = |name: Name| name.as_str().starts_with('n');
+        check_instantiation(inst, target, &has_net)
+            .into_iter()
+            .map(|d| {
+                let spans = d
+                    .get_segments()
+                    .iter()
+                    .filter(|s| matches!(s, DiagSegment::Span(_)))
+                    .count();
+                (d.get_message().to_string(), spans)
+            })
+            .collect()
+    }
+
+    const TARGET: &str = "module foo (input a, input b = 0, output c); endmodule\n";
+
+    #[test]
+    fn valid_connections() {
+        let diags = check(
+            "inst_valid.sv",
+            &format!(
+                "{}module top; foo u0 (.a(x), .c()); foo u1 (x, y, z); endmodule",
+                TARGET
+            ),
+        );
+        assert_eq!(diags, vec![]);
+    }
+
+    #[test]
+    fn unknown_port() {
+        let diags = check(
+            "inst_unknown.sv",
+            &format!(
+                "{}module top; foo u0 (.a(x), .d(y), .c()); endmodule",
+                TARGET
+            ),
+        );
+        assert_eq!(diags, vec![("module `foo` has no port `d`".to_string(), 2)]);
+    }
+
+    #[test]
+    fn duplicate_connection() {
+        let diags = check(
+            "inst_dup.sv",
+            &format!(
+                "{}module top; foo u0 (.a(x), .c(), .a(y)); endmodule",
+                TARGET
+            ),
+        );
+        assert_eq!(
+            diags,
+            vec![("port `a` connected multiple times".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn wildcard_and_implicit() {
+        let diags = check(
+            "inst_wildcard.sv",
+            &format!(
+                "{}module top; foo u0 (.*, .na); endmodule",
+                TARGET.replace(" a,", " na,")
+            ),
+        );
+        assert_eq!(
+            diags,
+            vec![
+                (
+                    "implicit connection of port `na` is redundant with `.*`".to_string(),
+                    3
+                ),
+                (
+                    "no net `c` in scope for `.*` connection of port `c`".to_string(),
+                    2
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_connection() {
+        let diags = check(
+            "inst_missing.sv",
+            &format!("{}module top; foo u0 (.c()); endmodule", TARGET),
+        );
+        assert_eq!(
+            diags,
+            vec![("port `a` of instance `u0` is not connected".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn implicit_without_net() {
+        let diags = check(
+            "inst_implicit.sv",
+            &format!("{}module top; foo u0 (.a, .c()); endmodule", TARGET),
+        );
+        assert_eq!(
+            diags,
+            vec![(
+                "no net `a` in scope for implicit connection of port `a`".to_string(),
+                2
+            )]
+        );
+    }
+}
@@ -0,0 +1,504 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! A JSON representation of the AST for external tooling.
+//!
+//! Names are emitted as strings, spans as `{file, begin, end}` objects, and
+//! expressions as their source text. Items without a dedicated representation
+//! carry their kind and source text. The corresponding AST nodes also implement
+//! `serde::Serialize`. Only available with the `serde` feature enabled.
+
+use crate::ast::*;
+use crate::pretty::render_dim;
+use moore_common::source::Span;
+use serde_json::{json, Map, Value as Json};
+
+/// Convert a module to JSON.
+///
+/// ```ignore
+/// let json = to_json(module);
+/// assert_eq!(json["name"], "foo");
+/// ```
+pub fn to_json(module: &Module) -> Json {
+    json!({
+        "kind": "module",
+        "span": span_to_json(module.span),
+        "name": module.name.value.to_string(),
+        "lifetime": lifetime_to_json(&module.lifetime),
+        "imports": module.imports.iter().map(import_to_json).collect::<Vec<_>>(),
+        "params": module.params.iter().map(param_decl_to_json).collect::<Vec<_>>(),
+        "ports": module.ports.iter().map(port_to_json).collect::<Vec<_>>(),
+        "items": module.items.iter().map(item_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Convert an interface to JSON.
+pub fn interface_to_json(intf: &Interface) -> Json {
+    json!({
+        "kind": "interface",
+        "span": span_to_json(intf.span),
+        "name": intf.name.value.to_string(),
+        "lifetime": lifetime_to_json(&intf.lifetime),
+        "params": intf.params.iter().map(param_decl_to_json).collect::<Vec<_>>(),
+        "ports": intf.ports.iter().map(port_to_json).collect::<Vec<_>>(),
+        "items": intf.items.iter().map(item_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Convert a span to JSON.
+pub fn span_to_json(span: Span) -> Json {
+    json!({
+        "file": span.source.get_path().to_string(),
+        "begin": span.begin,
+        "end": span.end,
+    })
+}
+
+/// Convert a lifetime to JSON.
+fn lifetime_to_json(lifetime: &Lifetime) -> Json {
+    match lifetime {
+        Lifetime::Static => json!("static"),
+        Lifetime::Automatic => json!("automatic"),
+    }
+}
+
+/// Convert an optional expression to JSON.
+fn expr_to_json(expr: Option<&Expr>) -> Json {
+    match expr {
+        Some(expr) => json!(expr.span.extract()),
+        None => Json::Null,
+    }
+}
+
+/// Convert an import declaration to JSON.
+fn import_to_json(decl: &ImportDecl) -> Json {
+    let items: Vec<_> = decl
+        .items
+        .iter()
+        .map(|item| {
+            json!({
+                "package": item.pkg.value.to_string(),
+                "name": item.name.map(|n| n.value.to_string()),
+            })
+        })
+        .collect();
+    json!({
+        "kind": "import",
+        "span": span_to_json(decl.span),
+        "items": items,
+    })
+}
+
+/// Convert a parameter declaration to JSON.
+pub fn param_decl_to_json(decl: &ParamDecl) -> Json {
+    let (kind, params) = match decl.kind {
+        ParamKind::Type(ref decls) => (
+            "type",
+            decls
+                .iter()
+                .map(|d| {
+                    json!({
+                        "name": d.name.value.to_string(),
+                        "default": d.ty.as_ref().map(type_to_json),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+        ParamKind::Value(ref decls) => (
+            "value",
+            decls
+                .iter()
+                .map(|d| {
+                    json!({
+                        "name": d.name.value.to_string(),
+                        "type": type_to_json(&d.ty),
+                        "dims": d.dims.iter().map(dim_to_json).collect::<Vec<_>>(),
+                        "default": expr_to_json(d.expr.as_ref()),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        ),
+    };
+    json!({
+        "kind": kind,
+        "span": span_to_json(decl.span),
+        "local": decl.local,
+        "params": params,
+    })
+}
+
+/// Convert a port to JSON.
+pub fn port_to_json(port: &Port) -> Json {
+    let mut map = Map::new();
+    map.insert("span".into(), span_to_json(port.span));
+    match port.data {
+        PortData::Intf {
+            modport,
+            name,
+            ref dims,
+            ref expr,
+        } => {
+            map.insert("kind".into(), json!("interface"));
+            map.insert("name".into(), json!(name.value.to_string()));
+            map.insert(
+                "modport".into(),
+                json!(modport.map(|m| m.value.to_string())),
+            );
+            map.insert("dims".into(), dims_to_json(dims));
+            map.insert("default".into(), expr_to_json(expr.as_ref()));
+        }
+        PortData::Explicit {
+            dir,
+            name,
+            ref expr,
+        } => {
+            map.insert("kind".into(), json!("explicit"));
+            map.insert("name".into(), json!(name.value.to_string()));
+            map.insert("dir".into(), json!(dir.map(|d| d.to_string())));
+            map.insert("expr".into(), expr_to_json(expr.as_ref()));
+        }
+        PortData::Named {
+            dir,
+            kind,
+            ref ty,
+            name,
+            ref dims,
+            ref expr,
+        } => {
+            map.insert("kind".into(), json!("named"));
+            map.insert("name".into(), json!(name.value.to_string()));
+            map.insert("dir".into(), json!(dir.map(|d| d.to_string())));
+            map.insert("var_kind".into(), json!(kind.map(|k| k.to_string())));
+            map.insert("type".into(), type_to_json(ty));
+            map.insert("dims".into(), dims_to_json(dims));
+            map.insert("default".into(), expr_to_json(expr.as_ref()));
+        }
+        PortData::Implicit(ref expr) => {
+            map.insert("kind".into(), json!("implicit"));
+            map.insert("expr".into(), expr_to_json(Some(expr)));
+        }
+    }
+    Json::Object(map)
+}
+
+/// Convert a type to JSON.
+pub fn type_to_json(ty: &Type) -> Json {
+    let sign = match ty.sign {
+        TypeSign::None => Json::Null,
+        TypeSign::Signed => json!("signed"),
+        TypeSign::Unsigned => json!("unsigned"),
+    };
+    json!({
+        "kind": type_kind_to_json(&ty.kind),
+        "sign": sign,
+        "dims": dims_to_json(&ty.dims),
+    })
+}
+
+/// Convert a type kind to JSON.
+///
+/// Simple types are represented by their keyword. Named, scoped, enum, and
+/// struct types are represented as objects.
+fn type_kind_to_json(kind: &TypeKind) -> Json {
+    let s = match kind.data {
+        ImplicitType => "implicit",
+        ImplicitSignedType => "implicit",
+        ImplicitUnsignedType => "implicit",
+        VoidType => "void",
+        StringType => "string",
+        ChandleType => "chandle",
+        EventType => "event",
+        MailboxType => "mailbox",
+        BitType => "bit",
+        LogicType => "logic",
+        RegType => "reg",
+        ByteType => "byte",
+        ShortIntType => "shortint",
+        IntType => "int",
+        IntegerType => "integer",
+        LongIntType => "longint",
+        TimeType => "time",
+        ShortRealType => "shortreal",
+        RealType => "real",
+        RealtimeType => "realtime",
+        NamedType(name) => return json!({ "named": name.value.to_string() }),
+        ScopedType {
+            ref ty,
+            member,
+            name,
+        } => {
+            return json!({
+                "scope": type_to_json(ty),
+                "member": member,
+                "name": name.value.to_string(),
+            })
+        }
+        EnumType(ref e) => {
+            let variants: Vec<_> = e
+                .variants
+                .iter()
+                .map(|v| {
+                    json!({
+                        "name": v.name.value.to_string(),
+                        "value": expr_to_json(v.value.as_ref()),
+                    })
+                })
+                .collect();
+            return json!({
+                "enum": variants,
+                "base": e.base_type.as_ref().map(|ty| type_to_json(ty)),
+            });
+        }
+        StructType(ref st) => {
+            let members: Vec<_> = st
+                .members
+                .iter()
+                .flat_map(|m| {
+                    m.names.iter().map(move |n| {
+                        json!({
+                            "name": n.name.to_string(),
+                            "type": type_to_json(&m.ty),
+                            "dims": dims_to_json(&n.dims),
+                        })
+                    })
+                })
+                .collect();
+            return json!({
+                "aggregate": st.kind.to_string(),
+                "members": members,
+                "packed": st.packed,
+            });
+        }
+        _ => return json!({ "text": kind.span.extract() }),
+    };
+    json!(s)
+}
+
+/// Convert a list of dimensions to JSON.
+fn dims_to_json(dims: &[TypeDim]) -> Json {
+    Json::Array(dims.iter().map(dim_to_json).collect())
+}
+
+/// Convert a dimension to JSON.
+///
+/// Each dimension is represented by its source text, e.g. `"[7:0]"`.
+fn dim_to_json(dim: &TypeDim) -> Json {
+    json!(render_dim(dim))
+}
+
+/// Convert the names of a variable, net, or port declaration to JSON.
+fn var_names_to_json(names: &[VarDeclName]) -> Json {
+    names
+        .iter()
+        .map(|n| {
+            json!({
+                "name": n.name.to_string(),
+                "dims": dims_to_json(&n.dims),
+                "init": expr_to_json(n.init.as_ref()),
+            })
+        })
+        .collect()
+}
+
+/// Convert an item to JSON.
+///
+/// Every item carries its kind and span. Declarations additionally carry
+/// their details, and all other items their source text.
+pub fn item_to_json(item: &Item) -> Json {
+    let mut map = Map::new();
+    map.insert(
+        "kind".into(),
+        json!(item.data.format_indefinite().to_string()),
+    );
+    map.insert("span".into(), span_to_json(item.span));
+    match item.data {
+        ItemData::ModuleDecl(ref x) => return to_json(x),
+        ItemData::InterfaceDecl(ref x) => return interface_to_json(x),
+        ItemData::ImportDecl(ref x) => return import_to_json(x),
+        ItemData::ParamDecl(ref x) => return param_decl_to_json(x),
+        ItemData::PortDecl(ref x) => {
+            map.insert("dir".into(), json!(x.dir.to_string()));
+            map.insert("var_kind".into(), json!(x.kind.map(|k| k.to_string())));
+            map.insert("type".into(), type_to_json(&x.ty));
+            map.insert("names".into(), var_names_to_json(&x.names));
+        }
+        ItemData::VarDecl(ref x) => {
+            map.insert("type".into(), type_to_json(&x.ty));
+            map.insert("names".into(), var_names_to_json(&x.names));
+        }
+        ItemData::NetDecl(ref x) => {
+            map.insert("kind".into(), json!(x.format_indefinite().to_string()));
+            map.insert("net_type".into(), json!(x.net_type.to_string()));
+            map.insert("type".into(), type_to_json(&x.ty));
+            map.insert("names".into(), var_names_to_json(&x.names));
+        }
+        ItemData::Typedef(ref x) => {
+            map.insert("name".into(), json!(x.name.value.to_string()));
+            map.insert("type".into(), type_to_json(&x.ty));
+            map.insert("dims".into(), dims_to_json(&x.dims));
+        }
+        ItemData::Inst(ref x) => {
+            map.insert("kind".into(), json!(x.format_indefinite().to_string()));
+            map.insert("target".into(), json!(x.target.value.to_string()));
+            let names: Vec<_> = x
+                .names
+                .iter()
+                .map(|n| json!(n.name.value.to_string()))
+                .collect();
+            map.insert("names".into(), Json::Array(names));
+            map.insert("text".into(), json!(item.span.extract()));
+        }
+        ItemData::Defparam(ref x) => {
+            let assignments: Vec<_> = x
+                .assignments
+                .iter()
+                .map(|(name, value)| {
+                    json!({
+                        "target": hier_name_to_json(name),
+                        "value": value.span.extract(),
+                    })
+                })
+                .collect();
+            map.insert("assignments".into(), Json::Array(assignments));
+        }
+        _ => {
+            map.insert("text".into(), json!(item.span.extract()));
+        }
+    }
+    Json::Object(map)
+}
+
+/// Convert a hierarchical name to JSON.
+///
+/// Each segment records whether it follows a `::` or a `.`, such that the path
+/// can be reconstructed faithfully.
+pub fn hier_name_to_json(name: &HierName) -> Json {
+    let segments: Vec<_> = name
+        .segments
+        .iter()
+        .map(|seg| {
+            json!({
+                "span": span_to_json(seg.span),
+                "scoped": seg.scoped,
+                "name": seg.name.value.to_string(),
+                "indices": seg.indices.iter().map(|i| i.span.extract()).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({
+        "span": span_to_json(name.span),
+        "text": name.to_string(),
+        "segments": segments,
+    })
+}
+
+mod serialize {
+    use super::*;
+    use serde::{Serialize, Serializer};
+
+    macro_rules! impl_serialize {
+        ($ty:ident, $func:path) => {
+            impl Serialize for $ty<'_> {
+                fn serialize<S: Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> std::result::Result<S::Ok, S::Error> {
+                    Serialize::serialize(&$func(self), serializer)
+                }
+            }
+        };
+    }
+
+    impl_serialize!(Module, to_json);
+    impl_serialize!(Interface, interface_to_json);
+    impl_serialize!(ParamDecl, param_decl_to_json);
+    impl_serialize!(Port, port_to_json);
+    impl_serialize!(Type, type_to_json);
+    impl_serialize!(Item, item_to_json);
+    impl_serialize!(HierName, hier_name_to_json);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    #[test]
+    fn module_schema() {
+        let input = "module foo #(parameter int N = 2) (input logic [N-1:0] a, output b);\n  wire c;\nendmodule\n";
+        let source = get_source_manager().add("json_module.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        let span = |begin: usize, end: usize| json!({ "file": "json_module.sv", "begin": begin, "end": end });
+        let expected = json!({
+            "kind": "module",
+            "span": span(0, 78),
+            "name": "foo",
+            "lifetime": "static",
+            "imports": [],
+            "params": [{
+                "kind": "value",
+                "span": span(13, 32),
+                "local": false,
+                "params": [{
+                    "name": "N",
+                    "type": { "kind": "int", "sign": null, "dims": [] },
+                    "dims": [],
+                    "default": "2",
+                }],
+            }],
+            "ports": [{
+                "kind": "named",
+                "span": span(35, 56),
+                "name": "a",
+                "dir": "input",
+                "var_kind": null,
+                "type": { "kind": "logic", "sign": null, "dims": ["[N - 1:0]"] },
+                "dims": [],
+                "default": null,
+            }, {
+                "kind": "named",
+                "span": span(58, 66),
+                "name": "b",
+                "dir": "output",
+                "var_kind": null,
+                "type": { "kind": "implicit", "sign": null, "dims": [] },
+                "dims": [],
+                "default": null,
+            }],
+            "items": [{
+                "kind": "net declaration",
+                "span": span(71, 78),
+                "net_type": "wire",
+                "type": { "kind": "implicit", "sign": null, "dims": [] },
+                "names": [{ "name": "c", "dims": [], "init": null }],
+            }],
+        });
+        assert_eq!(to_json(module), expected);
+    }
+
+    #[test]
+    fn defparam_path() {
+        let input = "module top; defparam u0.u1[2].W = 8; endmodule";
+        let source = get_source_manager().add("json_defparam.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        let json = item_to_json(&module.items[0]);
+        let target = &json["assignments"][0]["target"];
+        assert_eq!(target["text"], "u0.u1[2].W");
+        assert_eq!(target["segments"][1]["name"], "u1");
+        assert_eq!(target["segments"][1]["scoped"], false);
+        assert_eq!(target["segments"][1]["indices"], json!(["2"]));
+        assert_eq!(json["assignments"][0]["value"], "8");
+    }
+}
@@ -0,0 +1,513 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Expansion of let declarations.
+//!
+//! A let declaration defines an expression shorthand, such as
+//! `let max(a, b) = (a > b) ? a : b;`. Calls to a let are expanded by
+//! substituting the actual arguments for the formal arguments in the body of
+//! the let. See §11.12 of IEEE 1800-2017.
+
+use crate::ast::*;
+use moore_common::{errors::DiagBuilder2, id::NodeId, name::Name, source::Spanned};
+use std::collections::HashMap;
+
+/// Expand all let calls in an expression.
+///
+/// The `lookup` function maps a name to the let declaration visible at the
+/// expression, if any. Let calls in the actual arguments and in the bodies of
+/// expanded lets are expanded as well. Returns a new expression tree with
+/// fresh node IDs, or a diagnostic if a call does not match its let or a let
+/// expands into itself.
+pub fn expand_lets<'a, 'b>(
+    expr: &Expr<'a>,
+    lookup: &'b dyn Fn(Name) -> Option<&'b LetDecl<'a>>,
+) -> Result<Expr<'a>, DiagBuilder2> {
+    let mut expander = Expander {
+        lookup,
+        stack: vec![],
+    };
+    let mut expr = expr.clone();
+    expander.expand(&mut expr)?;
+    Ok(expr)
+}
+
+struct Expander<'a, 'b> {
+    lookup: &'b dyn Fn(Name) -> Option<&'b LetDecl<'a>>,
+    /// The lets currently being expanded.
+    stack: Vec<&'b LetDecl<'a>>,
+}
+
+impl<'a, 'b> Expander<'a, 'b> {
+    fn expand(&mut self, expr: &mut Expr<'a>) -> Result<(), DiagBuilder2> {
+        // Calls to a let are replaced by the let's body. A let without
+        // arguments may also be referred to by its name alone.
+        let call = match expr.data {
+            CallExpr(ref callee, ref args) => match callee.data {
+                IdentExpr(name) => (self.lookup)(name.value).map(|decl| (decl, &args[..])),
+                _ => None,
+            },
+            IdentExpr(name) => (self.lookup)(name.value).map(|decl| (decl, &[][..])),
+            _ => None,
+        };
+        if let Some((decl, args)) = call {
+            let mut body = self.instantiate(expr, decl, args)?;
+            self.stack.push(decl);
+            self.expand(&mut body)?;
+            self.stack.pop();
+            body.span = expr.span;
+            *expr = body;
+            return Ok(());
+        }
+
+        expr.id = NodeId::alloc();
+        for_each_child_mut(expr, &mut |child| self.expand(child), &mut |_| Ok(()))
+    }
+
+    /// Substitute the actual arguments of a call for the formal arguments in
+    /// the body of a let.
+    fn instantiate(
+        &mut self,
+        call: &Expr<'a>,
+        decl: &'b LetDecl<'a>,
+        args: &[CallArg<'a>],
+    ) -> Result<Expr<'a>, DiagBuilder2> {
+        if self.stack.iter().any(|d| std::ptr::eq(*d, decl)) {
+            return Err(DiagBuilder2::error(format!(
+                "{} expands into itself",
+                decl.to_definite_string()
+            ))
+            .span(call.span)
+            .add_note("Let declared here:")
+            .span(decl.name.span));
+        }
+
+        // Assign the actual arguments to the formal arguments.
+        let mut actuals: Vec<Option<Expr<'a>>> = vec![None; decl.ports.len()];
+        for (index, arg) in args.iter().enumerate() {
+            let slot = match arg.name {
+                Some(name) => decl
+                    .ports
+                    .iter()
+                    .position(|port| port.name.value == name.value)
+                    .ok_or_else(|| {
+                        DiagBuilder2::error(format!(
+                            "{} has no argument `{}`",
+                            decl.to_definite_string(),
+                            name
+                        ))
+                        .span(name.span)
+                    })?,
+                None if index < decl.ports.len() => index,
+                None => {
+                    return Err(DiagBuilder2::error(format!(
+                        "too many arguments in call to {}",
+                        decl.to_definite_string()
+                    ))
+                    .span(arg.span)
+                    .add_note("Let declared here:")
+                    .span(decl.name.span));
+                }
+            };
+            if let Some(ref expr) = arg.expr {
+                let mut actual = expr.clone();
+                self.expand(&mut actual)?;
+                actuals[slot] = Some(actual);
+            }
+        }
+
+        // Fall back to the default values for missing arguments.
+        let mut formals = HashMap::new();
+        for (port, actual) in decl.ports.iter().zip(actuals) {
+            let actual = match (actual, &port.default) {
+                (Some(actual), _) => actual,
+                (None, Some(default)) => default.clone(),
+                (None, None) => {
+                    return Err(DiagBuilder2::error(format!(
+                        "missing argument `{}` in call to {}",
+                        port.name,
+                        decl.to_definite_string()
+                    ))
+                    .span(call.span)
+                    .add_note("Argument declared here:")
+                    .span(port.name.span));
+                }
+            };
+            formals.insert(port.name.value, actual);
+        }
+
+        let mut body = decl.expr.clone();
+        substitute(&mut body, decl, &formals)?;
+        Ok(body)
+    }
+}
+
+/// Replace references to formal arguments with their actual arguments.
+///
+/// Formal arguments within `$bits` and type parameters cannot be replaced,
+/// since these refer to shared nodes in the arena. They produce a diagnostic.
+fn substitute<'a>(
+    expr: &mut Expr<'a>,
+    decl: &LetDecl<'a>,
+    formals: &HashMap<Name, Expr<'a>>,
+) -> Result<(), DiagBuilder2> {
+    if let IdentExpr(name) = expr.data {
+        if let Some(actual) = formals.get(&name.value) {
+            *expr = actual.clone();
+            return Ok(());
+        }
+    }
+    for_each_child_mut(
+        expr,
+        &mut |child| substitute(child, decl, formals),
+        &mut |shared| {
+            let mut finder = FormalFinder {
+                formals,
+                found: None,
+            };
+            match shared {
+                TypeOrExpr::Type(ty) => ty.walk(&mut finder),
+                TypeOrExpr::Expr(expr) => expr.walk(&mut finder),
+            }
+            match finder.found {
+                Some(name) => Err(DiagBuilder2::error(format!(
+                    "argument `{}` of {} cannot be used here",
+                    name,
+                    decl.to_definite_string()
+                ))
+                .span(name.span)
+                .add_note(
+                    "Arguments of a let cannot be substituted into `$bits` or type parameters",
+                )),
+                None => Ok(()),
+            }
+        },
+    )
+}
+
+/// A visitor that finds the first reference to a formal argument.
+struct FormalFinder<'b, 'a> {
+    formals: &'b HashMap<Name, Expr<'a>>,
+    found: Option<Spanned<Name>>,
+}
+
+impl<'b, 'a> Visitor<'a> for FormalFinder<'b, 'a> {
+    fn pre_visit_expr(&mut self, node: &'a Expr<'a>) -> bool {
+        match node.data {
+            IdentExpr(name) if self.found.is_none() && self.formals.contains_key(&name.value) => {
+                self.found = Some(name);
+                false
+            }
+            _ => self.found.is_none(),
+        }
+    }
+}
+
+/// Call a function for each immediate subexpression of an expression,
+/// including the subexpressions within its types.
+///
+/// Subexpressions behind shared references, such as the argument of `$bits`
+/// and type parameters, cannot be modified and are passed to `shared` instead.
+fn for_each_child_mut<'a>(
+    expr: &mut Expr<'a>,
+    f: &mut dyn FnMut(&mut Expr<'a>) -> Result<(), DiagBuilder2>,
+    shared: &mut dyn FnMut(TypeOrExpr<'a>) -> Result<(), DiagBuilder2>,
+) -> Result<(), DiagBuilder2> {
+    match expr.data {
+        ScopeExpr(ref mut expr, _)
+        | UnaryExpr { ref mut expr, .. }
+        | MemberExpr { ref mut expr, .. }
+        | CastSignExpr(_, ref mut expr) => f(expr),
+        CastExpr(ref mut ty, ref mut expr) => {
+            for_each_type_child_mut(ty, f, shared)?;
+            f(expr)
+        }
+        TypeExpr(ref mut ty) => for_each_type_child_mut(ty, f, shared),
+        BitsExpr { arg, .. } => shared(arg),
+        IndexExpr {
+            ref mut indexee,
+            ref mut index,
+        } => {
+            f(indexee)?;
+            f(index)
+        }
+        BinaryExpr {
+            ref mut lhs,
+            ref mut rhs,
+            ..
+        }
+        | AssignExpr {
+            ref mut lhs,
+            ref mut rhs,
+            ..
+        }
+        | RangeExpr {
+            ref mut lhs,
+            ref mut rhs,
+            ..
+        }
+        | CastSizeExpr(ref mut lhs, ref mut rhs) => {
+            f(lhs)?;
+            f(rhs)
+        }
+        TernaryExpr {
+            cond: ref mut a,
+            true_expr: ref mut b,
+            false_expr: ref mut c,
+        }
+        | MinTypMaxExpr {
+            min: ref mut a,
+            typ: ref mut b,
+            max: ref mut c,
+        } => {
+            f(a)?;
+            f(b)?;
+            f(c)
+        }
+        CallExpr(ref mut callee, ref mut args) => {
+            f(callee)?;
+            for arg in args.iter_mut().flat_map(|arg| arg.data.expr.as_mut()) {
+                f(arg)?;
+            }
+            Ok(())
+        }
+        ConstructorCallExpr(ref mut args) => {
+            for arg in args.iter_mut().flat_map(|arg| arg.data.expr.as_mut()) {
+                f(arg)?;
+            }
+            Ok(())
+        }
+        ClassNewExpr(Some(ref mut expr)) => f(expr),
+        ArrayNewExpr(ref mut size, ref mut init) => {
+            f(size)?;
+            if let Some(init) = init {
+                f(init)?;
+            }
+            Ok(())
+        }
+        ConcatExpr {
+            ref mut repeat,
+            ref mut exprs,
+        } => {
+            if let Some(repeat) = repeat {
+                f(repeat)?;
+            }
+            for expr in exprs {
+                f(expr)?;
+            }
+            Ok(())
+        }
+        InsideExpr(ref mut expr, ref mut ranges) => {
+            f(expr)?;
+            for range in ranges {
+                match range {
+                    ValueRange::Single(expr) => f(expr)?,
+                    ValueRange::Range { lo, hi, .. } => {
+                        f(lo)?;
+                        f(hi)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        PatternExpr(ref mut fields) => {
+            for field in fields {
+                match field.data {
+                    // The keys of member fields name struct members, not
+                    // expressions.
+                    PatternFieldData::Default(ref mut expr)
+                    | PatternFieldData::Member(_, ref mut expr)
+                    | PatternFieldData::Expr(ref mut expr) => f(expr)?,
+                    PatternFieldData::Type(ref mut ty, ref mut expr) => {
+                        for_each_type_child_mut(ty, f, shared)?;
+                        f(expr)?;
+                    }
+                    PatternFieldData::Repeat(ref mut count, ref mut exprs) => {
+                        f(count)?;
+                        for expr in exprs {
+                            f(expr)?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        StreamConcatExpr {
+            ref mut slice,
+            ref mut exprs,
+            ..
+        } => {
+            match slice {
+                Some(StreamConcatSlice::Expr(expr)) => f(expr)?,
+                Some(StreamConcatSlice::Type(ty)) => for_each_type_child_mut(ty, f, shared)?,
+                None => (),
+            }
+            for expr in exprs {
+                f(&mut expr.expr)?;
+                if let Some(ref mut range) = expr.range {
+                    f(range)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Call a function for each expression in the dimensions of a type, and in the
+/// types it is built from.
+fn for_each_type_child_mut<'a>(
+    ty: &mut Type<'a>,
+    f: &mut dyn FnMut(&mut Expr<'a>) -> Result<(), DiagBuilder2>,
+    shared: &mut dyn FnMut(TypeOrExpr<'a>) -> Result<(), DiagBuilder2>,
+) -> Result<(), DiagBuilder2> {
+    match ty.kind.data {
+        ScopedType { ref mut ty, .. } => for_each_type_child_mut(ty, f, shared)?,
+        SpecializedType(ref mut ty, ref params) => {
+            for_each_type_child_mut(ty, f, shared)?;
+            for param in params {
+                shared(param.expr)?;
+            }
+        }
+        _ => (),
+    }
+    for dim in &mut ty.dims {
+        match dim {
+            TypeDim::Expr(expr) | TypeDim::Queue(Some(expr)) => f(expr)?,
+            TypeDim::Range(lhs, rhs) => {
+                f(lhs)?;
+                f(rhs)?;
+            }
+            TypeDim::Associative(Some(ty)) => for_each_type_child_mut(ty, f, shared)?,
+            TypeDim::Queue(None) | TypeDim::Unsized | TypeDim::Associative(None) => (),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    /// Parse a module, and expand the right-hand side of its first continuous
+    /// assignment using the lets declared in the module.
+    fn expand(name: &str, input: &str, expected: Option<&str>) -> Result<bool, String> {
+        let arena = Arena::default();
+        let parse = |name: &str, input: &str| {
+            let source = get_source_manager().add(name, input);
+            let preproc = Preprocessor::new(source, &[], &[]);
+            parser::parse(Lexer::new(preproc), &arena).unwrap()
+        };
+        let file = parse(name, input);
+        let items = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => &m.items,
+            _ => panic!("expected module"),
+        };
+        let mut lets = HashMap::new();
+        let mut assign = None;
+        for item in items {
+            match item.data {
+                ItemData::LetDecl(ref decl) => {
+                    lets.insert(decl.name.value, decl);
+                }
+                ItemData::ContAssign(ref a) if assign.is_none() => {
+                    assign = Some(&a.assignments[0].1);
+                }
+                _ => (),
+            }
+        }
+        let lookup = |name| lets.get(&name).cloned();
+        let expanded =
+            expand_lets(assign.unwrap(), &lookup).map_err(|d| d.get_message().to_string())?;
+
+        let expected = match expected {
+            Some(x) => x,
+            None => return Ok(true),
+        };
+        let expected_file = parse(
+            &format!("{}.expected", name),
+            &format!("module m; assign x = {}; endmodule", expected),
+        );
+        let expected = match expected_file.items[0].data {
+            ItemData::ModuleDecl(ref m) => match m.items[0].data {
+                ItemData::ContAssign(ref a) => &a.assignments[0].1,
+                _ => panic!("expected continuous assignment"),
+            },
+            _ => panic!("expected module"),
+        };
+        Ok(expanded.spanless_eq(expected))
+    }
+
+    #[test]
+    fn nested_let() {
+        let input = "module m;
+            let max(a, b) = (a > b) ? a : b;
+            let max3(int a, b, c = 0) = max(max(a, b), c);
+            assign x = max3(p, .b(q + 1));
+        endmodule";
+        assert_eq!(
+            expand(
+                "let_nested.sv",
+                input,
+                Some("(((p > q + 1) ? p : q + 1) > 0) ? ((p > q + 1) ? p : q + 1) : 0")
+            ),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn patterns_types_and_streams() {
+        let input = "module m;
+            let pair(a) = '{a, default: a};
+            let trunc(a, w) = logic [w-1:0]'(a);
+            let rev(a, n) = {<<n{a}};
+            assign x = pair(p) + trunc(q, 4) + rev(r, 8);
+        endmodule";
+        assert_eq!(
+            expand(
+                "let_positions.sv",
+                input,
+                Some("'{p, default: p} + logic [4-1:0]'(q) + {<<8{r}}")
+            ),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn unsupported_position() {
+        let input = "module m;
+            let width(a) = $bits(a);
+            assign y = width(z);
+        endmodule";
+        assert_eq!(
+            expand("let_bits.sv", input, None),
+            Err("argument `a` of let `width` cannot be used here".to_string())
+        );
+    }
+
+    #[test]
+    fn recursive_let() {
+        let input = "module m;
+            let a(x) = b(x);
+            let b(x) = a(x) + 1;
+            assign y = a(1);
+        endmodule";
+        assert_eq!(
+            expand("let_recursive.sv", input, None),
+            Err("let `a` expands into itself".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_argument() {
+        let input = "module m;
+            let f(a, b) = a + b;
+            assign y = f(1);
+        endmodule";
+        assert_eq!(
+            expand("let_missing.sv", input, None),
+            Err("missing argument `b` in call to let `f`".to_string())
+        );
+    }
+}
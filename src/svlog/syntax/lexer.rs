@@ -3,6 +3,7 @@
 //! A lexical analyzer for SystemVerilog files, based on IEEE 1800-2009, section
 //! 5.
 
+use crate::ast::NetType;
 use crate::cat::CatTokenKind;
 use crate::preproc::*;
 pub use crate::token::*;
@@ -17,6 +18,12 @@ pub type TokenAndSpan = (Token, Span);
 pub struct Lexer<'a> {
     input: Preprocessor<'a>,
     peek: [CatTokenAndSpan; 4],
+    /// Whether we are inside an attribute instance `(* ... *)`.
+    in_attr: bool,
+    /// The number of tokens taken from the preprocessor so far.
+    pulled: usize,
+    /// The index of the preprocessor token at which the last token started.
+    token_start: usize,
 }
 
 impl<'a> Lexer<'a> {
@@ -24,16 +31,28 @@ impl<'a> Lexer<'a> {
         Lexer {
             input: input,
             peek: [(CatTokenKind::Eof, INVALID_SPAN); 4],
+            in_attr: false,
+            pulled: 0,
+            token_start: 0,
         }
     }
 
+    /// Get the `default_nettype` in effect at the last token returned by
+    /// `next_token`. `None` indicates that the nettype is `none`.
+    pub fn default_nettype(&self) -> Option<NetType> {
+        self.input.default_nettype_at(self.token_start)
+    }
+
     pub fn bump(&mut self) -> DiagResult2<()> {
         self.peek[0] = self.peek[1];
         self.peek[1] = self.peek[2];
         self.peek[2] = self.peek[3];
         self.peek[3] = match self.input.next() {
             Some(Err(e)) => return Err(e),
-            Some(Ok(x)) => x,
+            Some(Ok(x)) => {
+                self.pulled += 1;
+                x
+            }
             None => (CatTokenKind::Eof, self.peek[2].1),
         };
 
@@ -54,6 +73,34 @@ impl<'a> Lexer<'a> {
 
         loop {
             self.skip_noise()?;
+            let buffered = self
+                .peek
+                .iter()
+                .filter(|t| t.0 != CatTokenKind::Eof)
+                .count();
+            self.token_start = self.pulled - buffered;
+
+            // Match the delimiters of attribute instances. Take care not to
+            // confuse the `(*)` in `@(*)` with the start of an attribute.
+            match (self.peek[0].0, self.peek[1].0, self.peek[2].0) {
+                (CatTokenKind::Symbol('('), CatTokenKind::Symbol('*'), c2)
+                    if !self.in_attr && c2 != CatTokenKind::Symbol(')') =>
+                {
+                    let sp = Span::union(self.peek[0].1, self.peek[1].1);
+                    self.bump()?;
+                    self.bump()?;
+                    self.in_attr = true;
+                    return Ok((AttrOpen, sp));
+                }
+                (CatTokenKind::Symbol('*'), CatTokenKind::Symbol(')'), _) if self.in_attr => {
+                    let sp = Span::union(self.peek[0].1, self.peek[1].1);
+                    self.bump()?;
+                    self.bump()?;
+                    self.in_attr = false;
+                    return Ok((AttrClose, sp));
+                }
+                _ => (),
+            }
 
             // Match 4-character symbols
             if let (
@@ -314,6 +361,15 @@ impl<'a> Lexer<'a> {
                     } else {
                         None
                     };
+                    if frac.is_none()
+                        && &*value.as_str() == "1"
+                        && self.peek[0].0 == CatTokenKind::Text
+                        && self.peek[0].1.extract() == "step"
+                    {
+                        sp.expand(self.peek[0].1);
+                        self.bump()?; // eat the "step"
+                        return Ok((OneStep, sp));
+                    }
                     if let Some(unit) = self.try_time_unit() {
                         sp.expand(self.peek[0].1);
                         self.bump()?; // eat the unit
@@ -379,11 +435,10 @@ impl<'a> Lexer<'a> {
                                     }
                                 }
                             }
-                            (CatTokenKind::Newline, sp) => {
-                                return Err(DiagBuilder2::fatal(
-                                    "String literals cannot contain unescaped newlines",
-                                )
-                                .span(sp))
+                            (CatTokenKind::Newline, _) | (CatTokenKind::Eof, _) => {
+                                return Err(DiagBuilder2::error("unterminated string literal")
+                                    .span(span)
+                                    .add_note("String literals cannot contain unescaped newlines"))
                             }
                             (_, sp) => {
                                 span.expand(sp);
@@ -395,6 +450,11 @@ impl<'a> Lexer<'a> {
                     return Ok((Literal(Str(name_table.intern(&s, true))), span));
                 }
 
+                (CatTokenKind::Invalid, sp) => {
+                    self.bump()?;
+                    return Err(DiagBuilder2::error("invalid UTF-8 byte sequence").span(sp));
+                }
+
                 (CatTokenKind::Eof, sp) => return Ok((Eof, sp)),
                 (tkn, sp) => {
                     return Err(DiagBuilder2::fatal(format!("Unknown token {:?}", tkn)).span(sp))
@@ -441,30 +501,21 @@ impl<'a> Lexer<'a> {
                         }
                     }
                 }
-                // SystemVerilog Attributes
-                (CatTokenKind::Symbol('('), CatTokenKind::Symbol('*'))
-                    if self.peek[2].0 != CatTokenKind::Symbol(')') =>
-                {
-                    self.bump()?;
-                    self.bump()?;
-                    loop {
-                        match (self.peek[0].0, self.peek[1].0) {
-                            (CatTokenKind::Eof, _) => break,
-                            (CatTokenKind::Symbol('*'), CatTokenKind::Symbol(')')) => {
-                                self.bump()?;
-                                self.bump()?;
-                                break;
-                            }
-                            _ => self.bump()?,
-                        }
-                    }
-                }
                 _ => (),
             }
-            match self.peek[0].0 {
-                CatTokenKind::Whitespace | CatTokenKind::Newline | CatTokenKind::Comment => {
-                    self.bump()?
+            match self.peek[0] {
+                (CatTokenKind::Whitespace, sp) => {
+                    self.bump()?;
+                    if let Some((c, csp)) = first_non_ascii(sp) {
+                        return Err(DiagBuilder2::warning(format!(
+                            "non-ASCII whitespace character U+{:04X} is treated as a space",
+                            c as u32
+                        ))
+                        .code(DiagCode::NON_ASCII_WHITESPACE)
+                        .span(csp));
+                    }
                 }
+                (CatTokenKind::Newline, _) | (CatTokenKind::Comment, _) => self.bump()?,
                 _ => return Ok(()),
             }
         }
@@ -494,6 +545,20 @@ impl<'a> Lexer<'a> {
         if s.is_empty() {
             return Err(DiagBuilder2::fatal("Could not match an identifier here").span(sp));
         }
+        if let Some((c, csp)) = first_non_ascii(sp) {
+            let mut diag = DiagBuilder2::error(format!(
+                "non-ASCII character `{}` is not valid in SystemVerilog source",
+                c
+            ))
+            .span(csp)
+            .add_note("Identifiers with other characters must be escaped, as in `\\name `");
+            diag = match c {
+                '\u{201C}' | '\u{201D}' | '\u{201E}' => diag.add_note("Did you mean `\"`?"),
+                '\u{2018}' | '\u{2019}' => diag.add_note("Did you mean `'`?"),
+                _ => diag,
+            };
+            return Err(diag);
+        }
         assert!(!s.is_empty());
         Ok((s, sp))
     }
@@ -649,6 +714,19 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Find the first non-ASCII character in a span, alongside a span covering
+/// just that character.
+fn first_non_ascii(sp: Span) -> Option<(char, Span)> {
+    sp.with_text(|text| {
+        text.char_indices()
+            .find(|(_, c)| !c.is_ascii())
+            .map(|(i, c)| {
+                let begin = sp.begin + i;
+                (c, Span::new(sp.source, begin, begin + c.len_utf8()))
+            })
+    })
+}
+
 impl<'a> Iterator for Lexer<'a> {
     type Item = DiagResult2<TokenAndSpan>;
 
@@ -846,6 +924,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn one_step() {
+        check(
+            "#1step #1 step",
+            &[
+                Hashtag,
+                OneStep,
+                Hashtag,
+                Literal(Number(name("1"), None)),
+                Ident(name("step")),
+            ],
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        check(
+            "(* keep *) @(*) a*b",
+            &[
+                AttrOpen,
+                Ident(name("keep")),
+                AttrClose,
+                At,
+                OpenDelim(Paren),
+                Operator(Op::Mul),
+                CloseDelim(Paren),
+                Ident(name("a")),
+                Operator(Op::Mul),
+                Ident(name("b")),
+            ],
+        );
+    }
+
     #[test]
     fn number_literal() {
         check(
@@ -856,4 +967,92 @@ mod tests {
             ],
         );
     }
+
+    /// Lex the input and collect the tokens and the messages of the
+    /// diagnostics, alongside the text of their spans.
+    fn lex_diags(source: Source) -> (Vec<Token>, Vec<(String, String)>) {
+        let mut tokens = vec![];
+        let mut diags = vec![];
+        for result in Lexer::new(Preprocessor::new(source, &[], &[])) {
+            match result {
+                Ok((tkn, _)) => tokens.push(tkn),
+                Err(diag) => {
+                    let span = diag.get_segments().iter().find_map(|s| match *s {
+                        DiagSegment::Span(sp) => Some(sp.extract()),
+                        _ => None,
+                    });
+                    diags.push((diag.get_message().to_string(), span.unwrap()));
+                }
+            }
+        }
+        (tokens, diags)
+    }
+
+    #[test]
+    fn unicode_whitespace() {
+        let source = get_source_manager().add("lex_nbsp.sv", "wire\u{a0}x;");
+        let (tokens, diags) = lex_diags(source);
+        assert_eq!(tokens, vec![Keyword(Kw::Wire), Ident(name("x")), Semicolon]);
+        assert_eq!(
+            diags,
+            vec![(
+                "non-ASCII whitespace character U+00A0 is treated as a space".to_string(),
+                "\u{a0}".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn smart_quotes() {
+        let source = get_source_manager().add("lex_smart_quotes.sv", "\u{201c}hello\u{201d};");
+        let (tokens, diags) = lex_diags(source);
+        assert_eq!(tokens, vec![Semicolon]);
+        assert_eq!(
+            diags,
+            vec![(
+                "non-ASCII character `\u{201c}` is not valid in SystemVerilog source".to_string(),
+                "\u{201c}".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let path =
+            std::env::temp_dir().join(format!("moore_lex_invalid_{}.sv", std::process::id()));
+        std::fs::write(&path, b"wire \xff x\xff\xfe;").unwrap();
+        let source = get_source_manager().open(path.to_str().unwrap()).unwrap();
+        let (tokens, diags) = lex_diags(source);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(tokens, vec![Keyword(Kw::Wire), Ident(name("x")), Semicolon]);
+        assert_eq!(
+            diags
+                .iter()
+                .map(|(msg, _)| msg.as_str())
+                .collect::<Vec<_>>(),
+            vec!["invalid UTF-8 byte sequence"; 2]
+        );
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let source = get_source_manager().add("lex_unterminated.sv", "x = \"abc;\ny;");
+        let (tokens, diags) = lex_diags(source);
+        assert_eq!(
+            tokens,
+            vec![
+                Ident(name("x")),
+                Operator(Op::Assign),
+                Ident(name("y")),
+                Semicolon
+            ]
+        );
+        assert_eq!(
+            diags,
+            vec![(
+                "unterminated string literal".to_string(),
+                "\"abc;".to_string()
+            )]
+        );
+    }
 }
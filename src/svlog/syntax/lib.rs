@@ -7,8 +7,19 @@
 extern crate log;
 
 pub mod ast;
+pub mod builder;
 pub mod cat;
+pub mod extern_check;
+pub mod hier;
+pub mod inst_check;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod let_expand;
 pub mod lexer;
 pub mod parser;
 pub mod preproc;
+pub mod port_check;
+pub mod pretty;
+pub mod span_map;
 pub mod token;
+pub mod trivia;
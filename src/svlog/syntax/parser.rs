@@ -12,7 +12,7 @@ use crate::lexer::{Lexer, TokenAndSpan};
 use crate::token::*;
 use moore_common::{arenas::Alloc, errors::*, name::*, source::*, util::HasSpan};
 use std;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 // The problem with data_declaration and data_type_or_implicit:
 //
@@ -41,6 +41,11 @@ trait AbstractParser<'n> {
     fn last_span(&self) -> Span;
     fn add_diag(&mut self, diag: DiagBuilder2);
     fn severity(&self) -> Severity;
+    /// Attribute instances `(* ... *)` that precede the token at `offset`.
+    fn attrs_at(&mut self, offset: usize) -> Vec<ast::AttrSpec<'n>>;
+    /// The `default_nettype` in effect at the token at `offset`, or `None` if
+    /// it is `none`.
+    fn default_nettype_at(&mut self, offset: usize) -> Option<NetType>;
 
     fn try_eat_ident(&mut self) -> Option<(Name, Span)> {
         match self.peek(0) {
@@ -237,6 +242,12 @@ struct Parser<'a, 'n> {
     severity: Severity,
     consumed: usize,
     arena: &'n ast::Arena<'n>,
+    /// Where diagnostics are emitted. They are printed directly if not set.
+    emitter: Option<&'a dyn DiagEmitter>,
+    /// Attribute instances, keyed by the index of the token they precede.
+    attrs: HashMap<usize, Vec<ast::AttrSpec<'n>>>,
+    /// The `default_nettype` in effect from a given token index onwards.
+    nettype_changes: Vec<(usize, Option<NetType>)>,
 }
 
 impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
@@ -279,8 +290,13 @@ impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
     }
 
     fn add_diag(&mut self, diag: DiagBuilder2) {
-        eprintln!("");
-        eprintln!("{}", diag);
+        match self.emitter {
+            Some(emitter) => emitter.emit(diag.clone()),
+            None => {
+                eprintln!("");
+                eprintln!("{}", diag);
+            }
+        }
 
         // Emit a backtrace for this diagnostic.
         if diag.get_severity() >= Severity::Warning {
@@ -301,6 +317,25 @@ impl<'a, 'n> AbstractParser<'n> for Parser<'a, 'n> {
     fn severity(&self) -> Severity {
         self.severity
     }
+
+    fn attrs_at(&mut self, offset: usize) -> Vec<ast::AttrSpec<'n>> {
+        self.ensure_queue_filled(offset);
+        self.attrs
+            .get(&(self.consumed + offset))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn default_nettype_at(&mut self, offset: usize) -> Option<NetType> {
+        self.ensure_queue_filled(offset);
+        let index = self.consumed + offset;
+        self.nettype_changes
+            .iter()
+            .rev()
+            .find(|&&(from, _)| from <= index)
+            .map(|&(_, nettype)| nettype)
+            .unwrap_or(Some(NetType::Wire))
+    }
 }
 
 impl<'a, 'n> Parser<'a, 'n> {
@@ -313,6 +348,9 @@ impl<'a, 'n> Parser<'a, 'n> {
             severity: Severity::Note,
             consumed: 0,
             arena,
+            emitter: None,
+            attrs: HashMap::new(),
+            nettype_changes: Vec::new(),
         }
     }
 
@@ -323,13 +361,162 @@ impl<'a, 'n> Parser<'a, 'n> {
         while self.queue.len() <= min_tokens {
             match self.input.next_token() {
                 Ok((Eof, sp)) => self.queue.push_back((Eof, sp)),
-                Ok(tkn) => self.queue.push_back(tkn),
+                Ok((AttrOpen, sp)) => self.parse_attr_instance(sp),
+                Ok(tkn) => {
+                    self.track_default_nettype();
+                    self.queue.push_back(tkn);
+                }
+                Err(x) => self.add_diag(x),
+            }
+        }
+    }
+
+    /// Record the `default_nettype` in effect at the token about to be added
+    /// to the queue, if it differs from the one at the previous token.
+    fn track_default_nettype(&mut self) {
+        let nettype = self.input.default_nettype();
+        let current = self
+            .nettype_changes
+            .last()
+            .map(|&(_, nettype)| nettype)
+            .unwrap_or(Some(NetType::Wire));
+        if nettype != current {
+            let index = self.consumed + self.queue.len();
+            self.nettype_changes.push((index, nettype));
+        }
+    }
+
+    /// Parse an attribute instance `(* ... *)`, assuming the opening `(*` has
+    /// already been consumed, and associate it with the token that follows.
+    fn parse_attr_instance(&mut self, open: Span) {
+        let mut tokens = vec![];
+        let close = loop {
+            match self.input.next_token() {
+                Ok((AttrClose, sp)) => break sp,
+                Ok((Eof, sp)) => {
+                    self.add_diag(
+                        DiagBuilder2::error("attribute instance is missing a closing `*)`")
+                            .span(open),
+                    );
+                    self.queue.push_back((Eof, sp));
+                    return;
+                }
+                Ok(tkn) => tokens.push(tkn),
                 Err(x) => self.add_diag(x),
             }
+        };
+        tokens.push((Eof, close));
+
+        let mut tp = TokenParser::new(tokens, open, self.arena);
+        let result = parse_attr_specs(&mut tp);
+        for diag in tp.diagnostics {
+            self.add_diag(diag);
+        }
+        if let Ok(specs) = result {
+            let index = self.consumed + self.queue.len();
+            self.attrs.entry(index).or_default().extend(specs);
+        }
+    }
+}
+
+/// A parser over a fixed sequence of tokens terminated by `Eof`. Used to parse
+/// the contents of attribute instances, which the lexer hands out separately.
+struct TokenParser<'n> {
+    tokens: Vec<TokenAndSpan>,
+    consumed: usize,
+    diagnostics: Vec<DiagBuilder2>,
+    last_span: Span,
+    severity: Severity,
+    arena: &'n ast::Arena<'n>,
+}
+
+impl<'n> TokenParser<'n> {
+    fn new(tokens: Vec<TokenAndSpan>, last_span: Span, arena: &'n ast::Arena<'n>) -> Self {
+        TokenParser {
+            tokens,
+            consumed: 0,
+            diagnostics: Vec::new(),
+            last_span,
+            severity: Severity::Note,
+            arena,
+        }
+    }
+}
+
+impl<'n> AbstractParser<'n> for TokenParser<'n> {
+    fn arena(&self) -> &'n ast::Arena<'n> {
+        self.arena
+    }
+
+    fn peek(&mut self, offset: usize) -> TokenAndSpan {
+        let index = std::cmp::min(self.consumed + offset, self.tokens.len() - 1);
+        self.tokens[index]
+    }
+
+    fn bump(&mut self) {
+        if self.consumed + 1 < self.tokens.len() {
+            self.last_span = self.tokens[self.consumed].1;
+            self.consumed += 1;
+        }
+    }
+
+    fn skip(&mut self) {
+        self.bump()
+    }
+
+    fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    fn last_span(&self) -> Span {
+        self.last_span
+    }
+
+    fn add_diag(&mut self, diag: DiagBuilder2) {
+        if diag.get_severity() > self.severity {
+            self.severity = diag.get_severity();
         }
+        self.diagnostics.push(diag);
+    }
+
+    fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    fn attrs_at(&mut self, _offset: usize) -> Vec<ast::AttrSpec<'n>> {
+        Vec::new()
+    }
+
+    fn default_nettype_at(&mut self, _offset: usize) -> Option<NetType> {
+        Some(NetType::Wire)
     }
 }
 
+/// Parse the attribute specifications within an attribute instance.
+/// ```text
+/// attr_spec {"," attr_spec}
+/// attr_spec: ident ["=" expr]
+/// ```
+fn parse_attr_specs<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<ast::AttrSpec<'n>>> {
+    let specs = comma_list_nonempty(p, Eof, "attribute", |p| {
+        let mut span = p.peek(0).1;
+        let (name, name_span) = p.eat_ident("attribute name")?;
+        let value = if p.try_eat(Operator(Op::Assign)) {
+            Some(parse_expr(p)?)
+        } else {
+            None
+        };
+        span.expand(p.last_span());
+        Ok(ast::AttrSpec {
+            name: Spanned::new(name, name_span),
+            value,
+            span,
+        })
+    })?;
+    p.require_reported(Eof)?;
+    Ok(specs)
+}
+
 /// Parses the opening delimiter, calls the `inner` function, and parses the
 /// closing delimiter. Properly recovers to and including the closing
 /// delimiter if the `inner` function throws an error.
@@ -407,7 +594,11 @@ where
         } else if p.try_eat(Comma) {
             if term.matches(p) {
                 let q = p.last_span();
-                p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(q));
+                p.add_diag(
+                    DiagBuilder2::warning("superfluous trailing comma")
+                        .code(DiagCode::TRAILING_COMMA)
+                        .span(q),
+                );
                 break;
             }
         } else {
@@ -632,6 +823,51 @@ pub fn parse<'n>(input: Lexer, arena: &'n ast::Arena<'n>) -> Result<ast::SourceF
     }
 }
 
+/// Parse a source file and emit diagnostics through an emitter.
+///
+/// Unlike `parse`, which prints diagnostics directly, this passes them to
+/// `emitter`, such that options like the session's error limit apply to them
+/// as well.
+pub fn parse_with_emitter<'a, 'n>(
+    input: Lexer<'a>,
+    arena: &'n ast::Arena<'n>,
+    emitter: &'a dyn DiagEmitter,
+) -> Result<ast::SourceFile<'n>, ()> {
+    let mut p = Parser::new(input, arena);
+    p.emitter = Some(emitter);
+    let root = parse_source_text(&mut p);
+    if p.is_error() {
+        Err(())
+    } else {
+        Ok(root)
+    }
+}
+
+/// Parse a standalone expression.
+///
+/// This is useful for tools which evaluate snippets of source text outside of
+/// a source file, such as expressions given on the command line.
+pub fn parse_standalone_expr<'n>(
+    input: Lexer,
+    arena: &'n ast::Arena<'n>,
+) -> Result<ast::Expr<'n>, ()> {
+    let mut p = Parser::new(input, arena);
+    let expr = parse_expr(&mut p);
+    if expr.is_ok() {
+        let (tkn, sp) = p.peek(0);
+        if tkn != Eof {
+            p.add_diag(
+                DiagBuilder2::error(format!("expected end of expression, instead got `{}`", tkn))
+                    .span(sp),
+            );
+        }
+    }
+    match expr {
+        Ok(x) if !p.is_error() => Ok(x),
+        _ => Err(()),
+    }
+}
+
 fn parse_source_text<'n>(p: &mut dyn AbstractParser<'n>) -> ast::SourceFile<'n> {
     let mut span = p.peek(0).1;
     let mut root = ast::SourceFileData {
@@ -712,6 +948,7 @@ fn as_lifetime(tkn: Token) -> Option<Lifetime> {
 
 fn parse_interface_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Interface<'n>> {
     let mut span = p.peek(0).1;
+    let default_nettype = p.default_nettype_at(0);
     p.require_reported(Keyword(Kw::Interface))?;
     let result = recovered(p, Keyword(Kw::Endinterface), |p| {
         // Eat the optional lifetime.
@@ -762,6 +999,7 @@ fn parse_interface_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<In
             }
             items.push(parse_item(p)?);
         }
+        check_default_clocking(p, &items);
 
         span.expand(p.last_span());
         Ok(Interface::new(
@@ -772,6 +1010,7 @@ fn parse_interface_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<In
                 params: param_ports,
                 ports: ports,
                 items: items,
+                default_nettype,
             },
         ))
     });
@@ -792,6 +1031,7 @@ fn parse_parameter_port_list<'n>(
             // Parse the optional `parameter` or `localparam` keyword. If none is
             // provided, the previous scope is assumed.
             let mut outer_span = p.peek(0).1;
+            let attrs = p.attrs_at(0);
             match p.peek(0).0 {
                 Keyword(Kw::Parameter) => {
                     p.bump();
@@ -862,10 +1102,9 @@ fn parse_parameter_port_list<'n>(
             };
 
             outer_span.expand(p.last_span());
-            Ok(ast::ParamDecl::new(
-                outer_span,
-                ast::ParamDeclData { local, kind },
-            ))
+            let mut decl = ast::ParamDecl::new(outer_span, ast::ParamDeclData { local, kind });
+            decl.attrs = attrs;
+            Ok(decl)
         })
     })
 }
@@ -874,6 +1113,8 @@ fn parse_parameter_port_list<'n>(
 /// already been consumed.
 fn parse_module_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Module<'n>> {
     let mut span = p.peek(0).1;
+    let attrs = p.attrs_at(0);
+    let default_nettype = p.default_nettype_at(0);
     p.require_reported(Keyword(Kw::Module))?;
     let result = recovered(p, Keyword(Kw::Endmodule), |p| {
         // Eat the optional lifetime.
@@ -926,9 +1167,11 @@ fn parse_module_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Modul
             }
             items.push(parse_item(p)?);
         }
+        check_nested_module_names(p, Spanned::new(name, name_sp), &items);
+        check_default_clocking(p, &items);
 
         span.expand(p.last_span());
-        Ok(Module::new(
+        let mut module = Module::new(
             span,
             ModuleData {
                 lifetime,
@@ -937,8 +1180,11 @@ fn parse_module_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Modul
                 params,
                 ports,
                 items,
+                default_nettype,
             },
-        ))
+        );
+        module.attrs = attrs.clone();
+        Ok(module)
     });
     let sp = p.peek(0).1;
     p.require_reported(Keyword(Kw::Endmodule))?;
@@ -948,6 +1194,115 @@ fn parse_module_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Modul
     result
 }
 
+/// Report nested modules that have the same name as their parent.
+///
+/// A nested module is declared in the scope of its parent, where its name would
+/// shadow the parent module. See §23.4 of IEEE 1800-2017.
+fn check_nested_module_names<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    parent: Spanned<Name>,
+    items: &[Item<'n>],
+) {
+    for item in items {
+        if let ItemData::ModuleDecl(ref m) = item.data {
+            if m.name.value == parent.value {
+                p.add_diag(
+                    DiagBuilder2::error(format!(
+                        "nested module `{}` has the same name as its parent",
+                        parent.value
+                    ))
+                    .span(m.name.span)
+                    .add_note("Parent module declared here:")
+                    .span(parent.span),
+                );
+            }
+        }
+    }
+}
+
+/// Report scopes that declare more than one default clocking block.
+///
+/// See §14.12 of IEEE 1800-2017.
+fn check_default_clocking<'n>(p: &mut dyn AbstractParser<'n>, items: &[Item<'n>]) {
+    let mut first = None;
+    for item in items {
+        match item.data {
+            ItemData::ClockingDecl(ref c) if c.default => (),
+            ItemData::DefaultClocking(..) => (),
+            _ => continue,
+        }
+        match first {
+            None => first = Some(item.span),
+            Some(prev) => p.add_diag(
+                DiagBuilder2::error("multiple default clocking blocks in the same scope")
+                    .span(item.span)
+                    .add_note("Previous default clocking declared here:")
+                    .span(prev),
+            ),
+        }
+    }
+}
+
+/// Parse an extern module declaration.
+///
+/// ```text
+/// extern_module_decl:
+///   "extern" "module" [lifetime] ident {import_decl} ["#" param_port_list]
+///   ["(" port_list ")"] ";"
+/// ```
+fn parse_extern_module_decl<'n>(
+    p: &mut dyn AbstractParser<'n>,
+) -> ReportedResult<ExternModule<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Extern))?;
+    p.require_reported(Keyword(Kw::Module))?;
+
+    // Eat the optional lifetime.
+    let lifetime = match as_lifetime(p.peek(0).0) {
+        Some(l) => {
+            p.bump();
+            l
+        }
+        None => Lifetime::Static,
+    };
+
+    // Eat the module name.
+    let (name, name_sp) = p.eat_ident("module name")?;
+
+    // Eat the optional package import declarations.
+    let mut imports = vec![];
+    while p.peek(0).0 == Keyword(Kw::Import) {
+        imports.push(parse_import_decl(p)?);
+    }
+
+    // Eat the optional parameter port list.
+    let params = if p.try_eat(Hashtag) {
+        parse_parameter_port_list(p)?
+    } else {
+        Vec::new()
+    };
+
+    // Eat the optional list of ports.
+    let ports = if p.try_eat(OpenDelim(Paren)) {
+        parse_port_list(p)?
+    } else {
+        Vec::new()
+    };
+    p.require_reported(Semicolon)?;
+
+    span.expand(p.last_span());
+    Ok(ExternModule::new(
+        span,
+        ExternModuleData {
+            lifetime,
+            name: Spanned::new(name, name_sp),
+            imports,
+            params,
+            ports,
+        },
+    ))
+}
+
 fn parse_package_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Package<'n>> {
     let mut span = p.peek(0).1;
     p.require_reported(Keyword(Kw::Package))?;
@@ -991,33 +1346,184 @@ fn parse_package_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Pack
     result
 }
 
-fn parse_program_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<()> {
+/// Parse a program declaration.
+///
+/// ```text
+/// program_decl:
+///   "program" [lifetime] ident {import_decl} ["#" param_port_list]
+///   ["(" port_list ")"] ";" {item} "endprogram" [":" ident]
+/// ```
+fn parse_program_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Program<'n>> {
+    let mut span = p.peek(0).1;
     p.require_reported(Keyword(Kw::Program))?;
     let result = recovered(p, Keyword(Kw::Endprogram), |p| {
-        let q = p.peek(0).1;
-        p.add_diag(DiagBuilder2::error("Don't know how to parse program declarations").span(q));
-        Err(())
+        // Eat the optional lifetime.
+        let lifetime = match as_lifetime(p.peek(0).0) {
+            Some(l) => {
+                p.bump();
+                l
+            }
+            None => Lifetime::Static,
+        };
+
+        // Eat the program name.
+        let (name, name_sp) = p.eat_ident("program name")?;
+
+        // Eat the optional package import declarations.
+        let mut imports = vec![];
+        while p.peek(0).0 == Keyword(Kw::Import) {
+            imports.push(parse_import_decl(p)?);
+        }
+
+        // Eat the optional parameter port list.
+        let params = if p.try_eat(Hashtag) {
+            parse_parameter_port_list(p)?
+        } else {
+            Vec::new()
+        };
+
+        // Eat the optional list of ports.
+        let ports = if p.try_eat(OpenDelim(Paren)) {
+            parse_port_list(p)?
+        } else {
+            Vec::new()
+        };
+
+        // Eat the semicolon after the header.
+        if !p.try_eat(Semicolon) {
+            let q = p.peek(0).1.end();
+            p.add_diag(
+                DiagBuilder2::error(format!("Missing ; after header of program \"{}\"", name))
+                    .span(q),
+            );
+        }
+
+        // Parse the program items.
+        let mut items = Vec::new();
+        while !p.is_fatal() && p.peek(0).0 != Keyword(Kw::Endprogram) && p.peek(0).0 != Eof {
+            if p.try_eat(Semicolon) {
+                continue;
+            }
+            items.push(parse_item(p)?);
+        }
+        check_program_items(p, &items);
+        check_default_clocking(p, &items);
+
+        span.expand(p.last_span());
+        Ok(Program::new(
+            span,
+            ProgramData {
+                lifetime,
+                name: Spanned::new(name, name_sp),
+                imports,
+                params,
+                ports,
+                items,
+            },
+        ))
     });
     p.require_reported(Keyword(Kw::Endprogram))?;
+    if p.try_eat(Colon) {
+        p.eat_ident("program name")?;
+    }
     result
 }
 
-fn parse_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Item<'n>> {
-    let mut span = p.peek(0).1;
-    let item = parse_item_data(p)?;
-    span.expand(p.last_span());
-    Ok(Item::new(span, item))
+/// Report items that may not appear in a program.
+///
+/// Programs may not contain `always` procedures, or module, interface, and
+/// program declarations. See §24.3 of IEEE 1800-2017.
+fn check_program_items<'n>(p: &mut dyn AbstractParser<'n>, items: &[Item<'n>]) {
+    for item in items {
+        let what = match item.data {
+            ItemData::Procedure(ref x) => match x.kind {
+                ProcedureKind::Always => "`always` procedure",
+                ProcedureKind::AlwaysComb => "`always_comb` procedure",
+                ProcedureKind::AlwaysLatch => "`always_latch` procedure",
+                ProcedureKind::AlwaysFf => "`always_ff` procedure",
+                _ => continue,
+            },
+            ItemData::ModuleDecl(..) => "module declaration",
+            ItemData::InterfaceDecl(..) => "interface declaration",
+            ItemData::ProgramDecl(..) => "program declaration",
+            _ => continue,
+        };
+        let mut d =
+            DiagBuilder2::error(format!("{} not allowed in a program", what)).span(item.span);
+        if let ItemData::Procedure(..) = item.data {
+            d = d.add_note("Programs may only contain `initial` and `final` procedures");
+        }
+        p.add_diag(d);
+    }
 }
 
-fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemData<'n>> {
-    // Consume optional leading label.
-    if p.is_ident() && p.peek(1).0 == Colon {
-        p.bump();
-        p.bump();
-    }
+/// Parse a checker declaration.
+///
+/// ```text
+/// checker_decl:
+///   "checker" ident ["(" port_list ")"] ";" {item} "endchecker" [":" ident]
+/// ```
+fn parse_checker_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Checker<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Checker))?;
+    let result = recovered(p, Keyword(Kw::Endchecker), |p| {
+        // Eat the checker name.
+        let (name, name_sp) = p.eat_ident("checker name")?;
 
-    // First attempt the simple cases where a keyword reliably identifies the
-    // following item.
+        // Eat the optional list of ports.
+        let ports = if p.try_eat(OpenDelim(Paren)) {
+            parse_port_list(p)?
+        } else {
+            Vec::new()
+        };
+        p.require_reported(Semicolon)?;
+
+        // Parse the checker items.
+        let mut items = Vec::new();
+        while !p.is_fatal() && p.peek(0).0 != Keyword(Kw::Endchecker) && p.peek(0).0 != Eof {
+            if p.try_eat(Semicolon) {
+                continue;
+            }
+            items.push(parse_item(p)?);
+        }
+        check_default_clocking(p, &items);
+
+        span.expand(p.last_span());
+        Ok(Checker::new(
+            span,
+            CheckerData {
+                name: Spanned::new(name, name_sp),
+                ports,
+                items,
+            },
+        ))
+    });
+    p.require_reported(Keyword(Kw::Endchecker))?;
+    if p.try_eat(Colon) {
+        p.eat_ident("checker name")?;
+    }
+    result
+}
+
+fn parse_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Item<'n>> {
+    let mut span = p.peek(0).1;
+    let attrs = p.attrs_at(0);
+    let item = parse_item_data(p)?;
+    span.expand(p.last_span());
+    let mut item = Item::new(span, item);
+    item.attrs = attrs;
+    Ok(item)
+}
+
+fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemData<'n>> {
+    // Consume optional leading label.
+    if p.is_ident() && p.peek(1).0 == Colon {
+        p.bump();
+        p.bump();
+    }
+
+    // First attempt the simple cases where a keyword reliably identifies the
+    // following item.
     let class_follows = p.peek(1).0 == Keyword(Kw::Class);
     let strlit_follows = match p.peek(1).0 {
         Literal(Lit::Str(..)) => true,
@@ -1025,6 +1531,9 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
     };
     match p.peek(0).0 {
         Keyword(Kw::Module) => return parse_module_decl(p).map(ItemData::ModuleDecl),
+        Keyword(Kw::Extern) if p.peek(1).0 == Keyword(Kw::Module) => {
+            return parse_extern_module_decl(p).map(ItemData::ExternModuleDecl)
+        }
         Keyword(Kw::Interface) | Keyword(Kw::Virtual) if class_follows => {
             return parse_class_decl(p).map(ItemData::ClassDecl)
         }
@@ -1032,6 +1541,7 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
         Keyword(Kw::Interface) => return parse_interface_decl(p).map(ItemData::InterfaceDecl),
         Keyword(Kw::Package) => return parse_package_decl(p).map(ItemData::PackageDecl),
         Keyword(Kw::Program) => return parse_program_decl(p).map(ItemData::ProgramDecl),
+        Keyword(Kw::Checker) => return parse_checker_decl(p).map(ItemData::CheckerDecl),
 
         Keyword(Kw::Localparam) | Keyword(Kw::Parameter) => {
             let decl = parse_param_decl(p, false)?;
@@ -1080,6 +1590,9 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
             return parse_continuous_assign(p).map(|x| ItemData::ContAssign(x));
         }
 
+        // Parameter overrides
+        Keyword(Kw::Defparam) => return parse_defparam(p).map(ItemData::Defparam),
+
         // Genvar declaration
         Keyword(Kw::Genvar) => {
             p.bump();
@@ -1107,6 +1620,13 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
         | Keyword(Kw::Cover)
         | Keyword(Kw::Expect)
         | Keyword(Kw::Restrict) => return parse_assertion(p).map(|x| ItemData::Assertion(x)),
+        Keyword(Kw::Let) => return parse_let_decl(p).map(ItemData::LetDecl),
+        Keyword(Kw::Clocking) => {
+            let span = p.peek(0).1;
+            return parse_clocking_decl(p, span, false).map(ItemData::ClockingDecl);
+        }
+        Keyword(Kw::Property) => return parse_property_decl(p).map(ItemData::PropertyDecl),
+        Keyword(Kw::Sequence) => return parse_sequence_decl(p).map(ItemData::SequenceDecl),
         Semicolon => {
             p.bump();
             return Ok(ItemData::Dummy);
@@ -1116,11 +1636,17 @@ fn parse_item_data<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ItemDat
         Keyword(Kw::Default) => {
             p.bump();
             let mut span = p.last_span();
-            if p.try_eat(Keyword(Kw::Clocking)) {
-                let name = p.eat_ident("clocking identifier")?;
-                p.require_reported(Semicolon)?;
-                span.expand(p.last_span());
-                return Ok(ItemData::Dummy);
+            if p.peek(0).0 == Keyword(Kw::Clocking) {
+                // A `default clocking foo;` marker, or a full clocking block.
+                if let (Ident(name), sp) | (EscIdent(name), sp) = p.peek(1) {
+                    if p.peek(2).0 == Semicolon {
+                        p.bump();
+                        p.bump();
+                        p.bump();
+                        return Ok(ItemData::DefaultClocking(Spanned::new(name, sp)));
+                    }
+                }
+                return parse_clocking_decl(p, span, true).map(ItemData::ClockingDecl);
             }
             if p.try_eat(Keyword(Kw::Disable)) {
                 p.require_reported(Keyword(Kw::Iff))?;
@@ -1168,7 +1694,11 @@ fn parse_elab_system_task<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<
     };
     p.recover_balanced(&[Semicolon], true);
     span.expand(p.last_span());
-    p.add_diag(DiagBuilder2::warning("unsupported elaboration system task").span(span));
+    p.add_diag(
+        DiagBuilder2::warning("unsupported elaboration system task")
+            .code(DiagCode::UNSUPPORTED_IGNORED)
+            .span(span),
+    );
     Ok(())
 }
 
@@ -1207,7 +1737,11 @@ fn parse_localparam_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<(
                 // gracefully.
                 if p.peek(0).0 == Semicolon {
                     // TODO: This should be an error in pedantic mode.
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::warning("superfluous trailing comma")
+                            .code(DiagCode::TRAILING_COMMA)
+                            .span(sp),
+                    );
                     break;
                 }
             }
@@ -1404,12 +1938,50 @@ fn parse_modport_ports_decl<'n>(
     }
 
     // Attempt to parse a TF port.
-    if p.try_eat(Keyword(Kw::Import)) || p.try_eat(Keyword(Kw::Export)) {
-        // TODO: Parse modport_tf_ports_declaration.
-        p.add_diag(
-            DiagBuilder2::error("modport task/function ports not implemented").span(p.last_span()),
-        );
-        return Err(());
+    let tf_kind = match p.peek(0).0 {
+        Keyword(Kw::Import) => Some(ast::ModportTfKind::Import),
+        Keyword(Kw::Export) => Some(ast::ModportTfKind::Export),
+        _ => None,
+    };
+    if let Some(kind) = tf_kind {
+        let kind = Spanned::new(kind, p.peek(0).1);
+        let mut port: Vec<&_> = vec![];
+        p.bump();
+        loop {
+            let mut span = p.peek(0).1;
+            let (name, prototype) = match p.peek(0).0 {
+                Keyword(Kw::Task) | Keyword(Kw::Function) => {
+                    let prototype = parse_modport_subroutine_prototype(p)?;
+                    (prototype.name, Some(prototype))
+                }
+                _ => (parse_identifier_name(p, "task or function name")?, None),
+            };
+            span.expand(p.last_span());
+            port.push(p.arena().alloc(ast::ModportTfPort::new(
+                span,
+                ast::ModportTfPortData { name, prototype },
+            )));
+
+            // Same as for simple ports above, except that a comma followed by
+            // a `task` or `function` keyword introduces another prototype.
+            match (p.peek(0).0, p.peek(1).0) {
+                (Comma, Keyword(Kw::Task)) | (Comma, Keyword(Kw::Function)) => {
+                    p.bump();
+                    continue;
+                }
+                (Comma, Keyword(_)) => break,
+                (Comma, _) => {
+                    p.bump();
+                    continue;
+                }
+                _ => break,
+            }
+        }
+        span.expand(p.last_span());
+        return Ok(p.arena().alloc(ast::ModportPort::new(
+            span,
+            ast::ModportPortData::Tf { kind, port },
+        )));
     }
 
     // Attempt to parse a clocking declaration.
@@ -1724,8 +2296,17 @@ fn parse_enum_name<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<EnumNam
     // Eat the name.
     let name = parse_identifier_name(p, "enum name")?;
 
-    // Parse the optional range.
-    let range = try_flanked(p, Brack, parse_expr)?;
+    // Parse the optional range, either `[N]` or `[N:M]`.
+    let range = match try_dimension(p)? {
+        Some((dim @ TypeDim::Expr(..), _)) | Some((dim @ TypeDim::Range(..), _)) => Some(dim),
+        Some((_, span)) => {
+            p.add_diag(
+                DiagBuilder2::error("expected `[N]` or `[N:M]` range after enum name").span(span),
+            );
+            return Err(());
+        }
+        None => None,
+    };
 
     // Parse the optional value.
     let value = if p.try_eat(Operator(Op::Assign)) {
@@ -2089,7 +2670,7 @@ fn parse_expr_suffix<'n>(
     let (tkn, sp) = p.peek(0);
     match tkn {
         // Index: "[" range_expression "]"
-        OpenDelim(Brack) if precedence <= Precedence::Postfix => {
+        OpenDelim(Brack) if precedence <= Precedence::Postfix && !is_seqrep_start(p) => {
             p.bump();
             let expr = match parse_range_expr(p) {
                 Ok(x) => x,
@@ -2149,6 +2730,9 @@ fn parse_expr_suffix<'n>(
                 Span::union(prefix.span, p.last_span()),
                 ScopeExpr(Box::new(prefix), ident),
             );
+            if let Some(Err(diag)) = HierName::from_expr(&expr).map(|name| name.validate()) {
+                p.add_diag(diag);
+            }
             return parse_expr_suffix(p, expr, precedence);
         }
 
@@ -2181,11 +2765,13 @@ fn parse_expr_suffix<'n>(
         }
 
         // expr "?" expr ":" expr
+        // The conditional operator is right-associative, such that
+        // `a ? b : c ? d : e` groups as `a ? b : (c ? d : e)`.
         Ternary if precedence < Precedence::Ternary => {
             p.bump();
-            let true_expr = parse_expr_prec(p, Precedence::Ternary)?;
+            let true_expr = parse_expr_prec(p, Precedence::Implication)?;
             p.require_reported(Colon)?;
-            let false_expr = parse_expr_prec(p, Precedence::Ternary)?;
+            let false_expr = parse_expr_prec(p, Precedence::Implication)?;
             let expr = Expr::new(
                 Span::union(prefix.span, p.last_span()),
                 TernaryExpr {
@@ -2201,20 +2787,7 @@ fn parse_expr_suffix<'n>(
         Keyword(Kw::Inside) if precedence <= Precedence::Relational => {
             p.bump();
             let set = flanked(p, Brace, |p| {
-                comma_list_nonempty(p, CloseDelim(Brace), "range", |p| {
-                    if p.peek(0).0 == OpenDelim(Brack) {
-                        p.require_reported(OpenDelim(Brack))?;
-                        let mut sp = p.last_span();
-                        let lo = parse_expr(p)?;
-                        p.require_reported(Colon)?;
-                        let hi = parse_expr(p)?;
-                        p.require_reported(CloseDelim(Brack))?;
-                        sp.expand(p.last_span());
-                        Ok(ValueRange::Range { lo, hi, span: sp })
-                    } else {
-                        Ok(ValueRange::Single(parse_expr(p)?))
-                    }
-                })
+                comma_list_nonempty(p, CloseDelim(Brace), "range", parse_value_range)
             })?;
             let expr = Expr::new(
                 Span::union(prefix.span, p.last_span()),
@@ -2258,7 +2831,13 @@ fn parse_expr_suffix<'n>(
         let prec = op.get_precedence();
         if precedence < prec {
             p.bump();
-            let rhs = parse_expr_prec(p, prec)?;
+            // The only right-associative binary operators are `->` and `<->`,
+            // whose right-hand side may contain further implications.
+            let rhs_prec = match op.get_associativity() {
+                Associativity::Right => Precedence::Assignment,
+                _ => prec,
+            };
+            let rhs = parse_expr_prec(p, rhs_prec)?;
             let expr = Expr::new(
                 Span::union(prefix.span, p.last_span()),
                 BinaryExpr {
@@ -2488,11 +3067,6 @@ fn parse_pattern_field<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Pat
     Ok(PatternField::new(span, data))
 }
 
-pub enum StreamDir {
-    In,
-    Out,
-}
-
 fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprData<'n>> {
     // Streaming concatenations have a "<<" or ">>" following the opening "{".
     let stream = match p.peek(0).0 {
@@ -2545,6 +3119,7 @@ fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprD
         })?;
 
         return Ok(StreamConcatExpr {
+            dir,
             slice: slice_size,
             exprs: exprs,
         });
@@ -2578,7 +3153,11 @@ fn parse_concat_expr<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ExprD
     while p.try_eat(Comma) {
         if p.peek(0).0 == CloseDelim(Brace) {
             let q = p.peek(0).1;
-            p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(q));
+            p.add_diag(
+                DiagBuilder2::warning("superfluous trailing comma")
+                    .code(DiagCode::TRAILING_COMMA)
+                    .span(q),
+            );
             break;
         }
         exprs.push(parse_expr_prec(p, Precedence::Min)?);
@@ -2599,7 +3178,11 @@ fn parse_expr_list<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Exp
             (Comma, sp) => {
                 p.bump();
                 if p.peek(0).0 == CloseDelim(Brace) {
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::warning("superfluous trailing comma")
+                            .code(DiagCode::TRAILING_COMMA)
+                            .span(sp),
+                    );
                     break;
                 }
             }
@@ -2781,7 +3364,11 @@ fn parse_port_list<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Por
             (Comma, sp) => {
                 p.bump();
                 if p.peek(0).0 == CloseDelim(Paren) {
-                    p.add_diag(DiagBuilder2::warning("superfluous trailing comma").span(sp));
+                    p.add_diag(
+                        DiagBuilder2::warning("superfluous trailing comma")
+                            .code(DiagCode::TRAILING_COMMA)
+                            .span(sp),
+                    );
                     break;
                 }
             }
@@ -2935,12 +3522,15 @@ fn parse_port_list<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Vec<Por
 
 /// Parse a single port declaration. These can take a few different forms.
 fn parse_port<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ast::Port<'n>> {
+    let attrs = p.attrs_at(0);
     let mut pp = ParallelParser::new();
     pp.add_greedy("interface port", parse_interface_port);
     pp.add_greedy("explicit port", parse_explicit_port);
     pp.add_greedy("named port", parse_named_port);
     pp.add_greedy("implicit port", parse_implicit_port);
-    pp.finish(p, "port")
+    let mut port = pp.finish(p, "port")?;
+    port.attrs = attrs;
+    Ok(port)
 }
 
 /// Parse a interface port declaration.
@@ -3206,6 +3796,21 @@ fn parse_subroutine_kind<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<S
 
 fn parse_subroutine_prototype<'n>(
     p: &mut dyn AbstractParser<'n>,
+) -> ReportedResult<SubroutinePrototype<'n>> {
+    parse_subroutine_prototype_with(p, true)
+}
+
+/// Parse a subroutine prototype as it appears in a modport declaration, which
+/// is not followed by a ";" but by the "," or ")" of the modport port list.
+fn parse_modport_subroutine_prototype<'n>(
+    p: &mut dyn AbstractParser<'n>,
+) -> ReportedResult<SubroutinePrototype<'n>> {
+    parse_subroutine_prototype_with(p, false)
+}
+
+fn parse_subroutine_prototype_with<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    semicolon: bool,
 ) -> ReportedResult<SubroutinePrototype<'n>> {
     let mut span = p.peek(0).1;
 
@@ -3223,21 +3828,21 @@ fn parse_subroutine_prototype<'n>(
     // the optional argument list.
     let (retty, (name, args)) = if kind == SubroutineKind::Func {
         if p.peek(0).0 == Keyword(Kw::New) {
-            (None, parse_subroutine_prototype_tail(p)?)
+            (None, parse_subroutine_prototype_tail(p, semicolon)?)
         } else {
             let mut pp = ParallelParser::new();
-            pp.add("implicit function return type", |p| {
+            pp.add("implicit function return type", move |p| {
                 let ty = parse_implicit_type(p)?;
-                Ok((Some(ty), parse_subroutine_prototype_tail(p)?))
+                Ok((Some(ty), parse_subroutine_prototype_tail(p, semicolon)?))
             });
-            pp.add("explicit function return type", |p| {
+            pp.add("explicit function return type", move |p| {
                 let ty = parse_explicit_type(p)?;
-                Ok((Some(ty), parse_subroutine_prototype_tail(p)?))
+                Ok((Some(ty), parse_subroutine_prototype_tail(p, semicolon)?))
             });
             pp.finish(p, "implicit or explicit function return type")?
         }
     } else {
-        (None, parse_subroutine_prototype_tail(p)?)
+        (None, parse_subroutine_prototype_tail(p, semicolon)?)
     };
 
     span.expand(p.last_span());
@@ -3255,6 +3860,7 @@ fn parse_subroutine_prototype<'n>(
 
 fn parse_subroutine_prototype_tail<'n>(
     p: &mut dyn AbstractParser<'n>,
+    semicolon: bool,
 ) -> ReportedResult<(Spanned<Name>, Option<Vec<SubroutinePort<'n>>>)> {
     // Consume the subroutine name, or "new".
     // TODO: Make this accept the full `[interface_identifier "." | class_scope] tf_identifier`.
@@ -3331,8 +3937,25 @@ fn parse_subroutine_prototype_tail<'n>(
         })
     })?;
 
-    // Wrap things up.
-    p.require_reported(Semicolon)?;
+    // Wrap things up. Prototypes in modports are followed by the next port
+    // or the end of the port list instead of a ";".
+    if semicolon {
+        p.require_reported(Semicolon)?;
+    } else {
+        match p.peek(0) {
+            (Comma, _) | (CloseDelim(Paren), _) => (),
+            (tkn, sp) => {
+                p.add_diag(
+                    DiagBuilder2::error(format!(
+                        "expected , or ) after modport subroutine prototype, found {}",
+                        tkn
+                    ))
+                    .span(sp),
+                );
+                return Err(());
+            }
+        }
+    }
     Ok((name, args))
 }
 
@@ -3417,10 +4040,13 @@ fn parse_subroutine_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<S
 
 fn parse_stmt<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Stmt<'n>> {
     let mut span = p.peek(0).1;
+    let attrs = p.attrs_at(0);
 
     // Null statements simply consist of a semicolon.
     if p.try_eat(Semicolon) {
-        return Ok(Stmt::new_null(span));
+        let mut stmt = Stmt::new_null(span);
+        stmt.attrs = attrs;
+        return Ok(stmt);
     }
 
     // Consume the optional statement label.
@@ -3436,7 +4062,9 @@ fn parse_stmt<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Stmt<'n>> {
     let kind = parse_stmt_kind(p, &mut label)?;
     span.expand(p.last_span());
 
-    Ok(Stmt::new(span, StmtData { label, kind }))
+    let mut stmt = Stmt::new(span, StmtData { label, kind });
+    stmt.attrs = attrs;
+    Ok(stmt)
 }
 
 fn parse_stmt_kind<'n>(
@@ -3533,6 +4161,7 @@ fn parse_stmt_kind<'n>(
                 return Err(());
             }
             let expr = flanked(p, Paren, parse_expr)?;
+            p.require_reported(Semicolon)?;
             DoStmt(stmt, expr)
         }
         Keyword(Kw::For) => {
@@ -3700,7 +4329,9 @@ fn parse_block<'n>(
         if let Some(existing) = *label {
             if name == existing {
                 p.add_diag(
-                    DiagBuilder2::warning(format!("Block {} labelled twice", name)).span(name_span),
+                    DiagBuilder2::warning(format!("Block {} labelled twice", name))
+                        .code(DiagCode::REPEATED_LABEL)
+                        .span(name_span),
                 );
             } else {
                 p.add_diag(
@@ -3848,6 +4479,26 @@ fn parse_if_or_case<'n>(
 }
 
 /// Parse a case statement as per IEEE 1800-2009 section 12.5.
+/// Parse a single value or range of an `inside` set.
+///
+/// ```text
+/// value_range: expr | "[" expr ":" expr "]"
+/// ```
+fn parse_value_range<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ValueRange<'n>> {
+    if p.peek(0).0 == OpenDelim(Brack) {
+        p.require_reported(OpenDelim(Brack))?;
+        let mut sp = p.last_span();
+        let lo = parse_expr(p)?;
+        p.require_reported(Colon)?;
+        let hi = parse_expr(p)?;
+        p.require_reported(CloseDelim(Brack))?;
+        sp.expand(p.last_span());
+        Ok(ValueRange::Range { lo, hi, span: sp })
+    } else {
+        Ok(ValueRange::Single(parse_expr(p)?))
+    }
+}
+
 fn parse_case<'n>(
     p: &mut dyn AbstractParser<'n>,
     up: Option<UniquePriority>,
@@ -3882,12 +4533,14 @@ fn parse_case<'n>(
 
     // Parse the case items.
     let mut items = Vec::new();
+    let mut defaults = Vec::new();
     while p.peek(0).0 != Keyword(Kw::Endcase) && p.peek(0).0 != Eof {
         let mut span = p.peek(0).1;
 
         // Handle the default case items.
         if p.peek(0).0 == Keyword(Kw::Default) {
             p.bump();
+            defaults.push(p.last_span());
             p.try_eat(Colon);
             let stmt = Box::new(parse_stmt(p)?);
             items.push(CaseItem::Default(stmt));
@@ -3895,23 +4548,16 @@ fn parse_case<'n>(
         // Handle regular case items.
         else {
             let mut exprs = Vec::new();
+            let mut ranges = Vec::new();
             loop {
-                if p.peek(0).0 == OpenDelim(Brack) {
-                    // TODO(fschuiki): Keep track of results
-                    // TODO(fschuiki): Error recovery
-                    p.require_reported(OpenDelim(Brack))?;
-                    parse_expr(p)?;
-                    p.require_reported(Colon)?;
-                    parse_expr(p)?;
-                    p.require_reported(CloseDelim(Brack))?;
+                let result = if mode == CaseMode::Inside {
+                    parse_value_range(p).map(|x| ranges.push(x))
                 } else {
-                    match parse_expr(p) {
-                        Ok(x) => exprs.push(x),
-                        Err(()) => {
-                            p.recover_balanced(&[Colon], false);
-                            break;
-                        }
-                    }
+                    parse_expr(p).map(|x| exprs.push(x))
+                };
+                if result.is_err() {
+                    p.recover_balanced(&[Colon], false);
+                    break;
                 }
 
                 match p.peek(0) {
@@ -3919,7 +4565,9 @@ fn parse_case<'n>(
                         p.bump();
                         if p.try_eat(Colon) {
                             p.add_diag(
-                                DiagBuilder2::warning("superfluous trailing comma").span(sp),
+                                DiagBuilder2::warning("superfluous trailing comma")
+                                    .code(DiagCode::TRAILING_COMMA)
+                                    .span(sp),
                             );
                             break;
                         }
@@ -3937,11 +4585,16 @@ fn parse_case<'n>(
             // Parse the statement.
             p.require_reported(Colon)?;
             let stmt = Box::new(parse_stmt(p)?);
-            items.push(CaseItem::Expr(exprs, stmt));
+            if mode == CaseMode::Inside {
+                items.push(CaseItem::Inside(ranges, stmt));
+            } else {
+                items.push(CaseItem::Expr(exprs, stmt));
+            }
         }
     }
 
     p.require_reported(Keyword(Kw::Endcase))?;
+    check_case(p, kind, mode, q, &defaults);
 
     Ok(CaseStmt {
         up: up,
@@ -3952,6 +4605,47 @@ fn parse_case<'n>(
     })
 }
 
+/// Report questionable or invalid forms of a case statement.
+///
+/// Warns about `casex`, whose wildcards also match `x` and `z` bits in the
+/// case expression and may hide unknown values in simulation. Reports
+/// `inside` on `casez` and `casex`, and case statements with more than one
+/// `default` item. See §12.5 of IEEE 1800-2017.
+fn check_case<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    kind: CaseKind,
+    mode: CaseMode,
+    span: Span,
+    defaults: &[Span],
+) {
+    if mode == CaseMode::Inside && kind != CaseKind::Normal {
+        p.add_diag(
+            DiagBuilder2::error(format!(
+                "`{}` cannot be combined with `inside`",
+                span.extract()
+            ))
+            .span(span)
+            .add_note("Use `case` instead; `inside` already matches wildcards"),
+        );
+    }
+    if kind == CaseKind::DontCareXZ {
+        p.add_diag(
+            DiagBuilder2::warning("`casex` also treats `x` in the case expression as a wildcard")
+                .code(DiagCode::CASEX_WILDCARD)
+                .span(span)
+                .add_note("Consider using `casez` or `case inside` instead"),
+        );
+    }
+    for &default in defaults.iter().skip(1) {
+        p.add_diag(
+            DiagBuilder2::error("multiple `default` items in case statement")
+                .span(default)
+                .add_note("First `default` item is here:")
+                .span(defaults[0]),
+        );
+    }
+}
+
 fn parse_if<'n>(
     p: &mut dyn AbstractParser<'n>,
     up: Option<UniquePriority>,
@@ -4329,6 +5023,7 @@ fn parse_genvar_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Genva
 
 fn parse_generate_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Item<'n>> {
     let mut span = p.peek(0).1;
+    let attrs = p.attrs_at(0);
     let data = match p.peek(0).0 {
         Keyword(Kw::For) => ItemData::GenerateFor(parse_generate_for(p)?),
         Keyword(Kw::If) => ItemData::GenerateIf(parse_generate_if(p)?),
@@ -4336,7 +5031,72 @@ fn parse_generate_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ite
         _ => return parse_item(p),
     };
     span.expand(p.last_span());
-    Ok(Item::new(span, data))
+    let mut item = Item::new(span, data);
+    item.attrs = attrs;
+    Ok(item)
+}
+
+/// Parse a defparam statement.
+/// ```text
+/// "defparam" hierarchical_name "=" expr {"," hierarchical_name "=" expr} ";"
+/// ```
+fn parse_defparam<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Defparam<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Defparam))?;
+    p.add_diag(
+        DiagBuilder2::warning("`defparam` is deprecated")
+            .code(DiagCode::DEPRECATED_CONSTRUCT)
+            .span(span)
+            .add_note("See §C.4.1 of IEEE 1800-2017.")
+            .add_note("Consider overriding the parameter in the instantiation instead."),
+    );
+    let assignments = comma_list_nonempty(p, Semicolon, "defparam assignment", |p| {
+        let name = parse_hier_name(p)?;
+        p.require_reported(Operator(Op::Assign))?;
+        let expr = parse_expr(p)?;
+        Ok((name, expr))
+    })?;
+    p.require_reported(Semicolon)?;
+    span.expand(p.last_span());
+    Ok(Defparam::new(span, DefparamData { assignments }))
+}
+
+/// Parse a hierarchical name.
+/// ```text
+/// ident {"[" expr "]"} {("." | "::") ident {"[" expr "]"}}
+/// ```
+fn parse_hier_name<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<HierName<'n>> {
+    let mut span = p.peek(0).1;
+    let mut segments = vec![];
+    let mut scoped = false;
+    loop {
+        let mut seg_span = p.peek(0).1;
+        let name = parse_identifier_name(p, "hierarchical name")?;
+        let mut indices = vec![];
+        while p.try_eat(OpenDelim(Brack)) {
+            indices.push(parse_expr(p)?);
+            p.require_reported(CloseDelim(Brack))?;
+        }
+        seg_span.expand(p.last_span());
+        segments.push(HierNameSegment {
+            span: seg_span,
+            scoped,
+            name,
+            indices,
+        });
+        scoped = match p.peek(0).0 {
+            Period => false,
+            Namespace => true,
+            _ => break,
+        };
+        p.bump();
+    }
+    span.expand(p.last_span());
+    let name = HierName { span, segments };
+    if let Err(diag) = name.validate() {
+        p.add_diag(diag);
+    }
+    Ok(name)
 }
 
 /// Parse a generate-for construct.
@@ -4443,7 +5203,9 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
             if existing.value == n.value {
                 p.add_diag(
                     DiagBuilder2::warning(format!("Generate block {} labelled twice", n))
-                        .span(n.span),
+                        .code(DiagCode::REPEATED_LABEL)
+                        .span_label(n.span, "labelled again here")
+                        .span_label(existing.span, "first labelled here"),
                 );
             } else {
                 p.add_diag(
@@ -4451,7 +5213,8 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
                         "Generate block given conflicting labels {} and {}",
                         existing, n
                     ))
-                    .span(n.span),
+                    .span_label(n.span, "conflicting label here")
+                    .span_label(existing.span, "first labelled here"),
                 );
                 return Err(());
             }
@@ -4484,6 +5247,7 @@ fn parse_generate_block<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Ge
                     "Generate block has trailing label {}, but is missing leading label",
                     n
                 ))
+                .code(DiagCode::MISSING_LEADING_LABEL)
                 .span(n.span),
             );
         }
@@ -4635,6 +5399,23 @@ fn parse_class_item<'n>(
                 data: ClassItemData::Typedef(def),
             });
         }
+        // Skip covergroups and nested classes, which are not supported.
+        Keyword(Kw::Covergroup) | Keyword(Kw::Class) => {
+            let term = match p.peek(0).0 {
+                Keyword(Kw::Covergroup) => Keyword(Kw::Endgroup),
+                _ => Keyword(Kw::Endclass),
+            };
+            p.recover_balanced(&[term], true);
+            if p.try_eat(Colon) {
+                p.eat_ident("name")?;
+            }
+            span.expand(p.last_span());
+            return Ok(ClassItem {
+                span,
+                qualifiers: vec![],
+                data: ClassItemData::Unsupported(span),
+            });
+        }
         _ => (),
     }
 
@@ -4652,7 +5433,7 @@ fn parse_class_item<'n>(
                 parse_variable_decl_assignment,
             )?;
             p.require_reported(Semicolon)?;
-            Ok(ClassItemData::Property)
+            Ok(ClassItemData::Property { ty, names })
         });
         if intf {
             pp.add("class function or task prototype", |p| {
@@ -5042,6 +5823,14 @@ impl<'tp, 'n> AbstractParser<'n> for BranchParser<'tp, 'n> {
     fn severity(&self) -> Severity {
         self.severity
     }
+
+    fn attrs_at(&mut self, offset: usize) -> Vec<ast::AttrSpec<'n>> {
+        self.parser.attrs_at(self.consumed + offset)
+    }
+
+    fn default_nettype_at(&mut self, offset: usize) -> Option<NetType> {
+        self.parser.default_nettype_at(self.consumed + offset)
+    }
 }
 
 fn parse_typedef<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Typedef<'n>> {
@@ -5227,9 +6016,9 @@ fn parse_net_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<NetDecl<
     // This function handles parsing of everything after the type.
     fn tail<'n>(
         p: &mut dyn AbstractParser<'n>,
-    ) -> ReportedResult<(Option<DelayControl<'n>>, Vec<VarDeclName<'n>>)> {
+    ) -> ReportedResult<(Option<Delay3<'n>>, Vec<VarDeclName<'n>>)> {
         // Parse the optional delay.
-        let delay = try_delay_control(p)?;
+        let delay = try_delay3(p)?;
 
         // Parse the names and assignments.
         let names = comma_list_nonempty(
@@ -5243,6 +6032,7 @@ fn parse_net_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<NetDecl<
     }
 
     span.expand(p.last_span());
+    check_net_strength(p, net_type, strength, span, &names);
     Ok(ast::NetDecl::new(
         span,
         ast::NetDeclData {
@@ -5256,6 +6046,93 @@ fn parse_net_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<NetDecl<
     ))
 }
 
+/// Report strengths that are not allowed on a net declaration.
+///
+/// Charge strengths may only be given on `trireg` nets, which cannot have an
+/// initializer. See §6.6.4 and §6.7 of IEEE 1800-2017.
+fn check_net_strength<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    net_type: NetType,
+    strength: Option<NetStrength>,
+    span: Span,
+    names: &[VarDeclName<'n>],
+) {
+    if let Some(NetStrength::Charge(..)) = strength {
+        if net_type != NetType::TriReg {
+            p.add_diag(
+                DiagBuilder2::error(format!(
+                    "charge strength not allowed on `{}` net; only on `trireg`",
+                    net_type
+                ))
+                .span(span),
+            );
+        }
+        for name in names {
+            if let Some(ref init) = name.init {
+                p.add_diag(
+                    DiagBuilder2::error(format!(
+                        "net `{}` with charge strength cannot have an initializer",
+                        name.name
+                    ))
+                    .span(init.span),
+                );
+            }
+        }
+    }
+}
+
+/// Parse the optional delay of a net declaration.
+///
+/// ```text
+/// delay3: "#" delay_value | "#" "(" mintypmax ["," mintypmax ["," mintypmax]] ")"
+/// ```
+fn try_delay3<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<Option<Delay3<'n>>> {
+    if !p.try_eat(Hashtag) {
+        return Ok(None);
+    }
+    let mut span = p.last_span();
+
+    // Parse either a single delay value, or up to three min-typ-max
+    // expressions in parenthesis.
+    let (tkn, sp) = p.peek(0);
+    let values = match tkn {
+        OpenDelim(Paren) => flanked(p, Paren, |p| {
+            comma_list_nonempty(
+                p,
+                CloseDelim(Paren),
+                "delay value",
+                parse_primary_parenthesis,
+            )
+        })?,
+        Literal(Number(..)) | Literal(Time(..)) | Ident(..) => {
+            vec![parse_expr_first(p, Precedence::Max)?]
+        }
+        _ => {
+            p.add_diag(DiagBuilder2::error("expected delay value or expression after #").span(sp));
+            return Err(());
+        }
+    };
+    span.expand(p.last_span());
+    if values.len() > 3 {
+        p.add_diag(
+            DiagBuilder2::error(format!(
+                "expected at most 3 delay values (rise, fall, turn-off), found {}",
+                values.len()
+            ))
+            .span(span),
+        );
+        return Err(());
+    }
+
+    let mut values = values.into_iter();
+    Ok(Some(Delay3 {
+        span,
+        rise: values.next().unwrap(),
+        fall: values.next(),
+        turnoff: values.next(),
+    }))
+}
+
 fn try_drive_strength<'n>(
     p: &mut dyn AbstractParser<'n>,
 ) -> ReportedResult<Option<(DriveStrength, DriveStrength)>> {
@@ -5372,12 +6249,79 @@ fn parse_dpi_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ast::Dpi
     };
 
     span.expand(p.last_span());
+    check_dpi_decl(p, &data);
     Ok(ast::DpiDecl::new(span, data))
 }
 
-/// Parse an import declaration.
-/// ```text
-/// "import" package_ident "::" "*" ";"
+/// Check a DPI declaration for consistency.
+///
+/// The specifier string must be `"DPI-C"` or the deprecated `"DPI"`. A `pure`
+/// import must be a non-void function without `output` or `inout` arguments.
+/// See §35.5 of IEEE 1800-2017.
+fn check_dpi_decl<'n>(p: &mut dyn AbstractParser<'n>, data: &ast::DpiDeclData<'n>) {
+    let spec = match *data {
+        ast::DpiDeclData::Import { spec, .. } | ast::DpiDeclData::Export { spec, .. } => spec,
+    };
+    match &*spec.value.as_str() {
+        "DPI-C" => (),
+        "DPI" => p.add_diag(
+            DiagBuilder2::warning("`\"DPI\"` is deprecated; use `\"DPI-C\"` instead")
+                .code(DiagCode::DEPRECATED_CONSTRUCT)
+                .span(spec.span),
+        ),
+        x => p.add_diag(
+            DiagBuilder2::error(format!(
+                "unknown DPI specifier `\"{}\"`; expected `\"DPI-C\"` or `\"DPI\"`",
+                x
+            ))
+            .span(spec.span),
+        ),
+    }
+
+    let prototype = match *data {
+        ast::DpiDeclData::Import {
+            property: Some(ref property),
+            ref prototype,
+            ..
+        } if property.value == ast::DpiProperty::Pure => prototype,
+        _ => return,
+    };
+    if prototype.kind == SubroutineKind::Task {
+        p.add_diag(
+            DiagBuilder2::error(format!("DPI task `{}` cannot be `pure`", prototype.name))
+                .span(prototype.span),
+        );
+        return;
+    }
+    if let Some(VoidType) = prototype.retty.as_ref().map(|ty| &ty.kind.data) {
+        p.add_diag(
+            DiagBuilder2::error(format!(
+                "pure DPI function `{}` must return a value",
+                prototype.name
+            ))
+            .span(prototype.span),
+        );
+    }
+    let mut dir = SubroutinePortDir::Input;
+    for arg in prototype.args.iter().flatten() {
+        if let Some(d) = arg.dir {
+            dir = d;
+        }
+        if dir == SubroutinePortDir::Output || dir == SubroutinePortDir::Inout {
+            p.add_diag(
+                DiagBuilder2::error(format!(
+                    "pure DPI function `{}` cannot have `{}` arguments",
+                    prototype.name, dir
+                ))
+                .span(arg.span),
+            );
+        }
+    }
+}
+
+/// Parse an import declaration.
+/// ```text
+/// "import" package_ident "::" "*" ";"
 /// "import" package_ident "::" ident ";"
 /// ```
 fn parse_import_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ImportDecl<'n>> {
@@ -5595,31 +6539,380 @@ fn parse_assertion_action_block<'n>(
     }
 }
 
-fn parse_property_spec<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<PropSpec> {
+fn parse_property_spec<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<PropSpec<'n>> {
+    let mut span = p.peek(0).1;
+
+    // Parse the optional clocking event.
+    let clock = if p.try_eat(At) {
+        Some(parse_event_expr(p, EventPrecedence::Max)?)
+    } else {
+        None
+    };
+
+    // Parse the optional "disable iff" clause.
+    let disable = if p.try_eat(Keyword(Kw::Disable)) {
+        p.require_reported(Keyword(Kw::Iff))?;
+        Some(flanked(p, Paren, parse_expr)?)
+    } else {
+        None
+    };
+
+    // Parse the property expression. Only a subset of the property operators
+    // is supported. Anything else is skipped up to the end of the property and
+    // recorded as unsupported.
+    let mut pp = ParallelParser::new();
+    pp.add_greedy("property expression", parse_propexpr);
+    pp.add("unsupported property expression", |p| {
+        let span = skip_unsupported_assertion_expr(p);
+        Ok(PropExpr {
+            span,
+            data: PropExprData::Unsupported(span),
+        })
+    });
+    let prop = pp.finish(p, "property expression")?;
+
+    span.expand(p.last_span());
+    Ok(PropSpec {
+        span,
+        clock,
+        disable,
+        prop,
+    })
+}
+
+/// Skip an unsupported property or sequence expression.
+///
+/// Consumes tokens up to the closing parenthesis or semicolon that terminates
+/// the expression, and returns the span of the skipped tokens.
+fn skip_unsupported_assertion_expr<'n>(p: &mut dyn AbstractParser<'n>) -> Span {
     let mut span = p.peek(0).1;
+    p.recover_balanced(&[CloseDelim(Paren), Semicolon], false);
+    span.expand(p.last_span());
+    span
+}
+
+/// Parse a clocking block declaration.
+///
+/// The `span` marks the start of the declaration, which is the `default`
+/// keyword for default clocking blocks.
+///
+/// ```text
+/// clocking_decl:
+///   ["default"] "clocking" [ident] "@" event_expr ";" {clocking_item}
+///   "endclocking" [":" ident]
+/// clocking_item:
+///   "default" "input" clocking_skew ["output" clocking_skew] ";"
+///   "default" "output" clocking_skew ";"
+///   clocking_dir clocking_signal {"," clocking_signal} ";"
+/// ```
+fn parse_clocking_decl<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    mut span: Span,
+    default: bool,
+) -> ReportedResult<ClockingDecl<'n>> {
+    p.require_reported(Keyword(Kw::Clocking))?;
+    let result = recovered(p, Keyword(Kw::Endclocking), |p| {
+        // Eat the optional name.
+        let name = match p.peek(0) {
+            (Ident(name), sp) | (EscIdent(name), sp) => {
+                p.bump();
+                Some(Spanned::new(name, sp))
+            }
+            _ => None,
+        };
+
+        // Parse the clocking event.
+        p.require_reported(At)?;
+        let event = parse_event_expr(p, EventPrecedence::Max)?;
+        p.require_reported(Semicolon)?;
 
-    // TODO: Actually parse this stuff, rather than just chicken out.
-    p.recover_balanced(&[CloseDelim(Paren)], false);
-    return Ok(PropSpec);
+        // Parse the clocking items.
+        let mut default_input = None;
+        let mut default_output = None;
+        let mut items = Vec::new();
+        while !p.is_fatal() && p.peek(0).0 != Keyword(Kw::Endclocking) && p.peek(0).0 != Eof {
+            if p.try_eat(Semicolon) {
+                continue;
+            }
+            if p.try_eat(Keyword(Kw::Default)) {
+                parse_default_skew(p, &mut default_input, &mut default_output)?;
+                continue;
+            }
+            items.push(parse_clocking_item(p)?);
+        }
+
+        span.expand(p.last_span());
+        Ok(ClockingDecl::new(
+            span,
+            ClockingDeclData {
+                default,
+                name,
+                event,
+                default_input,
+                default_output,
+                items,
+            },
+        ))
+    });
+    p.require_reported(Keyword(Kw::Endclocking))?;
+    if p.try_eat(Colon) {
+        p.eat_ident("clocking block name")?;
+    }
+    result
+}
+
+/// Parse the default skews of a clocking block, after the `default` keyword.
+fn parse_default_skew<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    input: &mut Option<ClockingSkew<'n>>,
+    output: &mut Option<ClockingSkew<'n>>,
+) -> ReportedResult<()> {
+    let span = p.last_span();
+    let mut any = false;
+    if p.try_eat(Keyword(Kw::Input)) {
+        any = true;
+        parse_default_skew_into(p, "input", input)?;
+    }
+    if p.try_eat(Keyword(Kw::Output)) {
+        any = true;
+        parse_default_skew_into(p, "output", output)?;
+    }
+    if !any {
+        p.add_diag(DiagBuilder2::error("expected `input` or `output` after `default`").span(span));
+        p.recover_balanced(&[Semicolon], true);
+        return Err(());
+    }
+    p.require_reported(Semicolon)?;
+    Ok(())
+}
+
+fn parse_default_skew_into<'n>(
+    p: &mut dyn AbstractParser<'n>,
+    dir: &str,
+    slot: &mut Option<ClockingSkew<'n>>,
+) -> ReportedResult<()> {
+    let dir_span = p.last_span();
+    let skew = match try_clocking_skew(p)? {
+        Some(skew) => skew,
+        None => {
+            let q = p.peek(0).1;
+            p.add_diag(
+                DiagBuilder2::error(format!("expected clocking skew after `{}`", dir)).span(q),
+            );
+            return Err(());
+        }
+    };
+    if slot.is_some() {
+        p.add_diag(
+            DiagBuilder2::error(format!("default {} skew specified multiple times", dir))
+                .span(dir_span),
+        );
+    }
+    *slot = Some(skew);
+    Ok(())
+}
+
+/// Parse a direction-qualified list of signals in a clocking block.
+fn parse_clocking_item<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<ClockingItem<'n>> {
+    let mut span = p.peek(0).1;
+    let dir = match p.peek(0).0 {
+        Keyword(Kw::Input) => {
+            p.bump();
+            let input = try_clocking_skew(p)?;
+            if p.try_eat(Keyword(Kw::Output)) {
+                ClockingDir::InputOutput(input, try_clocking_skew(p)?)
+            } else {
+                ClockingDir::Input(input)
+            }
+        }
+        Keyword(Kw::Output) => {
+            p.bump();
+            ClockingDir::Output(try_clocking_skew(p)?)
+        }
+        Keyword(Kw::Inout) => {
+            p.bump();
+            ClockingDir::Inout
+        }
+        _ => {
+            p.add_diag(
+                DiagBuilder2::error("expected `input`, `output`, or `inout` clocking item")
+                    .span(span),
+            );
+            p.recover_balanced(&[Semicolon], true);
+            return Err(());
+        }
+    };
+    let signals = comma_list_nonempty(p, Semicolon, "clocking signal", |p| {
+        let mut span = p.peek(0).1;
+        let name = parse_identifier_name(p, "clocking signal name")?;
+        let expr = if p.try_eat(Operator(Op::Assign)) {
+            Some(parse_expr(p)?)
+        } else {
+            None
+        };
+        span.expand(p.last_span());
+        Ok(ClockingSignal::new(span, ClockingSignalData { name, expr }))
+    })?;
+    p.require_reported(Semicolon)?;
+    span.expand(p.last_span());
+    Ok(ClockingItem::new(span, ClockingItemData { dir, signals }))
+}
+
+/// Try to parse a clocking skew.
+///
+/// ```text
+/// clocking_skew:
+///   edge_ident ["#" delay_value]
+///   "#" delay_value
+///   "#" "1step"
+/// ```
+fn try_clocking_skew<'n>(
+    p: &mut dyn AbstractParser<'n>,
+) -> ReportedResult<Option<ClockingSkew<'n>>> {
+    let mut span = p.peek(0).1;
+    let edge = as_edge_ident(p.peek(0).0);
+    if edge != EdgeIdent::Implicit {
+        p.bump();
+    }
+    let delay = if p.peek(0).0 == Hashtag && p.peek(1).0 == OneStep {
+        let mut sp = p.peek(0).1;
+        p.bump();
+        p.bump();
+        sp.expand(p.last_span());
+        Some(ClockingDelay::Step(sp))
+    } else {
+        try_delay_control(p)?.map(|d| ClockingDelay::Expr(d.expr))
+    };
+    if edge == EdgeIdent::Implicit && delay.is_none() {
+        return Ok(None);
+    }
+    span.expand(p.last_span());
+    Ok(Some(ClockingSkew { span, edge, delay }))
+}
+
+/// Parse a let declaration.
+///
+/// ```text
+/// "let" ident ["(" [let_port {"," let_port}] ")"] "=" expr ";"
+/// let_port ::= ["untyped" | data_type] ident ["=" expr]
+/// ```
+fn parse_let_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<LetDecl<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Let))?;
+    let name = parse_identifier_name(p, "let name")?;
+    let ports = try_flanked(p, Paren, |p| {
+        comma_list(p, CloseDelim(Paren), "let argument", parse_let_port)
+    })?
+    .unwrap_or_default();
+    p.require_reported(Operator(Op::Assign))?;
+    let expr = parse_expr(p)?;
+    p.require_reported(Semicolon)?;
+    span.expand(p.last_span());
+    Ok(LetDecl::new(span, LetDeclData { name, ports, expr }))
+}
+
+fn parse_let_port<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<LetPort<'n>> {
+    let mut span = p.peek(0).1;
+
+    // An identifier directly followed by the end of the argument is the
+    // argument name. Otherwise the argument starts with a type.
+    let ty = if p.try_eat(Keyword(Kw::Untyped)) {
+        None
+    } else {
+        match (p.peek(0).0, p.peek(1).0) {
+            (Ident(_), Comma)
+            | (Ident(_), CloseDelim(Paren))
+            | (Ident(_), Operator(Op::Assign))
+            | (EscIdent(_), Comma)
+            | (EscIdent(_), CloseDelim(Paren))
+            | (EscIdent(_), Operator(Op::Assign)) => None,
+            _ => Some(parse_explicit_type(p)?),
+        }
+    };
+    let name = parse_identifier_name(p, "let argument name")?;
+    let default = if p.try_eat(Operator(Op::Assign)) {
+        Some(parse_expr(p)?)
+    } else {
+        None
+    };
+    span.expand(p.last_span());
+    Ok(LetPort::new(span, LetPortData { ty, name, default }))
+}
 
-    // // Parse the optional event expression.
-    // let event = if p.try_eat(At) {
-    //     Some(parse_event_expr(p, EventPrecedence::Min)?)
-    // } else {
-    //     None
-    // };
+/// Parse a property declaration.
+///
+/// ```text
+/// "property" ident ["(" {ident} ")"] ";" property_spec [";"] "endproperty" [":" ident]
+/// ```
+fn parse_property_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<PropertyDecl<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Property))?;
+    let result = recovered(p, Keyword(Kw::Endproperty), |p| {
+        let name = parse_identifier_name(p, "property name")?;
+        let ports = parse_assertion_decl_ports(p)?;
+        p.require_reported(Semicolon)?;
+        let spec = parse_property_spec(p)?;
+        p.try_eat(Semicolon);
+        span.expand(p.last_span());
+        Ok(PropertyDecl::new(
+            span,
+            PropertyDeclData { name, ports, spec },
+        ))
+    });
+    p.require_reported(Keyword(Kw::Endproperty))?;
+    if p.try_eat(Colon) {
+        p.eat_ident("property name")?;
+    }
+    result
+}
 
-    // // Parse the optional "disable iff" clause.
-    // let disable = if p.try_eat(Keyword(Kw::Disable)) {
-    //     p.require_reported(Keyword(Kw::Iff))?;
-    //     Some(flanked(p, Paren, parse_expr)?)
-    // } else {
-    //     None
-    // };
+/// Parse a sequence declaration.
+///
+/// ```text
+/// "sequence" ident ["(" {ident} ")"] ";" sequence_expr [";"] "endsequence" [":" ident]
+/// ```
+fn parse_sequence_decl<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<SequenceDecl<'n>> {
+    let mut span = p.peek(0).1;
+    p.require_reported(Keyword(Kw::Sequence))?;
+    let result = recovered(p, Keyword(Kw::Endsequence), |p| {
+        let name = parse_identifier_name(p, "sequence name")?;
+        let ports = parse_assertion_decl_ports(p)?;
+        p.require_reported(Semicolon)?;
+        let mut pp = ParallelParser::new();
+        pp.add_greedy("sequence expression", parse_seqexpr);
+        pp.add("unsupported sequence expression", |p| {
+            let span = skip_unsupported_assertion_expr(p);
+            Ok(SeqExpr {
+                span,
+                data: SeqExprData::Unsupported(span),
+            })
+        });
+        let seq = pp.finish(p, "sequence expression")?;
+        p.try_eat(Semicolon);
+        span.expand(p.last_span());
+        Ok(SequenceDecl::new(
+            span,
+            SequenceDeclData { name, ports, seq },
+        ))
+    });
+    p.require_reported(Keyword(Kw::Endsequence))?;
+    if p.try_eat(Colon) {
+        p.eat_ident("sequence name")?;
+    }
+    result
+}
 
-    // // Parse the property expression.
-    // let prop = parse_propexpr(p)?;
-    // Ok(PropSpec)
+/// Parse the optional list of untyped formal arguments of a property or
+/// sequence declaration.
+fn parse_assertion_decl_ports<'n>(
+    p: &mut dyn AbstractParser<'n>,
+) -> ReportedResult<Vec<Spanned<Name>>> {
+    Ok(try_flanked(p, Paren, |p| {
+        comma_list(p, CloseDelim(Paren), "formal argument", |p| {
+            parse_identifier_name(p, "formal argument name")
+        })
+    })?
+    .unwrap_or_default())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -5810,6 +7103,13 @@ fn parse_seqexpr_expr<'n>(
     p: &mut dyn AbstractParser<'n>,
     precedence: PropSeqPrecedence,
 ) -> ReportedResult<SeqExprData<'n>> {
+    // Leading cycle delay, e.g. `##1 a`.
+    if p.peek(0).0 == DoubleHashtag {
+        let delay = parse_seq_delay(p)?;
+        let rhs = parse_seqexpr_prec(p, PropSeqPrecedence::Brack)?;
+        return Ok(SeqExprData::Delay(None, delay, Box::new(rhs)));
+    }
+
     // TODO: Handle all the non-trivial cases.
     let q = p.peek(0).1;
     p.add_diag(
@@ -5847,10 +7147,61 @@ fn parse_seqexpr_suffix<'n>(
     prefix: SeqExpr<'n>,
     precedence: PropSeqPrecedence,
 ) -> ReportedResult<SeqExpr<'n>> {
-    // TODO: Handle all the binary operators.
+    // Cycle delay concatenation, e.g. `a ##1 b`.
+    if precedence <= PropSeqPrecedence::CycleDelay && p.peek(0).0 == DoubleHashtag {
+        let delay = parse_seq_delay(p)?;
+        let rhs = parse_seqexpr_prec(p, PropSeqPrecedence::Brack)?;
+        let expr = SeqExpr {
+            span: Span::union(prefix.span, rhs.span),
+            data: SeqExprData::Delay(Some(Box::new(prefix)), delay, Box::new(rhs)),
+        };
+        return parse_seqexpr_suffix(p, expr, precedence);
+    }
+
+    // TODO: Handle all the other binary operators.
     Ok(prefix)
 }
 
+/// Parse a cycle delay in a sequence.
+///
+/// ```text
+/// "##" constant_primary
+/// "##" "[" const_expr ":" (const_expr | "$") "]"
+/// "##" "[" ("*" | "+") "]"
+/// ```
+fn parse_seq_delay<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<SeqDelay<'n>> {
+    p.require_reported(DoubleHashtag)?;
+    if p.peek(0).0 != OpenDelim(Brack) {
+        return Ok(SeqDelay::Exact(parse_expr_prec(p, Precedence::Scope)?));
+    }
+    flanked(p, Brack, |p| match (p.peek(0).0, p.peek(1).0) {
+        (Operator(Op::Mul), CloseDelim(Brack)) => {
+            p.bump();
+            Ok(SeqDelay::Star)
+        }
+        (Operator(Op::Add), CloseDelim(Brack)) => {
+            p.bump();
+            Ok(SeqDelay::Plus)
+        }
+        _ => {
+            let lo = parse_expr(p)?;
+            p.require_reported(Colon)?;
+            let hi = parse_expr(p)?;
+            Ok(SeqDelay::Range(lo, hi))
+        }
+    })
+}
+
+/// Check whether the upcoming `[` opens a sequence repetition rather than an
+/// index, e.g. `[*2]`, `[+]`, `[=2]`, or `[->2]`.
+fn is_seqrep_start<'n>(p: &mut dyn AbstractParser<'n>) -> bool {
+    match p.peek(1).0 {
+        Operator(Op::Mul) | Operator(Op::Assign) | Operator(Op::LogicImpl) => true,
+        Operator(Op::Add) => p.peek(2).0 == CloseDelim(Brack),
+        _ => false,
+    }
+}
+
 fn parse_seqrep<'n>(p: &mut dyn AbstractParser<'n>) -> ReportedResult<SeqRep<'n>> {
     match p.peek(0).0 {
         // [*]
@@ -5994,6 +7345,7 @@ fn parse_param_decl<'n>(
     keyword_optional: bool,
 ) -> ReportedResult<ast::ParamDecl<'n>> {
     let mut span = p.peek(0).1;
+    let attrs = p.attrs_at(0);
 
     // Eat the possibly optional `parameter` or `localparam` keyword. This
     // determines whether the parameter is considered local. Omitting the
@@ -6048,6 +7400,17 @@ fn parse_param_decl<'n>(
         let decls = comma_list_nonempty(p, predicate, "parameter name", |p| {
             let mut span = p.peek(0).1;
             let name = parse_identifier_name(p, "parameter name")?;
+            let (dims, dims_span) = parse_optional_dimensions(p)?;
+            if !dims.is_empty() {
+                p.add_diag(
+                    DiagBuilder2::error(format!(
+                        "type parameter `{}` cannot have unpacked dimensions",
+                        name
+                    ))
+                    .span(dims_span)
+                    .add_note("Move the dimensions into the type assigned to the parameter."),
+                );
+            }
             let ty = if p.try_eat(Operator(Op::Assign)) {
                 Some(parse_explicit_type(p)?)
             } else {
@@ -6108,10 +7471,9 @@ fn parse_param_decl<'n>(
     };
 
     span.expand(p.last_span());
-    Ok(ast::ParamDecl::new(
-        span,
-        ast::ParamDeclData { local, kind },
-    ))
+    let mut decl = ast::ParamDecl::new(span, ast::ParamDeclData { local, kind });
+    decl.attrs = attrs;
+    Ok(decl)
 }
 
 fn parse_hname<'n>(p: &mut dyn AbstractParser<'n>, msg: &str) -> ReportedResult<ast::Identifier> {
@@ -6133,3 +7495,605 @@ fn try_builtin_system_task<'n>(
         _ => None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    fn parse_items<'n>(
+        name: &str,
+        input: &str,
+        arena: &'n ast::Arena<'n>,
+    ) -> Result<Vec<Item<'n>>, ()> {
+        let source = get_source_manager().add(name, input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let file = parse(Lexer::new(preproc), arena)?;
+        match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => Ok(m.items.clone()),
+            _ => panic!("expected module"),
+        }
+    }
+
+    #[test]
+    fn attributes() {
+        let arena = ast::Arena::default();
+        let source = get_source_manager().add(
+            "attributes.sv",
+            "(* top *) module foo ((* keep *) input a);
+                (* ram_style = \"block\", Keep = 1 *) (* keep *) logic [7:0] m;
+                always @(*) (* parallel_case *) m = 0;
+            endmodule",
+        );
+        let file = parse(Lexer::new(Preprocessor::new(source, &[], &[])), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        assert!(file.items[0].attr("top").is_some());
+        assert!(module.attr("top").is_some());
+
+        // Attributes on ports.
+        let port = &module.ports[0];
+        assert_eq!(port.attrs.len(), 1);
+        assert!(port.attr("keep").unwrap().value.is_none());
+
+        // Attributes on items, with multiple instances concatenated and the
+        // lookup being case-sensitive.
+        let item = &module.items[0];
+        assert_eq!(item.attrs.len(), 3);
+        let ram_style = item.attr("ram_style").unwrap();
+        assert_eq!(ram_style.name.value, Name::from("ram_style"));
+        assert_eq!(ram_style.span.extract(), "ram_style = \"block\"");
+        assert!(ram_style.value.is_some());
+        assert!(item.attr("Keep").unwrap().value.is_some());
+        assert!(item.attr("keep").unwrap().value.is_none());
+        assert!(item.attr("KEEP").is_none());
+
+        // Attributes on statements, and `@(*)` still parsing as an event.
+        let stmt = match module.items[1].data {
+            ItemData::Procedure(ref p) => match p.stmt.kind {
+                TimedStmt(_, ref stmt) => stmt,
+                _ => panic!("expected timed statement"),
+            },
+            _ => panic!("expected procedure"),
+        };
+        assert!(stmt.attr("parallel_case").is_some());
+        assert!(module.items[1].attrs.is_empty());
+    }
+
+    #[test]
+    fn modport_subroutine_prototypes() {
+        let arena = ast::Arena::default();
+        let source = get_source_manager().add(
+            "modport_tf.sv",
+            "interface bar;
+                modport in (input data, import put, task wait_ready());
+                modport out (export function void put(logic [31:0] d), output ready);
+            endinterface",
+        );
+        let file = parse(Lexer::new(Preprocessor::new(source, &[], &[])), &arena).unwrap();
+        let items = match file.items[0].data {
+            ItemData::InterfaceDecl(ref i) => &i.items,
+            _ => panic!("expected interface"),
+        };
+        let tf_ports = |item: &Item, index: usize| match item.data {
+            ItemData::ModportDecl(ref m) => match m.names[0].ports[index].data {
+                ModportPortData::Tf { ref port, .. } => port
+                    .iter()
+                    .map(|p| (p.name.to_string(), p.prototype.is_some()))
+                    .collect::<Vec<_>>(),
+                _ => panic!("expected task or function port"),
+            },
+            _ => panic!("expected modport"),
+        };
+        assert_eq!(
+            tf_ports(&items[0], 1),
+            vec![("put".to_string(), false), ("wait_ready".to_string(), true)]
+        );
+        assert_eq!(tf_ports(&items[1], 0), vec![("put".to_string(), true)]);
+    }
+
+    #[test]
+    fn net_decl_strength_and_delay() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "net_decl_full.sv",
+            "module foo; wire (pull1, strong0) #(1:2:3) w = a & b; trireg (large) #(4, 5, 6) c; endmodule",
+            &arena,
+        )
+        .unwrap();
+        let nets: Vec<_> = items
+            .iter()
+            .map(|item| match item.data {
+                ItemData::NetDecl(ref n) => n,
+                _ => panic!("expected net declaration"),
+            })
+            .collect();
+
+        let w = nets[0];
+        assert_eq!(w.net_type, NetType::Wire);
+        assert_eq!(
+            w.strength,
+            Some(NetStrength::Drive(
+                DriveStrength::Pull1,
+                DriveStrength::Strong0
+            ))
+        );
+        let delay = w.delay.as_ref().unwrap();
+        assert_eq!(delay.rise.span.extract(), "1:2:3");
+        assert!(delay.fall.is_none() && delay.turnoff.is_none());
+        assert_eq!(w.names[0].name.to_string(), "w");
+        assert_eq!(w.names[0].init.as_ref().unwrap().span.extract(), "a & b");
+
+        let c = nets[1];
+        assert_eq!(c.net_type, NetType::TriReg);
+        assert_eq!(c.strength, Some(NetStrength::Charge(ChargeStrength::Large)));
+        let delay = c.delay.as_ref().unwrap();
+        assert_eq!(delay.rise.span.extract(), "4");
+        assert_eq!(delay.fall.as_ref().unwrap().span.extract(), "5");
+        assert_eq!(delay.turnoff.as_ref().unwrap().span.extract(), "6");
+        assert!(c.names[0].init.is_none());
+    }
+
+    #[test]
+    fn net_decl_charge_strength_on_wire() {
+        let arena = ast::Arena::default();
+        let result = parse_items(
+            "net_decl_charge.sv",
+            "module foo; wire (small) c; endmodule",
+            &arena,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn genvar_decl_forms() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "genvar_forms.sv",
+            "module foo; genvar i, j = 1; for (i = 0; i < 2; i++) begin end for (genvar k = 0; k < 4; k++) begin end endmodule",
+            &arena,
+        )
+        .unwrap();
+
+        // The standalone declaration.
+        let names: Vec<_> = match items[0].data {
+            ItemData::GenvarDecl(ref decls) => decls.iter().map(|d| d.name.to_string()).collect(),
+            _ => panic!("expected genvar declaration"),
+        };
+        assert_eq!(names, vec!["i", "j"]);
+
+        // The inline declaration in the loop initializer.
+        let decls = match items[2].data {
+            ItemData::GenerateFor(ref gen) => match gen.init.kind {
+                GenvarDeclStmt(ref decls) => decls,
+                _ => panic!("expected inline genvar declaration"),
+            },
+            _ => panic!("expected generate loop"),
+        };
+        assert_eq!(decls[0].name.to_string(), "k");
+        assert_eq!(decls[0].init.as_ref().unwrap().span.extract(), "0");
+    }
+
+    #[test]
+    fn case_inside_with_ranges() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "case_inside.sv",
+            "module foo; always_comb begin unique case (x) inside 0, [2:5]: y = 1; [8:$]: y = 2; default: y = 0; endcase priority casez (x) 4'b1???: y = 3; endcase end endmodule",
+            &arena,
+        )
+        .unwrap();
+        let stmts = match items[0].data {
+            ItemData::Procedure(ref p) => match p.stmt.kind {
+                SequentialBlock(ref stmts) => stmts,
+                _ => panic!("expected block"),
+            },
+            _ => panic!("expected procedure"),
+        };
+
+        match stmts[0].kind {
+            CaseStmt {
+                up,
+                kind,
+                mode,
+                ref items,
+                ..
+            } => {
+                assert_eq!(up, Some(UniquePriority::Unique));
+                assert_eq!(kind, CaseKind::Normal);
+                assert_eq!(mode, CaseMode::Inside);
+                assert_eq!(items.len(), 3);
+                match items[0] {
+                    CaseItem::Inside(ref ranges, _) => {
+                        assert_eq!(ranges.len(), 2);
+                        match ranges[1] {
+                            ValueRange::Range { ref lo, ref hi, .. } => {
+                                assert_eq!(lo.span.extract(), "2");
+                                assert_eq!(hi.span.extract(), "5");
+                            }
+                            _ => panic!("expected range"),
+                        }
+                    }
+                    _ => panic!("expected inside item"),
+                }
+                assert!(matches!(items[2], CaseItem::Default(..)));
+            }
+            _ => panic!("expected case statement"),
+        }
+
+        match stmts[1].kind {
+            CaseStmt { up, kind, mode, .. } => {
+                assert_eq!(up, Some(UniquePriority::Priority));
+                assert_eq!(kind, CaseKind::DontCareZ);
+                assert_eq!(mode, CaseMode::Normal);
+            }
+            _ => panic!("expected case statement"),
+        }
+    }
+
+    #[test]
+    fn case_multiple_defaults() {
+        let arena = ast::Arena::default();
+        let result = parse_items(
+            "case_defaults.sv",
+            "module foo; always_comb case (x) default: y = 0; 1: y = 1; default: y = 2; endcase endmodule",
+            &arena,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn concurrent_assertion() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "assert_property.sv",
+            "module foo; assert property (@(posedge clk) req |-> ##[1:3] gnt); endmodule",
+            &arena,
+        )
+        .unwrap();
+        let spec = match items[0].data {
+            ItemData::Assertion(Assertion {
+                data: AssertionData::Concurrent(ConcurrentAssertion::AssertProperty(ref spec, _)),
+                ..
+            }) => spec,
+            _ => panic!("expected concurrent assertion"),
+        };
+        match spec.clock {
+            Some(EventExpr::Edge {
+                edge: EdgeIdent::Posedge,
+                ref value,
+                ..
+            }) => assert_eq!(value.span.extract(), "clk"),
+            _ => panic!("expected posedge clock"),
+        }
+        let (lhs, rhs) = match spec.prop.data {
+            PropExprData::SeqBinOp(
+                PropSeqBinOp::ImplOverlap,
+                PropSeqOp::None,
+                ref lhs,
+                ref rhs,
+            ) => (lhs, rhs),
+            _ => panic!("expected overlapping implication"),
+        };
+        assert_eq!(lhs.span.extract(), "req");
+        let seq = match rhs.data {
+            PropExprData::SeqOp(PropSeqOp::None, ref seq) => seq,
+            _ => panic!("expected sequence"),
+        };
+        match seq.data {
+            SeqExprData::Delay(None, SeqDelay::Range(ref lo, ref hi), ref seq) => {
+                assert_eq!(lo.span.extract(), "1");
+                assert_eq!(hi.span.extract(), "3");
+                assert_eq!(seq.span.extract(), "gnt");
+            }
+            _ => panic!("expected cycle delay range"),
+        }
+    }
+
+    #[test]
+    fn immediate_assertion_with_else() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "assert_immediate.sv",
+            "module foo; initial assert (a == b) else $error(\"mismatch\"); endmodule",
+            &arena,
+        )
+        .unwrap();
+        let stmt = match items[0].data {
+            ItemData::Procedure(ref p) => &p.stmt,
+            _ => panic!("expected procedure"),
+        };
+        match stmt.kind {
+            AssertionStmt(ref a) => match a.data {
+                AssertionData::Immediate(BlockingAssertion::Assert(
+                    ref cond,
+                    AssertionActionBlock::Negative(ref action),
+                )) => {
+                    assert_eq!(cond.span.extract(), "a == b");
+                    assert_eq!(action.span.extract(), "$error(\"mismatch\");");
+                }
+                _ => panic!("expected immediate assertion with else action"),
+            },
+            _ => panic!("expected assertion statement"),
+        }
+    }
+
+    #[test]
+    fn property_and_sequence_decls() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "assert_decls.sv",
+            "module foo;
+                sequence s(a, b); a ##1 b[*2]; endsequence
+                property p(a); @(posedge clk) a |=> s_eventually a; endproperty : p
+            endmodule",
+            &arena,
+        )
+        .unwrap();
+        let seq = match items[0].data {
+            ItemData::SequenceDecl(ref s) => s,
+            _ => panic!("expected sequence declaration"),
+        };
+        assert_eq!(seq.name.value.to_string(), "s");
+        assert_eq!(seq.ports.len(), 2);
+        match seq.seq.data {
+            SeqExprData::Delay(Some(ref lhs), SeqDelay::Exact(ref n), ref rhs) => {
+                assert_eq!(lhs.span.extract(), "a");
+                assert_eq!(n.span.extract(), "1");
+                match rhs.data {
+                    SeqExprData::Expr(ref e, Some(SeqRep::Consec(ref n))) => {
+                        assert_eq!(e.span.extract(), "b");
+                        assert_eq!(n.span.extract(), "2");
+                    }
+                    _ => panic!("expected repetition"),
+                }
+            }
+            _ => panic!("expected cycle delay"),
+        }
+
+        // Unsupported operators are skipped and recorded as such.
+        let prop = match items[1].data {
+            ItemData::PropertyDecl(ref p) => p,
+            _ => panic!("expected property declaration"),
+        };
+        assert_eq!(prop.name.value.to_string(), "p");
+        assert!(prop.spec.clock.is_some());
+        match prop.spec.prop.data {
+            PropExprData::Unsupported(span) => {
+                assert_eq!(span.extract(), "a |=> s_eventually a")
+            }
+            _ => panic!("expected unsupported property"),
+        }
+    }
+
+    #[test]
+    fn dpi_import_with_alias() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "dpi_import.sv",
+            "module foo; import \"DPI-C\" context c_func = function int my_func(input int a); endmodule",
+            &arena,
+        )
+        .unwrap();
+        match items[0].data {
+            ItemData::DpiDecl(ref decl) => match decl.data {
+                DpiDeclData::Import {
+                    spec,
+                    ref property,
+                    cident,
+                    ref prototype,
+                } => {
+                    assert_eq!(spec.value.to_string(), "DPI-C");
+                    assert_eq!(property.as_ref().unwrap().value, DpiProperty::Context);
+                    assert_eq!(cident.unwrap().value.to_string(), "c_func");
+                    assert_eq!(prototype.name.value.to_string(), "my_func");
+                    assert_eq!(prototype.args.as_ref().unwrap().len(), 1);
+                }
+                _ => panic!("expected DPI import"),
+            },
+            _ => panic!("expected DPI declaration"),
+        }
+    }
+
+    #[test]
+    fn dpi_pure_with_output() {
+        let arena = ast::Arena::default();
+        let result = parse_items(
+            "dpi_pure.sv",
+            "module foo; import \"DPI-C\" pure function int f(input int a, output int b); endmodule",
+            &arena,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn dpi_unknown_spec() {
+        let arena = ast::Arena::default();
+        let result = parse_items(
+            "dpi_spec.sv",
+            "module foo; export \"C\" function f; endmodule",
+            &arena,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn clocking_block() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "clocking.sv",
+            "module foo;
+                clocking cb @(posedge clk);
+                    default input #1step output negedge #2;
+                    input #1step data;
+                    output drive, strobe = top.s;
+                    input posedge output #1 io;
+                endclocking : cb
+                default clocking cb;
+            endmodule",
+            &arena,
+        )
+        .unwrap();
+        let cb = match items[0].data {
+            ItemData::ClockingDecl(ref c) => c,
+            _ => panic!("expected clocking block"),
+        };
+        assert!(!cb.default);
+        assert_eq!(cb.name.unwrap().value.to_string(), "cb");
+        match cb.event {
+            EventExpr::Edge {
+                edge, ref value, ..
+            } => {
+                assert_eq!(edge, EdgeIdent::Posedge);
+                assert_eq!(value.span.extract(), "clk");
+            }
+            _ => panic!("expected edge event"),
+        }
+        match cb.default_input {
+            Some(ClockingSkew {
+                edge: EdgeIdent::Implicit,
+                delay: Some(ClockingDelay::Step(span)),
+                ..
+            }) => assert_eq!(span.extract(), "#1step"),
+            _ => panic!("expected default input skew"),
+        }
+        match cb.default_output {
+            Some(ClockingSkew {
+                edge: EdgeIdent::Negedge,
+                delay: Some(ClockingDelay::Expr(ref e)),
+                ..
+            }) => assert_eq!(e.span.extract(), "2"),
+            _ => panic!("expected default output skew"),
+        }
+        assert_eq!(cb.items.len(), 3);
+        match cb.items[0].dir {
+            ClockingDir::Input(Some(ClockingSkew {
+                delay: Some(ClockingDelay::Step(_)),
+                ..
+            })) => (),
+            _ => panic!("expected input with step skew"),
+        }
+        assert_eq!(cb.items[0].signals[0].name.value.to_string(), "data");
+        assert_eq!(cb.items[1].dir, ClockingDir::Output(None));
+        assert_eq!(cb.items[1].signals.len(), 2);
+        assert_eq!(
+            cb.items[1].signals[1].expr.as_ref().unwrap().span.extract(),
+            "top.s"
+        );
+        match cb.items[2].dir {
+            ClockingDir::InputOutput(Some(ref input), Some(ref output)) => {
+                assert_eq!(input.edge, EdgeIdent::Posedge);
+                assert_eq!(output.span.extract(), "#1");
+            }
+            _ => panic!("expected input and output"),
+        }
+        match items[1].data {
+            ItemData::DefaultClocking(name) => assert_eq!(name.value.to_string(), "cb"),
+            _ => panic!("expected default clocking"),
+        }
+    }
+
+    #[test]
+    fn duplicate_default_clocking() {
+        let arena = ast::Arena::default();
+        let result = parse_items(
+            "default_clocking_dup.sv",
+            "module foo;
+                default clocking @(posedge clk); endclocking
+                default clocking cb;
+            endmodule",
+            &arena,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn class_decl() {
+        let arena = ast::Arena::default();
+        let items = parse_items(
+            "class_decl.sv",
+            "module foo;
+                class bar #(type T = int) extends base #(T) (1);
+                    local T value = 0;
+                    virtual function T get(); return value; endfunction
+                    covergroup cg @(posedge clk); coverpoint value; endgroup : cg
+                endclass
+            endmodule",
+            &arena,
+        )
+        .unwrap();
+        let class = match items[0].data {
+            ItemData::ClassDecl(ref c) => c,
+            _ => panic!("expected class declaration"),
+        };
+        assert_eq!(class.name.value.to_string(), "bar");
+        assert_eq!(class.params.len(), 1);
+        match class.extends {
+            Some((ref base, ref args)) => {
+                assert_eq!(base.span.extract(), "base #(T)");
+                assert_eq!(args.len(), 1);
+            }
+            None => panic!("expected extends clause"),
+        }
+        assert_eq!(class.items.len(), 3);
+        match class.items[0].data {
+            ClassItemData::Property { ref ty, ref names } => {
+                assert_eq!(ty.span.extract(), "T");
+                assert_eq!(names[0].name.to_string(), "value");
+                assert!(names[0].init.is_some());
+            }
+            _ => panic!("expected class property"),
+        }
+        assert_eq!(class.items[0].qualifiers[0].0, ClassItemQualifier::Local);
+        match class.items[1].data {
+            ClassItemData::SubroutineDecl(ref decl) => {
+                assert_eq!(decl.prototype.name.value.to_string(), "get")
+            }
+            _ => panic!("expected method"),
+        }
+        assert_eq!(class.items[1].qualifiers[0].0, ClassItemQualifier::Virtual);
+        match class.items[2].data {
+            ClassItemData::Unsupported(span) => assert!(span.extract().starts_with("covergroup")),
+            _ => panic!("expected unsupported item"),
+        }
+    }
+
+    #[test]
+    fn default_nettype_directive() {
+        let arena = ast::Arena::default();
+        let source = get_source_manager().add(
+            "default_nettype.sv",
+            "module a; endmodule
+            `default_nettype none
+            module b; endmodule
+            interface c; endinterface
+            `default_nettype tri
+            module d; endmodule
+            `resetall
+            module e; endmodule",
+        );
+        let file = parse(Lexer::new(Preprocessor::new(source, &[], &[])), &arena).unwrap();
+        let nettypes: Vec<_> = file
+            .items
+            .iter()
+            .map(|item| match item.data {
+                ItemData::ModuleDecl(ref m) => m.default_nettype,
+                ItemData::InterfaceDecl(ref i) => i.default_nettype,
+                _ => panic!("expected module or interface"),
+            })
+            .collect();
+        assert_eq!(
+            nettypes,
+            vec![
+                Some(NetType::Wire),
+                None,
+                None,
+                Some(NetType::Tri),
+                Some(NetType::Wire),
+            ]
+        );
+    }
+}
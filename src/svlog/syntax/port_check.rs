@@ -0,0 +1,388 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! Lints for common mistakes in the ports of a module.
+//!
+//! The ports are first resolved to their direction, kind, and packed
+//! dimensions, following the rules for ANSI and non-ANSI port lists in §23.2
+//! of IEEE 1800-2017. The checks then flag procedural assignments to output
+//! nets, `ref` ports in static modules, `inout` variables, packed ranges that
+//! run against the module's prevailing convention, and ports whose parameter
+//! math yields a zero width.
+
+use crate::{ast::*, pretty::render_expr};
+use moore_common::{
+    errors::{DiagBuilder2, DiagCode},
+    name::Name,
+    source::Span,
+};
+
+/// Options for the port checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortCheckOptions {
+    /// Warn about packed ranges whose direction differs from the one used by
+    /// the majority of ranges in the module.
+    pub range_direction: bool,
+}
+
+impl Default for PortCheckOptions {
+    fn default() -> Self {
+        PortCheckOptions {
+            range_direction: true,
+        }
+    }
+}
+
+/// Check the ports of a module for common mistakes.
+///
+/// The `assigned_procedurally` function reports whether a port is assigned in
+/// a procedural block somewhere in the design. The `eval` function evaluates
+/// a constant expression in the dimensions of a port, or returns `None` if the
+/// expression is not constant. Every diagnostic points at the name of the
+/// offending port.
+pub fn check_ports(
+    module: &Module,
+    options: &PortCheckOptions,
+    assigned_procedurally: &dyn Fn(Name) -> bool,
+    eval: &dyn Fn(&Expr) -> Option<i64>,
+) -> Vec<DiagBuilder2> {
+    let ports = resolve_ports(module);
+    let mut diags = vec![];
+
+    for port in &ports {
+        match (port.dir, port.var) {
+            (PortDir::Output, false) if assigned_procedurally(port.name) => diags.push(
+                DiagBuilder2::error(format!(
+                    "output port `{}` is a net but is assigned procedurally",
+                    port.name
+                ))
+                .span(port.name_span)
+                .add_note("Consider declaring the port as a variable."),
+            ),
+            (PortDir::Inout, true) => diags.push(
+                DiagBuilder2::error(format!(
+                    "inout port `{}` must be a net; but is declared as variable",
+                    port.name
+                ))
+                .span(port.name_span),
+            ),
+            (PortDir::Ref, _) if module.lifetime == Lifetime::Static => diags.push(
+                DiagBuilder2::warning(format!(
+                    "ref port `{}` in {} with static lifetime",
+                    port.name,
+                    module.to_definite_string()
+                ))
+                .code(DiagCode::STATIC_REF_PORT)
+                .span(port.name_span),
+            ),
+            _ => (),
+        }
+    }
+
+    // Evaluate the packed ranges and determine the prevailing direction. A
+    // range whose bounds differ by one is ambiguous, since it may just as well
+    // be an empty range in the opposite direction.
+    let ranges: Vec<Vec<_>> = ports
+        .iter()
+        .map(|port| {
+            port.packed
+                .iter()
+                .map(|dim| match *dim {
+                    TypeDim::Range(ref msb, ref lsb) => match (eval(msb), eval(lsb)) {
+                        (Some(m), Some(l)) => Some((msb, lsb, m, l)),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect();
+    let (descending, ascending) =
+        ranges
+            .iter()
+            .flatten()
+            .flatten()
+            .fold((0, 0), |(d, a), &(_, _, m, l)| match m - l {
+                x if x > 1 => (d + 1, a),
+                x if x < -1 => (d, a + 1),
+                _ => (d, a),
+            });
+    let prevailing_descending = descending >= ascending;
+    let (prevailing, reversed) = if prevailing_descending {
+        ("descending", "ascending")
+    } else {
+        ("ascending", "descending")
+    };
+
+    for (port, ranges) in ports.iter().zip(&ranges) {
+        for (dim, range) in port.packed.iter().zip(ranges) {
+            if let TypeDim::Expr(ref size) = *dim {
+                match eval(size) {
+                    Some(s) if s <= 0 => diags.push(
+                        DiagBuilder2::warning(format!("port `{}` has zero width", port.name))
+                            .code(DiagCode::ZERO_WIDTH_PORT)
+                            .span(port.name_span)
+                            .add_note(format!(
+                                "Dimension `[{}]` evaluates to `[{}]`",
+                                render_expr(size),
+                                s
+                            )),
+                    ),
+                    _ => (),
+                }
+            }
+            let &(msb, lsb, m, l) = match range {
+                Some(range) => range,
+                None => continue,
+            };
+            let width = if prevailing_descending {
+                m - l + 1
+            } else {
+                l - m + 1
+            };
+            if width == 0 {
+                diags.push(
+                    DiagBuilder2::warning(format!("port `{}` has zero width", port.name))
+                        .code(DiagCode::ZERO_WIDTH_PORT)
+                        .span(port.name_span)
+                        .add_note(format!(
+                            "Range `[{}:{}]` evaluates to `[{}:{}]`",
+                            render_expr(msb),
+                            render_expr(lsb),
+                            m,
+                            l
+                        )),
+                );
+            } else if width < 0 && options.range_direction {
+                diags.push(
+                    DiagBuilder2::warning(format!(
+                        "packed range of port `{}` is reversed",
+                        port.name
+                    ))
+                    .code(DiagCode::REVERSED_PORT_RANGE)
+                    .span(port.name_span)
+                    .add_note(format!(
+                        "Range `[{}:{}]` is {}, but {} mostly uses {} ranges",
+                        render_expr(msb),
+                        render_expr(lsb),
+                        reversed,
+                        module.to_definite_string(),
+                        prevailing
+                    )),
+                );
+            }
+        }
+    }
+    diags
+}
+
+/// A port with its direction and kind resolved.
+struct ResolvedPort<'p, 'a> {
+    name: Name,
+    name_span: Span,
+    dir: PortDir,
+    /// Whether the port is a variable, as opposed to a net.
+    var: bool,
+    packed: &'p [TypeDim<'a>],
+}
+
+/// Resolve the direction, kind, and packed dimensions of the ports of a
+/// module. Interface ports and ports given as port expressions are skipped.
+fn resolve_ports<'p, 'a>(module: &'p Module<'a>) -> Vec<ResolvedPort<'p, 'a>> {
+    let nonansi = match module.ports.first().map(|p| &p.data) {
+        Some(PortData::Named {
+            dir: None,
+            kind: None,
+            ty,
+            expr: None,
+            ..
+        }) => ty.kind.data == ImplicitType && ty.sign == TypeSign::None && ty.dims.is_empty(),
+        Some(PortData::Explicit { dir: None, .. }) | Some(PortData::Implicit(_)) => true,
+        _ => false,
+    };
+    let mut ports = vec![];
+
+    if nonansi {
+        for item in &module.items {
+            let decl = match item.data {
+                ItemData::PortDecl(ref decl) => decl,
+                _ => continue,
+            };
+            for name in &decl.names {
+                let var = match decl.kind {
+                    Some(kind) => kind.is_var(),
+                    None => match body_decl_kind(module, name.name) {
+                        Some(var) => var,
+                        None => default_is_var(decl.dir, &decl.ty),
+                    },
+                };
+                ports.push(ResolvedPort {
+                    name: name.name,
+                    name_span: name.name_span,
+                    dir: decl.dir,
+                    var,
+                    packed: &decl.ty.dims,
+                });
+            }
+        }
+        return ports;
+    }
+
+    // ANSI ports inherit the direction, kind, and type of the preceding port
+    // if all of them are omitted, and the direction alone otherwise.
+    let mut prev: Option<(PortDir, bool, &'p [TypeDim<'a>])> = None;
+    for port in &module.ports {
+        let (dir, kind, ty, name) = match port.data {
+            PortData::Named {
+                dir,
+                kind,
+                ref ty,
+                name,
+                ..
+            } => (dir, kind, ty, name),
+            _ => {
+                prev = None;
+                continue;
+            }
+        };
+        let implicit = ty.kind.data == ImplicitType && ty.sign == TypeSign::None;
+        let (dir, var, packed) = match (dir, kind, prev) {
+            (None, None, Some(prev)) if implicit && ty.dims.is_empty() => prev,
+            _ => {
+                let dir = dir.or(prev.map(|p| p.0)).unwrap_or(PortDir::Inout);
+                let var = match kind {
+                    Some(kind) => kind.is_var(),
+                    None => default_is_var(dir, ty),
+                };
+                (dir, var, &ty.dims[..])
+            }
+        };
+        prev = Some((dir, var, packed));
+        ports.push(ResolvedPort {
+            name: name.value,
+            name_span: name.span,
+            dir,
+            var,
+            packed,
+        });
+    }
+    ports
+}
+
+/// Determine whether a port without explicit kind is a variable.
+fn default_is_var(dir: PortDir, ty: &Type) -> bool {
+    match dir {
+        PortDir::Input | PortDir::Inout => false,
+        PortDir::Output => ty.kind.data != ImplicitType,
+        PortDir::Ref => true,
+    }
+}
+
+/// Find the kind of a variable or net declared in the body of a module.
+/// Returns whether the declaration is a variable.
+fn body_decl_kind(module: &Module, name: Name) -> Option<bool> {
+    module.items.iter().find_map(|item| match item.data {
+        ItemData::VarDecl(ref decl) if decl.names.iter().any(|n| n.name == name) => Some(true),
+        ItemData::NetDecl(ref decl) if decl.names.iter().any(|n| n.name == name) => Some(false),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lexer::Lexer,
+        parser,
+        preproc::Preprocessor,
+        token::{Lit, Op},
+    };
+    use moore_common::source::get_source_manager;
+
+    /// Check the ports of a module. Parameters are evaluated to their default
+    /// value, and the port `q` is considered to be assigned procedurally.
+    fn check(name: &str, input: &str) -> Vec<String> {
+        let source = get_source_manager().add(name, input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        let params: Vec<_> = module
+            .params
+            .iter()
+            .flat_map(|p| match p.kind {
+                ParamKind::Value(ref decls) => decls.iter().collect(),
+                _ => vec![],
+            })
+            .collect();
+        fn eval(expr: &Expr, params: &[&ParamValueDecl]) -> Option<i64> {
+            match expr.data {
+                LiteralExpr(Lit::Number(v, None)) => v.as_str().parse().ok(),
+                IdentExpr(name) => params
+                    .iter()
+                    .find(|p| p.name.value == name.value)
+                    .and_then(|p| p.expr.as_ref())
+                    .and_then(|e| eval(e, params)),
+                BinaryExpr {
+                    op: Op::Sub,
+                    ref lhs,
+                    ref rhs,
+                } => Some(eval(lhs, params)? - eval(rhs, params)?),
+                _ => None,
+            }
+        }
+        check_ports(
+            module,
+            &PortCheckOptions::default(),
+            &|name| &*name.as_str() == "q",
+            &|expr| eval(expr, &params),
+        )
+        .into_iter()
+        .map(|d| d.get_message().to_string())
+        .collect()
+    }
+
+    #[test]
+    fn inout_variable() {
+        let diags = check(
+            "port_check_inout.sv",
+            "module foo (inout var logic a, inout logic b, output q); endmodule",
+        );
+        assert_eq!(
+            diags,
+            vec![
+                "inout port `a` must be a net; but is declared as variable",
+                "output port `q` is a net but is assigned procedurally",
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_width() {
+        let diags = check(
+            "port_check_zero_width.sv",
+            "module foo #(parameter N = 0) (input [7:0] a, input [N-1:0] b, [0:3] c); endmodule",
+        );
+        assert_eq!(
+            diags,
+            vec![
+                "port `b` has zero width",
+                "packed range of port `c` is reversed",
+            ]
+        );
+    }
+
+    #[test]
+    fn nonansi_ports() {
+        let diags = check(
+            "port_check_nonansi.sv",
+            "module foo (a, q); inout var a; output q; logic q; endmodule",
+        );
+        assert_eq!(
+            diags,
+            vec!["inout port `a` must be a net; but is declared as variable"]
+        );
+    }
+}
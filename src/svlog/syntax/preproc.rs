@@ -4,10 +4,11 @@
 //! tokens generated by a lexer and performs include and macro
 //! resolution.
 
+use crate::ast::NetType;
 use crate::cat::*;
-use moore_common::errors::{DiagBuilder2, DiagResult2};
+use moore_common::errors::{DiagBuilder2, DiagCode, DiagResult2};
 use moore_common::source::*;
-use std::{collections::HashMap, fmt, path::Path, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, path::Path, rc::Rc};
 
 use once_cell::sync::Lazy;
 
@@ -39,6 +40,17 @@ pub struct Preprocessor<'a> {
     defcond_stack: Vec<Defcond>,
     /// Currently enabled directives.
     dirs: Directives,
+    /// The remaining tokens of an escaped identifier or string escape sequence
+    /// that is being emitted, in reverse order. These are passed on without
+    /// being interpreted.
+    verbatim: Vec<TokenAndSpan>,
+    /// Whether the emitted tokens are inside a string literal.
+    in_string: bool,
+    /// The number of tokens emitted so far.
+    emitted: usize,
+    /// The `default_nettype` in effect from a given emitted token onwards, in
+    /// the order in which the directives were encountered.
+    nettype_changes: Vec<(usize, Option<NetType>)>,
 }
 
 impl<'a> Preprocessor<'a> {
@@ -57,10 +69,12 @@ impl<'a> Preprocessor<'a> {
                 let body = match value {
                     Some(value) => {
                         // Create dummy sources for each user defined macro.
-                        let src = get_source_manager().add_anonymous(*value);
-                        let span = Span::new(src, 0, value.len());
+                        let src = get_source_manager().add_named_virtual(
+                            &format!("<define {} from command line>", name),
+                            *value,
+                        );
                         Cat::new(Box::new(value.char_indices()))
-                            .map(|x| (x.0, span))
+                            .map(|x| (x.0, Span::new(src, x.1, x.2)))
                             .collect()
                     }
                     None => Vec::new(),
@@ -88,6 +102,30 @@ impl<'a> Preprocessor<'a> {
             include_paths: include_paths,
             defcond_stack: Vec::new(),
             dirs: Default::default(),
+            verbatim: Vec::new(),
+            in_string: false,
+            emitted: 0,
+            nettype_changes: Vec::new(),
+        }
+    }
+
+    /// Get the `default_nettype` in effect at the emitted token with the given
+    /// index, counting from zero. `None` indicates that the nettype is `none`
+    /// and implicit nets are an error.
+    pub fn default_nettype_at(&self, index: usize) -> Option<NetType> {
+        self.nettype_changes
+            .iter()
+            .rev()
+            .find(|&&(from, _)| from <= index)
+            .map(|&(_, nettype)| nettype)
+            .unwrap_or(Some(NetType::Wire))
+    }
+
+    /// Change the `default_nettype` for the tokens emitted from now on.
+    fn set_default_nettype(&mut self, nettype: Option<NetType>) {
+        self.dirs.default_nettype = nettype;
+        if self.default_nettype_at(self.emitted) != nettype {
+            self.nettype_changes.push((self.emitted, nettype));
         }
     }
 
@@ -172,7 +210,7 @@ impl<'a> Preprocessor<'a> {
                             .span(sp));
                         }
                         Some((_, sp)) => {
-                            filename.push_str(&sp.extract());
+                            sp.with_text(|s| filename.push_str(s));
                             self.bump();
                         }
                         None => {
@@ -196,7 +234,8 @@ impl<'a> Preprocessor<'a> {
                             "cannot open included file \"{}\"",
                             filename
                         ))
-                        .span(Span::union(name_p, name_q)));
+                        .span_label(Span::union(name_p, name_q), "file not found")
+                        .span_label(span, "included here"));
                     }
                 };
 
@@ -396,14 +435,39 @@ impl<'a> Preprocessor<'a> {
                         let mut replacement = Vec::<TokenAndSpan>::new();
                         // TODO: Make this work for argument names that contain
                         // underscores.
-                        for tkn in &makro.body {
-                            match *tkn {
-                                (Text, sp) => match args.get(&sp.extract()) {
+                        let mut body = makro.body.iter().peekable();
+                        while let Some(&tkn) = body.next() {
+                            match tkn {
+                                (Text, sp) => match sp.with_text(|s| args.get(s)) {
                                     Some(substitute) => {
                                         replacement.extend(substitute);
                                     }
-                                    None => replacement.push(*tkn),
+                                    None => replacement.push(tkn),
                                 },
+                                // Escaped identifiers are only substituted if
+                                // their entire name is a macro argument, as in
+                                // `\x `. The backslash is kept.
+                                (Symbol('\\'), _) => {
+                                    let mut ident = vec![tkn];
+                                    while let Some(&&x) = body.peek() {
+                                        match x.0 {
+                                            Whitespace | Newline => break,
+                                            _ => ident.push(x),
+                                        }
+                                        body.next();
+                                    }
+                                    let substitute = match ident[..] {
+                                        [_, (Text, sp)] => sp.with_text(|s| args.get(s)),
+                                        _ => None,
+                                    };
+                                    match substitute {
+                                        Some(substitute) => {
+                                            replacement.push(tkn);
+                                            replacement.extend(substitute);
+                                        }
+                                        None => replacement.extend(ident),
+                                    }
+                                }
                                 x => replacement.push(x),
                             }
                         }
@@ -434,9 +498,19 @@ impl<'a> Preprocessor<'a> {
                 return Ok(());
             }
 
+            // Expand to the line number in a dummy source, just like the
+            // values of macros defined on the command line. As with macro
+            // expansion, the token after the directive has to follow the line
+            // number.
             Directive::CurrentLine => {
                 if !self.is_inactive() {
-                    self.macro_stack.push((CatTokenKind::Digits, span));
+                    let line = get_source_manager().line_col_of(span).0;
+                    if let Some(token) = self.token {
+                        self.macro_stack.push(token);
+                    }
+                    self.macro_stack
+                        .push((CatTokenKind::Digits, line_number_span(line)));
+                    self.bump();
                 }
                 return Ok(());
             }
@@ -444,6 +518,7 @@ impl<'a> Preprocessor<'a> {
             Directive::Resetall => {
                 if !self.is_inactive() {
                     self.dirs = Default::default();
+                    self.set_default_nettype(self.dirs.default_nettype);
                 }
                 return Ok(());
             }
@@ -485,16 +560,21 @@ impl<'a> Preprocessor<'a> {
                     };
 
                     // Store the nettype in the directive set.
-                    self.dirs.default_nettype = if tkn.1.extract() == "none" {
-                        None
-                    } else {
-                        Some(tkn)
+                    let nettype = match tkn.1.with_text(parse_default_nettype) {
+                        Some(nettype) => nettype,
+                        None => {
+                            return Err(DiagBuilder2::error(format!(
+                                "unknown nettype `{}` in `default_nettype",
+                                tkn.1.extract()
+                            ))
+                            .span(tkn.1));
+                        }
                     };
+                    self.set_default_nettype(nettype);
                     debug!(
                         "Set default_nettype to `{}`",
-                        self.dirs
-                            .default_nettype
-                            .map(|(_, sp)| sp.extract())
+                        nettype
+                            .map(|nt| nt.to_string())
                             .unwrap_or_else(|| "none".to_string())
                     );
                 }
@@ -524,7 +604,7 @@ impl<'a> Preprocessor<'a> {
                         if tkn.0 == Symbol('"') {
                             break;
                         }
-                        spec.push_str(&tkn.1.extract());
+                        tkn.1.with_text(|s| spec.push_str(s));
                         self.bump();
                     }
 
@@ -614,7 +694,7 @@ impl<'a> Preprocessor<'a> {
                         if tkn.0 == Symbol('"') {
                             break;
                         }
-                        filename.push_str(&tkn.1.extract());
+                        tkn.1.with_text(|s| filename.push_str(s));
                         self.bump();
                     }
 
@@ -695,9 +775,17 @@ impl<'a> Preprocessor<'a> {
             }
         }
 
-        return Err(
-            DiagBuilder2::fatal(format!("unknown compiler directive '`{}'", dir_name)).span(span),
-        );
+        let mut d = DiagBuilder2::fatal(format!("unknown compiler directive '`{}'", dir_name))
+            .code(DiagCode::UNKNOWN_DIRECTIVE)
+            .span(span);
+        let candidates = DIRECTIVES_TABLE
+            .keys()
+            .cloned()
+            .chain(self.macro_defs.keys().map(String::as_str));
+        if let Some(suggestion) = closest_name(dir_name, candidates) {
+            d = d.add_note(format!("Did you mean '`{}'?", suggestion));
+        }
+        return Err(d);
     }
 
     fn open_include(&mut self, filename: &str, current_file: &str) -> Option<Source> {
@@ -743,7 +831,7 @@ impl<'a> Preprocessor<'a> {
         loop {
             match self.token {
                 Some((Text, sp)) | Some((Digits, sp)) | Some((Symbol('_'), sp)) => {
-                    name.push_str(&sp.extract());
+                    sp.with_text(|s| name.push_str(s));
                     span.expand(sp);
                     self.bump();
                 }
@@ -765,6 +853,38 @@ impl<'a> Preprocessor<'a> {
         }
     }
 
+    /// Consume an escaped identifier such as `\\bus[3] `, or an escape sequence
+    /// such as `\\"` if inside a string literal, starting at the current token.
+    /// The tokens are appended to `tokens` without being interpreted, such
+    /// that backticks and parentheses within them have no effect. The
+    /// whitespace terminating an escaped identifier is not consumed. Returns
+    /// whether anything was consumed.
+    fn eat_escaped(&mut self, in_string: bool, tokens: &mut Vec<TokenAndSpan>) -> bool {
+        match self.token {
+            Some(x @ (Symbol('\\'), _)) => {
+                tokens.push(x);
+                self.bump();
+            }
+            _ => return false,
+        }
+        if in_string {
+            if let Some(x) = self.token {
+                tokens.push(x);
+                self.bump();
+            }
+            return true;
+        }
+        loop {
+            match self.token {
+                Some((Whitespace, _)) | Some((Newline, _)) | None => return true,
+                Some(x) => {
+                    tokens.push(x);
+                    self.bump();
+                }
+            }
+        }
+    }
+
     // Parse the macro definition following a '`define' directive.
     fn handle_macro_definition(&mut self, define_span: Span) -> Result<Macro, DiagBuilder2> {
         let mut all_span = define_span;
@@ -800,11 +920,11 @@ impl<'a> Preprocessor<'a> {
                 }
                 // Some((Whitespace, _)) => self.bump(),
                 // Some((Comment, _)) => self.bump(),
-                Some((Symbol('\\'), _)) => {
+                Some(x @ (Symbol('\\'), _)) => {
                     self.bump();
                     match self.token {
                         Some((Newline, _)) => self.bump(),
-                        _ => (),
+                        _ => makro.body.push(x),
                     };
                 }
                 Some(x) => {
@@ -864,8 +984,23 @@ impl<'a> Preprocessor<'a> {
                     self.skip_whitespace();
                     let mut tokens = vec![];
                     let mut nesting = 0;
+                    let mut in_string = false;
                     loop {
+                        if self.eat_escaped(in_string, &mut tokens) {
+                            continue;
+                        }
                         match self.token {
+                            Some(x @ (Symbol('"'), _)) => {
+                                in_string = !in_string;
+                                all_span.expand(x.1);
+                                tokens.push(x);
+                                self.bump();
+                            }
+                            Some(x) if in_string => {
+                                all_span.expand(x.1);
+                                tokens.push(x);
+                                self.bump();
+                            }
                             Some((Symbol(','), _)) | Some((Symbol(')'), _)) if nesting == 0 => {
                                 match tokens.last() {
                                     Some((Whitespace, _)) => {
@@ -976,8 +1111,23 @@ impl<'a> Preprocessor<'a> {
         'outer: loop {
             let mut arg_tokens = Vec::<TokenAndSpan>::new();
             let mut nesting = 0;
+            let mut in_string = false;
             loop {
+                if self.eat_escaped(in_string, &mut arg_tokens) {
+                    continue;
+                }
                 match self.token {
+                    Some(x @ (Symbol('"'), _)) => {
+                        in_string = !in_string;
+                        arg_tokens.push(x);
+                        self.bump();
+                        all_span.expand(x.1);
+                    }
+                    Some(x) if in_string => {
+                        arg_tokens.push(x);
+                        self.bump();
+                        all_span.expand(x.1);
+                    }
                     Some((Symbol(','), sp)) if nesting == 0 => {
                         args.push(arg_tokens);
                         all_span.expand(sp);
@@ -1038,9 +1188,11 @@ impl<'a> Preprocessor<'a> {
                 makro.name,
                 makro.args.len()
             ))
-            .span(all_span)
-            .add_note(format!("Definition of `{}` was here:", makro.name))
-            .span(makro.span);
+            .span_label(all_span, format!("{} arguments supplied here", args.len()))
+            .span_label(
+                makro.span,
+                format!("macro declared with {} arguments here", makro.args.len()),
+            );
             return Err(d);
         }
         let args = makro
@@ -1089,8 +1241,21 @@ impl<'a> Iterator for Preprocessor<'a> {
     type Item = DiagResult2<TokenAndSpan>;
 
     fn next(&mut self) -> Option<DiagResult2<TokenAndSpan>> {
+        let next = self.next_inner();
+        if let Some(Ok(_)) = next {
+            self.emitted += 1;
+        }
+        next
+    }
+}
+
+impl<'a> Preprocessor<'a> {
+    fn next_inner(&mut self) -> Option<DiagResult2<TokenAndSpan>> {
         // In case this is the first call to next(), the token has not been
         // populated yet. In this case we need to artificially bump the lexer.
+        if let Some(tkn) = self.verbatim.pop() {
+            return Some(Ok(tkn));
+        }
         if self.token.is_none() {
             self.bump();
         }
@@ -1101,7 +1266,9 @@ impl<'a> Iterator for Preprocessor<'a> {
             // directive or we're inside an inactive `ifdef block), the loop
             // continues with the next token.
             match self.token {
-                Some((Symbol('`'), sp_backtick)) => {
+                // Backticks inside string literals are emitted like any other
+                // token below.
+                Some((Symbol('`'), sp_backtick)) if !self.in_string => {
                     self.bump(); // consume the backtick
                     if let Some((name, sp)) = self.try_eat_name() {
                         // We arrive here if the sequence a backtick
@@ -1135,7 +1302,25 @@ impl<'a> Iterator for Preprocessor<'a> {
                         .span(sp_backtick)));
                     }
                 }
+                // Escaped identifiers and escape sequences in strings are
+                // emitted as they are, without interpreting their content.
+                Some((Symbol('\\'), _)) => {
+                    let mut tokens = vec![];
+                    self.eat_escaped(self.in_string, &mut tokens);
+                    if !self.is_inactive() {
+                        self.verbatim.extend(tokens.into_iter().rev());
+                        return self.verbatim.pop().map(Ok);
+                    }
+                }
                 _ => {
+                    // Keep track of string literals, which end at the latest
+                    // at the end of the line.
+                    match self.token {
+                        Some((Symbol('"'), _)) => self.in_string = !self.in_string,
+                        Some((Newline, _)) => self.in_string = false,
+                        _ => (),
+                    }
+
                     // All tokens other than preprocessor directives are
                     // emitted, unless we're currently inside a disabled define
                     // conditional.
@@ -1213,6 +1398,57 @@ impl fmt::Display for Directive {
     }
 }
 
+/// Find the candidate closest to a name that is not known.
+///
+/// Returns the candidate with the smallest edit distance to `name`, if that
+/// distance is at most a third of the length of `name`. Names with two or
+/// fewer characters never produce a suggestion.
+/// Obtain a span that contains a line number, for the expansion of
+/// `` `__LINE__ ``.
+///
+/// Each line number is added to the source manager as a dummy source once,
+/// such that expanding the directive on the same line again, in this or any
+/// other file, reuses that source.
+fn line_number_span(line: u32) -> Span {
+    thread_local!(static SOURCES: RefCell<HashMap<u32, Source>> = RefCell::new(HashMap::new()));
+    let text = line.to_string();
+    let source = SOURCES.with(|sources| {
+        *sources.borrow_mut().entry(line).or_insert_with(|| {
+            get_source_manager().add_named_virtual("<builtin macro __LINE__>", text.as_str())
+        })
+    });
+    Span::new(source, 0, text.len())
+}
+
+fn closest_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let len = name.chars().count();
+    if len <= 2 {
+        return None;
+    }
+    let threshold = std::cmp::max(1, len / 3);
+    candidates
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|&(d, _)| d <= threshold)
+        .min()
+        .map(|(_, c)| c)
+}
+
+/// Compute the Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let subst = diag + (ca != cb) as usize;
+            diag = row[j + 1];
+            row[j + 1] = subst.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
 static DIRECTIVES_TABLE: Lazy<HashMap<&'static str, Directive>> = Lazy::new(|| {
     let mut table = HashMap::new();
     table.insert("include", Directive::Include);
@@ -1271,14 +1507,44 @@ enum Defcond {
     Disabled,
 }
 
-#[derive(Default)]
 struct Directives {
     celldefine: bool,
-    default_nettype: Option<TokenAndSpan>,
+    /// The nettype of implicit nets, or `None` if they are an error.
+    default_nettype: Option<NetType>,
     keywords: Vec<KeywordsDirective>,
     unconnected_drive: Option<UnconnectedDrive>,
 }
 
+impl Default for Directives {
+    fn default() -> Self {
+        Directives {
+            celldefine: false,
+            default_nettype: Some(NetType::Wire),
+            keywords: Vec::new(),
+            unconnected_drive: None,
+        }
+    }
+}
+
+/// Parse the argument of a `default_nettype directive. Returns `Some(None)`
+/// for `none`, and `None` if the argument is not a valid nettype.
+fn parse_default_nettype(text: &str) -> Option<Option<NetType>> {
+    Some(Some(match text {
+        "none" => return Some(None),
+        "wire" => NetType::Wire,
+        "tri" => NetType::Tri,
+        "tri0" => NetType::Tri0,
+        "tri1" => NetType::Tri1,
+        "wand" => NetType::WireAnd,
+        "triand" => NetType::TriAnd,
+        "wor" => NetType::WireOr,
+        "trior" => NetType::TriOr,
+        "trireg" => NetType::TriReg,
+        "uwire" => NetType::Uwire,
+        _ => return None,
+    }))
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Debug)]
 enum KeywordsDirective {
@@ -1313,6 +1579,7 @@ impl KeywordsDirective {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use moore_common::errors::DiagSegment;
 
     fn preproc(input: &str) -> Preprocessor {
         use std::cell::Cell;
@@ -1343,6 +1610,71 @@ mod tests {
         assert_eq!(actual, &[Text, Newline, Text, Newline, Newline, Text,]);
     }
 
+    #[test]
+    fn current_line() {
+        check_str("a\r\n`__LINE__ b\n\n`__LINE__", "a\r\n2 b\n\n4");
+    }
+
+    #[test]
+    fn current_line_reuses_source() {
+        let sm = get_source_manager();
+        let a = sm.add("current_line_a.sv", "`__LINE__ `__LINE__\n`__LINE__");
+        let b = sm.add("current_line_b.sv", "`__LINE__");
+        let spans: Vec<_> = Preprocessor::new(a, &[], &[])
+            .chain(Preprocessor::new(b, &[], &[]))
+            .map(|x| x.unwrap())
+            .filter(|x| x.0 == Digits)
+            .map(|x| x.1)
+            .collect();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[0], spans[1]);
+        assert_eq!(spans[0], spans[3]);
+        assert_ne!(spans[0].source, spans[2].source);
+        assert_eq!(spans[2].extract(), "2");
+    }
+
+    #[test]
+    fn include_overlay() {
+        let sm = get_source_manager();
+        sm.add(
+            "overlay/top.sv",
+            "`include \"defs.svh\"\nlogic [`WIDTH-1:0] x;\n",
+        );
+        let expand = || -> String {
+            Preprocessor::new(sm.open("overlay/top.sv").unwrap(), &[], &[])
+                .map(|x| x.unwrap().1.extract())
+                .collect()
+        };
+        let first = sm.add_overlay("overlay/defs.svh", "`define WIDTH 8\n");
+        assert_eq!(expand(), "\nlogic [8-1:0] x;\n");
+        sm.add_overlay("overlay/defs.svh", "`define WIDTH 16\n");
+        assert_eq!(expand(), "\nlogic [16-1:0] x;\n");
+        assert_eq!(first.extract(14, 15), "8");
+    }
+
+    #[test]
+    fn mapped_file() {
+        let text = format!(
+            "`define W 8\n{}",
+            "module foo;\n  logic [`W-1:0] 老虎;\nendmodule\n".repeat(100)
+        );
+        let sm = get_source_manager();
+        let expand = |name: &str, threshold| -> String {
+            let path = std::env::temp_dir().join(name);
+            std::fs::write(&path, &text).unwrap();
+            sm.set_mmap_threshold(threshold);
+            let source = sm.open(path.to_str().unwrap()).unwrap();
+            Preprocessor::new(source, &[], &[])
+                .map(|x| x.unwrap().1.extract())
+                .collect()
+        };
+        let mapped = expand("moore-test-preproc-mapped.sv", 0);
+        let read = expand("moore-test-preproc-read.sv", usize::MAX);
+        sm.set_mmap_threshold(DEFAULT_MMAP_THRESHOLD);
+        assert_eq!(mapped, read);
+        assert!(mapped.contains("logic [8-1:0] 老虎;"));
+    }
+
     #[test]
     fn include_and_define() {
         let sm = get_source_manager();
@@ -1381,6 +1713,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn macro_too_many_args_labels() {
+        let err = preproc("`define foo(x) x\n`foo(1, 2)\n")
+            .find_map(|x| x.err())
+            .expect("expected an error");
+        let labels: Vec<_> = err
+            .get_segments()
+            .iter()
+            .map(|s| match s {
+                DiagSegment::Label(sp, text) => (sp.extract(), text.as_str()),
+                _ => panic!("expected only labels"),
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("`foo(1, 2)".to_string(), "2 arguments supplied here"),
+                ("foo".to_string(), "macro declared with 1 arguments here"),
+            ]
+        );
+    }
+
+    /// Get the notes of the error raised while preprocessing the input.
+    fn error_notes(input: &str) -> Vec<String> {
+        preproc(input)
+            .find_map(|x| x.err())
+            .expect("expected an error")
+            .get_segments()
+            .iter()
+            .filter_map(|s| match s {
+                DiagSegment::Note(note) => Some(note.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn suggest_directive() {
+        assert_eq!(
+            error_notes("`includ \"foo.sv\"\n"),
+            vec!["Did you mean '`include'?"]
+        );
+    }
+
+    #[test]
+    fn suggest_macro() {
+        assert_eq!(
+            error_notes("`define WIDTH 8\nlogic [`WDTH-1:0] x;\n"),
+            vec!["Did you mean '`WIDTH'?"]
+        );
+    }
+
+    #[test]
+    fn suggest_nothing_for_short_names() {
+        assert!(error_notes("`define AB 1\n`AC\n").is_empty());
+        assert!(error_notes("`frobnicate\n").is_empty());
+    }
+
     /// Verify that macros that take no arguments but have parantheses around
     /// their body parse properly.
     #[test]
@@ -1395,4 +1785,52 @@ mod tests {
     fn macro_name_with_digits_and_underscores() {
         check_str("`define AXI_BUS21_SV 42\n`AXI_BUS21_SV", "42");
     }
+
+    #[test]
+    fn escaped_ident() {
+        check_str(
+            "`define FOO 1\nassign \\a`FOO(  = `FOO;\n",
+            "assign \\a`FOO(  = 1;\n",
+        );
+    }
+
+    #[test]
+    fn escaped_ident_macro_arg() {
+        check_str(
+            "`define ID(x) x\n`ID(\\b`x) )\n`ID(\"a\\\")\")\n",
+            "\\b`x)\n\"a\\\")\"\n",
+        );
+    }
+
+    #[test]
+    fn escaped_ident_substitution() {
+        check_str(
+            "`define SIG(x) \\x \\x.y \\x\n`SIG(bus[3])\n",
+            "\\bus[3] \\x.y \\bus[3]\n",
+        );
+    }
+
+    #[test]
+    fn strings() {
+        check_str(
+            "`define ID(x) x\n$display(\"`include //x\", `ID(\"a, `b\"));\n",
+            "$display(\"`include //x\", \"a, `b\");\n",
+        );
+    }
+
+    #[test]
+    fn command_line_define_source() {
+        use moore_common::errors::{DiagRenderer, HumanRenderer};
+        let source = get_source_manager().add("cmdline_define.sv", "logic [`WIDTH:0] x;\n");
+        let defines = [("WIDTH", Some("7 + `bogus"))];
+        let err = Preprocessor::new(source, &[], &defines)
+            .find_map(|x| x.err())
+            .expect("expected an error");
+        let text = HumanRenderer { color: false }.render(&err);
+        assert!(
+            text.contains("--> <define WIDTH from command line>:1:5-11:"),
+            "{}",
+            text
+        );
+    }
 }
@@ -0,0 +1,1604 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! A pretty printer that renders the AST back into SystemVerilog source text.
+//!
+//! The printer produces deterministic output that re-parses to an equivalent
+//! AST. Declarations, ports, parameters, types, generate constructs,
+//! instantiations, and expressions are rendered from their AST representation.
+//! Expressions are printed with the minimal parentheses required by operator
+//! precedence and associativity. Procedures, subroutines, and statements are
+//! laid out one statement per line at the configured indentation. Constructs
+//! without a dedicated rendering, such as assertions and covergroups, are
+//! reproduced verbatim from the source they were parsed from.
+
+use crate::{
+    ast::*,
+    token::{Associativity, Lit, Precedence},
+};
+
+/// A pretty printer for the AST.
+///
+/// ```ignore
+/// let mut printer = Printer::new().indent(4).width(100);
+/// printer.print_source_file(&file);
+/// println!("{}", printer.finish());
+/// ```
+pub struct Printer {
+    /// Number of spaces per indentation level.
+    indent: usize,
+    /// Maximum line width before lists are broken across lines.
+    width: usize,
+    /// Whether ports print the direction, kind, and type they inherit from
+    /// the preceding port.
+    explicit_ports: bool,
+    /// The current indentation level.
+    level: usize,
+    /// The text printed so far.
+    out: String,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Printer {
+    /// Create a new printer with an indentation of 2 and a line width of 80.
+    pub fn new() -> Self {
+        Printer {
+            indent: 2,
+            width: 80,
+            explicit_ports: false,
+            level: 0,
+            out: String::new(),
+        }
+    }
+
+    /// Set the number of spaces per indentation level.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Set the line width beyond which lists are broken across lines.
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Print the direction, kind, and type that ports inherit from the
+    /// preceding port explicitly, instead of omitting them.
+    pub fn explicit_ports(mut self, explicit_ports: bool) -> Self {
+        self.explicit_ports = explicit_ports;
+        self
+    }
+
+    /// Consume the printer and return the printed text.
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    /// Print an entire source file.
+    pub fn print_source_file(&mut self, file: &SourceFile) {
+        if file.timeunits.unit.is_some() || file.timeunits.prec.is_some() {
+            self.print_timeunit(&file.timeunits);
+        }
+        self.print_items(&file.items);
+    }
+
+    /// Print a sequence of items.
+    pub fn print_items(&mut self, items: &[Item]) {
+        for item in items {
+            self.print_item(item);
+        }
+    }
+
+    /// Print an item.
+    pub fn print_item(&mut self, item: &Item) {
+        match item.data {
+            ItemData::Dummy => (),
+            ItemData::ModuleDecl(ref x) => self.print_module(x),
+            ItemData::ExternModuleDecl(ref x) => self.print_extern_module(x),
+            ItemData::InterfaceDecl(ref x) => self.print_interface(x),
+            ItemData::PackageDecl(ref x) => self.print_package(x),
+            ItemData::ProgramDecl(ref x) => self.print_program(x),
+            ItemData::CheckerDecl(ref x) => self.print_checker(x),
+            ItemData::ClassDecl(ref x) => self.print_class(x),
+            ItemData::ImportDecl(ref x) => {
+                let s = render_import(x);
+                self.line(&s);
+            }
+            ItemData::ParamDecl(ref x) => {
+                for s in render_param_decl(x) {
+                    self.line(&format!("{};", s));
+                }
+            }
+            ItemData::Typedef(ref x) => {
+                let s = render_typedef(x);
+                self.line(&s);
+            }
+            ItemData::PortDecl(ref x) => {
+                let mut s = format!("{}", x.dir);
+                if let Some(kind) = x.kind {
+                    s.push_str(&format!(" {}", kind));
+                }
+                push_word(&mut s, &render_type(&x.ty));
+                self.list(&format!("{} ", s), &render_var_names(&x.names), ";");
+            }
+            ItemData::Defparam(ref x) => {
+                let assigns: Vec<_> = x
+                    .assignments
+                    .iter()
+                    .map(|(name, value)| format!("{} = {}", name, render_expr(value)))
+                    .collect();
+                self.list("defparam ", &assigns, ";");
+            }
+            ItemData::ContAssign(ref x)
+                if x.strength.is_none() && x.delay.is_none() && x.delay_control.is_none() =>
+            {
+                let assigns: Vec<_> = x
+                    .assignments
+                    .iter()
+                    .map(|(lhs, rhs)| format!("{} = {}", render_expr(lhs), render_expr(rhs)))
+                    .collect();
+                self.list("assign ", &assigns, ";");
+            }
+            ItemData::GenvarDecl(ref decls) => {
+                let names: Vec<_> = decls
+                    .iter()
+                    .map(|d| match d.init {
+                        Some(ref init) => format!("{} = {}", d.name.value, render_expr(init)),
+                        None => format!("{}", d.name.value),
+                    })
+                    .collect();
+                self.list("genvar ", &names, ";");
+            }
+            ItemData::GenerateRegion(_, ref items) => {
+                self.line("generate");
+                self.level += 1;
+                self.print_items(items);
+                self.level -= 1;
+                self.line("endgenerate");
+            }
+            ItemData::GenerateFor(ref x) => {
+                let init = x.init.span.extract();
+                let s = format!(
+                    "for ({}; {}; {})",
+                    init.trim_end().trim_end_matches(';'),
+                    render_expr(&x.cond),
+                    render_expr(&x.step)
+                );
+                self.print_generate_block(&s, &x.block);
+            }
+            ItemData::GenerateIf(ref x) => {
+                let s = format!("if ({})", render_expr(&x.cond));
+                self.print_generate_block(&s, &x.main_block);
+                if let Some(ref block) = x.else_block {
+                    self.print_generate_block("else", block);
+                }
+            }
+            ItemData::NetDecl(ref x) if x.strength.is_none() && x.delay.is_none() => {
+                let mut s = format!("{}", x.net_type);
+                match x.kind {
+                    NetKind::Vectored => s.push_str(" vectored"),
+                    NetKind::Scalared => s.push_str(" scalared"),
+                    NetKind::None => (),
+                }
+                push_word(&mut s, &render_type(&x.ty));
+                self.list(&format!("{} ", s), &render_var_names(&x.names), ";");
+            }
+            ItemData::VarDecl(ref x) => {
+                let s = render_var_decl_type(x);
+                self.list(&format!("{} ", s), &render_var_names(&x.names), ";");
+            }
+            ItemData::Inst(ref x) => self.print_inst(x),
+            ItemData::Timeunit(ref x) => self.print_timeunit(x),
+            ItemData::Procedure(ref x) => self.print_procedure(x),
+            ItemData::SubroutineDecl(ref x) => self.print_subroutine(String::new(), x),
+            _ => self.verbatim("", item.span),
+        }
+    }
+
+    /// Print a module.
+    pub fn print_module(&mut self, module: &Module) {
+        let mut head = format!("module {}", render_lifetime(&module.lifetime));
+        head.push_str(&format!("{}", module.name.value));
+        for import in &module.imports {
+            head.push_str(&format!(" {}", render_import(import)));
+        }
+        self.print_header(head, &module.params, &module.ports);
+        self.print_body(&module.items, "endmodule");
+    }
+
+    /// Print an extern module declaration.
+    pub fn print_extern_module(&mut self, module: &ExternModule) {
+        let mut head = format!("extern module {}", render_lifetime(&module.lifetime));
+        head.push_str(&format!("{}", module.name.value));
+        for import in &module.imports {
+            head.push_str(&format!(" {}", render_import(import)));
+        }
+        self.print_header(head, &module.params, &module.ports);
+    }
+
+    /// Print an interface.
+    pub fn print_interface(&mut self, intf: &Interface) {
+        let head = format!(
+            "interface {}{}",
+            render_lifetime(&intf.lifetime),
+            intf.name.value
+        );
+        self.print_header(head, &intf.params, &intf.ports);
+        self.print_body(&intf.items, "endinterface");
+    }
+
+    /// Print a program.
+    pub fn print_program(&mut self, prog: &Program) {
+        let mut head = format!("program {}", render_lifetime(&prog.lifetime));
+        head.push_str(&format!("{}", prog.name.value));
+        for import in &prog.imports {
+            head.push_str(&format!(" {}", render_import(import)));
+        }
+        self.print_header(head, &prog.params, &prog.ports);
+        self.print_body(&prog.items, "endprogram");
+    }
+
+    /// Print a checker.
+    pub fn print_checker(&mut self, checker: &Checker) {
+        let head = format!("checker {}", checker.name.value);
+        self.print_header(head, &[], &checker.ports);
+        self.print_body(&checker.items, "endchecker");
+    }
+
+    /// Print a package.
+    pub fn print_package(&mut self, pkg: &Package) {
+        let head = format!(
+            "package {}{};",
+            render_lifetime(&pkg.lifetime),
+            pkg.name.value
+        );
+        self.line(&head);
+        self.print_body(&pkg.items, "endpackage");
+    }
+
+    /// Print a class.
+    pub fn print_class(&mut self, class: &ClassDecl) {
+        let mut head = String::new();
+        if class.virt {
+            head.push_str("virtual ");
+        }
+        head.push_str(&format!(
+            "class {}{}",
+            render_lifetime(&class.lifetime),
+            class.name.value
+        ));
+        let params: Vec<_> = class.params.iter().flat_map(render_param_decl).collect();
+        if !params.is_empty() {
+            head.push_str(&format!(" #({})", params.join(", ")));
+        }
+        if let Some((ref base, ref args)) = class.extends {
+            head.push_str(&format!(" extends {}", render_type(base)));
+            if !args.is_empty() {
+                let args: Vec<_> = args.iter().map(render_call_arg).collect();
+                head.push_str(&format!("({})", args.join(", ")));
+            }
+        }
+        if !class.impls.is_empty() {
+            let impls: Vec<_> = class.impls.iter().map(|i| format!("{}", i.value)).collect();
+            head.push_str(&format!(" implements {}", impls.join(", ")));
+        }
+        head.push(';');
+        self.line(&head);
+        self.level += 1;
+        for item in &class.items {
+            self.print_class_item(item);
+        }
+        self.level -= 1;
+        self.line("endclass");
+    }
+
+    /// Print an item of a class.
+    fn print_class_item(&mut self, item: &ClassItem) {
+        match item.data {
+            ClassItemData::Null => (),
+            ClassItemData::Property { ref ty, ref names } => {
+                let mut s = String::new();
+                for &(qualifier, _) in &item.qualifiers {
+                    push_word(&mut s, render_class_item_qualifier(qualifier));
+                }
+                push_word(&mut s, &render_type(ty));
+                self.list(&format!("{} ", s), &render_var_names(names), ";");
+            }
+            ClassItemData::ParamDecl(ref x) => {
+                for s in render_param_decl(x) {
+                    self.line(&format!("{};", s));
+                }
+            }
+            ClassItemData::Typedef(ref x) => {
+                let s = render_typedef(x);
+                self.line(&s);
+            }
+            ClassItemData::SubroutineDecl(ref x) => {
+                let mut s = String::new();
+                for &(qualifier, _) in &item.qualifiers {
+                    push_word(&mut s, render_class_item_qualifier(qualifier));
+                }
+                self.print_subroutine(s, x);
+            }
+            _ => self.verbatim("", item.span),
+        }
+    }
+
+    /// Print the parameter and port lists of a module, interface, program, or
+    /// checker header.
+    fn print_header(&mut self, head: String, params: &[ParamDecl], ports: &[Port]) {
+        let params: Vec<_> = params.iter().flat_map(render_param_decl).collect();
+        let ports = self.render_ports(ports);
+        let mut inline = head.clone();
+        if !params.is_empty() {
+            inline.push_str(&format!(" #({})", params.join(", ")));
+        }
+        if !ports.is_empty() {
+            inline.push_str(&format!(" ({})", ports.join(", ")));
+        }
+        inline.push(';');
+        if self.fits(&inline) {
+            self.line(&inline);
+            return;
+        }
+
+        // Break the parameter and port lists across lines.
+        let mut open = head;
+        if !params.is_empty() {
+            open.push_str(" #(");
+            self.broken_list(&open, &params);
+            open = String::from(")");
+        }
+        if !ports.is_empty() {
+            open.push_str(" (");
+            self.broken_list(&open, &ports);
+            open = String::from(")");
+        }
+        open.push(';');
+        self.line(&open);
+    }
+
+    /// Print the items of a module, interface, or package, followed by the
+    /// closing keyword.
+    fn print_body(&mut self, items: &[Item], end: &str) {
+        self.level += 1;
+        self.print_items(items);
+        self.level -= 1;
+        self.line(end);
+    }
+
+    /// Print a generate block, preceded by the construct that introduces it.
+    fn print_generate_block(&mut self, head: &str, block: &GenerateBlock) {
+        match block.label {
+            Some(label) => self.line(&format!("{} begin : {}", head, label.value)),
+            None => self.line(&format!("{} begin", head)),
+        }
+        self.level += 1;
+        self.print_items(&block.items);
+        self.level -= 1;
+        self.line("end");
+    }
+
+    /// Print an instantiation.
+    fn print_inst(&mut self, inst: &Inst) {
+        let mut head = format!("{}", inst.target.value);
+        if !inst.params.is_empty() {
+            let params: Vec<_> = inst.params.iter().map(render_param_assignment).collect();
+            head.push_str(&format!(" #({})", params.join(", ")));
+        }
+        for (i, name) in inst.names.iter().enumerate() {
+            let conns: Vec<_> = name.conns.iter().map(render_port_conn).collect();
+            let open = format!(
+                "{} {}{} (",
+                if i == 0 { head.as_str() } else { "" },
+                name.name.value,
+                render_dims(&name.dims)
+            );
+            let open = open.trim_start();
+            let close = if i + 1 == inst.names.len() {
+                ");"
+            } else {
+                "),"
+            };
+            let inline = format!("{}{}{}", open, conns.join(", "), close);
+            if i > 0 {
+                self.level += 1;
+            }
+            if conns.is_empty() || self.fits(&inline) {
+                self.line(&inline);
+            } else {
+                self.broken_list(open, &conns);
+                self.line(close);
+            }
+            if i > 0 {
+                self.level -= 1;
+            }
+        }
+    }
+
+    /// Print a procedure.
+    pub fn print_procedure(&mut self, prc: &Procedure) {
+        let keyword = match prc.kind {
+            ProcedureKind::Initial => "initial",
+            ProcedureKind::Always => "always",
+            ProcedureKind::AlwaysComb => "always_comb",
+            ProcedureKind::AlwaysLatch => "always_latch",
+            ProcedureKind::AlwaysFf => "always_ff",
+            ProcedureKind::Final => "final",
+        };
+        self.print_stmt(keyword, &prc.stmt);
+    }
+
+    /// Print a function or task declaration, preceded by its qualifiers.
+    fn print_subroutine(&mut self, head: String, decl: &SubroutineDecl) {
+        self.verbatim(&head, decl.prototype.span);
+        self.level += 1;
+        for item in &decl.items {
+            match *item {
+                SubroutineItem::PortDecl(ref x) => {
+                    let mut s = format!("{}", x.dir);
+                    if x.var {
+                        s.push_str(" var");
+                    }
+                    push_word(&mut s, &render_type(&x.ty));
+                    self.list(&format!("{} ", s), &render_var_names(&x.names), ";");
+                }
+                SubroutineItem::Stmt(ref x) => self.print_stmt("", x),
+            }
+        }
+        self.level -= 1;
+        match decl.prototype.kind {
+            SubroutineKind::Func => self.line("endfunction"),
+            SubroutineKind::Task => self.line("endtask"),
+        }
+    }
+
+    /// Print a statement, preceded by the construct that introduces it, such
+    /// as `always_ff @(posedge clk)` or `else`.
+    ///
+    /// Blocks open and simple statements continue on the line of the
+    /// construct. Other statements are indented on the lines below it, except
+    /// for an `if` following an `else`.
+    pub fn print_stmt(&mut self, head: &str, stmt: &Stmt) {
+        let mut head = String::from(head);
+        let (open, stmts, close) = match stmt.kind {
+            SequentialBlock(ref stmts) => ("begin", stmts, "end"),
+            ParallelBlock(ref stmts, JoinKind::All) => ("fork", stmts, "join"),
+            ParallelBlock(ref stmts, JoinKind::Any) => ("fork", stmts, "join_any"),
+            ParallelBlock(ref stmts, JoinKind::None) => ("fork", stmts, "join_none"),
+            _ => {
+                let label = match stmt.label {
+                    Some(label) => format!("{}:", label),
+                    None => String::new(),
+                };
+                if let Some(s) = render_simple_stmt(stmt) {
+                    push_word(&mut head, &label);
+                    if s == ";" {
+                        head.push(';');
+                    } else {
+                        push_word(&mut head, &s);
+                    }
+                    self.line(&head);
+                    return;
+                }
+                let chained = match stmt.kind {
+                    TimedStmt(..) => true,
+                    IfStmt { .. } => head.is_empty() || head == "else",
+                    _ => head.is_empty(),
+                };
+                if chained {
+                    push_word(&mut head, &label);
+                    self.print_compound_stmt(head, stmt);
+                } else {
+                    self.line(&head);
+                    self.level += 1;
+                    self.print_compound_stmt(label, stmt);
+                    self.level -= 1;
+                }
+                return;
+            }
+        };
+        push_word(&mut head, open);
+        if let Some(label) = stmt.label {
+            head.push_str(&format!(" : {}", label));
+        }
+        self.line(&head);
+        self.level += 1;
+        for stmt in stmts {
+            self.print_stmt("", stmt);
+        }
+        self.level -= 1;
+        self.line(close);
+    }
+
+    /// Print a statement that is neither a block nor fits on a single line.
+    fn print_compound_stmt(&mut self, mut head: String, stmt: &Stmt) {
+        match stmt.kind {
+            TimedStmt(ref tc, ref inner) => match render_timing_control(tc) {
+                Some(tc) => {
+                    push_word(&mut head, &tc);
+                    self.print_stmt(&head, inner);
+                }
+                None => self.verbatim(&head, stmt.span),
+            },
+            IfStmt {
+                up,
+                ref cond,
+                ref main_stmt,
+                ref else_stmt,
+            } => {
+                push_word(&mut head, render_unique_priority(up));
+                push_word(&mut head, &format!("if ({})", render_expr(cond)));
+                self.print_stmt(&head, main_stmt);
+                if let Some(ref else_stmt) = else_stmt {
+                    self.print_stmt("else", else_stmt);
+                }
+            }
+            CaseStmt {
+                up,
+                kind,
+                ref expr,
+                mode,
+                ref items,
+            } => {
+                push_word(&mut head, render_unique_priority(up));
+                let keyword = match kind {
+                    CaseKind::Normal => "case",
+                    CaseKind::DontCareZ => "casez",
+                    CaseKind::DontCareXZ => "casex",
+                };
+                push_word(&mut head, &format!("{} ({})", keyword, render_expr(expr)));
+                match mode {
+                    CaseMode::Normal => (),
+                    CaseMode::Inside => head.push_str(" inside"),
+                    CaseMode::Pattern => head.push_str(" matches"),
+                }
+                self.line(&head);
+                self.level += 1;
+                for item in items {
+                    match *item {
+                        CaseItem::Default(ref stmt) => self.print_stmt("default:", stmt),
+                        CaseItem::Expr(ref exprs, ref stmt) => {
+                            let exprs: Vec<_> = exprs.iter().map(render_expr).collect();
+                            self.print_stmt(&format!("{}:", exprs.join(", ")), stmt);
+                        }
+                        CaseItem::Inside(ref ranges, ref stmt) => {
+                            let ranges: Vec<_> = ranges.iter().map(render_value_range).collect();
+                            self.print_stmt(&format!("{}:", ranges.join(", ")), stmt);
+                        }
+                    }
+                }
+                self.level -= 1;
+                self.line("endcase");
+            }
+            ForeverStmt(ref body) => {
+                push_word(&mut head, "forever");
+                self.print_stmt(&head, body);
+            }
+            RepeatStmt(ref expr, ref body) => {
+                push_word(&mut head, &format!("repeat ({})", render_expr(expr)));
+                self.print_stmt(&head, body);
+            }
+            WhileStmt(ref expr, ref body) => {
+                push_word(&mut head, &format!("while ({})", render_expr(expr)));
+                self.print_stmt(&head, body);
+            }
+            DoStmt(ref body, ref expr) => {
+                push_word(&mut head, "do");
+                self.print_stmt(&head, body);
+                self.line(&format!("while ({});", render_expr(expr)));
+            }
+            ForStmt(ref init, ref cond, ref step, ref body) => match render_simple_stmt(init) {
+                Some(init) => {
+                    push_word(
+                        &mut head,
+                        &format!(
+                            "for ({} {}; {})",
+                            init,
+                            render_expr(cond),
+                            render_expr(step)
+                        ),
+                    );
+                    self.print_stmt(&head, body);
+                }
+                None => self.verbatim(&head, stmt.span),
+            },
+            ForeachStmt(ref expr, ref vars, ref body) => {
+                let len = vars.iter().map(|v| v.index + 1).max().unwrap_or(0);
+                let mut names = vec![String::new(); len];
+                for var in vars {
+                    names[var.index] = format!("{}", var.name.value);
+                }
+                push_word(
+                    &mut head,
+                    &format!("foreach ({}[{}])", render_expr(expr), names.join(", ")),
+                );
+                self.print_stmt(&head, body);
+            }
+            WaitExprStmt(ref expr, ref body) => {
+                push_word(&mut head, &format!("wait ({})", render_expr(expr)));
+                self.print_stmt(&head, body);
+            }
+            _ => self.verbatim(&head, stmt.span),
+        }
+    }
+
+    /// Print a `timeunit` or `timeprecision` declaration.
+    fn print_timeunit(&mut self, tu: &Timeunit) {
+        match (&tu.unit, &tu.prec) {
+            (Some(unit), Some(prec)) => self.line(&format!(
+                "timeunit {} / {};",
+                unit.span.extract(),
+                prec.span.extract()
+            )),
+            (Some(unit), None) => self.line(&format!("timeunit {};", unit.span.extract())),
+            (None, Some(prec)) => self.line(&format!("timeprecision {};", prec.span.extract())),
+            (None, None) => (),
+        }
+    }
+
+    /// Render the ports of a module or interface.
+    pub(crate) fn render_ports(&self, ports: &[Port]) -> Vec<String> {
+        let mut carry_dir = PortDir::Inout;
+        let mut carry_kind = None;
+        let mut carry_ty = None;
+        let mut rendered = vec![];
+        for port in ports {
+            let s = match port.data {
+                PortData::Intf {
+                    modport,
+                    name,
+                    ref dims,
+                    ref expr,
+                } => {
+                    let mut s = String::from("interface");
+                    if let Some(modport) = modport {
+                        s.push_str(&format!(".{}", modport.value));
+                    }
+                    s.push_str(&format!(" {}{}", name.value, render_dims(dims)));
+                    push_init(&mut s, expr);
+                    s
+                }
+                PortData::Explicit {
+                    dir,
+                    name,
+                    ref expr,
+                } => {
+                    let mut s = String::new();
+                    if let Some(dir) = dir {
+                        s.push_str(&format!("{} ", dir));
+                    }
+                    s.push_str(&format!(".{}(", name.value));
+                    if let Some(ref expr) = expr {
+                        s.push_str(&render_expr(expr));
+                    }
+                    s.push(')');
+                    s
+                }
+                PortData::Named {
+                    dir,
+                    kind,
+                    ref ty,
+                    name,
+                    ref dims,
+                    ref expr,
+                } => {
+                    // Mirror the inheritance rules of the port list: an
+                    // explicit direction resets the carried kind and type, and
+                    // a missing type inherits the previous one.
+                    let inherits_ty = ty.kind.data == ImplicitType
+                        && ty.sign == TypeSign::None
+                        && ty.dims.is_empty();
+                    if let Some(dir) = dir {
+                        carry_dir = dir;
+                        carry_kind = None;
+                        carry_ty = None;
+                    }
+                    carry_kind = kind.or(carry_kind);
+                    if !inherits_ty {
+                        carry_ty = Some(render_type(ty));
+                    }
+                    let mut s = String::new();
+                    if self.explicit_ports {
+                        s.push_str(&format!("{}", carry_dir));
+                        if let Some(kind) = carry_kind {
+                            push_word(&mut s, &format!("{}", kind));
+                        }
+                        if let Some(ref ty) = carry_ty {
+                            push_word(&mut s, ty);
+                        }
+                    } else {
+                        if let Some(dir) = dir {
+                            s.push_str(&format!("{}", dir));
+                        }
+                        if let Some(kind) = kind {
+                            push_word(&mut s, &format!("{}", kind));
+                        }
+                        push_word(&mut s, &render_type(ty));
+                    }
+                    push_word(&mut s, &format!("{}{}", name.value, render_dims(dims)));
+                    push_init(&mut s, expr);
+                    s
+                }
+                PortData::Implicit(ref expr) => render_expr(expr),
+            };
+            rendered.push(s);
+        }
+        rendered
+    }
+
+    /// Print a comma-separated list, inline if it fits the line width, or
+    /// broken across lines otherwise.
+    fn list(&mut self, open: &str, items: &[String], close: &str) {
+        let inline = format!("{}{}{}", open, items.join(", "), close);
+        if self.fits(&inline) {
+            self.line(&inline);
+        } else {
+            self.broken_list(open.trim_end(), items);
+            self.line(close);
+        }
+    }
+
+    /// Print a list with one item per line, indented below `open`.
+    fn broken_list(&mut self, open: &str, items: &[String]) {
+        self.line(open);
+        self.level += 1;
+        for (i, item) in items.iter().enumerate() {
+            if i + 1 == items.len() {
+                self.line(item);
+            } else {
+                self.line(&format!("{},", item));
+            }
+        }
+        self.level -= 1;
+    }
+
+    /// Reproduce the source text of a span, re-indented to the current level
+    /// and preceded by `head` on its first line.
+    ///
+    /// Continuation lines keep their indentation relative to the least
+    /// indented one, which is usually the closing keyword.
+    fn verbatim(&mut self, head: &str, span: moore_common::source::Span) {
+        let text = span.extract();
+        let mut lines = text.lines();
+        let first = match lines.next() {
+            Some(first) => first,
+            None => return,
+        };
+        let rest: Vec<_> = lines.collect();
+        let strip = rest
+            .iter()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let mut first_line = String::from(head);
+        push_word(&mut first_line, first.trim());
+        self.line(&first_line);
+        for line in rest {
+            self.line(line.get(strip..).unwrap_or("").trim_end());
+        }
+    }
+
+    /// Check whether a line fits the configured width at the current level.
+    fn fits(&self, line: &str) -> bool {
+        self.level * self.indent + line.len() <= self.width
+    }
+
+    /// Emit a line at the current indentation level.
+    fn line(&mut self, line: &str) {
+        if !line.is_empty() {
+            for _ in 0..self.level * self.indent {
+                self.out.push(' ');
+            }
+            self.out.push_str(line);
+        }
+        self.out.push('\n');
+    }
+}
+
+/// Append a word to a string, separated by a space if necessary.
+fn push_word(s: &mut String, word: &str) {
+    if word.is_empty() {
+        return;
+    }
+    if !s.is_empty() {
+        s.push(' ');
+    }
+    s.push_str(word);
+}
+
+/// Append an optional initializer to a string.
+fn push_init(s: &mut String, expr: &Option<Expr>) {
+    if let Some(ref expr) = *expr {
+        s.push_str(&format!(" = {}", render_expr(expr)));
+    }
+}
+
+/// Render a lifetime as a header prefix.
+fn render_lifetime(lifetime: &Lifetime) -> &'static str {
+    match lifetime {
+        Lifetime::Static => "",
+        Lifetime::Automatic => "automatic ",
+    }
+}
+
+/// Render an import declaration, including the trailing semicolon.
+fn render_import(decl: &ImportDecl) -> String {
+    let items: Vec<_> = decl
+        .items
+        .iter()
+        .map(|item| match item.name {
+            Some(name) => format!("{}::{}", item.pkg.value, name.value),
+            None => format!("{}::*", item.pkg.value),
+        })
+        .collect();
+    format!("import {};", items.join(", "))
+}
+
+/// Render a parameter declaration as one entry per declared parameter.
+pub(crate) fn render_param_decl(decl: &ParamDecl) -> Vec<String> {
+    let keyword = if decl.local {
+        "localparam"
+    } else {
+        "parameter"
+    };
+    match decl.kind {
+        ParamKind::Type(ref decls) => decls
+            .iter()
+            .map(|d| match d.ty {
+                Some(ref ty) => format!("{} type {} = {}", keyword, d.name.value, render_type(ty)),
+                None => format!("{} type {}", keyword, d.name.value),
+            })
+            .collect(),
+        ParamKind::Value(ref decls) => decls
+            .iter()
+            .map(|d| {
+                let mut s = String::from(keyword);
+                push_word(&mut s, &render_type(&d.ty));
+                push_word(&mut s, &format!("{}{}", d.name.value, render_dims(&d.dims)));
+                push_init(&mut s, &d.expr);
+                s
+            })
+            .collect(),
+    }
+}
+
+/// Render the qualifiers and type of a variable declaration.
+fn render_var_decl_type(decl: &VarDecl) -> String {
+    let mut s = String::new();
+    if decl.konst {
+        push_word(&mut s, "const");
+    }
+    if decl.var {
+        push_word(&mut s, "var");
+    }
+    match decl.lifetime {
+        Some(Lifetime::Static) => push_word(&mut s, "static"),
+        Some(Lifetime::Automatic) => push_word(&mut s, "automatic"),
+        None => (),
+    }
+    push_word(&mut s, &render_type(&decl.ty));
+    s
+}
+
+/// Render a type definition, including the trailing semicolon.
+fn render_typedef(def: &Typedef) -> String {
+    format!(
+        "typedef {} {}{};",
+        render_type(&def.ty),
+        def.name.value,
+        render_dims(&def.dims)
+    )
+}
+
+/// Render a qualifier of a class item.
+fn render_class_item_qualifier(qualifier: ClassItemQualifier) -> &'static str {
+    match qualifier {
+        ClassItemQualifier::Static => "static",
+        ClassItemQualifier::Protected => "protected",
+        ClassItemQualifier::Local => "local",
+        ClassItemQualifier::Rand => "rand",
+        ClassItemQualifier::Randc => "randc",
+        ClassItemQualifier::Pure => "pure",
+        ClassItemQualifier::Virtual => "virtual",
+        ClassItemQualifier::Const => "const",
+    }
+}
+
+/// Render an argument of a call or constructor.
+fn render_call_arg(arg: &CallArg) -> String {
+    let value = arg.expr.as_ref().map(render_expr).unwrap_or_default();
+    match arg.name {
+        Some(name) => format!(".{}({})", name.value, value),
+        None => value,
+    }
+}
+
+/// Render the names of a variable, net, or port declaration.
+fn render_var_names(names: &[VarDeclName]) -> Vec<String> {
+    names
+        .iter()
+        .map(|n| {
+            let mut s = format!("{}{}", n.name, render_dims(&n.dims));
+            push_init(&mut s, &n.init);
+            s
+        })
+        .collect()
+}
+
+/// Render a parameter assignment of an instantiation.
+fn render_param_assignment(assign: &ParamAssignment) -> String {
+    let value = match assign.expr {
+        TypeOrExpr::Type(ty) => render_type(ty),
+        TypeOrExpr::Expr(expr) => render_expr(expr),
+    };
+    match assign.name {
+        Some(ref name) => format!(".{}({})", name.name, value),
+        None => value,
+    }
+}
+
+/// Render a port connection of an instantiation.
+fn render_port_conn(conn: &PortConn) -> String {
+    match conn.data {
+        PortConnData::Auto => String::from(".*"),
+        PortConnData::Named(name, PortConnMode::Auto) => format!(".{}", name.value),
+        PortConnData::Named(name, PortConnMode::Unconnected) => format!(".{}()", name.value),
+        PortConnData::Named(name, PortConnMode::Connected(ref expr)) => {
+            format!(".{}({})", name.value, render_expr(expr))
+        }
+        PortConnData::Positional(ref expr) => render_expr(expr),
+    }
+}
+
+/// Render a statement that fits on a single line, including the trailing
+/// semicolon, or `None` if the statement spans multiple lines.
+fn render_simple_stmt(stmt: &Stmt) -> Option<String> {
+    Some(match stmt.kind {
+        NullStmt => String::from(";"),
+        BlockingAssignStmt {
+            ref lhs,
+            ref rhs,
+            op,
+        } => format!(
+            "{} {} {};",
+            render_expr(lhs),
+            render_assign_op(op),
+            render_expr(rhs)
+        ),
+        NonblockingAssignStmt {
+            ref lhs,
+            ref rhs,
+            ref delay,
+            ..
+        } => match delay {
+            Some(delay) => format!(
+                "{} <= {} {};",
+                render_expr(lhs),
+                render_delay_control(delay),
+                render_expr(rhs)
+            ),
+            None => format!("{} <= {};", render_expr(lhs), render_expr(rhs)),
+        },
+        ExprStmt(ref expr) => format!("{};", render_expr(expr)),
+        VarDeclStmt(ref decl) => {
+            let names = render_var_names(&decl.names);
+            format!("{} {};", render_var_decl_type(decl), names.join(", "))
+        }
+        GenvarDeclStmt(ref decls) => {
+            let names: Vec<_> = decls
+                .iter()
+                .map(|d| match d.init {
+                    Some(ref init) => format!("{} = {}", d.name.value, render_expr(init)),
+                    None => format!("{}", d.name.value),
+                })
+                .collect();
+            format!("genvar {};", names.join(", "))
+        }
+        ContinueStmt => String::from("continue;"),
+        BreakStmt => String::from("break;"),
+        ReturnStmt(None) => String::from("return;"),
+        ReturnStmt(Some(ref expr)) => format!("return {};", render_expr(expr)),
+        ImportStmt(ref decl) => render_import(decl),
+        WaitForkStmt => String::from("wait fork;"),
+        DisableForkStmt => String::from("disable fork;"),
+        DisableStmt(name) => format!("disable {};", name),
+        _ => return None,
+    })
+}
+
+/// Render the `unique`, `unique0`, or `priority` keyword of a statement.
+fn render_unique_priority(up: Option<UniquePriority>) -> &'static str {
+    match up {
+        Some(UniquePriority::Unique) => "unique",
+        Some(UniquePriority::Unique0) => "unique0",
+        Some(UniquePriority::Priority) => "priority",
+        None => "",
+    }
+}
+
+/// Render a timing control, or `None` if it cannot be rendered.
+fn render_timing_control(tc: &TimingControl) -> Option<String> {
+    match *tc {
+        TimingControl::Delay(ref dc) => Some(render_delay_control(dc)),
+        TimingControl::Event(ref ec) => Some(match ec.data {
+            EventControlData::Implicit => String::from("@(*)"),
+            EventControlData::Expr(ref expr) => format!("@({})", render_event_expr(expr)),
+        }),
+        TimingControl::Cycle(_) => None,
+    }
+}
+
+/// Render a delay control, such as `#1ns` or `#(a + b)`.
+fn render_delay_control(dc: &DelayControl) -> String {
+    match dc.expr.data {
+        LiteralExpr(_) | IdentExpr(_) | MinTypMaxExpr { .. } => {
+            format!("#{}", render_expr(&dc.expr))
+        }
+        _ => format!("#({})", render_expr(&dc.expr)),
+    }
+}
+
+/// Render an event expression, such as `posedge clk or negedge rst`.
+fn render_event_expr(expr: &EventExpr) -> String {
+    match *expr {
+        EventExpr::Edge {
+            edge, ref value, ..
+        } => {
+            let value = render_expr(value);
+            match edge {
+                EdgeIdent::Implicit => value,
+                EdgeIdent::Edge => format!("edge {}", value),
+                EdgeIdent::Posedge => format!("posedge {}", value),
+                EdgeIdent::Negedge => format!("negedge {}", value),
+            }
+        }
+        EventExpr::Iff {
+            ref expr, ref cond, ..
+        } => format!("{} iff {}", render_event_expr(expr), render_expr(cond)),
+        EventExpr::Or {
+            ref lhs, ref rhs, ..
+        } => format!("{} or {}", render_event_expr(lhs), render_event_expr(rhs)),
+    }
+}
+
+/// Render a value or range of an `inside` set.
+fn render_value_range(range: &ValueRange) -> String {
+    match range {
+        ValueRange::Single(e) => render_expr(e),
+        ValueRange::Range { lo, hi, .. } => format!("[{}:{}]", render_expr(lo), render_expr(hi)),
+    }
+}
+
+/// Render a type, including its sign and packed dimensions.
+///
+/// Implicit types without sign or dimensions render as the empty string.
+pub fn render_type(ty: &Type) -> String {
+    let mut s = render_type_kind(&ty.kind);
+    match ty.sign {
+        TypeSign::Signed if ty.kind.data != ImplicitSignedType => push_word(&mut s, "signed"),
+        TypeSign::Unsigned if ty.kind.data != ImplicitUnsignedType => push_word(&mut s, "unsigned"),
+        _ => (),
+    }
+    push_word(&mut s, &render_dims(&ty.dims));
+    s
+}
+
+/// Render a type without its sign and packed dimensions.
+fn render_type_kind(kind: &TypeKind) -> String {
+    let s = match kind.data {
+        ImplicitType => "",
+        ImplicitSignedType => "signed",
+        ImplicitUnsignedType => "unsigned",
+        VoidType => "void",
+        NamedType(name) => return format!("{}", name.value),
+        StringType => "string",
+        ChandleType => "chandle",
+        EventType => "event",
+        MailboxType => "mailbox",
+        BitType => "bit",
+        LogicType => "logic",
+        RegType => "reg",
+        ByteType => "byte",
+        ShortIntType => "shortint",
+        IntType => "int",
+        IntegerType => "integer",
+        LongIntType => "longint",
+        TimeType => "time",
+        ShortRealType => "shortreal",
+        RealType => "real",
+        RealtimeType => "realtime",
+        ScopedType {
+            ref ty,
+            member,
+            name,
+        } => {
+            let sep = if member { "." } else { "::" };
+            return format!("{}{}{}", render_type(ty), sep, name.value);
+        }
+        EnumType(ref e) => {
+            let mut s = String::from("enum");
+            if let Some(ref base) = e.base_type {
+                push_word(&mut s, &render_type(base));
+            }
+            let variants: Vec<_> = e
+                .variants
+                .iter()
+                .map(|v| {
+                    let mut s = format!("{}", v.name.value);
+                    if let Some(ref range) = v.range {
+                        s.push_str(&render_dim(range));
+                    }
+                    push_init(&mut s, &v.value);
+                    s
+                })
+                .collect();
+            return format!("{} {{ {} }}", s, variants.join(", "));
+        }
+        StructType(ref st) => {
+            let mut s = format!("{}", st.kind);
+            if st.packed {
+                s.push_str(" packed");
+            }
+            match st.signing {
+                TypeSign::Signed => s.push_str(" signed"),
+                TypeSign::Unsigned => s.push_str(" unsigned"),
+                TypeSign::None => (),
+            }
+            let members: Vec<_> = st
+                .members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "{} {};",
+                        render_type(&m.ty),
+                        render_var_names(&m.names).join(", ")
+                    )
+                })
+                .collect();
+            return format!("{} {{ {} }}", s, members.join(" "));
+        }
+        SpecializedType(ref ty, ref params) => {
+            let params: Vec<_> = params.iter().map(render_param_assignment).collect();
+            return format!("{} #({})", render_type(ty), params.join(", "));
+        }
+        _ => return kind.span.extract(),
+    };
+    String::from(s)
+}
+
+/// Render an expression.
+///
+/// Parentheses are only emitted where the operator precedence and
+/// associativity require them for the text to parse back into the same tree.
+pub fn render_expr(expr: &Expr) -> String {
+    match expr.data {
+        LiteralExpr(ref lit) => render_lit(lit),
+        IdentExpr(name) => format!("{}", name.value),
+        SysIdentExpr(name) => format!("${}", name.value),
+        ThisExpr => String::from("this"),
+        DollarExpr => String::from("$"),
+        NullExpr => String::from("null"),
+        EmptyQueueExpr => String::from("{}"),
+        ScopeExpr(ref inner, name) => {
+            format!(
+                "{}::{}",
+                render_lhs(inner, leading_suffix(expr)),
+                name.value
+            )
+        }
+        MemberExpr {
+            expr: ref inner,
+            name,
+        } => format!("{}.{}", render_lhs(inner, leading_suffix(expr)), name.value),
+        IndexExpr {
+            ref indexee,
+            ref index,
+        } => format!(
+            "{}[{}]",
+            render_lhs(indexee, leading_suffix(expr)),
+            render_expr(index)
+        ),
+        CallExpr(ref callee, ref args) => format!(
+            "{}({})",
+            render_lhs(callee, leading_suffix(expr)),
+            render_call_args(args)
+        ),
+        UnaryExpr {
+            op,
+            expr: ref inner,
+            postfix: true,
+        } => format!("{}{}", render_lhs(inner, leading_suffix(expr)), op),
+        UnaryExpr {
+            op,
+            expr: ref inner,
+            postfix: false,
+        } => {
+            let operand = render_operand(inner, Precedence::Unary);
+            // Keep adjacent operators apart, such that `-(-a)` does not turn
+            // into the decrement `--a`.
+            if operand.starts_with(|c| "+-!~&|^".contains(c)) {
+                format!("{}({})", op, operand)
+            } else {
+                format!("{}{}", op, operand)
+            }
+        }
+        BinaryExpr {
+            op,
+            ref lhs,
+            ref rhs,
+        } => format!(
+            "{} {} {}",
+            render_lhs(lhs, leading_suffix(expr)),
+            op,
+            render_operand(rhs, trailing_precedence(expr).unwrap_or(Precedence::Max))
+        ),
+        TernaryExpr {
+            ref cond,
+            ref true_expr,
+            ref false_expr,
+        } => format!(
+            "{} ? {} : {}",
+            render_lhs(cond, leading_suffix(expr)),
+            render_operand(true_expr, Precedence::Implication),
+            render_operand(false_expr, Precedence::Implication)
+        ),
+        AssignExpr {
+            op,
+            ref lhs,
+            ref rhs,
+        } => format!(
+            "{} {} {}",
+            render_lhs(lhs, leading_suffix(expr)),
+            render_assign_op(op),
+            render_operand(rhs, Precedence::Assignment)
+        ),
+        InsideExpr(ref inner, ref ranges) => {
+            let ranges: Vec<_> = ranges.iter().map(render_value_range).collect();
+            format!(
+                "{} inside {{{}}}",
+                render_lhs(inner, leading_suffix(expr)),
+                ranges.join(", ")
+            )
+        }
+        ConcatExpr {
+            ref repeat,
+            ref exprs,
+        } => {
+            let exprs: Vec<_> = exprs.iter().map(render_expr).collect();
+            match repeat {
+                Some(repeat) => format!("{{{}{{{}}}}}", render_expr(repeat), exprs.join(", ")),
+                None => format!("{{{}}}", exprs.join(", ")),
+            }
+        }
+        MinTypMaxExpr {
+            ref min,
+            ref typ,
+            ref max,
+        } => format!(
+            "({}:{}:{})",
+            render_expr(min),
+            render_expr(typ),
+            render_expr(max)
+        ),
+        RangeExpr {
+            mode,
+            ref lhs,
+            ref rhs,
+        } => {
+            let sep = match mode {
+                RangeMode::Absolute => ":",
+                RangeMode::RelativeUp => "+:",
+                RangeMode::RelativeDown => "-:",
+            };
+            format!("{}{}{}", render_expr(lhs), sep, render_expr(rhs))
+        }
+        CastExpr(ref ty, ref inner) => match inner.data {
+            PatternExpr(..) => format!("{}{}", render_type(ty), render_expr(inner)),
+            _ => format!("{}'({})", render_type(ty), render_expr(inner)),
+        },
+        CastSignExpr(sign, ref inner) => {
+            let sign = match sign.value {
+                TypeSign::Signed => "signed",
+                TypeSign::Unsigned => "unsigned",
+                TypeSign::None => "",
+            };
+            format!("{}'({})", sign, render_expr(inner))
+        }
+        CastSizeExpr(ref size, ref inner) => format!(
+            "{}'({})",
+            render_lhs(size, leading_suffix(expr)),
+            render_expr(inner)
+        ),
+        TypeExpr(ref ty) => render_type(ty),
+        ConstructorCallExpr(ref args) => format!("new({})", render_call_args(args)),
+        ClassNewExpr(None) => String::from("new"),
+        ClassNewExpr(Some(ref inner)) => format!("new {}", render_expr(inner)),
+        ArrayNewExpr(ref size, ref init) => match init {
+            Some(init) => format!("new[{}]({})", render_expr(size), render_expr(init)),
+            None => format!("new[{}]", render_expr(size)),
+        },
+        BitsExpr { name, ref arg } => {
+            let arg = match *arg {
+                TypeOrExpr::Type(ty) => render_type(ty),
+                TypeOrExpr::Expr(expr) => render_expr(expr),
+            };
+            format!("${}({})", name.value, arg)
+        }
+        _ => expr.span.extract(),
+    }
+}
+
+/// How an operator attaches to the expression on its left: the operator's
+/// precedence, and whether the parser accepts the operator when parsing at
+/// exactly that precedence.
+#[derive(Clone, Copy)]
+struct Suffix(Precedence, bool);
+
+impl Suffix {
+    /// Check whether the parser consumes the operator while parsing an
+    /// expression at a given precedence.
+    fn accepted_at(self, prec: Precedence) -> bool {
+        prec < self.0 || (self.1 && prec == self.0)
+    }
+}
+
+/// Determine how the outermost operator of an expression attaches.
+fn leading_suffix(expr: &Expr) -> Suffix {
+    let inclusive = match expr.data {
+        BinaryExpr { .. } | TernaryExpr { .. } => false,
+        _ => true,
+    };
+    Suffix(expr.precedence(), inclusive)
+}
+
+/// Determine the precedence at which the parser parses the rightmost operand
+/// of an expression, or `None` if the expression ends in a closing token.
+fn trailing_precedence(expr: &Expr) -> Option<Precedence> {
+    match expr.data {
+        BinaryExpr { op, .. } => Some(match op.get_associativity() {
+            Associativity::Right => Precedence::Assignment,
+            _ => op.get_precedence(),
+        }),
+        UnaryExpr { postfix: false, .. } => Some(Precedence::Unary),
+        TernaryExpr { .. } => Some(Precedence::Implication),
+        AssignExpr { .. } => Some(Precedence::Assignment),
+        _ => None,
+    }
+}
+
+/// Render an operand that is parsed at a given precedence, parenthesized if
+/// the parser would otherwise stop short of its outermost operator.
+fn render_operand(expr: &Expr, prec: Precedence) -> String {
+    let s = render_expr(expr);
+    if leading_suffix(expr).accepted_at(prec) {
+        s
+    } else {
+        format!("({})", s)
+    }
+}
+
+/// Render the operand on the left of an operator, parenthesized if it binds
+/// more loosely than the operator, or its rightmost operand would otherwise
+/// absorb the operator.
+fn render_lhs(expr: &Expr, op: Suffix) -> String {
+    let s = render_expr(expr);
+    let absorbs = trailing_precedence(expr).map_or(false, |prec| op.accepted_at(prec));
+    if absorbs || leading_suffix(expr).0 < op.0 {
+        format!("({})", s)
+    } else {
+        s
+    }
+}
+
+/// Render a literal.
+fn render_lit(lit: &Lit) -> String {
+    match *lit {
+        Lit::Str(value) => format!(
+            "\"{}\"",
+            value.as_str().replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        Lit::BasedInteger(size, signed, base, value) => format!(
+            "{}'{}{}{}",
+            size.map(|s| s.to_string()).unwrap_or_default(),
+            if signed { "s" } else { "" },
+            base,
+            value
+        ),
+        Lit::UnbasedUnsized(c) => format!("'{}", c),
+        Lit::Number(int, None) => format!("{}", int),
+        Lit::Number(int, Some(frac)) => format!("{}.{}", int, frac),
+        Lit::Time(int, None, unit) => format!("{}{}", int, unit.as_str()),
+        Lit::Time(int, Some(frac), unit) => format!("{}.{}{}", int, frac, unit.as_str()),
+    }
+}
+
+/// Render an assignment operator.
+fn render_assign_op(op: AssignOp) -> &'static str {
+    match op {
+        AssignOp::Identity => "=",
+        AssignOp::Add => "+=",
+        AssignOp::Sub => "-=",
+        AssignOp::Mul => "*=",
+        AssignOp::Div => "/=",
+        AssignOp::Mod => "%=",
+        AssignOp::BitAnd => "&=",
+        AssignOp::BitOr => "|=",
+        AssignOp::BitXor => "^=",
+        AssignOp::LogicShL => "<<=",
+        AssignOp::LogicShR => ">>=",
+        AssignOp::ArithShL => "<<<=",
+        AssignOp::ArithShR => ">>>=",
+    }
+}
+
+/// Render the arguments of a call.
+fn render_call_args(args: &[CallArg]) -> String {
+    let args: Vec<_> = args.iter().map(render_call_arg).collect();
+    args.join(", ")
+}
+
+/// Render a list of dimensions.
+fn render_dims(dims: &[TypeDim]) -> String {
+    dims.iter().map(render_dim).collect()
+}
+
+/// Render a single dimension.
+pub(crate) fn render_dim(dim: &TypeDim) -> String {
+    match *dim {
+        TypeDim::Expr(ref expr) => format!("[{}]", render_expr(expr)),
+        TypeDim::Range(ref lhs, ref rhs) => {
+            format!("[{}:{}]", render_expr(lhs), render_expr(rhs))
+        }
+        TypeDim::Queue(None) => format!("[$]"),
+        TypeDim::Queue(Some(ref expr)) => format!("[$:{}]", render_expr(expr)),
+        TypeDim::Unsized => format!("[]"),
+        TypeDim::Associative(None) => format!("[*]"),
+        TypeDim::Associative(Some(ref ty)) => format!("[{}]", render_type(ty)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    fn parse<'a>(input: &str, arena: &'a Arena<'a>) -> SourceFile<'a> {
+        use std::cell::Cell;
+        thread_local!(static INDEX: Cell<usize> = Cell::new(0));
+        let idx = INDEX.with(|i| {
+            let v = i.get();
+            i.set(v + 1);
+            v
+        });
+        let source = get_source_manager().add(&format!("pretty_{}.sv", idx), input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        parser::parse(Lexer::new(preproc), arena).unwrap()
+    }
+
+    fn print_with(input: &str, printer: Printer) -> String {
+        let arena = Arena::default();
+        let file = parse(input, &arena);
+        let mut printer = printer;
+        printer.print_source_file(&file);
+        printer.finish()
+    }
+
+    fn check(input: &str, printer: impl Fn() -> Printer, expected: &str) {
+        let actual = print_with(input, printer());
+        assert_eq!(actual, expected);
+        // The printed text must re-parse and print identically.
+        assert_eq!(print_with(&actual, printer()), actual);
+        // Unless ports are made explicit, the printed text must describe the
+        // same tree as the input.
+        if !printer().explicit_ports {
+            let arena = Arena::default();
+            assert!(parse(input, &arena).spanless_eq(&parse(&actual, &arena)));
+        }
+    }
+
+    #[test]
+    fn module_with_params() {
+        check(
+            "module foo #(parameter int N = 4, type T = logic) (input T [N-1:0] a, output b); localparam M = N * 2; endmodule",
+            || Printer::new().width(120),
+            "module foo #(parameter int N = 4, parameter type T = logic) (input T [N - 1:0] a, output b);\n  localparam M = N * 2;\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn mixed_ports_with_dims() {
+        let input = "module bar (input logic [7:0] a, b [2], output wire c, inout d); endmodule";
+        check(
+            input,
+            || Printer::new().width(40),
+            "module bar (\n  input logic [7:0] a,\n  b[2],\n  output wire c,\n  inout d\n);\nendmodule\n",
+        );
+        check(
+            input,
+            || Printer::new().indent(4).width(40).explicit_ports(true),
+            "module bar (\n    input logic [7:0] a,\n    input logic [7:0] b[2],\n    output wire c,\n    inout d\n);\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn generate_block() {
+        check(
+            "module baz; genvar i; for (i = 0; i < 4; i++) begin : g logic [i:0] x; assign x = '0; end if (1) begin foo #(.N(2)) u0 (.a(x), .b()); end else begin end endmodule",
+            Printer::new,
+            "module baz;\n  genvar i;\n  for (i = 0; i < 4; i++) begin : g\n    logic [i:0] x;\n    assign x = '0;\n  end\n  if (1) begin\n    foo #(.N(2)) u0 (.a(x), .b());\n  end\n  else begin\n  end\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn package_and_procedure() {
+        check(
+            "package p; typedef enum logic [1:0] { A, B = 2 } e_t; endpackage\nmodule q import p::*; (input clk);\n    always_ff @(posedge clk) begin\n        x <= y;\n    end\nendmodule",
+            Printer::new,
+            "package p;\n  typedef enum logic [1:0] { A, B = 2 } e_t;\nendpackage\nmodule q import p::*; (input clk);\n  always_ff @(posedge clk) begin\n    x <= y;\n  end\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn statements() {
+        check(
+            "module s; always @(posedge clk or negedge rst_n) if (!rst_n) q <= 0; else if (en) begin : upd q <= #1 d; cnt += 1; end else q <= q; \
+             always_comb unique case (sel) 2'd0, 2'd1: y = a; default: begin y = b; end endcase \
+             initial begin for (int i = 0; i < 4; i++) foreach (m[j, k]) m[j][k] = i; while (x) x--; do @(*) x = 1; while (y); repeat (3) #5; forever fork wait (z); l: disable l; join_none end endmodule",
+            || Printer::new().indent(4),
+            "module s;\n    always @(posedge clk or negedge rst_n)\n        if (!rst_n) q <= 0;\n        else if (en) begin : upd\n            q <= #1 d;\n            cnt += 1;\n        end\n        else q <= q;\n    always_comb\n        unique case (sel)\n            2'd0, 2'd1: y = a;\n            default: begin\n                y = b;\n            end\n        endcase\n    initial begin\n        for (int i = 0; i < 4; i++)\n            foreach (m[j, k]) m[j][k] = i;\n        while (x) x--;\n        do @(*) x = 1;\n        while (y);\n        repeat (3) #5;\n        forever fork\n            wait (z);\n            l: disable l;\n        join_none\n    end\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn subroutine() {
+        check(
+            "module f; task automatic t; input logic [3:0] a, b; output c; c = a < b; endtask function int g(int x); return x + 1; endfunction endmodule",
+            Printer::new,
+            "module f;\n  task automatic t;\n    input logic [3:0] a, b;\n    output c;\n    c = a < b;\n  endtask\n  function int g(int x);\n    return x + 1;\n  endfunction\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn program_and_checker() {
+        check(
+            "program automatic tb #(N = 2) (input clk); logic [N-1:0] x; initial x = 0; endprogram\nchecker c (a, b); logic y; endchecker",
+            Printer::new,
+            "program automatic tb #(parameter N = 2) (input clk);\n  logic [N - 1:0] x;\n  initial x = 0;\nendprogram\nchecker c (a, b);\n  logic y;\nendchecker\n",
+        );
+    }
+
+    #[test]
+    fn class_decl() {
+        check(
+            "class foo #(int N = 1) extends base #(N) (N, 2); rand logic [N-1:0] data = 0, mask; virtual function int get(); return data; endfunction covergroup cg; endgroup endclass",
+            Printer::new,
+            "class foo #(parameter int N = 1) extends base #(N)(N, 2);\n  rand logic [N - 1:0] data = 0, mask;\n  virtual function int get();\n    return data;\n  endfunction\n  covergroup cg; endgroup\nendclass\n",
+        );
+    }
+
+    #[test]
+    fn expr_precedence() {
+        check(
+            "module m; assign x = ((a - (b - c)) * (-(d + e)) ** 2) ? (f ? g : h) : (i ? j : (k << (l & m))); assign y = -(-a[0]) + (b ? c : d)[1]; assign z = {2{a, b[3+:4]}} inside {1, [2:3]} || int'(p.q) == 8'hFF; endmodule",
+            || Printer::new().width(120),
+            "module m;\n  assign x = (a - (b - c)) * -(d + e) ** 2 ? f ? g : h : i ? j : k << (l & m);\n  assign y = -(-a[0]) + (b ? c : d)[1];\n  assign z = {2{a, b[3+:4]}} inside {1, [2:3]} || int'(p.q) == 8'hFF;\nendmodule\n",
+        );
+    }
+
+    #[test]
+    fn defparam_paths() {
+        check(
+            "module top; defparam u0.u1[2].WIDTH = 8, u2.DEPTH = 4; endmodule",
+            Printer::new,
+            "module top;\n  defparam u0.u1[2].WIDTH = 8, u2.DEPTH = 4;\nendmodule\n",
+        );
+    }
+}
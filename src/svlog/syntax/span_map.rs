@@ -0,0 +1,151 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! A side table that maps AST node IDs to their spans and parents.
+//!
+//! Every AST node carries a unique `NodeId` allocated at construction. The
+//! `SpanMap` records the span and parent of each node in a tree, such that
+//! later stages and tools can map an ID back to a location in the source
+//! without holding on to the node itself.
+
+use crate::ast::{AnyNode, Visitor};
+use moore_common::{
+    id::NodeId,
+    source::{Source, Span},
+};
+use std::collections::HashMap;
+
+/// A table of node spans and parent links.
+#[derive(Debug, Default, Clone)]
+pub struct SpanMap {
+    entries: HashMap<NodeId, Entry>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    span: Span,
+    parent: Option<NodeId>,
+}
+
+impl SpanMap {
+    /// Build the span map for a node and all its descendants.
+    pub fn build<'a>(node: &'a dyn AnyNode<'a>) -> Self {
+        let mut builder = Builder {
+            map: Default::default(),
+            stack: vec![],
+        };
+        builder.pre_visit_node(node);
+        node.accept(&mut builder);
+        builder.post_visit_node(node);
+        builder.map
+    }
+
+    /// Get the span of a node.
+    pub fn span_of(&self, id: NodeId) -> Option<Span> {
+        self.entries.get(&id).map(|e| e.span)
+    }
+
+    /// Get the parent of a node.
+    ///
+    /// Returns `None` for the root node and for nodes not in the map.
+    pub fn parent_of(&self, id: NodeId) -> Option<NodeId> {
+        self.entries.get(&id).and_then(|e| e.parent)
+    }
+
+    /// Find the innermost node whose span covers a byte offset in a source.
+    ///
+    /// If multiple nodes share the innermost span, the most deeply nested one
+    /// is returned.
+    pub fn find_node_at(&self, source: Source, offset: usize) -> Option<NodeId> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| {
+                e.span.source == source && e.span.begin <= offset && offset < e.span.end
+            })
+            .min_by_key(|(&id, e)| (e.span.end - e.span.begin, std::cmp::Reverse(self.depth(id))))
+            .map(|(&id, _)| id)
+    }
+
+    /// Iterate over the nodes in the map and their spans, in no particular
+    /// order.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, Span)> + '_ {
+        self.entries.iter().map(|(&id, e)| (id, e.span))
+    }
+
+    /// Get the number of nodes in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Compute the number of ancestors of a node.
+    fn depth(&self, mut id: NodeId) -> usize {
+        let mut depth = 0;
+        while let Some(parent) = self.parent_of(id) {
+            id = parent;
+            depth += 1;
+        }
+        depth
+    }
+}
+
+/// A visitor that records the span and parent of every node.
+struct Builder {
+    map: SpanMap,
+    stack: Vec<NodeId>,
+}
+
+impl<'a> Visitor<'a> for Builder {
+    fn pre_visit_node(&mut self, node: &'a dyn AnyNode<'a>) -> bool {
+        let entry = Entry {
+            span: node.span(),
+            parent: self.stack.last().cloned(),
+        };
+        self.map.entries.insert(node.id(), entry);
+        self.stack.push(node.id());
+        true
+    }
+
+    fn post_visit_node(&mut self, _node: &'a dyn AnyNode<'a>) {
+        self.stack.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::*, lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    #[test]
+    fn port_name_lookup() {
+        let input = "module foo (input logic a, output logic bar);\nendmodule\n";
+        let source = get_source_manager().add("span_map.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        let map = SpanMap::build(&file);
+
+        // An offset inside the port name `bar` resolves to the port.
+        let port = &module.ports[1];
+        let offset = input.find("bar").unwrap() + 1;
+        assert_eq!(map.find_node_at(source, offset), Some(port.id()));
+        assert_eq!(map.span_of(port.id()), Some(port.span()));
+
+        // The port's parent chain leads back to the source file.
+        let parent = map.parent_of(port.id()).unwrap();
+        assert_eq!(parent, module.id());
+        assert_eq!(
+            map.parent_of(map.parent_of(parent).unwrap()),
+            Some(file.id())
+        );
+        assert_eq!(map.parent_of(file.id()), None);
+    }
+}
@@ -27,6 +27,12 @@ pub enum Token {
     SubColon,
     Apostrophe,
     Dollar,
+    /// The `1step` delay value
+    OneStep,
+    /// The `(*` opening an attribute instance
+    AttrOpen,
+    /// The `*)` closing an attribute instance
+    AttrClose,
 
     Operator(Op),
 
@@ -71,6 +77,9 @@ impl Token {
             SubColon => "-:",
             Apostrophe => "'",
             Dollar => "$",
+            OneStep => "1step",
+            AttrOpen => "(*",
+            AttrClose => "*)",
 
             Operator(op) => op.as_str(),
 
@@ -140,6 +149,19 @@ pub enum TimeUnit {
     FemtoSecond,
 }
 
+impl TimeUnit {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeUnit::Second => "s",
+            TimeUnit::MilliSecond => "ms",
+            TimeUnit::MicroSecond => "us",
+            TimeUnit::NanoSecond => "ns",
+            TimeUnit::PicoSecond => "ps",
+            TimeUnit::FemtoSecond => "fs",
+        }
+    }
+}
+
 /// Operator symbols.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Op {
@@ -340,6 +362,15 @@ impl Op {
             Op::SeqImplOl | Op::SeqImplNol | Op::SeqFollowOl | Op::SeqFollowNol => Precedence::Max,
         }
     }
+
+    /// The associativity of the operator, as per IEEE 1800-2017 Table 11-2.
+    pub fn get_associativity(self) -> Associativity {
+        match self.get_precedence() {
+            Precedence::Implication | Precedence::Ternary => Associativity::Right,
+            Precedence::Assignment | Precedence::Unary | Precedence::Max => Associativity::None,
+            _ => Associativity::Left,
+        }
+    }
 }
 
 impl Display for Op {
@@ -375,6 +406,17 @@ pub enum Precedence {
     Max,
 }
 
+/// Operator associativity.
+///
+/// Determines how a sequence of operators of equal precedence groups, for
+/// example `a - b - c` as `(a - b) - c` for left-associative operators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
 macro_rules! declare_keywords {(
     $( ($konst: ident, $string: expr) )*
 ) => {
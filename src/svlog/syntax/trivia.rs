@@ -0,0 +1,274 @@
+// Copyright (c) 2016-2021 Fabian Schuiki
+
+//! A side table that attaches comments to AST nodes.
+//!
+//! The lexer discards comments, such that they do not appear in the AST. Tools
+//! such as documentation generators can recover them through a `TriviaMap`,
+//! which matches the comments in a source file against the node spans recorded
+//! in a `SpanMap`. A comment that starts on the line on which a node ends
+//! trails that node. A block of comments on lines of their own leads the node
+//! that starts on the line immediately below, unless a blank line separates
+//! the two.
+
+use crate::{
+    cat::{Cat, CatToken, CatTokenKind},
+    span_map::SpanMap,
+};
+use moore_common::{
+    id::NodeId,
+    source::{Source, Span},
+};
+use std::collections::HashMap;
+
+/// A comment in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentSpan {
+    /// The span of the comment, including the comment markers.
+    pub span: Span,
+    /// Whether this is a `/* ... */` comment, as opposed to a `// ...` one.
+    pub block: bool,
+}
+
+/// The comments attached to a node.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    /// The comments immediately above the node.
+    pub leading: Vec<CommentSpan>,
+    /// The comments on the line on which the node ends.
+    pub trailing: Vec<CommentSpan>,
+}
+
+/// A table of the comments attached to nodes.
+#[derive(Debug, Default, Clone)]
+pub struct TriviaMap {
+    entries: HashMap<NodeId, Trivia>,
+}
+
+impl TriviaMap {
+    /// Attach the comments in a source file to the nodes in a span map.
+    ///
+    /// A comment is attached to every node that starts or ends at the matching
+    /// position, such that an item and the declaration it wraps both report
+    /// the comment.
+    pub fn build(source: Source, spans: &SpanMap) -> Self {
+        let content = source.get_content();
+        let text = content.bytes();
+        let lines = Lines::new(text);
+        let mut by_begin: HashMap<usize, Vec<NodeId>> = HashMap::new();
+        let mut by_end: HashMap<usize, Vec<NodeId>> = HashMap::new();
+        for (id, span) in spans.iter().filter(|(_, s)| s.source == source) {
+            by_begin.entry(span.begin).or_default().push(id);
+            by_end.entry(span.end).or_default().push(id);
+        }
+        let mut ends: Vec<usize> = by_end.keys().cloned().collect();
+        ends.sort();
+        let comments: Vec<_> = Cat::new(content.iter())
+            .filter_map(|CatToken(kind, begin, end)| match kind {
+                CatTokenKind::Comment => Some(CommentSpan {
+                    span: Span::new(source, begin, end),
+                    block: text[begin + 1] == b'*',
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let mut map = TriviaMap::default();
+        let mut leading: Vec<CommentSpan> = vec![];
+        for (index, &comment) in comments.iter().enumerate() {
+            let begin = comment.span.begin;
+
+            // Comments that follow a node on the same line, separated by at
+            // most a comma or semicolon, trail that node.
+            let prev_end = ends[..ends.partition_point(|&end| end <= begin)].last();
+            if let Some(&end) = prev_end {
+                let between = &text[end..begin];
+                let punct = between.iter().filter(|c| !is_space(**c)).count();
+                let sep = between.iter().find(|c| !is_space(**c));
+                if !between.contains(&b'\n')
+                    && punct <= 1
+                    && sep.map_or(true, |c| b",;".contains(c))
+                {
+                    for &id in &by_end[&end] {
+                        map.entries.entry(id).or_default().trailing.push(comment);
+                    }
+                    continue;
+                }
+            }
+
+            // Other comments on lines of their own are grouped into blocks,
+            // which lead the node on the line immediately below.
+            if !text[lines.start_of(begin)..begin]
+                .iter()
+                .all(|&c| is_space(c))
+            {
+                continue;
+            }
+            if let Some(prev) = leading.last() {
+                if lines.line_of(prev.span.end) + 1 != lines.line_of(begin) {
+                    leading.clear();
+                }
+            }
+            leading.push(comment);
+            let next_begin = comments.get(index + 1).map(|c| c.span.begin);
+            let next = match text[comment.span.end..].iter().position(|&c| !is_space(c)) {
+                Some(offset) => comment.span.end + offset,
+                None => continue,
+            };
+            if Some(next) == next_begin {
+                continue;
+            }
+            if lines.line_of(next) == lines.line_of(comment.span.end - 1) + 1 {
+                for &id in by_begin.get(&next).into_iter().flatten() {
+                    map.entries
+                        .entry(id)
+                        .or_default()
+                        .leading
+                        .extend(leading.iter().cloned());
+                }
+            }
+            leading.clear();
+        }
+        map
+    }
+
+    /// Get the comments attached to a node.
+    pub fn get(&self, id: NodeId) -> Option<&Trivia> {
+        self.entries.get(&id)
+    }
+
+    /// Get the documentation comment of a node.
+    ///
+    /// Uses the leading comments of the node, or its trailing comments if it
+    /// has no leading ones. The comment markers and leading asterisks of block
+    /// comments are stripped, and the lines joined by newlines.
+    pub fn doc_comment_of(&self, id: NodeId) -> Option<String> {
+        let trivia = self.get(id)?;
+        let comments = if trivia.leading.is_empty() {
+            &trivia.trailing
+        } else {
+            &trivia.leading
+        };
+        let mut lines = vec![];
+        for comment in comments {
+            let text = comment.span.extract();
+            if comment.block {
+                let text = text.trim_start_matches("/*").trim_start_matches('*');
+                let text = text.strip_suffix("*/").unwrap_or(text);
+                lines.extend(text.lines().map(|line| {
+                    let line = line.trim_start();
+                    let line = line.strip_prefix('*').unwrap_or(line);
+                    strip_space(line).trim_end().to_string()
+                }));
+            } else {
+                let line = text.trim_start_matches('/');
+                let line = line.strip_prefix('!').unwrap_or(line);
+                lines.push(strip_space(line).trim_end().to_string());
+            }
+        }
+        while lines.first().map_or(false, |l| l.is_empty()) {
+            lines.remove(0);
+        }
+        while lines.last().map_or(false, |l| l.is_empty()) {
+            lines.pop();
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+/// The line structure of a source file.
+struct Lines {
+    starts: Vec<usize>,
+}
+
+impl Lines {
+    fn new(text: &[u8]) -> Self {
+        let mut starts = vec![0];
+        starts.extend(
+            text.iter()
+                .enumerate()
+                .filter(|(_, &c)| c == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Lines { starts }
+    }
+
+    /// Get the zero-based line number of a byte offset.
+    fn line_of(&self, offset: usize) -> usize {
+        match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    /// Get the offset at which the line containing a byte offset starts.
+    fn start_of(&self, offset: usize) -> usize {
+        self.starts[self.line_of(offset)]
+    }
+}
+
+fn is_space(c: u8) -> bool {
+    c == b' ' || c == b'\t' || c == b'\r' || c == b'\n'
+}
+
+fn strip_space(line: &str) -> &str {
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::*, lexer::Lexer, parser, preproc::Preprocessor};
+    use moore_common::source::get_source_manager;
+
+    #[test]
+    fn port_doc_comments() {
+        let input = "// Unrelated.\n\n\
+            /// A simple adder.\n\
+            module foo (\n    \
+                /// The first operand.\n    \
+                /// Must be stable.\n    \
+                input logic a,\n    \
+                input logic b, // The second operand.\n\n    \
+                /* The result. */\n    \
+                output logic c\n\
+            );\n\
+            endmodule\n";
+        let source = get_source_manager().add("trivia.sv", input);
+        let preproc = Preprocessor::new(source, &[], &[]);
+        let arena = Arena::default();
+        let file = parser::parse(Lexer::new(preproc), &arena).unwrap();
+        let module = match file.items[0].data {
+            ItemData::ModuleDecl(ref m) => m,
+            _ => panic!("expected module"),
+        };
+        let trivia = TriviaMap::build(source, &SpanMap::build(&file));
+
+        assert_eq!(
+            trivia.doc_comment_of(module.id()).as_deref(),
+            Some("A simple adder.")
+        );
+        assert_eq!(
+            trivia.doc_comment_of(module.ports[0].id()).as_deref(),
+            Some("The first operand.\nMust be stable.")
+        );
+        assert_eq!(
+            trivia.doc_comment_of(module.ports[1].id()).as_deref(),
+            Some("The second operand.")
+        );
+        assert_eq!(trivia.get(module.ports[1].id()).unwrap().leading, vec![]);
+        assert_eq!(
+            trivia.doc_comment_of(module.ports[2].id()).as_deref(),
+            Some("The result.")
+        );
+
+        // The comment separated by a blank line is not attached to anything.
+        assert_eq!(
+            trivia.doc_comment_of(file.id()).as_deref(),
+            Some("A simple adder.")
+        );
+    }
+}
@@ -1900,15 +1900,27 @@ impl<'a> StructType<'a> {
 
     /// Compute the size of this struct in bits.
     ///
-    /// Returns `None` if any member of the type has a `[]` dimension.
+    /// The members of a union overlap, such that its size is that of its
+    /// largest member. Returns `None` if any member of the type has a `[]`
+    /// dimension.
     pub fn get_bit_size(&self) -> Option<usize> {
         let mut size = 0;
         for m in &self.members {
-            size += m.ty.get_bit_size()?;
+            let member_size = m.ty.get_bit_size()?;
+            if self.is_union() {
+                size = std::cmp::max(size, member_size);
+            } else {
+                size += member_size;
+            }
         }
         Some(size)
     }
 
+    /// Check whether this is an untagged union.
+    pub fn is_union(&self) -> bool {
+        self.kind == ast::StructKind::Union
+    }
+
     /// Helper function to format this struct.
     fn format(
         &self,
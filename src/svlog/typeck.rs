@@ -33,6 +33,7 @@ use crate::{
     call_mapping::CallArgSource,
     common::arenas::Alloc,
     hir::HirNode,
+    param_env::emit_in_env_contexts,
     port_list,
     resolver::{DefNode, InstTarget},
     syntax::ast::BasicNode,
@@ -135,6 +136,25 @@ pub(crate) fn type_of_int_port<'a>(
                 None => return ty,
             };
 
+            // Interface ports carry no direction. See §25.5.
+            if let Some(&ast::PortData::Named { dir: Some(dir), .. }) =
+                port.ast.as_all().get_port().map(|p| &p.data)
+            {
+                cx.emit(
+                    DiagBuilder2::error(format!(
+                        "interface port `{}` cannot have a direction",
+                        port.name
+                    ))
+                    .span(port.span)
+                    .add_note(format!(
+                        "Remove the `{}`; the directions of the signals in an interface are \
+                         given by its modports.",
+                        dir
+                    )),
+                );
+                return UnpackedType::make_error();
+            }
+
             // Get the node assigned to this port that implicitly
             // parametrizes it.
             let assigned = match cx.param_env_data(env).find_interface(port.id) {
@@ -520,19 +540,15 @@ pub(crate) fn map_to_type<'a>(
             }
 
             // Otherwise complain.
-            let d = DiagBuilder2::error(format!("{} not assigned and has no default", ast,));
-            let contexts = cx.param_env_contexts(env);
-            for &context in &contexts {
-                cx.emit(
-                    d.clone()
-                        .span(cx.span(context))
-                        .add_note("parameter declared here:")
-                        .span(ast.human_span()),
-                );
-            }
-            if contexts.is_empty() {
-                cx.emit(d.span(ast.human_span()));
+            let mut d = DiagBuilder2::error(format!("{} not assigned and has no default", ast))
+                .span(ast.human_span());
+            let bindings = param_env_text(cx, env);
+            if !bindings.is_empty() {
+                d = d.add_note(format!("Assigned in this instance: {}", bindings));
             }
+            emit_in_env_contexts(cx, d, env, |context| {
+                Some(("Instantiated here:".to_string(), cx.span(context)))
+            });
             Some(UnpackedType::make_error())
         }
         _ => {
@@ -663,6 +679,34 @@ pub(crate) fn packed_type_from_ast<'a>(
                 }
             }
 
+            // The members of a packed untagged union must all have the same
+            // size. See §7.3.1.
+            if strukt.packed && def.is_union() {
+                let sizes: Vec<_> = def
+                    .members
+                    .iter()
+                    .map(|m| (m, m.ty.get_bit_size()))
+                    .collect();
+                if let Some(&(first, first_size)) = sizes.first() {
+                    for &(member, size) in &sizes[1..] {
+                        if size != first_size && !member.ty.is_error() && !first.ty.is_error() {
+                            cx.emit(
+                                DiagBuilder2::error(format!(
+                                    "members of packed union must have the same size, but \
+                                     `{}` is {} bits and `{}` is {} bits",
+                                    member.name,
+                                    size.unwrap_or(0),
+                                    first.name,
+                                    first_size.unwrap_or(0),
+                                ))
+                                .span(member.name.span),
+                            );
+                            failed = true;
+                        }
+                    }
+                }
+            }
+
             // Keep track of the sign, and complain if the packed type itself
             // has separate sign information.
             if ast_sign != ast::TypeSign::None {
@@ -872,6 +916,19 @@ pub(crate) fn packed_type_from_ast<'a>(
         // Handle the packed case, which can still be extended by a sign and
         // packed dimensions.
         Packed(core) => {
+            // The `void` type carries no bits, so a sign or packed dimensions
+            // are meaningless.
+            if let PackedCore::Void = core {
+                if ast_sign != ast::TypeSign::None || !ast.dims.is_empty() {
+                    cx.emit(
+                        DiagBuilder2::error(
+                            "`void` cannot be signed, unsigned, or have packed dimensions",
+                        )
+                        .span(ast.span()),
+                    );
+                    return UnpackedType::make_error();
+                }
+            }
             let sign = match ast_sign {
                 ast::TypeSign::None => core.default_sign(),
                 ast::TypeSign::Unsigned => ty::Sign::Unsigned,
@@ -1097,7 +1154,10 @@ pub(crate) fn type_of_expr<'a>(
 
         // Unsized constants infer their type from the context if possible, and
         // otherwise fall back to a self-determined mode.
-        hir::ExprKind::UnsizedConst(..) => cx
+        //
+        // Streaming concatenations are left-aligned in and then zero-filled
+        // or truncated to their context's type.
+        hir::ExprKind::UnsizedConst(..) | hir::ExprKind::StreamConcat { .. } => cx
             .type_context(Ref(expr), env)
             .and_then(|x| x.ty().get_simple_bit_vector())
             .map(|x| x.to_unpacked(cx))
@@ -1625,44 +1685,13 @@ fn self_determined_expr_type<'gcx>(
         //
         // See §11.8.1 "Rules for expression types".
         hir::ExprKind::Concat(repeat, ref exprs) => {
-            let mut failed = false;
-
             // Determine the cumulative width of all fields.
-            let mut bit_width = 0;
-            let mut domain = ty::Domain::TwoValued;
-            for &expr in exprs {
-                let ty = cx.need_self_determined_type(expr, env);
-                if ty.is_error() {
-                    failed = true;
-                    continue;
-                }
-                if ty.domain() == ty::Domain::FourValued {
-                    domain = ty::Domain::FourValued;
-                }
-                match ty.get_simple_bit_vector() {
-                    Some(sbv) => bit_width += sbv.size,
-                    None => {
-                        cx.emit(
-                            DiagBuilder2::error(format!(
-                                "cannot concatenate a value of type `{}`",
-                                ty
-                            ))
-                            .span(cx.span(expr))
-                            .add_note(format!(
-                                "`{}` has no simple bit-vector type representation",
-                                ty
-                            )),
-                        );
-                        failed = true;
-                        continue;
-                    }
-                }
-            }
+            let (bit_width, domain, mut failed) = concat_width(cx, exprs, env);
 
             // Determine the repetition factor.
             let repeat = match repeat {
-                Some(repeat) => match cx.constant_int_value_of(repeat, env) {
-                    Ok(r) => r.to_usize().unwrap(),
+                Some(repeat) => match cx.replication_count(repeat, env) {
+                    Ok(r) => r,
                     Err(()) => {
                         failed = true;
                         0
@@ -1679,6 +1708,19 @@ fn self_determined_expr_type<'gcx>(
             }
         }
 
+        // Streaming concatenations yield an unsigned vector just like regular
+        // concatenations.
+        //
+        // See §11.4.14 "Streaming operators (pack/unpack)".
+        hir::ExprKind::StreamConcat { ref exprs, .. } => {
+            let (bit_width, domain, failed) = concat_width(cx, exprs, env);
+            if failed {
+                Some(UnpackedType::make_error())
+            } else {
+                Some(SbvType::new(domain, Sign::Unsigned, bit_width).to_unpacked(cx))
+            }
+        }
+
         // Casts trivially evaluate to the cast type.
         hir::ExprKind::Cast(ty, _) => Some(cx.packed_type_from_ast(
             Ref(cx.ast_for_id(ty).as_all().get_type().unwrap()),
@@ -1997,6 +2039,96 @@ fn self_determined_sign_cast_type<'gcx>(
     sbv.change_sign(sign).to_unpacked(cx)
 }
 
+/// Determine the cumulative width and domain of the fields of a concatenation.
+///
+/// Returns the width, the domain, and whether any of the fields failed to map
+/// to a simple bit vector.
+fn concat_width<'gcx>(
+    cx: &impl Context<'gcx>,
+    exprs: &[NodeId],
+    env: ParamEnv,
+) -> (usize, ty::Domain, bool) {
+    let mut failed = false;
+    let mut bit_width = 0;
+    let mut domain = ty::Domain::TwoValued;
+    for &expr in exprs {
+        let ty = cx.need_self_determined_type(expr, env);
+        if ty.is_error() {
+            failed = true;
+            continue;
+        }
+        if ty.domain() == ty::Domain::FourValued {
+            domain = ty::Domain::FourValued;
+        }
+        match ty.get_simple_bit_vector() {
+            Some(sbv) => bit_width += sbv.size,
+            None => {
+                cx.emit(
+                    DiagBuilder2::error(format!("cannot concatenate a value of type `{}`", ty))
+                        .span(cx.span(expr))
+                        .add_note(format!(
+                            "`{}` has no simple bit-vector type representation",
+                            ty
+                        )),
+                );
+                failed = true;
+                continue;
+            }
+        }
+    }
+    (bit_width, domain, failed)
+}
+
+/// The largest replication count accepted in a concatenation.
+///
+/// Larger counts are almost certainly a mistake, and would otherwise exhaust
+/// memory during constant folding and code generation.
+pub(crate) const MAX_REPLICATION_COUNT: usize = 1 << 20;
+
+/// Determine the replication count of a concatenation, e.g. the `4` in
+/// `{4{a}}`.
+///
+/// The count must be a non-negative constant no larger than
+/// `MAX_REPLICATION_COUNT`.
+#[moore_derive::query]
+pub(crate) fn replication_count<'a>(
+    cx: &impl Context<'a>,
+    count: NodeId,
+    env: ParamEnv,
+) -> Result<usize> {
+    if !cx.is_constant(count)? {
+        cx.emit(
+            DiagBuilder2::error("replication count must be a constant expression")
+                .span(cx.span(count)),
+        );
+        return Err(());
+    }
+    let value = cx.constant_int_value_of(count, env)?;
+    if value.is_negative() {
+        cx.emit(
+            DiagBuilder2::error(format!(
+                "replication count must not be negative, but is {}",
+                value
+            ))
+            .span(cx.span(count)),
+        );
+        return Err(());
+    }
+    match value.to_usize() {
+        Some(v) if v <= MAX_REPLICATION_COUNT => Ok(v),
+        _ => {
+            cx.emit(
+                DiagBuilder2::error(format!(
+                    "replication count {} exceeds the maximum of {}",
+                    value, MAX_REPLICATION_COUNT
+                ))
+                .span(cx.span(count)),
+            );
+            Err(())
+        }
+    }
+}
+
 /// Get the operation type of an expression.
 #[moore_derive::query]
 pub(crate) fn operation_type<'a>(
@@ -2164,8 +2296,8 @@ pub(crate) fn operation_type<'a>(
                 let (a, b) = match r.value {
                     hir::InsideRange::Single(rhs) => (cx.self_determined_type(rhs, env), None),
                     hir::InsideRange::Range(lo, hi) => (
-                        cx.self_determined_type(lo, env),
-                        cx.self_determined_type(hi, env),
+                        lo.and_then(|lo| cx.self_determined_type(lo, env)),
+                        hi.and_then(|hi| cx.self_determined_type(hi, env)),
                     ),
                 };
                 a.into_iter().chain(b.into_iter())
@@ -2224,8 +2356,22 @@ fn unify_operator_types<'gcx>(
     _env: ParamEnv,
     types: impl Iterator<Item = &'gcx UnpackedType<'gcx>>,
 ) -> Option<&'gcx UnpackedType<'gcx>> {
+    let types: Vec<_> = types.collect();
+
+    // If any of the operands is a time, the operation is performed on time
+    // values. Integer operands are converted to time in the process.
+    if types
+        .iter()
+        .any(|ty| ty.get_packed().map(|ty| ty.is_time()).unwrap_or(false))
+    {
+        return Some(UnpackedType::make_time());
+    }
+
     // Map the iterator to a sequence of sign, domain, and bit width tuples.
-    let inner: Vec<_> = types.flat_map(|ty| ty.get_simple_bit_vector()).collect();
+    let inner: Vec<_> = types
+        .iter()
+        .flat_map(|ty| ty.get_simple_bit_vector())
+        .collect();
 
     // Determine the maximum width, sign, and domain.
     let width: Option<usize> = inner.iter().map(|&sbv| sbv.size).max();
@@ -2561,7 +2707,7 @@ fn type_context_imposed_by_expr<'gcx>(
 
         // Concatenations require their arguments (including repetition counts)
         // to map to a corresponding SBVT.
-        hir::ExprKind::Concat(..) => {
+        hir::ExprKind::Concat(..) | hir::ExprKind::StreamConcat { .. } => {
             let ty = cx.need_self_determined_type(onto, env);
             if ty.is_error() {
                 return Some(ty.into());
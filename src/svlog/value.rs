@@ -12,14 +12,17 @@
 //! uses.
 
 use crate::{
+    common::Session,
     crate_prelude::*,
     hir::HirNode,
+    param_env::emit_in_env_contexts,
     ty::{SbvType, UnpackedType},
     ParamEnv, ParamEnvBinding,
 };
 use bit_vec::BitVec;
 use itertools::Itertools;
-use num::{BigInt, BigRational, Integer, One, ToPrimitive, Zero};
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+use std::collections::HashMap;
 
 /// A verilog value.
 pub type Value<'t> = &'t ValueData<'t>;
@@ -51,11 +54,32 @@ impl<'t> ValueData<'t> {
             ValueKind::Int(ref v, ..) => v.is_zero(),
             ValueKind::Time(ref v) => v.is_zero(),
             ValueKind::StructOrArray(_) => false,
+            ValueKind::Aggregate(ref v) => v.is_empty(),
             ValueKind::String(ref v) => v.is_empty(),
             ValueKind::Error => true,
         }
     }
 
+    /// Determine the truth value of this value, taking x and z bits into
+    /// account.
+    ///
+    /// Returns `Some(true)` if any known bit is 1, `Some(false)` if all bits
+    /// are known to be 0, and `None` if the value has x or z bits but no
+    /// known 1 bit.
+    pub fn truth(&self) -> Option<bool> {
+        match self.kind {
+            ValueKind::Int(ref v, ref xz) if xz.any() => {
+                let known = v - (v & xz.special_mask());
+                if !known.is_zero() {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            _ => Some(self.is_true()),
+        }
+    }
+
     /// Convert the value to an integer.
     pub fn get_int(&self) -> Option<&BigInt> {
         match self.kind {
@@ -63,14 +87,319 @@ impl<'t> ValueData<'t> {
             _ => None,
         }
     }
+
+    /// Convert the value to a time in seconds.
+    pub fn get_time(&self) -> Option<&BigRational> {
+        match self.kind {
+            ValueKind::Time(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Convert the value to an integer, interpreting it according to the
+    /// sign of its type.
+    ///
+    /// Integers are not stored in a canonical form, such that the same bit
+    /// pattern may be represented by a negative or a positive number. This
+    /// function maps the value into the range of its type, e.g. `-128..128`
+    /// for a `byte` and `0..256` for a `bit [7:0]`.
+    pub fn get_int_signed(&self) -> Option<BigInt> {
+        let v = self.get_int()?;
+        let width = match self.ty.get_bit_size() {
+            Some(w) => w,
+            None => return Some(v.clone()),
+        };
+        let modulus = BigInt::one() << width;
+        let v = v.mod_floor(&modulus);
+        if self.ty.sign() == ty::Sign::Signed && width > 0 && v >= (&modulus >> 1) {
+            Some(v - modulus)
+        } else {
+            Some(v)
+        }
+    }
+
+    /// Convert the value to a `u64`.
+    ///
+    /// The conversion fails if the value is not an integer, has x or z bits,
+    /// or does not fit into the target type after being interpreted according
+    /// to the sign of its type.
+    ///
+    /// ```
+    /// # use moore_common::Session;
+    /// # use moore_svlog::{GlobalArenas, GlobalContext};
+    /// use bit_vec::BitVec;
+    /// use moore_svlog::ty::{Domain, SbvType, Sign};
+    /// use moore_svlog::value::{make_int, make_int_special, ValueConversionError};
+    /// use num::BigInt;
+    /// # let sess = Session::new();
+    /// # let arena = GlobalArenas::default();
+    /// # let gcx = GlobalContext::new(&sess, &arena);
+    /// let byte = SbvType::new(Domain::TwoValued, Sign::Signed, 8).to_unpacked(&gcx);
+    /// let wide = SbvType::new(Domain::TwoValued, Sign::Unsigned, 72).to_unpacked(&gcx);
+    /// let nibble = SbvType::new(Domain::FourValued, Sign::Unsigned, 4).to_unpacked(&gcx);
+    ///
+    /// // Negative values are recovered from their bit pattern.
+    /// let v = make_int(byte, 0xfe.into());
+    /// assert_eq!(v.to_i64_checked(), Ok(-2));
+    /// assert_eq!(v.to_u64_checked(), Err(ValueConversionError::OutOfRange));
+    ///
+    /// // Values of more than 64 bits convert only if they fit.
+    /// let v = make_int(wide, BigInt::from(1) << 70);
+    /// assert_eq!(v.to_u64_checked(), Err(ValueConversionError::OutOfRange));
+    /// assert_eq!(make_int(wide, 42.into()).to_u64_checked(), Ok(42));
+    ///
+    /// // Values with x bits never convert.
+    /// let mut x = BitVec::from_elem(4, false);
+    /// x.set(0, true);
+    /// let v = make_int_special(nibble, 0.into(), x.clone(), x);
+    /// assert_eq!(v.to_usize_checked(), Err(ValueConversionError::Unknown));
+    /// ```
+    pub fn to_u64_checked(&self) -> std::result::Result<u64, ValueConversionError> {
+        self.to_known_int()?
+            .to_u64()
+            .ok_or(ValueConversionError::OutOfRange)
+    }
+
+    /// Convert the value to an `i64`.
+    pub fn to_i64_checked(&self) -> std::result::Result<i64, ValueConversionError> {
+        self.to_known_int()?
+            .to_i64()
+            .ok_or(ValueConversionError::OutOfRange)
+    }
+
+    /// Convert the value to a `usize`.
+    pub fn to_usize_checked(&self) -> std::result::Result<usize, ValueConversionError> {
+        self.to_known_int()?
+            .to_usize()
+            .ok_or(ValueConversionError::OutOfRange)
+    }
+
+    /// Get the sign-interpreted integer value, provided it has no x or z bits.
+    fn to_known_int(&self) -> std::result::Result<BigInt, ValueConversionError> {
+        match self.kind {
+            ValueKind::Int(_, ref xz) if xz.any() => Err(ValueConversionError::Unknown),
+            ValueKind::Int(..) => Ok(self.get_int_signed().unwrap()),
+            _ => Err(ValueConversionError::NotInteger),
+        }
+    }
+}
+
+/// An error converting a value to a machine integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueConversionError {
+    /// The value is not an integer.
+    NotInteger,
+    /// The value has x or z bits.
+    Unknown,
+    /// The value does not fit into the requested integer type.
+    OutOfRange,
+}
+
+impl std::fmt::Display for ValueConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::NotInteger => write!(f, "value is not an integer"),
+            Self::Unknown => write!(f, "value has x or z bits"),
+            Self::OutOfRange => write!(f, "value is out of range"),
+        }
+    }
 }
 
+impl std::error::Error for ValueConversionError {}
+
+impl<'t> ValueData<'t> {
+    /// Format the value as a sized literal in a given radix.
+    ///
+    /// Integers are rendered as sized based literals such as `8'b1010_xx11`,
+    /// taking the width and sign from the value's type and showing its x and z
+    /// bits. Digits are grouped in fours by underscores. Structs and arrays are
+    /// rendered as `'{...}` patterns with each element formatted recursively.
+    pub fn format(&self, radix: ValueRadix) -> String {
+        let mut s = String::new();
+        self.write_radix(&mut s, radix).unwrap();
+        s
+    }
+
+    fn write_radix(&self, f: &mut impl std::fmt::Write, radix: ValueRadix) -> std::fmt::Result {
+        match self.kind {
+            ValueKind::Int(ref v, ref xz) => {
+                let width = self
+                    .ty
+                    .get_bit_size()
+                    .unwrap_or_else(|| std::cmp::max(v.bits() as usize, 1));
+                write_int_literal(f, width, self.ty.sign(), v, xz, radix)
+            }
+            ValueKind::StructOrArray(ref v) | ValueKind::Aggregate(ref v) => {
+                write!(f, "'{{")?;
+                for (i, v) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    v.write_radix(&mut *f, radix)?;
+                }
+                write!(f, "}}")
+            }
+            ref kind => write!(f, "{}", kind),
+        }
+    }
+}
+
+/// Formats the value.
+///
+/// The plain form `{}` prints integers as bare decimal numbers. The alternate
+/// form `{:#}` prints sized decimal literals such as `32'd42`. Use `{:x}`,
+/// `{:o}`, and `{:b}` to print sized hexadecimal, octal, and binary literals.
 impl std::fmt::Display for ValueData<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.kind)
+        if f.alternate() {
+            self.write_radix(f, ValueRadix::Decimal)
+        } else {
+            write!(f, "{}", self.kind)
+        }
+    }
+}
+
+impl std::fmt::LowerHex for ValueData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write_radix(f, ValueRadix::Hex)
+    }
+}
+
+impl std::fmt::Octal for ValueData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write_radix(f, ValueRadix::Octal)
+    }
+}
+
+impl std::fmt::Binary for ValueData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.write_radix(f, ValueRadix::Binary)
     }
 }
 
+/// The radix in which a value is formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueRadix {
+    /// Decimal, e.g. `32'd42`.
+    Decimal,
+    /// Hexadecimal, e.g. `32'h2a`.
+    Hex,
+    /// Octal, e.g. `32'o52`.
+    Octal,
+    /// Binary, e.g. `8'b10_1010`.
+    Binary,
+}
+
+impl ValueRadix {
+    /// The base character of a literal in this radix, e.g. `h` in `8'hff`.
+    fn base_char(self) -> char {
+        match self {
+            ValueRadix::Decimal => 'd',
+            ValueRadix::Hex => 'h',
+            ValueRadix::Octal => 'o',
+            ValueRadix::Binary => 'b',
+        }
+    }
+
+    /// The number of bits represented by a single digit.
+    ///
+    /// Returns 0 for decimal, where digits do not map to a fixed set of bits.
+    fn digit_bits(self) -> usize {
+        match self {
+            ValueRadix::Decimal => 0,
+            ValueRadix::Hex => 4,
+            ValueRadix::Octal => 3,
+            ValueRadix::Binary => 1,
+        }
+    }
+}
+
+/// Write an integer as a sized based literal, e.g. `8'b1010_xx11`.
+///
+/// Digits whose bits are all x or all z are printed as `x` or `z`. A digit can
+/// not express a mix of x, z, and known bits, so values with such digits are
+/// printed in binary instead. Decimal literals can only express fully known
+/// values or values which are entirely x or z; everything else is printed in
+/// binary as well.
+fn write_int_literal(
+    f: &mut impl std::fmt::Write,
+    width: usize,
+    sign: ty::Sign,
+    value: &BigInt,
+    xz: &XzInfo,
+    radix: ValueRadix,
+) -> std::fmt::Result {
+    let is_special = |i: usize| xz.is_special(i);
+    let is_x = |i: usize| xz.is_x(i);
+    let is_z = |i: usize| xz.is_z(i);
+    let signed = sign == ty::Sign::Signed;
+    let s = if signed { "s" } else { "" };
+    let modulus = BigInt::one() << width;
+
+    if radix == ValueRadix::Decimal {
+        if !(0..width).any(is_special) {
+            let v = value.mod_floor(&modulus);
+            if signed && width > 0 && v >= (&modulus >> 1) {
+                return write!(f, "-{}'sd{}", width, modulus - v);
+            }
+            return write!(f, "{}'{}d{}", width, s, v);
+        }
+        if width > 0 && (0..width).all(is_x) {
+            return write!(f, "{}'{}dx", width, s);
+        }
+        if width > 0 && (0..width).all(is_z) {
+            return write!(f, "{}'{}dz", width, s);
+        }
+        return write_int_literal(&mut *f, width, sign, value, xz, ValueRadix::Binary);
+    }
+
+    // Determine the bit pattern of the value, LSB first.
+    let pattern: Vec<bool> = value
+        .mod_floor(&modulus)
+        .to_str_radix(2)
+        .bytes()
+        .rev()
+        .map(|b| b == b'1')
+        .collect();
+
+    // Assemble the digits, LSB first.
+    let digit_bits = radix.digit_bits();
+    let num_digits = std::cmp::max((width + digit_bits - 1) / digit_bits, 1);
+    let mut digits = Vec::with_capacity(num_digits);
+    for d in 0..num_digits {
+        let lsb = d * digit_bits;
+        let bits = lsb..std::cmp::min(lsb + digit_bits, width);
+        let num_bits = bits.len();
+        let num_x = bits.clone().filter(|&i| is_x(i)).count();
+        let num_z = bits.clone().filter(|&i| is_z(i)).count();
+        digits.push(if num_bits > 0 && num_x == num_bits {
+            'x'
+        } else if num_bits > 0 && num_z == num_bits {
+            'z'
+        } else if num_x > 0 || num_z > 0 {
+            return write_int_literal(&mut *f, width, sign, value, xz, ValueRadix::Binary);
+        } else {
+            let v = bits
+                .filter(|&i| pattern.get(i).copied().unwrap_or(false))
+                .fold(0, |v, i| v | 1 << (i - lsb));
+            std::char::from_digit(v, 16).unwrap()
+        });
+    }
+
+    // Drop leading zeros and print the digits MSB first, grouped in fours.
+    while digits.len() > 1 && digits.last() == Some(&'0') {
+        digits.pop();
+    }
+    write!(f, "{}'{}{}", width, s, radix.base_char())?;
+    for (i, &c) in digits.iter().enumerate().rev() {
+        write!(f, "{}", c)?;
+        if i > 0 && i % 4 == 0 {
+            write!(f, "_")?;
+        }
+    }
+    Ok(())
+}
+
 /// The different forms a value can assume.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ValueKind<'t> {
@@ -78,13 +407,20 @@ pub enum ValueKind<'t> {
     Void,
     /// An arbitrary precision integer.
     ///
-    /// The first field contains the value. The second field indicates the
-    /// special bits (x or z), and the third indicates the x bits.
-    Int(BigInt, BitVec, BitVec),
+    /// The first field contains the value. The second field indicates which
+    /// bits are x or z, if any.
+    Int(BigInt, XzInfo),
     /// An arbitrary precision time interval.
     Time(BigRational),
     /// A struct.
     StructOrArray(Vec<Value<'t>>),
+    /// A dynamically-sized container.
+    ///
+    /// This covers queues, dynamic arrays, and associative arrays. In contrast
+    /// to `StructOrArray`, the number of elements is not implied by the type,
+    /// such that an empty queue can be told apart from a fixed-size array of
+    /// length zero.
+    Aggregate(Vec<Value<'t>>),
     /// A string.
     ///
     /// Note that we use a raw `u8` array, instead of Rust's `String` type. This
@@ -103,6 +439,19 @@ impl<'t> ValueKind<'t> {
             _ => false,
         }
     }
+
+    /// Describe the kind of value, as in "an integer".
+    pub fn desc(&self) -> &'static str {
+        match self {
+            ValueKind::Void => "void",
+            ValueKind::Int(..) => "an integer",
+            ValueKind::Time(..) => "a time",
+            ValueKind::StructOrArray(..) => "a struct or array",
+            ValueKind::Aggregate(..) => "an aggregate",
+            ValueKind::String(..) => "a string",
+            ValueKind::Error => "an error",
+        }
+    }
 }
 
 impl std::fmt::Display for ValueKind<'_> {
@@ -111,7 +460,7 @@ impl std::fmt::Display for ValueKind<'_> {
             ValueKind::Void => write!(f, "void"),
             ValueKind::Int(v, ..) => write!(f, "{}", v),
             ValueKind::Time(v) => write!(f, "{}", v),
-            ValueKind::StructOrArray(v) => {
+            ValueKind::StructOrArray(v) | ValueKind::Aggregate(v) => {
                 write!(f, "{{ {} }}", v.iter().map(|v| &v.kind).format(", "))
             }
             ValueKind::String(v) => {
@@ -130,6 +479,115 @@ impl std::fmt::Display for ValueKind<'_> {
     }
 }
 
+/// The x and z bits of an integer value.
+///
+/// Most integer values are fully known, in which case no bit vectors are
+/// allocated at all. This keeps the common two-valued case cheap for very wide
+/// values.
+///
+/// ```
+/// # use moore_common::Session;
+/// # use moore_svlog::{GlobalArenas, GlobalContext};
+/// use bit_vec::BitVec;
+/// use moore_svlog::ty::{Domain, SbvType, Sign};
+/// use moore_svlog::value::{make_int, make_int_special, ValueKind, XzInfo};
+/// use num::BigInt;
+/// # let sess = Session::new();
+/// # let arena = GlobalArenas::default();
+/// # let gcx = GlobalContext::new(&sess, &arena);
+/// let wide = SbvType::new(Domain::FourValued, Sign::Unsigned, 4096).to_unpacked(&gcx);
+///
+/// // Known values carry no x or z storage.
+/// let v = make_int(wide, (BigInt::from(1) << 4000) - 1);
+/// assert!(matches!(v.kind, ValueKind::Int(_, XzInfo::None)));
+///
+/// // Special bits which are all cleared are dropped as well.
+/// let none = BitVec::from_elem(4096, false);
+/// let v = make_int_special(wide, 42.into(), none.clone(), none);
+/// assert!(matches!(v.kind, ValueKind::Int(_, XzInfo::None)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum XzInfo {
+    /// All bits are 0 or 1.
+    None,
+    /// Some bits are x or z.
+    Bits {
+        /// The special bits (x or z).
+        special: BitVec,
+        /// Which of the special bits are x, as opposed to z.
+        x: BitVec,
+    },
+}
+
+impl XzInfo {
+    /// Create the x and z information from the special and x bits.
+    ///
+    /// Returns `XzInfo::None` if none of the bits are special, such that the
+    /// representation of a value is unique.
+    pub fn new(special: BitVec, x: BitVec) -> Self {
+        if special.any() {
+            XzInfo::Bits { special, x }
+        } else {
+            XzInfo::None
+        }
+    }
+
+    /// Check whether any bit is x or z.
+    pub fn any(&self) -> bool {
+        match self {
+            XzInfo::None => false,
+            XzInfo::Bits { .. } => true,
+        }
+    }
+
+    /// Check whether bit `i` is x or z.
+    pub fn is_special(&self, i: usize) -> bool {
+        match self {
+            XzInfo::None => false,
+            XzInfo::Bits { special, .. } => special.get(i).unwrap_or(false),
+        }
+    }
+
+    /// Check whether bit `i` is x.
+    pub fn is_x(&self, i: usize) -> bool {
+        match self {
+            XzInfo::None => false,
+            XzInfo::Bits { special, x } => {
+                special.get(i).unwrap_or(false) && x.get(i).unwrap_or(false)
+            }
+        }
+    }
+
+    /// Check whether bit `i` is z.
+    pub fn is_z(&self, i: usize) -> bool {
+        self.is_special(i) && !self.is_x(i)
+    }
+
+    /// Get the special bits of a value of the given width.
+    pub fn special_bits(&self, width: usize) -> BitVec {
+        match self {
+            XzInfo::None => BitVec::from_elem(width, false),
+            XzInfo::Bits { special, .. } => special.clone(),
+        }
+    }
+
+    /// Get the x bits of a value of the given width.
+    pub fn x_bits(&self, width: usize) -> BitVec {
+        match self {
+            XzInfo::None => BitVec::from_elem(width, false),
+            XzInfo::Bits { x, .. } => x.clone(),
+        }
+    }
+
+    /// Get an integer with all special bits set.
+    pub fn special_mask(&self) -> BigInt {
+        match self {
+            XzInfo::None => BigInt::zero(),
+            XzInfo::Bits { special, .. } => bit_mask(special),
+        }
+    }
+}
+
 /// Create a new tombstone value.
 pub fn make_error<'a>(ty: &'a UnpackedType<'a>) -> ValueData<'a> {
     ValueData {
@@ -146,12 +604,7 @@ pub fn make_int<'a>(ty: &'a UnpackedType<'a>, value: BigInt) -> ValueData<'a> {
         Some(x) => x,
         None => panic!("make_int got type `{}` which has no size", ty),
     };
-    make_int_special(
-        ty,
-        value,
-        BitVec::from_elem(w, false),
-        BitVec::from_elem(w, false),
-    )
+    make_int_xz(ty, value, XzInfo::None)
 }
 
 /// Create a new integer value with special bits.
@@ -163,13 +616,45 @@ pub fn make_int_special<'a>(
     special_bits: BitVec,
     x_bits: BitVec,
 ) -> ValueData<'a> {
+    make_int_xz(ty, value, XzInfo::new(special_bits, x_bits))
+}
+
+/// Create a new integer value with the given x and z information.
+///
+/// Panics if `ty` is not an integer type. Truncates the value to `ty`.
+pub fn make_int_xz<'a>(ty: &'a UnpackedType<'a>, value: BigInt, xz: XzInfo) -> ValueData<'a> {
     let w = ty.get_bit_size().unwrap();
     ValueData {
         ty: ty,
-        kind: ValueKind::Int(value % (BigInt::from(1) << w), special_bits, x_bits),
+        kind: ValueKind::Int(value % (BigInt::from(1) << w), xz),
     }
 }
 
+/// Create a new integer value with every bit set to `bit`.
+///
+/// Panics if `ty` is not an integer type. Filling a two-valued type with x or z
+/// yields zero.
+pub fn make_fill<'a>(ty: &'a UnpackedType<'a>, bit: mir::FillBit) -> ValueData<'a> {
+    let w = ty.get_bit_size().unwrap();
+    let special = match bit {
+        mir::FillBit::X | mir::FillBit::Z => ty.domain() == ty::Domain::FourValued,
+        _ => false,
+    };
+    let value = match bit {
+        mir::FillBit::One => (BigInt::one() << w) - 1,
+        _ => BigInt::zero(),
+    };
+    let xz = if special {
+        XzInfo::Bits {
+            special: BitVec::from_elem(w, true),
+            x: BitVec::from_elem(w, bit == mir::FillBit::X),
+        }
+    } else {
+        XzInfo::None
+    };
+    make_int_xz(ty, value, xz)
+}
+
 /// Create a new time value.
 pub fn make_time<'a>(value: BigRational) -> ValueData<'a> {
     ValueData {
@@ -196,6 +681,18 @@ pub fn make_array<'a>(ty: &'a UnpackedType<'a>, elements: Vec<Value<'a>>) -> Val
     }
 }
 
+/// Create a new queue, dynamic array, or associative array value.
+pub fn make_aggregate<'a>(ty: &'a UnpackedType<'a>, elements: Vec<Value<'a>>) -> ValueData<'a> {
+    assert!(match ty.outermost_dim() {
+        Some(ty::Dim::Unpacked(dim)) => dim.get_size().is_none(),
+        _ => false,
+    });
+    ValueData {
+        ty,
+        kind: ValueKind::Aggregate(elements),
+    }
+}
+
 /// Create a new string value.
 pub fn make_string<'a>(ty: &'a UnpackedType<'a>, bytes: Vec<u8>) -> ValueData<'a> {
     assert!(ty.is_string());
@@ -205,27 +702,442 @@ pub fn make_string<'a>(ty: &'a UnpackedType<'a>, bytes: Vec<u8>) -> ValueData<'a
     }
 }
 
+/// Convert a value to JSON.
+///
+/// The result is an object with the type of the value rendered as a string in
+/// `type`, and the value itself in `value`:
+///
+/// - integers as an object with the sign-interpreted decimal value in `int`,
+///   the hexadecimal bit pattern in `hex` if the value has no x or z bits, and
+///   the positions of x and z bits as arrays in `x` and `z`;
+/// - time values as a rational string in seconds, e.g. `"1/1000000000"`;
+/// - strings as strings;
+/// - structs and arrays as arrays of their converted fields or elements;
+/// - `void` and errors as `null`.
+///
+/// ```
+/// # use moore_common::{source::get_source_manager, Session};
+/// # use moore_svlog::{ast, lexer::Lexer, parser, preproc::Preprocessor};
+/// # use moore_svlog::{Context, GlobalArenas, GlobalContext, QueryDatabase};
+/// # let sess = Session::new();
+/// # let arena = GlobalArenas::default();
+/// # let source = get_source_manager().add(
+/// #     "to_json.sv",
+/// #     "module foo;
+/// #          typedef struct { logic [3:0] a; int b; } inner_t;
+/// #          typedef struct { inner_t i; bit c; } outer_t;
+/// #          localparam outer_t p = '{i: '{a: 4'b1xx1, b: -3}, c: 1};
+/// #      endmodule",
+/// # );
+/// # let preproc = Preprocessor::new(source, &[], &[]);
+/// # let file = parser::parse(Lexer::new(preproc), &arena.ast).unwrap();
+/// # let gcx = GlobalContext::new(&sess, &arena);
+/// # gcx.add_files(std::iter::once(&file));
+/// # let module = match file.items[0].data {
+/// #     ast::ItemData::ModuleDecl(ref m) => m,
+/// #     _ => unreachable!(),
+/// # };
+/// # let expr = match module.items[2].data {
+/// #     ast::ItemData::ParamDecl(ref p) => match p.kind {
+/// #         ast::ParamKind::Value(ref decls) => decls[0].expr.as_ref().unwrap(),
+/// #         _ => unreachable!(),
+/// #     },
+/// #     _ => unreachable!(),
+/// # };
+/// # let env = gcx.default_param_env();
+/// // Evaluate `'{i: '{a: 4'b1xx1, b: -3}, c: 1}` of type `outer_t`.
+/// let value = gcx.constant_value_of(ast::AnyNode::id(expr), env);
+/// assert_eq!(
+///     moore_svlog::value::to_json(value).to_string(),
+///     concat!(
+///         r#"{"type":"outer_t","value":["#,
+///         r#"{"type":"inner_t","value":["#,
+///         r#"{"type":"logic [3:0]","value":{"int":"9","x":[1,2]}},"#,
+///         r#"{"type":"int","value":{"hex":"fffffffd","int":"-3"}}]},"#,
+///         r#"{"type":"bit","value":{"hex":"1","int":"1"}}]}"#,
+///     )
+/// );
+/// ```
+///
+/// Only available with the `serde` feature enabled.
+#[cfg(feature = "serde")]
+pub fn to_json(value: &ValueData) -> serde_json::Value {
+    serde_json::json!({
+        "type": value.ty.to_string(),
+        "value": kind_to_json(&value.kind, Some(value)),
+    })
+}
+
+/// Convert a value kind to JSON, using the value's type if available.
+#[cfg(feature = "serde")]
+fn kind_to_json(kind: &ValueKind, data: Option<&ValueData>) -> serde_json::Value {
+    use serde_json::{json, Map, Value as Json};
+    match *kind {
+        ValueKind::Void | ValueKind::Error => Json::Null,
+        ValueKind::Int(ref v, ref xz) => {
+            let mut map = Map::new();
+            let v = data
+                .and_then(|d| d.get_int_signed())
+                .unwrap_or_else(|| v.clone());
+            map.insert("int".into(), json!(v.to_string()));
+            if !xz.any() {
+                if let Some(width) = data.and_then(|d| d.ty.get_bit_size()) {
+                    let bits = v.mod_floor(&(BigInt::one() << width));
+                    map.insert("hex".into(), json!(bits.to_str_radix(16)));
+                }
+            }
+            let positions = |x: bool| -> Vec<usize> {
+                match xz {
+                    XzInfo::None => vec![],
+                    XzInfo::Bits { special, .. } => (0..special.len())
+                        .filter(|&i| xz.is_special(i) && xz.is_x(i) == x)
+                        .collect(),
+                }
+            };
+            let (xs, zs) = (positions(true), positions(false));
+            if !xs.is_empty() {
+                map.insert("x".into(), json!(xs));
+            }
+            if !zs.is_empty() {
+                map.insert("z".into(), json!(zs));
+            }
+            Json::Object(map)
+        }
+        ValueKind::Time(ref v) => json!(v.to_string()),
+        ValueKind::String(ref v) => json!(String::from_utf8_lossy(v)),
+        ValueKind::StructOrArray(ref v) | ValueKind::Aggregate(ref v) => {
+            Json::Array(v.iter().map(|&v| to_json(v)).collect())
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValueData<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&to_json(self), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValueKind<'_> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&kind_to_json(self, None), serializer)
+    }
+}
+
+/// A sink for the results of constant evaluation.
+///
+/// Install a tracer with `GlobalContext::set_const_tracer` to observe every
+/// value computed by `const_node` and `const_mir_rvalue`. If no tracer
+/// is installed, the session prints the values to stderr when
+/// `Verbosity::CONSTS` is set.
+///
+/// ```
+/// # use moore_common::{source::get_source_manager, Session};
+/// # use moore_svlog::{ast, lexer::Lexer, parser, preproc::Preprocessor};
+/// # use moore_svlog::{Context, GlobalArenas, GlobalContext, QueryDatabase};
+/// use moore_svlog::value::{ConstTrace, ConstTraceKind, ConstTraceRecord};
+/// use std::cell::RefCell;
+///
+/// struct Collector(RefCell<Vec<(ConstTraceKind, String, String)>>);
+///
+/// impl<'a> ConstTrace<'a> for Collector {
+///     fn trace(&self, record: &ConstTraceRecord<'a>) {
+///         self.0.borrow_mut().push((
+///             record.kind,
+///             record.input_text.clone(),
+///             format!("{:x}", record.result_value),
+///         ));
+///     }
+/// }
+///
+/// let sess = Session::new();
+/// let arena = GlobalArenas::default();
+/// let collector = Collector(Default::default());
+/// let source = get_source_manager().add(
+///     "const_trace.sv",
+///     "module foo; localparam int a = 2 + 3; endmodule",
+/// );
+/// let preproc = Preprocessor::new(source, &[], &[]);
+/// let file = parser::parse(Lexer::new(preproc), &arena.ast).unwrap();
+/// let gcx = GlobalContext::new(&sess, &arena);
+/// gcx.set_const_tracer(&collector);
+/// gcx.add_files(std::iter::once(&file));
+///
+/// // Find the `2 + 3` expression and evaluate it.
+/// let module = match file.items[0].data {
+///     ast::ItemData::ModuleDecl(ref m) => m,
+///     _ => unreachable!(),
+/// };
+/// let expr = match module.items[0].data {
+///     ast::ItemData::ParamDecl(ref p) => match p.kind {
+///         ast::ParamKind::Value(ref decls) => decls[0].expr.as_ref().unwrap(),
+///         _ => unreachable!(),
+///     },
+///     _ => unreachable!(),
+/// };
+/// let env = gcx.default_param_env();
+/// gcx.constant_value_of(ast::AnyNode::id(expr), env);
+///
+/// let records = collector.0.borrow();
+/// let last = records.last().unwrap();
+/// assert_eq!(last.0, ConstTraceKind::Node);
+/// assert_eq!(last.1, "2 + 3");
+/// assert_eq!(last.2, "32'sh5");
+/// ```
+pub trait ConstTrace<'a> {
+    /// Called for every constant value that is computed.
+    fn trace(&self, record: &ConstTraceRecord<'a>);
+}
+
+/// A single constant evaluation observed by a `ConstTrace`.
+#[derive(Debug, Clone)]
+pub struct ConstTraceRecord<'a> {
+    /// The location of the evaluated node.
+    pub span: Span,
+    /// What has been evaluated.
+    pub kind: ConstTraceKind,
+    /// The source text of the evaluated node.
+    pub input_text: String,
+    /// The type of the result.
+    pub result_ty: &'a UnpackedType<'a>,
+    /// The resulting value.
+    pub result_value: Value<'a>,
+}
+
+/// The kind of node for which a constant value has been computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstTraceKind {
+    /// An AST/HIR node, evaluated by `const_node`.
+    Node,
+    /// An MIR rvalue, evaluated by `const_mir_rvalue`.
+    Mir,
+}
+
+/// Formats the record as `<line>: const(<input>) = <type>, <value>`.
+impl std::fmt::Display for ConstTraceRecord<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}({}) = {}, {:x}",
+            self.span.begin().human_line(),
+            match self.kind {
+                ConstTraceKind::Node => "const",
+                ConstTraceKind::Mir => "const_mir",
+            },
+            self.input_text,
+            self.result_ty,
+            self.result_value
+        )
+    }
+}
+
+/// Prints the records to stderr if `Verbosity::CONSTS` is set.
+impl<'a> ConstTrace<'a> for Session {
+    fn trace(&self, record: &ConstTraceRecord<'a>) {
+        if self.has_verbosity(Verbosity::CONSTS) {
+            eprintln!("{}", record);
+        }
+    }
+}
+
+/// Statistics about constant evaluation.
+///
+/// The counters are collected for the lifetime of a `GlobalContext` and can be
+/// obtained through `GlobalContext::const_stats`. The driver prints them at
+/// the end of compilation if `Verbosity::CONST_STATS` is set.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConstStats {
+    /// The number of calls to `Context::constant_value_of`.
+    pub constant_value_of: usize,
+    /// The number of calls to `constant_value_of` that were answered from the
+    /// query cache.
+    pub cache_hits: usize,
+    /// The number of nodes evaluated by `const_node`.
+    pub const_node: usize,
+    /// The number of MIR rvalues evaluated by `const_mir_rvalue`.
+    pub const_mir_rvalue: usize,
+    /// The number of values created by `Context::intern_value`.
+    pub interned_values: usize,
+    /// The number of error tombstones produced by `const_node` and
+    /// `const_mir_rvalue`.
+    pub tombstones: usize,
+    /// The number of nodes evaluated by `const_node`, per enclosing module.
+    pub per_module: HashMap<Name, usize>,
+}
+
+impl ConstStats {
+    /// Get the `n` modules with the most evaluated nodes, most expensive
+    /// first.
+    pub fn top_modules(&self, n: usize) -> Vec<(Name, usize)> {
+        let mut modules: Vec<_> = self.per_module.iter().map(|(&m, &c)| (m, c)).collect();
+        modules.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.as_str().cmp(&b.0.as_str())));
+        modules.truncate(n);
+        modules
+    }
+}
+
+/// Formats the statistics as a table, listing the ten most expensive modules.
+impl std::fmt::Display for ConstStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Constant evaluation statistics:")?;
+        writeln!(f, "  constant_value_of calls: {}", self.constant_value_of)?;
+        writeln!(f, "  cache hits: {}", self.cache_hits)?;
+        writeln!(f, "  const_node evaluations: {}", self.const_node)?;
+        writeln!(
+            f,
+            "  const_mir_rvalue evaluations: {}",
+            self.const_mir_rvalue
+        )?;
+        writeln!(f, "  interned values: {}", self.interned_values)?;
+        writeln!(f, "  tombstones: {}", self.tombstones)?;
+        writeln!(f, "  Top modules by evaluation count:")?;
+        for (module, count) in self.top_modules(10) {
+            writeln!(f, "    {}: {}", module, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// The reason why a node has no constant value.
+///
+/// Constant evaluation emits a diagnostic wherever an error originates, and
+/// passes the error on to everything that depends on the failed value. Use
+/// `Context::try_constant_value_of` to obtain the error for a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstEvalError {
+    /// A parameter was neither assigned nor has a default value.
+    UnboundParameter {
+        /// The parameter.
+        node: NodeId,
+        /// The environment in which the parameter was looked up.
+        env: ParamEnv,
+    },
+    /// An expression refers to something that is not constant, such as a
+    /// variable or a port.
+    NotConstant {
+        /// The offending expression.
+        span: Span,
+    },
+    /// The value depends on itself.
+    Circular {
+        /// The nodes being evaluated, starting with the re-entered one.
+        chain: Vec<(NodeId, ParamEnv)>,
+    },
+    /// The evaluated code is invalid, such as a division by zero or an
+    /// operand of the wrong kind, or an error tombstone.
+    Invalid {
+        /// The offending expression or declaration.
+        span: Span,
+    },
+    /// An internal error, which has been reported as a compiler bug.
+    Internal {
+        /// The expression being evaluated.
+        span: Span,
+    },
+}
+
+impl From<ConstEvalError> for () {
+    fn from(_: ConstEvalError) {}
+}
+
+/// Pass on a value that is not an error tombstone, or report the tombstone as
+/// an invalid expression at `span`.
+fn reject_tombstone<'a>(
+    value: Value<'a>,
+    span: Span,
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    if value.is_error() {
+        Err(ConstEvalError::Invalid { span })
+    } else {
+        Ok(value)
+    }
+}
+
+/// Report a computed constant to the context's tracer.
+fn trace_const<'a>(cx: &impl Context<'a>, kind: ConstTraceKind, span: Span, value: Value<'a>) {
+    let tracer: &dyn ConstTrace<'a> = match cx.const_tracer() {
+        Some(tracer) => tracer,
+        None if cx.sess().has_verbosity(Verbosity::CONSTS) => cx.sess(),
+        None => return,
+    };
+    tracer.trace(&ConstTraceRecord {
+        span,
+        kind,
+        input_text: span.extract(),
+        result_ty: value.ty,
+        result_value: value,
+    });
+}
+
 /// Determine the constant value of a node.
+///
+/// The result is memoized per node and parameter environment. Use
+/// `Context::try_constant_value_of` or `Context::constant_value_of` instead of
+/// calling this query directly, such that circular dependencies are diagnosed
+/// rather than aborting compilation.
 #[moore_derive::query]
-pub(crate) fn constant_value_of<'a>(
+pub(crate) fn const_node<'a>(
     cx: &impl Context<'a>,
     node_id: NodeId,
     env: ParamEnv,
-) -> Value<'a> {
-    let v = constant_value_of_inner(cx, node_id, env);
-    if cx.sess().has_verbosity(Verbosity::CONSTS) {
-        let span = cx.span(node_id);
-        let ext = span.extract();
-        let line = span.begin().human_line();
-        println!("{}: const({}) = {}, {}", line, ext, v.ty, v.kind);
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    let span = cx.span(node_id);
+    let v = const_node_inner(cx, node_id, env).and_then(|v| reject_tombstone(v, span));
+    let traced = match v {
+        Ok(v) => v,
+        Err(_) => cx.intern_value(make_error(UnpackedType::make_error())),
+    };
+    trace_const(cx, ConstTraceKind::Node, span, traced);
+    let module = cx.enclosing_module(node_id).map(|m| m.name.value);
+    let mut stats = cx.tables().const_stats.borrow_mut();
+    stats.const_node += 1;
+    stats.tombstones += v.is_err() as usize;
+    if let Some(module) = module {
+        *stats.per_module.entry(module).or_default() += 1;
     }
     v
 }
 
-fn constant_value_of_inner<'a>(cx: &impl Context<'a>, node_id: NodeId, env: ParamEnv) -> Value<'a> {
+/// Report a circular dependency in constant evaluation.
+///
+/// The `cycle` lists the nodes currently being evaluated, starting with the
+/// node that has been re-entered.
+pub(crate) fn report_const_cycle<'a>(cx: &impl Context<'a>, cycle: &[(NodeId, ParamEnv)]) {
+    let mut d =
+        DiagBuilder2::error("circular dependency in constant evaluation").span(cx.span(cycle[0].0));
+    for &(id, _) in cycle {
+        // Only mention the named constants involved, not every intermediate
+        // expression along the way.
+        let hir = match cx.hir_of(id) {
+            Ok(hir @ HirNode::ValueParam(..)) | Ok(hir @ HirNode::GenvarDecl(..)) => hir,
+            _ => continue,
+        };
+        d = d
+            .add_note(format!("{} is part of the cycle", hir.desc_full()))
+            .span(hir.human_span());
+    }
+    cx.emit(d);
+}
+
+fn const_node_inner<'a>(
+    cx: &impl Context<'a>,
+    node_id: NodeId,
+    env: ParamEnv,
+) -> std::result::Result<Value<'a>, ConstEvalError> {
     let hir = match cx.hir_of(node_id) {
         Ok(x) => x,
-        _ => return cx.intern_value(make_error(UnpackedType::make_error())),
+        _ => {
+            return Err(ConstEvalError::Invalid {
+                span: cx.span(node_id),
+            })
+        }
     };
     match hir {
         HirNode::Expr(expr) => {
@@ -236,58 +1148,72 @@ fn constant_value_of_inner<'a>(cx: &impl Context<'a>, node_id: NodeId, env: Para
             let env_data = cx.param_env_data(env);
             match env_data.find_value(node_id) {
                 Some(ParamEnvBinding::Indirect(assigned_id)) => {
-                    return cx.constant_value_of(assigned_id.id(), assigned_id.env())
+                    return cx.try_constant_value_of(assigned_id.id(), assigned_id.env())
                 }
-                Some(ParamEnvBinding::Direct(v)) => return v,
+                Some(ParamEnvBinding::Direct(v)) => return Ok(v),
                 _ => (),
             }
             if let Some(default) = param.default {
-                return cx.constant_value_of(default, env);
+                return cx.try_constant_value_of(default, env);
             }
-            let d = DiagBuilder2::error(format!(
+            let mut d = DiagBuilder2::error(format!(
                 "{} not assigned and has no default",
                 param.desc_full(),
-            ));
-            let contexts = cx.param_env_contexts(env);
-            for &context in &contexts {
-                cx.emit(
-                    d.clone()
-                        .span(cx.span(context))
-                        .add_note("Parameter declared here:")
-                        .span(param.human_span()),
-                );
-            }
-            if contexts.is_empty() {
-                cx.emit(d.span(param.human_span()));
+            ))
+            .span(param.human_span());
+            let bindings = param_env_text(cx, env);
+            if !bindings.is_empty() {
+                d = d.add_note(format!("Assigned in this instance: {}", bindings));
             }
-            cx.intern_value(make_error(UnpackedType::make_error()))
+            emit_in_env_contexts(cx, d, env, |context| {
+                Some(("Instantiated here:".to_string(), cx.span(context)))
+            });
+            Err(ConstEvalError::UnboundParameter { node: node_id, env })
         }
         HirNode::GenvarDecl(decl) => {
             let env_data = cx.param_env_data(env);
             match env_data.find_value(node_id) {
                 Some(ParamEnvBinding::Indirect(assigned_id)) => {
-                    return cx.constant_value_of(assigned_id.id(), assigned_id.env())
+                    return cx.try_constant_value_of(assigned_id.id(), assigned_id.env())
                 }
-                Some(ParamEnvBinding::Direct(v)) => return v,
+                Some(ParamEnvBinding::Direct(v)) => return Ok(v),
                 _ => (),
             }
             if let Some(init) = decl.init {
-                return cx.constant_value_of(init, env);
+                return cx.try_constant_value_of(init, env);
             }
             cx.emit(
                 DiagBuilder2::error(format!("{} not initialized", decl.desc_full()))
                     .span(decl.human_span()),
             );
-            cx.intern_value(make_error(UnpackedType::make_error()))
+            Err(ConstEvalError::Invalid {
+                span: decl.human_span(),
+            })
         }
+        HirNode::VarDecl(decl) if decl.konst => match decl.init {
+            Some(init) => cx.try_constant_value_of(init, env),
+            None => {
+                cx.emit(
+                    DiagBuilder2::error(format!("{} not initialized", decl.desc_full()))
+                        .span(decl.human_span()),
+                );
+                Err(ConstEvalError::Invalid {
+                    span: decl.human_span(),
+                })
+            }
+        },
         HirNode::EnumVariant(var) => match var.value {
-            Some(v) => cx.constant_value_of(v, env),
+            Some(v) => cx.try_constant_value_of(v, env),
             None => {
                 let ty = match cx.type_of(node_id, env) {
                     Ok(x) => x,
-                    _ => return cx.intern_value(make_error(UnpackedType::make_error())),
+                    _ => {
+                        return Err(ConstEvalError::Invalid {
+                            span: cx.span(node_id),
+                        })
+                    }
                 };
-                cx.intern_value(make_int(ty, var.index.into()))
+                Ok(cx.intern_int(ty, var.index.into()))
             }
         },
         _ => {
@@ -295,7 +1221,9 @@ fn constant_value_of_inner<'a>(cx: &impl Context<'a>, node_id: NodeId, env: Para
                 DiagBuilder2::error(format!("{} has no constant value", hir.desc_full()))
                     .span(hir.human_span()),
             );
-            cx.intern_value(make_error(UnpackedType::make_error()))
+            Err(ConstEvalError::NotConstant {
+                span: hir.human_span(),
+            })
         }
     }
 }
@@ -307,19 +1235,20 @@ fn constant_value_of_inner<'a>(cx: &impl Context<'a>, node_id: NodeId, env: Para
 pub(crate) fn const_mir_rvalue_int<'a>(
     cx: &impl Context<'a>,
     mir: Ref<'a, mir::Rvalue<'a>>,
-) -> Result<&'a num::BigInt> {
-    match cx.const_mir_rvalue(mir).kind {
+) -> std::result::Result<&'a num::BigInt, ConstEvalError> {
+    match cx.const_mir_rvalue(mir)?.kind {
         ValueKind::Int(ref x, ..) => Ok(x),
-        ValueKind::Error => Err(()),
         _ => {
-            cx.emit(
+            emit_const_diag(
+                cx,
+                mir.0,
                 DiagBuilder2::error(format!(
                     "`{}` is not a constant integer",
                     mir.span.extract()
                 ))
                 .span(mir.span),
             );
-            Err(())
+            Err(ConstEvalError::Invalid { span: mir.span })
         }
     }
 }
@@ -331,45 +1260,60 @@ pub(crate) fn const_mir_rvalue_int<'a>(
 pub(crate) fn const_mir_rvalue_string<'a>(
     cx: &impl Context<'a>,
     mir: Ref<'a, mir::Rvalue<'a>>,
-) -> Result<&'a Vec<u8>> {
-    match cx.const_mir_rvalue(mir).kind {
+) -> std::result::Result<&'a Vec<u8>, ConstEvalError> {
+    match cx.const_mir_rvalue(mir)?.kind {
         ValueKind::String(ref x) => Ok(x),
-        ValueKind::Error => Err(()),
         _ => {
-            cx.emit(
+            emit_const_diag(
+                cx,
+                mir.0,
                 DiagBuilder2::error(format!("`{}` is not a constant string", mir.span.extract()))
                     .span(mir.span),
             );
-            Err(())
+            Err(ConstEvalError::Invalid { span: mir.span })
         }
     }
 }
 
 /// Determine the constant value of an MIR rvalue.
+///
+/// The value is never an error tombstone. Aggregates may contain tombstones
+/// for elements that have no constant value, however.
 #[moore_derive::query]
 pub(crate) fn const_mir_rvalue<'a>(
     cx: &impl Context<'a>,
     Ref(mir): Ref<'a, mir::Rvalue<'a>>,
-) -> Value<'a> {
-    let v = const_mir_rvalue_inner(cx, mir);
-    if cx.sess().has_verbosity(Verbosity::CONSTS) {
-        let ext = mir.span.extract();
-        let line = mir.span.begin().human_line();
-        println!("{}: const_mir({}) = {}, {}", line, ext, v.ty, v.kind);
-    }
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    let v = const_mir_rvalue_inner(cx, mir).and_then(|v| reject_tombstone(v, mir.span));
+    let traced = match v {
+        Ok(v) => v,
+        Err(_) => cx.intern_value(make_error(mir.ty)),
+    };
+    trace_const(cx, ConstTraceKind::Mir, mir.span, traced);
+    let mut stats = cx.tables().const_stats.borrow_mut();
+    stats.const_mir_rvalue += 1;
+    stats.tombstones += v.is_err() as usize;
     v
 }
 
-fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -> Value<'a> {
+fn const_mir_rvalue_inner<'a>(
+    cx: &impl Context<'a>,
+    mir: &'a mir::Rvalue<'a>,
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    if let mir::RvalueKind::Const(value) = mir.kind {
+        if value.is_error() {
+            return Err(const_folded_error(cx, mir));
+        }
+    }
+
     // Propagate MIR tombstones immediately.
     if mir.is_error() {
-        return cx.intern_value(make_error(mir.ty));
+        return Err(ConstEvalError::Invalid { span: mir.span });
     }
 
     match mir.kind {
         // TODO: Casts are just transparent at the moment. That's pretty bad.
         mir::RvalueKind::CastValueDomain { value, .. }
-        | mir::RvalueKind::CastSign(_, value)
         | mir::RvalueKind::Truncate(_, value)
         | mir::RvalueKind::ZeroExtend(_, value)
         | mir::RvalueKind::SignExtend(_, value) => {
@@ -379,65 +1323,119 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                 value.ty,
                 mir.ty
             );
-            let v = cx.const_mir_rvalue(value.into());
+            let v = cx.const_mir_rvalue(value.into())?;
+
+            // Warn if ignoring a truncation changes the value.
+            if let (mir::RvalueKind::Truncate(size, _), ValueKind::Int(ref int, _)) =
+                (&mir.kind, &v.kind)
+            {
+                if int.bits() as usize > *size {
+                    emit_const_diag(
+                        cx,
+                        mir,
+                        DiagBuilder2::warning(format!(
+                            "truncation of `{}` to {} bits ignored during constant evaluation",
+                            value.span.extract(),
+                            size
+                        ))
+                        .code(DiagCode::CAST_IGNORED)
+                        .span(mir.span)
+                        .add_note(format!(
+                            "The value {} is used as `{}` without being truncated.",
+                            int, mir.ty
+                        )),
+                    );
+                }
+            }
+
             // TODO: This is an incredibly ugly hack.
-            cx.intern_value(ValueData {
+            Ok(cx.intern_value(ValueData {
                 ty: mir.ty,
                 kind: v.kind.clone(),
-            })
+            }))
+        }
+
+        // Sign casts such as `$signed` and `$unsigned` keep the bit pattern,
+        // but change how it is interpreted in subsequent operations.
+        mir::RvalueKind::CastSign(_, value) => {
+            let v = cx.const_mir_rvalue(value.into())?;
+            let cast = ValueData {
+                ty: mir.ty,
+                kind: v.kind.clone(),
+            };
+            match (cast.get_int_signed(), &v.kind) {
+                (Some(int), ValueKind::Int(_, xz)) => {
+                    Ok(cx.intern_value(make_int_xz(mir.ty, int, xz.clone())))
+                }
+                _ => const_operand_bug(cx, mir, &[v]),
+            }
         }
 
         mir::RvalueKind::Transmute(value) => {
-            let v = cx.const_mir_rvalue(value.into());
-            cx.intern_value(ValueData {
+            let v = cx.const_mir_rvalue(value.into())?;
+            Ok(cx.intern_value(ValueData {
                 ty: mir.ty,
                 kind: v.kind.clone(),
-            })
+            }))
         }
 
         mir::RvalueKind::CastToBool(value) => {
-            let value = cx.const_mir_rvalue(value.into());
-            if value.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
-            cx.intern_value(make_int(mir.ty, (value.is_true() as usize).into()))
+            let value = cx.const_mir_rvalue(value.into())?;
+            Ok(cx.intern_int(mir.ty, (value.is_true() as usize).into()))
         }
 
         mir::RvalueKind::ApplyTimescale(value, ref scale) => {
-            let value = cx.const_mir_rvalue(value.into());
-            if value.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let value = cx.const_mir_rvalue(value.into())?;
             let int = value
                 .get_int()
                 .expect("value to be timescaled should be int");
-            cx.intern_value(make_time(BigRational::from(int.clone()) * scale).into())
+            Ok(cx.intern_value(make_time(BigRational::from(int.clone()) * scale).into()))
         }
 
-        mir::RvalueKind::ConstructArray(ref values) => cx.intern_value(make_array(
-            mir.ty,
-            (0..values.len())
-                .map(|index| cx.const_mir_rvalue(values[&index].into()))
-                .collect(),
-        )),
+        mir::RvalueKind::ConstructArray(ref values) => {
+            // Elements which are not explicitly provided assume the default
+            // value of the element type.
+            let length = mir
+                .ty
+                .outermost_dim()
+                .and_then(|dim| dim.get_size())
+                .unwrap_or(values.len());
+            let elem_ty = mir.ty.pop_dim(cx);
+            Ok(cx.intern_value(make_array(
+                mir.ty,
+                (0..length)
+                    .map(|index| match (values.get(&index), elem_ty) {
+                        (Some(&value), _) => const_element(cx, value),
+                        (None, Some(elem_ty)) => cx.type_default_value(elem_ty),
+                        (None, None) => cx.intern_value(make_error(mir.ty)),
+                    })
+                    .collect(),
+            )))
+        }
 
-        mir::RvalueKind::ConstructStruct(ref values) => cx.intern_value(make_struct(
-            mir.ty,
-            values
+        mir::RvalueKind::ConstructStruct(ref values) => {
+            // Always produce as many fields as the struct has members, such
+            // that member accesses remain valid even if error recovery dropped
+            // some of the values.
+            let mut fields: Vec<_> = values
                 .iter()
-                .map(|&value| cx.const_mir_rvalue(value.into()))
-                .collect(),
-        )),
+                .map(|&value| const_element(cx, value))
+                .collect();
+            if let Some(strukt) = mir.ty.get_struct() {
+                for member in strukt.members.iter().skip(fields.len()) {
+                    fields.push(cx.intern_value(make_error(member.ty)));
+                }
+            }
+            Ok(cx.intern_value(make_struct(mir.ty, fields)))
+        }
 
-        mir::RvalueKind::Const(value) => value,
+        mir::RvalueKind::Const(value) => Ok(value),
+        mir::RvalueKind::Fill(bit) => Ok(cx.intern_value(make_fill(mir.ty, bit))),
 
         mir::RvalueKind::UnaryBitwise { op, arg } => {
-            let arg_val = cx.const_mir_rvalue(arg.into());
-            if arg_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let arg_val = cx.const_mir_rvalue(arg.into())?;
             match arg_val.kind {
-                ValueKind::Int(ref arg_int, ..) => cx.intern_value(make_int(
+                ValueKind::Int(ref arg_int, ..) => Ok(cx.intern_int(
                     mir.ty,
                     const_unary_bitwise_int(
                         cx,
@@ -446,56 +1444,71 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                         arg_int,
                     ),
                 )),
-                _ => unreachable!(),
+                _ => const_operand_bug(cx, mir, &[arg_val]),
             }
         }
 
         mir::RvalueKind::BinaryBitwise { op, lhs, rhs } => {
-            let lhs_val = cx.const_mir_rvalue(lhs.into());
-            let rhs_val = cx.const_mir_rvalue(rhs.into());
-            if lhs_val.is_error() || rhs_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let lhs_val = cx.const_mir_rvalue(lhs.into())?;
+            let rhs_val = cx.const_mir_rvalue(rhs.into())?;
             match (&lhs_val.kind, &rhs_val.kind) {
-                (ValueKind::Int(lhs_int, ..), ValueKind::Int(rhs_int, ..)) => {
-                    cx.intern_value(make_int(
-                        mir.ty,
-                        const_binary_bitwise_int(
-                            cx,
-                            mir.ty.simple_bit_vector(cx, mir.span),
-                            op,
-                            lhs_int,
-                            rhs_int,
-                        ),
-                    ))
+                (ValueKind::Int(_, lhs_xz), ValueKind::Int(_, rhs_xz))
+                    if lhs_xz.any() || rhs_xz.any() =>
+                {
+                    Ok(cx.intern_value(const_binary_bitwise_xz(mir.ty, op, lhs_val, rhs_val)))
                 }
-                _ => unreachable!(),
+                (ValueKind::Int(lhs_int, ..), ValueKind::Int(rhs_int, ..)) => Ok(cx.intern_int(
+                    mir.ty,
+                    const_binary_bitwise_int(
+                        cx,
+                        mir.ty.simple_bit_vector(cx, mir.span),
+                        op,
+                        lhs_int,
+                        rhs_int,
+                    ),
+                )),
+                _ => const_operand_bug(cx, mir, &[lhs_val, rhs_val]),
             }
         }
 
         mir::RvalueKind::IntUnaryArith { op, arg, .. } => {
-            let arg_val = cx.const_mir_rvalue(arg.into());
-            if arg_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let arg_val = cx.const_mir_rvalue(arg.into())?;
             match arg_val.kind {
-                ValueKind::Int(ref arg_int, ..) => cx.intern_value(make_int(
+                ValueKind::Int(ref arg_int, ..) => Ok(cx.intern_int(
                     mir.ty,
                     const_unary_arith_int(cx, mir.ty.simple_bit_vector(cx, mir.span), op, arg_int),
                 )),
-                _ => unreachable!(),
+                ValueKind::Time(ref arg_time) => match op {
+                    mir::IntUnaryArithOp::Neg => Ok(cx.intern_value(make_time(-arg_time))),
+                },
+                _ => const_operand_bug(cx, mir, &[arg_val]),
             }
         }
 
         mir::RvalueKind::IntBinaryArith { op, lhs, rhs, .. } => {
-            let lhs_val = cx.const_mir_rvalue(lhs.into());
-            let rhs_val = cx.const_mir_rvalue(rhs.into());
-            if lhs_val.is_error() || rhs_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let lhs_val = cx.const_mir_rvalue(lhs.into())?;
+            let rhs_val = cx.const_mir_rvalue(rhs.into())?;
             match (&lhs_val.kind, &rhs_val.kind) {
                 (ValueKind::Int(lhs_int, ..), ValueKind::Int(rhs_int, ..)) => {
-                    cx.intern_value(make_int(
+                    let what = match op {
+                        mir::IntBinaryArithOp::Div => "division",
+                        mir::IntBinaryArithOp::Mod => "modulo",
+                        _ => "",
+                    };
+                    if !what.is_empty() && rhs_int.is_zero() {
+                        emit_const_diag(
+                            cx,
+                            mir,
+                            add_operand_notes(
+                                cx,
+                                DiagBuilder2::error(format!("constant {} by zero", what))
+                                    .span(mir.span),
+                                &[("left", lhs, lhs_val), ("right", rhs, rhs_val)],
+                            ),
+                        );
+                        return Err(ConstEvalError::Invalid { span: mir.span });
+                    }
+                    Ok(cx.intern_int(
                         mir.ty,
                         const_binary_arith_int(
                             cx,
@@ -506,57 +1519,114 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                         ),
                     ))
                 }
-                _ => unreachable!(),
+                (ValueKind::Time(lhs_time), ValueKind::Time(rhs_time)) => {
+                    match const_binary_arith_time(cx, mir, op, lhs, rhs, lhs_time, rhs_time) {
+                        Ok(v) => Ok(cx.intern_value(make_time(v))),
+                        Err(()) => Err(ConstEvalError::Invalid { span: mir.span }),
+                    }
+                }
+                _ => const_operand_bug(cx, mir, &[lhs_val, rhs_val]),
             }
         }
 
         mir::RvalueKind::IntComp { op, lhs, rhs, .. } => {
-            let lhs_val = cx.const_mir_rvalue(lhs.into());
-            let rhs_val = cx.const_mir_rvalue(rhs.into());
-            if lhs_val.is_error() || rhs_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
+            let lhs_val = cx.const_mir_rvalue(lhs.into())?;
+            let rhs_val = cx.const_mir_rvalue(rhs.into())?;
+            if lhs_val.get_time().is_some() || rhs_val.get_time().is_some() {
+                let lhs_time = const_time_operand(cx, mir, lhs_val);
+                let rhs_time = const_time_operand(cx, mir, rhs_val);
+                return Ok(cx.intern_int(
+                    mir.ty,
+                    const_comp_int(
+                        cx,
+                        mir.ty.simple_bit_vector(cx, mir.span),
+                        op,
+                        &lhs_time,
+                        &rhs_time,
+                    ),
+                ));
             }
-            match (&lhs_val.kind, &rhs_val.kind) {
-                (ValueKind::Int(lhs_int, ..), ValueKind::Int(rhs_int, ..)) => {
-                    cx.intern_value(make_int(
-                        mir.ty,
-                        const_comp_int(
-                            cx,
-                            mir.ty.simple_bit_vector(cx, mir.span),
-                            op,
-                            lhs_int,
-                            rhs_int,
-                        ),
-                    ))
-                }
-                _ => unreachable!(),
+            // Compare the values as interpreted by the sign of their type.
+            match (lhs_val.get_int_signed(), rhs_val.get_int_signed()) {
+                (Some(lhs_int), Some(rhs_int)) => Ok(cx.intern_int(
+                    mir.ty,
+                    const_comp_int(
+                        cx,
+                        mir.ty.simple_bit_vector(cx, mir.span),
+                        op,
+                        &lhs_int,
+                        &rhs_int,
+                    ),
+                )),
+                _ => const_operand_bug(cx, mir, &[lhs_val, rhs_val]),
             }
         }
 
         mir::RvalueKind::Concat(ref values) => {
             let mut result = BigInt::zero();
             for &value in values {
-                result <<= value.ty.simple_bit_vector(cx, value.span).size;
-                result |= cx
-                    .const_mir_rvalue(value.into())
-                    .get_int()
-                    .expect("concat non-integer");
+                let size = value.ty.simple_bit_vector(cx, value.span).size;
+                if size == 0 {
+                    continue;
+                }
+                let value_int = const_int_operand(cx, value, "concatenate")?;
+                result <<= size;
+                result |= value_int.mod_floor(&(BigInt::one() << size));
             }
-            cx.intern_value(make_int(mir.ty, result))
+            Ok(cx.intern_int(mir.ty, result))
         }
 
-        mir::RvalueKind::Repeat(count, value) => {
-            let value_const = cx.const_mir_rvalue(value.into());
-            if value_const.is_error() {
-                return cx.intern_value(make_error(mir.ty));
+        mir::RvalueKind::StreamConcat {
+            dir,
+            slice,
+            ref values,
+        } => {
+            let mut stream = BigInt::zero();
+            let mut width = 0;
+            for &value in values {
+                let size = value.ty.simple_bit_vector(cx, value.span).size;
+                let value_int = const_int_operand(cx, value, "stream")?;
+                stream <<= size;
+                stream |= value_int.mod_floor(&(BigInt::one() << size));
+                width += size;
+            }
+
+            // Reverse the order of the slices for right-to-left streams. The
+            // first slice is taken from the LSB and ends up at the MSB.
+            if dir == ast::StreamDir::Out {
+                let mut reversed = BigInt::zero();
+                let mut offset = 0;
+                while offset < width {
+                    let chunk = std::cmp::min(slice, width - offset);
+                    reversed <<= chunk;
+                    reversed |= (&stream >> offset).mod_floor(&(BigInt::one() << chunk));
+                    offset += chunk;
+                }
+                stream = reversed;
+            }
+
+            // Left-align the stream in the result.
+            let result_width = mir.ty.simple_bit_vector(cx, mir.span).size;
+            if result_width >= width {
+                stream <<= result_width - width;
+            } else {
+                stream >>= width - result_width;
             }
-            let sbvt = value.ty.simple_bit_vector(cx, value.span);
+            Ok(cx.intern_int(mir.ty, stream))
+        }
+
+        mir::RvalueKind::Repeat(0, _) => Ok(cx.intern_int(mir.ty, BigInt::zero())),
+
+        mir::RvalueKind::Repeat(count, value) => {
+            let size = value.ty.simple_bit_vector(cx, value.span).size;
+            let value_int =
+                const_int_operand(cx, value, "replicate")?.mod_floor(&(BigInt::one() << size));
             let mut result = BigInt::zero();
             for _ in 0..count {
-                result <<= sbvt.size;
-                result |= value_const.get_int().expect("repeat non-integer");
+                result <<= size;
+                result |= &value_int;
             }
-            cx.intern_value(make_int(mir.ty, result))
+            Ok(cx.intern_int(mir.ty, result))
         }
 
         mir::RvalueKind::Assignment { .. }
@@ -565,18 +1635,110 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
         | mir::RvalueKind::Arg(_)
         | mir::RvalueKind::IntfSignal(..)
         | mir::RvalueKind::Intf(..) => {
-            cx.emit(DiagBuilder2::error("value is not constant").span(mir.span));
-            cx.intern_value(make_error(mir.ty))
+            emit_const_diag(
+                cx,
+                mir,
+                DiagBuilder2::error("value is not constant").span(mir.span),
+            );
+            Err(ConstEvalError::NotConstant { span: mir.span })
         }
 
         mir::RvalueKind::Member { value, field } => {
-            let value_const = cx.const_mir_rvalue(value.into());
-            if value_const.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let value_const = cx.const_mir_rvalue(value.into())?;
             match value_const.kind {
-                ValueKind::StructOrArray(ref fields) => fields[field],
-                _ => unreachable!("member access on non-struct should be caught in typeck"),
+                ValueKind::StructOrArray(ref fields) if field < fields.len() => Ok(fields[field]),
+                _ => const_invariant_error(
+                    cx,
+                    mir,
+                    format!(
+                        "member {} of constant `{}` does not exist",
+                        field, value_const
+                    ),
+                ),
+            }
+        }
+
+        mir::RvalueKind::UpdateMember {
+            value,
+            field: index,
+            new_value,
+        }
+        | mir::RvalueKind::UpdateIndex {
+            value,
+            index,
+            new_value,
+        } => {
+            let value_const = cx.const_mir_rvalue(value.into())?;
+            let new_const = cx.const_mir_rvalue(new_value.into())?;
+            let mut elements = match value_const.kind {
+                ValueKind::StructOrArray(ref elements) => elements.clone(),
+                _ => return const_operand_bug(cx, mir, &[value_const]),
+            };
+            match elements.get_mut(index) {
+                Some(slot) => *slot = new_const,
+                None => {
+                    emit_const_diag(
+                        cx,
+                        mir,
+                        DiagBuilder2::error(format!(
+                            "index {} out of bounds in constant `{}`",
+                            index, value.ty
+                        ))
+                        .span(mir.span)
+                        .add_note(format!(
+                            "Value has {} elements: {}",
+                            elements.len(),
+                            value_const
+                        )),
+                    );
+                    return Err(ConstEvalError::Invalid { span: mir.span });
+                }
+            }
+            Ok(cx.intern_value(ValueData {
+                ty: mir.ty,
+                kind: ValueKind::StructOrArray(elements),
+            }))
+        }
+
+        mir::RvalueKind::Inside {
+            value, ref members, ..
+        } => {
+            let value_val = cx.const_mir_rvalue(value.into())?;
+
+            // The result is 1 if any member matches, x if none matches but
+            // some comparisons are ambiguous due to x or z bits, and 0
+            // otherwise.
+            let mut result = Some(false);
+            for member in members {
+                let matched = match *member {
+                    mir::InsideMember::Single(single) => {
+                        let single_val = cx.const_mir_rvalue(single.into())?;
+                        const_wildcard_eq(value_val, single_val)
+                    }
+                    mir::InsideMember::Range(lo, hi) => {
+                        let lo_val = lo.map(|lo| cx.const_mir_rvalue(lo.into())).transpose()?;
+                        let hi_val = hi.map(|hi| cx.const_mir_rvalue(hi.into())).transpose()?;
+                        const_inside_range(value_val, lo_val, hi_val)
+                    }
+                };
+                result = match (result, matched) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (None, _) | (_, None) => None,
+                    _ => Some(false),
+                };
+            }
+            match result {
+                Some(result) => Ok(cx.intern_int(mir.ty, (result as usize).into())),
+                None => {
+                    let width = mir.ty.get_bit_size().unwrap();
+                    let x_bits = BitVec::from_elem(width, true);
+                    Ok(cx.intern_value(make_int_special(
+                        mir.ty,
+                        BigInt::zero(),
+                        x_bits.clone(),
+                        x_bits,
+                    )))
+                }
             }
         }
 
@@ -585,12 +1747,17 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
             true_value,
             false_value,
         } => {
-            let cond_val = cx.const_mir_rvalue(cond.into());
-            let true_val = cx.const_mir_rvalue(true_value.into());
-            let false_val = cx.const_mir_rvalue(false_value.into());
-            match cond_val.is_true() {
-                true => true_val,
-                false => false_val,
+            // Only evaluate the branch that is taken, such that guards like
+            // `N != 0 ? 8 / N : 0` never evaluate the guarded expression.
+            let cond_val = cx.const_mir_rvalue(cond.into())?;
+            match cond_val.truth() {
+                Some(true) => cx.const_mir_rvalue(true_value.into()),
+                Some(false) => cx.const_mir_rvalue(false_value.into()),
+                None => {
+                    let true_val = cx.const_mir_rvalue(true_value.into())?;
+                    let false_val = cx.const_mir_rvalue(false_value.into())?;
+                    Ok(const_merge_ternary(cx, mir.ty, true_val, false_val))
+                }
             }
         }
 
@@ -601,40 +1768,39 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
             amount,
             ..
         } => {
-            let value_val = cx.const_mir_rvalue(value.into());
-            let amount_val = cx.const_mir_rvalue(amount.into());
-            if value_val.is_error() || amount_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let value_val = cx.const_mir_rvalue(value.into())?;
+            let amount_val = cx.const_mir_rvalue(amount.into())?;
             match (&value_val.kind, &amount_val.kind) {
                 (ValueKind::Int(value_int, ..), ValueKind::Int(amount_int, ..)) => {
-                    cx.intern_value(make_int(
-                        mir.ty,
-                        const_shift_int(
-                            cx,
-                            value.ty.simple_bit_vector(cx, value.span),
-                            op,
-                            arith,
-                            value_int,
-                            amount_int,
-                        ),
-                    ))
+                    match const_shift_int(
+                        cx,
+                        mir,
+                        value.ty.simple_bit_vector(cx, value.span),
+                        op,
+                        arith,
+                        value_int,
+                        amount_int,
+                        &[
+                            ("shifted", value, value_val),
+                            ("amount", amount, amount_val),
+                        ],
+                    ) {
+                        Ok(v) => Ok(cx.intern_int(mir.ty, v)),
+                        Err(()) => Err(ConstEvalError::Invalid { span: mir.span }),
+                    }
                 }
-                _ => unreachable!(),
+                _ => const_operand_bug(cx, mir, &[value_val, amount_val]),
             }
         }
 
         mir::RvalueKind::Reduction { op, arg } => {
-            let arg_val = cx.const_mir_rvalue(arg.into());
-            if arg_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let arg_val = cx.const_mir_rvalue(arg.into())?;
             match arg_val.kind {
-                ValueKind::Int(ref arg_int, ..) => cx.intern_value(make_int(
+                ValueKind::Int(ref arg_int, ..) => Ok(cx.intern_int(
                     mir.ty,
                     const_reduction_int(cx, arg.ty.simple_bit_vector(cx, arg.span), op, arg_int),
                 )),
-                _ => unreachable!(),
+                _ => const_operand_bug(cx, mir, &[arg_val]),
             }
         }
 
@@ -644,18 +1810,34 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
             length,
             ..
         } => {
-            let inner_val = cx.const_mir_rvalue(value.into());
-            if inner_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
-            let base = match cx.const_mir_rvalue_int(Ref(base)) {
-                Ok(x) => x.to_isize().expect("base out of bounds"),
-                _ => return cx.intern_value(make_error(mir.ty)),
+            let inner_val = cx.const_mir_rvalue(value.into())?;
+            let base_int = cx.const_mir_rvalue_int(Ref(base))?;
+            let base = match base_int.to_isize() {
+                Some(x) => x,
+                None => {
+                    emit_const_diag(
+                        cx,
+                        mir,
+                        add_operand_notes(
+                            cx,
+                            DiagBuilder2::error(format!(
+                                "constant index {} is out of range",
+                                base_int
+                            ))
+                            .span(mir.span),
+                            &[
+                                ("indexed", value, inner_val),
+                                ("index", base, cx.const_mir_rvalue(base.into())?),
+                            ],
+                        ),
+                    );
+                    return Err(ConstEvalError::Invalid { span: mir.span });
+                }
             };
             match inner_val.kind {
                 // TODO: This magic should all be replaced by a dedicated
                 // arithmetic module which handles the semantics of SV properly.
-                ValueKind::Int(ref int, ref special_bits, ref x_bits) => {
+                ValueKind::Int(ref int, ref xz) => {
                     let length = std::cmp::max(length, 1); // bit-select same as length-1-select
                     let v = if base < 0 {
                         int << (-base) as usize
@@ -663,21 +1845,24 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                         int >> base as usize
                     };
                     let v = v % (BigInt::one() << length);
+                    if !xz.any() {
+                        return Ok(cx.intern_value(make_int(mir.ty, v)));
+                    }
                     let mut new_special_bits = BitVec::from_elem(length, false);
                     let mut new_x_bits = BitVec::from_elem(length, false);
                     for i in 0..length as isize {
                         if i >= base && i < base + length as isize {
-                            new_special_bits.set((i - base) as usize, special_bits[i as usize]);
-                            new_x_bits.set((i - base) as usize, x_bits[i as usize]);
+                            new_special_bits.set((i - base) as usize, xz.is_special(i as usize));
+                            new_x_bits.set((i - base) as usize, xz.is_x(i as usize));
                         }
                     }
-                    cx.intern_value(make_int_special(mir.ty, v, new_special_bits, new_x_bits))
+                    Ok(cx.intern_value(make_int_special(mir.ty, v, new_special_bits, new_x_bits)))
                 }
                 ValueKind::StructOrArray(ref values) if length == 0 => {
                     if base < 0 || base >= values.len() as isize {
-                        cx.type_default_value(mir.ty)
+                        Ok(cx.type_default_value(mir.ty))
                     } else {
-                        values[base as usize]
+                        Ok(values[base as usize])
                     }
                 }
                 ValueKind::StructOrArray(ref values) => {
@@ -700,27 +1885,25 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                             new_values.push(default);
                         }
                     }
-                    cx.intern_value(make_array(mir.ty, new_values))
+                    Ok(cx.intern_value(make_array(mir.ty, new_values)))
                 }
-                _ => unreachable!("const index op on value {:?}", inner_val),
+                _ => const_invariant_error(
+                    cx,
+                    mir,
+                    format!("cannot index into constant `{}`", inner_val),
+                ),
             }
         }
 
         // Pack a string into a vector.
-        mir::RvalueKind::PackString(value) => match cx.const_mir_rvalue_string(value.into()) {
-            Ok(v) => cx.intern_value(make_int(
-                mir.ty,
-                BigInt::from_bytes_be(num::bigint::Sign::Plus, v),
-            )),
-            Err(()) => cx.intern_value(make_error(mir.ty)),
-        },
+        mir::RvalueKind::PackString(value) => {
+            let v = cx.const_mir_rvalue_string(value.into())?;
+            Ok(cx.intern_int(mir.ty, BigInt::from_bytes_be(num::bigint::Sign::Plus, v)))
+        }
 
         // Unpack a string from a vector.
         mir::RvalueKind::UnpackString(value) => {
-            let mut konst = match cx.const_mir_rvalue_int(value.into()) {
-                Ok(v) => v.clone(),
-                Err(()) => return cx.intern_value(make_error(mir.ty)),
-            };
+            let mut konst = cx.const_mir_rvalue_int(value.into())?.clone();
             let mut bytes = vec![];
             while !konst.is_zero() {
                 let byte = (&konst & BigInt::from(0xFF)).to_usize().unwrap() as u8;
@@ -730,28 +1913,203 @@ fn const_mir_rvalue_inner<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -
                 konst >>= 8;
             }
             bytes.reverse();
-            cx.intern_value(make_string(mir.ty, bytes))
+            Ok(cx.intern_value(make_string(mir.ty, bytes)))
         }
 
         mir::RvalueKind::StringComp { op, lhs, rhs, .. } => {
-            let lhs_val = cx.const_mir_rvalue(lhs.into());
-            let rhs_val = cx.const_mir_rvalue(rhs.into());
-            if lhs_val.is_error() || rhs_val.is_error() {
-                return cx.intern_value(make_error(mir.ty));
-            }
+            let lhs_val = cx.const_mir_rvalue(lhs.into())?;
+            let rhs_val = cx.const_mir_rvalue(rhs.into())?;
             match (&lhs_val.kind, &rhs_val.kind) {
-                (ValueKind::String(lhs_string), ValueKind::String(rhs_string)) => cx.intern_value(
-                    make_int(mir.ty, const_comp_string(cx, op, lhs_string, rhs_string)),
-                ),
-                _ => unreachable!(),
+                (ValueKind::String(lhs_string), ValueKind::String(rhs_string)) => Ok(cx
+                    .intern_value(make_int(
+                        mir.ty,
+                        const_comp_string(cx, op, lhs_string, rhs_string),
+                    ))),
+                _ => const_operand_bug(cx, mir, &[lhs_val, rhs_val]),
             }
         }
 
         // TODO(fschuiki): Fix this once we support constant function calls.
-        mir::RvalueKind::Call { .. } => cx.intern_value(make_error(mir.ty)),
+        mir::RvalueKind::Call { .. } => Err(ConstEvalError::NotConstant { span: mir.span }),
 
         // Propagate tombstones.
-        mir::RvalueKind::Error => cx.intern_value(make_error(mir.ty)),
+        mir::RvalueKind::Error => Err(ConstEvalError::Invalid { span: mir.span }),
+    }
+}
+
+/// Emit a diagnostic about the constant evaluation of an rvalue.
+///
+/// If the rvalue lives in the parameter environment of an instance, notes are
+/// added that point at the instances whose parameter bindings led to the
+/// evaluation.
+fn emit_const_diag<'a>(cx: &impl Context<'a>, mir: &mir::Rvalue<'a>, diag: DiagBuilder2) {
+    if mir.env == cx.default_param_env() {
+        cx.emit(diag);
+        return;
+    }
+    let bindings = param_env_text(cx, mir.env);
+    emit_in_env_contexts(cx, diag, mir.env, |context| {
+        let inst = match cx.hir_of(context) {
+            Ok(HirNode::Inst(inst)) => inst,
+            _ => return None,
+        };
+        let msg = if bindings.is_empty() {
+            format!("while elaborating {}", inst.desc_full())
+        } else {
+            format!("while elaborating {} with {}", inst.desc_full(), bindings)
+        };
+        Some((msg, inst.human_span()))
+    });
+}
+
+/// Report a violated invariant of a constant value.
+///
+/// This is used instead of panicking in places where error recovery upstream
+/// may have produced malformed values.
+fn const_invariant_error<'a>(
+    cx: &impl Context<'a>,
+    mir: &'a mir::Rvalue<'a>,
+    msg: String,
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    cx.emit(DiagBuilder2::bug(msg).span(mir.span).add_note(format!(
+        "Encountered at {}:{}",
+        file!(),
+        line!()
+    )));
+    Err(ConstEvalError::Internal { span: mir.span })
+}
+
+/// Determine why a constant folded during MIR lowering has no value.
+///
+/// References to parameters and other constants are replaced by their value
+/// when the MIR is lowered, which is a tombstone if the evaluation failed. The
+/// reason is obtained again from the referenced declaration, whose evaluation
+/// is memoized. If that evaluation is still in progress, the reference closes
+/// a cycle, which has already been reported.
+fn const_folded_error<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -> ConstEvalError {
+    let invalid = ConstEvalError::Invalid { span: mir.span };
+    match cx.hir_of(mir.origin) {
+        Ok(HirNode::Expr(hir::Expr {
+            kind: hir::ExprKind::Ident(..) | hir::ExprKind::Scope(..),
+            ..
+        })) => (),
+        _ => return invalid,
+    }
+    let key = match cx.resolve_node(mir.origin, mir.env) {
+        Ok(binding) => (binding, mir.env),
+        Err(()) => return invalid,
+    };
+    let cycle_start = cx
+        .tables()
+        .const_eval_stack
+        .borrow()
+        .iter()
+        .position(|&x| x == key);
+    if let Some(start) = cycle_start {
+        let chain = cx.tables().const_eval_stack.borrow()[start..].to_vec();
+        return ConstEvalError::Circular { chain };
+    }
+    match cx.try_constant_value_of(key.0, key.1) {
+        Err(err) => err,
+        Ok(_) => invalid,
+    }
+}
+
+/// Evaluate an element of an array or struct.
+///
+/// Elements without a constant value become tombstones within the aggregate,
+/// such that the remaining elements can still be accessed.
+fn const_element<'a>(cx: &impl Context<'a>, mir: &'a mir::Rvalue<'a>) -> Value<'a> {
+    match cx.const_mir_rvalue(mir.into()) {
+        Ok(value) => value,
+        Err(_) => cx.intern_value(make_error(mir.ty)),
+    }
+}
+
+/// An operand of an rvalue, together with the name used to refer to it in
+/// diagnostics and the value it evaluated to.
+type ConstOperand<'a> = (&'static str, &'a mir::Rvalue<'a>, Value<'a>);
+
+/// Add notes to a diagnostic that show what the operands of an rvalue
+/// evaluated to.
+///
+/// Operands that directly refer to a value parameter additionally point at
+/// the place where the parameter is bound.
+fn add_operand_notes<'a>(
+    cx: &impl Context<'a>,
+    diag: DiagBuilder2,
+    operands: &[ConstOperand<'a>],
+) -> DiagBuilder2 {
+    let mut diag = diag;
+    for &(name, operand, value) in operands {
+        let (msg, span) = match operand_param_binding(cx, operand) {
+            Some((param, span)) => (
+                format!(
+                    "{} operand evaluates to {:#}, from parameter `{}` bound here",
+                    name, value, param
+                ),
+                span,
+            ),
+            None => (
+                format!("{} operand evaluates to {:#}", name, value),
+                operand.span,
+            ),
+        };
+        diag = diag.add_note(msg).span(span);
+    }
+    diag
+}
+
+/// Find the value parameter an operand refers to, and where it is bound.
+fn operand_param_binding<'a>(
+    cx: &impl Context<'a>,
+    operand: &'a mir::Rvalue<'a>,
+) -> Option<(Name, Span)> {
+    match cx.hir_of(operand.origin) {
+        Ok(HirNode::Expr(hir::Expr {
+            kind: hir::ExprKind::Ident(..),
+            ..
+        })) => (),
+        _ => return None,
+    }
+    let param = match cx.hir_of(cx.resolve_node(operand.origin, operand.env).ok()?) {
+        Ok(HirNode::ValueParam(param)) => param,
+        _ => return None,
+    };
+    let span = match cx.param_env_data(operand.env).find_value(param.id) {
+        Some(ParamEnvBinding::Indirect(assigned_id)) => cx.span(assigned_id.id()),
+        _ => param
+            .default
+            .map(|id| cx.span(id))
+            .unwrap_or(param.name.span),
+    };
+    Some((param.name.value, span))
+}
+
+/// Evaluate an operand which must be an integer, or emit a diagnostic.
+fn const_int_operand<'a>(
+    cx: &impl Context<'a>,
+    mir: &'a mir::Rvalue<'a>,
+    action: &str,
+) -> std::result::Result<BigInt, ConstEvalError> {
+    let value = cx.const_mir_rvalue(mir.into())?;
+    match value.get_int() {
+        Some(v) => Ok(v.clone()),
+        None => {
+            emit_const_diag(
+                cx,
+                mir,
+                DiagBuilder2::error(format!(
+                    "cannot {} value `{}` of type `{}`",
+                    action,
+                    mir.span.extract(),
+                    value.ty
+                ))
+                .span(mir.span)
+                .add_note(format!("Value is {}", value)),
+            );
+            Err(ConstEvalError::Invalid { span: mir.span })
+        }
     }
 }
 
@@ -780,6 +2138,144 @@ fn const_binary_bitwise_int<'gcx>(
     }
 }
 
+/// Apply a binary bitwise operator to integers with x or z bits.
+///
+/// Per IEEE 1800-2017 section 11.4.8, a known 0 dominates `&` and a known 1
+/// dominates `|`. All other combinations involving x or z bits yield x.
+fn const_binary_bitwise_xz<'gcx>(
+    ty: &'gcx UnpackedType<'gcx>,
+    op: mir::BinaryBitwiseOp,
+    lhs: Value<'gcx>,
+    rhs: Value<'gcx>,
+) -> ValueData<'gcx> {
+    let width = ty.get_bit_size().unwrap();
+    let all = (BigInt::one() << width) - 1;
+    let split = |v: Value<'gcx>| match v.kind {
+        ValueKind::Int(ref int, ref xz) => {
+            let unknown = xz.special_mask() & &all;
+            let ones = int.mod_floor(&(&all + 1)) & (&all ^ &unknown);
+            let zeros = &all ^ (&ones | &unknown);
+            (ones, zeros, unknown)
+        }
+        _ => unreachable!(),
+    };
+    let (lhs_ones, lhs_zeros, lhs_unknown) = split(lhs);
+    let (rhs_ones, rhs_zeros, rhs_unknown) = split(rhs);
+    let (ones, unknown) = match op {
+        mir::BinaryBitwiseOp::And => {
+            let ones = &lhs_ones & &rhs_ones;
+            let zeros = &lhs_zeros | &rhs_zeros;
+            let unknown = &all ^ (&ones | &zeros);
+            (ones, unknown)
+        }
+        mir::BinaryBitwiseOp::Or => {
+            let ones = &lhs_ones | &rhs_ones;
+            let zeros = &lhs_zeros & &rhs_zeros;
+            let unknown = &all ^ (&ones | &zeros);
+            (ones, unknown)
+        }
+        mir::BinaryBitwiseOp::Xor => {
+            let unknown = &lhs_unknown | &rhs_unknown;
+            let ones = (&lhs_ones ^ &rhs_ones) & (&all ^ &unknown);
+            (ones, unknown)
+        }
+    };
+    let special_bits: BitVec = (0..width).map(|i| !(&unknown >> i).is_even()).collect();
+    make_int_special(ty, ones, special_bits.clone(), special_bits)
+}
+
+/// Combine the two branches of a ternary operator with an unknown condition.
+///
+/// Per IEEE 1800-2017 section 11.4.11, the bits of integral results are
+/// compared and set to x wherever they differ or either of them is x or z.
+/// Other values resolve to the default value of their type unless they are
+/// identical.
+fn const_merge_ternary<'gcx>(
+    cx: &impl Context<'gcx>,
+    ty: &'gcx UnpackedType<'gcx>,
+    true_val: Value<'gcx>,
+    false_val: Value<'gcx>,
+) -> Value<'gcx> {
+    match (&true_val.kind, &false_val.kind) {
+        (ValueKind::Int(a, sa), ValueKind::Int(b, sb)) => {
+            let width = ty.get_bit_size().unwrap();
+            let modulus = BigInt::one() << width;
+            let a = a.mod_floor(&modulus);
+            let b = b.mod_floor(&modulus);
+            let differ = ((&a ^ &b) | sa.special_mask() | sb.special_mask()) & (&modulus - 1);
+            let mut special_bits = BitVec::from_elem(width, false);
+            for i in 0..width {
+                special_bits.set(i, !(&differ >> i).is_even());
+            }
+            let value = &a - (&a & &differ);
+            cx.intern_value(make_int_special(
+                ty,
+                value,
+                special_bits.clone(),
+                special_bits,
+            ))
+        }
+        _ if true_val == false_val => true_val,
+        _ => type_default_value(cx, ty),
+    }
+}
+
+/// Compare two values using wildcard equality, as performed by `==?` and
+/// `inside`.
+///
+/// The x and z bits of `rhs` match any bit of `lhs`. Returns `None` if the
+/// result is ambiguous due to x or z bits in `lhs`.
+fn const_wildcard_eq(lhs: Value, rhs: Value) -> Option<bool> {
+    match (&lhs.kind, &rhs.kind) {
+        (ValueKind::Int(a, sa), ValueKind::Int(b, sb)) => {
+            let width = lhs.ty.get_bit_size().unwrap();
+            let modulus = BigInt::one() << width;
+            let care = (&modulus - 1) - (sb.special_mask() & (&modulus - 1));
+            let unknown = &care & sa.special_mask();
+            let known = &care - &unknown;
+            let mismatch = (a.mod_floor(&modulus) ^ b.mod_floor(&modulus)) & known;
+            if !mismatch.is_zero() {
+                Some(false)
+            } else if !unknown.is_zero() {
+                None
+            } else {
+                Some(true)
+            }
+        }
+        _ => Some(lhs == rhs),
+    }
+}
+
+/// Check whether a value lies within an inclusive range, as performed by
+/// `inside`.
+///
+/// Missing bounds are treated as the extremes of the value's type. Returns
+/// `None` if the value or one of the bounds has x or z bits.
+fn const_inside_range(value: Value, lo: Option<Value>, hi: Option<Value>) -> Option<bool> {
+    let is_unknown = |v: Value| match v.kind {
+        ValueKind::Int(_, ref xz) => xz.any(),
+        _ => false,
+    };
+    if is_unknown(value) || lo.into_iter().chain(hi).any(is_unknown) {
+        return None;
+    }
+    let value = value.get_int_signed()?;
+    let lo_ok = lo.map(|lo| Some(value >= lo.get_int_signed()?));
+    let hi_ok = hi.map(|hi| Some(value <= hi.get_int_signed()?));
+    Some(lo_ok.unwrap_or(Some(true))? && hi_ok.unwrap_or(Some(true))?)
+}
+
+/// Convert a bit vector to an integer with the corresponding bits set.
+fn bit_mask(bits: &BitVec) -> BigInt {
+    let mut mask = BigInt::zero();
+    for (i, bit) in bits.iter().enumerate() {
+        if bit {
+            mask |= BigInt::one() << i;
+        }
+    }
+    mask
+}
+
 fn const_unary_arith_int<'gcx>(
     _cx: &impl Context<'gcx>,
     _ty: SbvType,
@@ -816,12 +2312,187 @@ fn const_binary_arith_int<'gcx>(
     }
 }
 
-fn const_comp_int<'gcx>(
+/// Fold an arithmetic operation on two time values.
+///
+/// Integer operands are converted to `time` by scaling them with the active
+/// timescale before the operation is performed. For multiplication and
+/// division this scaling is undone, such that `10ns * 2` yields `20ns` rather
+/// than the product of two times. Dividing two times yields their ratio, which
+/// is converted back to `time` according to the timescale in effect for the
+/// expression, since the result type of the operation is `time`.
+fn const_binary_arith_time<'gcx>(
+    cx: &impl Context<'gcx>,
+    mir: &'gcx mir::Rvalue<'gcx>,
+    op: mir::IntBinaryArithOp,
+    lhs: &'gcx mir::Rvalue<'gcx>,
+    rhs: &'gcx mir::Rvalue<'gcx>,
+    lhs_time: &BigRational,
+    rhs_time: &BigRational,
+) -> Result<BigRational> {
+    // Recover the unscaled value of an operand that was converted from an
+    // integer.
+    let unscaled = |value: &'gcx mir::Rvalue<'gcx>| match value.kind {
+        mir::RvalueKind::ApplyTimescale(_, ref scale) => Some(scale),
+        _ => None,
+    };
+    let divide = |lhs: &BigRational, rhs: &BigRational| {
+        if rhs.is_zero() {
+            emit_const_diag(
+                cx,
+                mir,
+                DiagBuilder2::error("constant division by zero").span(mir.span),
+            );
+            Err(())
+        } else {
+            Ok(lhs / rhs)
+        }
+    };
+    match op {
+        mir::IntBinaryArithOp::Add => Ok(lhs_time + rhs_time),
+        mir::IntBinaryArithOp::Sub => Ok(lhs_time - rhs_time),
+        mir::IntBinaryArithOp::Mul => match (unscaled(lhs), unscaled(rhs)) {
+            (_, Some(scale)) => Ok(lhs_time * rhs_time / scale),
+            (Some(scale), _) => Ok(lhs_time / scale * rhs_time),
+            (None, None) => {
+                emit_const_diag(
+                    cx,
+                    mir,
+                    DiagBuilder2::error("cannot multiply two time values")
+                        .span(mir.span)
+                        .add_note("The product of two times is not a time"),
+                );
+                Err(())
+            }
+        },
+        mir::IntBinaryArithOp::Div => match unscaled(rhs) {
+            Some(scale) => divide(&(lhs_time * scale), rhs_time),
+            None => {
+                let scale = crate::mir::lower::rvalue::timeunit_of(cx, mir.origin)
+                    .unwrap_or_else(|| BigRational::new(1.into(), 1_000_000_000.into()));
+                Ok(divide(lhs_time, rhs_time)? * scale)
+            }
+        },
+        mir::IntBinaryArithOp::Mod => {
+            if rhs_time.is_zero() {
+                emit_const_diag(
+                    cx,
+                    mir,
+                    DiagBuilder2::error("constant modulo by zero").span(mir.span),
+                );
+                Err(())
+            } else {
+                Ok(lhs_time % rhs_time)
+            }
+        }
+        mir::IntBinaryArithOp::Pow => {
+            emit_const_diag(
+                cx,
+                mir,
+                DiagBuilder2::error("cannot raise a time value to a power").span(mir.span),
+            );
+            Err(())
+        }
+    }
+}
+
+/// Report operands of an unexpected kind during constant evaluation.
+///
+/// Typeck is supposed to reject such operands, so reaching this is a compiler
+/// bug. Rather than panicking, a bug diagnostic pointing at the MIR node and
+/// describing the operands is emitted, and an internal error returned, such
+/// that elaboration continues. Debug builds panic instead if the
+/// `MOORE_PANIC_ON_BUG` environment variable is set.
+///
+/// ```
+/// # use moore_common::{source::{get_source_manager, Span}, Session};
+/// # use moore_svlog::{mir, ty, value, Context, GlobalArenas, GlobalContext, QueryDatabase, Ref};
+/// let sess = Session::new();
+/// let arena = GlobalArenas::default();
+/// let gcx = GlobalContext::new(&sess, &arena);
+///
+/// // Forge a bitwise negation of a string, which typeck would reject.
+/// let source = get_source_manager().add_anonymous("~\"x\"");
+/// let span = Span::new(source, 0, 4);
+/// let string_ty = ty::UnpackedType::make(&gcx, ty::UnpackedCore::String);
+/// let forge = |ty, kind| {
+///     let id = gcx.alloc_id(span);
+///     &*arena.alloc_mir_rvalue(mir::Rvalue {
+///         id,
+///         origin: id,
+///         env: gcx.default_param_env(),
+///         span,
+///         ty,
+///         kind,
+///         konst: true,
+///     })
+/// };
+/// let string = gcx.intern_value(value::make_string(string_ty, b"x".to_vec()));
+/// let arg = forge(string_ty, mir::RvalueKind::Const(string));
+/// let not = forge(
+///     string_ty,
+///     mir::RvalueKind::UnaryBitwise {
+///         op: mir::UnaryBitwiseOp::Not,
+///         arg,
+///     },
+/// );
+///
+/// // Evaluation reports a bug instead of panicking.
+/// assert!(matches!(
+///     gcx.const_mir_rvalue(Ref(not)),
+///     Err(value::ConstEvalError::Internal { .. })
+/// ));
+/// assert!(sess.failed());
+/// ```
+fn const_operand_bug<'a>(
+    cx: &impl Context<'a>,
+    mir: &'a mir::Rvalue<'a>,
+    operands: &[Value<'a>],
+) -> std::result::Result<Value<'a>, ConstEvalError> {
+    let msg = format!(
+        "unexpected operand in constant evaluation of `{}`",
+        mir.span.extract()
+    );
+    let mut d = DiagBuilder2::bug(&msg).span(mir.span);
+    for operand in operands {
+        d = d.add_note(format!(
+            "Operand is {} of type `{}`",
+            operand.kind.desc(),
+            operand.ty
+        ));
+    }
+    cx.emit(d);
+    if cfg!(debug_assertions) && std::env::var_os("MOORE_PANIC_ON_BUG").is_some() {
+        panic!("{}", msg);
+    }
+    Err(ConstEvalError::Internal { span: mir.span })
+}
+
+/// Interpret an operand of a comparison against a time value as a time.
+///
+/// Integers are treated as a time in the time unit in effect for the
+/// expression, as if they had been converted to `time`.
+fn const_time_operand<'gcx>(
+    cx: &impl Context<'gcx>,
+    mir: &'gcx mir::Rvalue<'gcx>,
+    value: Value<'gcx>,
+) -> BigRational {
+    if let Some(time) = value.get_time() {
+        return time.clone();
+    }
+    let int = value
+        .get_int_signed()
+        .expect("time comparison operand should be time or int");
+    let scale = crate::mir::lower::rvalue::timeunit_of(cx, mir.origin)
+        .unwrap_or_else(|| BigRational::new(1.into(), 1_000_000_000.into()));
+    BigRational::from(int) * scale
+}
+
+fn const_comp_int<'gcx, T: PartialOrd>(
     _cx: &impl Context<'gcx>,
     _ty: SbvType,
     op: mir::IntCompOp,
-    lhs: &BigInt,
-    rhs: &BigInt,
+    lhs: &T,
+    rhs: &T,
 ) -> BigInt {
     match op {
         mir::IntCompOp::Eq => ((lhs == rhs) as usize).into(),
@@ -833,25 +2504,110 @@ fn const_comp_int<'gcx>(
     }
 }
 
+/// The maximum number of bits a constant left shift may produce.
+///
+/// Shifting a very wide value would otherwise allocate an arbitrarily large
+/// integer during constant evaluation.
+pub const MAX_CONST_SHIFT_WIDTH: usize = 1 << 20;
+
 fn const_shift_int<'gcx>(
-    _cx: &impl Context<'gcx>,
-    _ty: SbvType,
+    cx: &impl Context<'gcx>,
+    mir: &'gcx mir::Rvalue<'gcx>,
+    ty: SbvType,
     op: mir::ShiftOp,
-    _arith: bool,
+    arith: bool,
     value: &BigInt,
     amount: &BigInt,
-) -> BigInt {
+    operands: &[ConstOperand<'gcx>],
+) -> Result<BigInt> {
+    let span = mir.span;
+    // Shift amounts are unsigned in SystemVerilog. Negative amounts can only
+    // arise from signed operands, in which case we shift in the opposite
+    // direction.
+    let (op, amount) = if amount.is_negative() {
+        emit_const_diag(
+            cx,
+            mir,
+            add_operand_notes(
+                cx,
+                DiagBuilder2::warning(format!("negative shift amount {}", amount))
+                    .code(DiagCode::NEGATIVE_SHIFT)
+                    .span(span)
+                    .add_note(
+                        "Shift amounts are unsigned; shifting in the opposite direction instead.",
+                    ),
+                operands,
+            ),
+        );
+        let op = match op {
+            mir::ShiftOp::Left => mir::ShiftOp::Right,
+            mir::ShiftOp::Right => mir::ShiftOp::Left,
+        };
+        (op, -amount)
+    } else {
+        (op, amount.clone())
+    };
+
+    // Shifting by the width of the value or more shifts out all bits, leaving
+    // only zeros or the sign bit for arithmetic right shifts.
+    let modulus = BigInt::one() << ty.size;
+    let value = value.mod_floor(&modulus);
+    let sign_fill = arith
+        && op == mir::ShiftOp::Right
+        && ty.is_signed()
+        && ty.size > 0
+        && value >= (&modulus >> 1);
+    let amount = match amount.to_usize() {
+        Some(amount) if amount < ty.size => amount,
+        _ => {
+            emit_const_diag(
+                cx,
+                mir,
+                add_operand_notes(
+                    cx,
+                    DiagBuilder2::warning(format!(
+                        "shift amount {} exceeds the {}-bit width of the shifted value",
+                        amount, ty.size
+                    ))
+                    .code(DiagCode::SHIFT_EXCEEDS_WIDTH)
+                    .span(span)
+                    .add_note(if sign_fill {
+                        "The result has all bits set to the sign bit."
+                    } else {
+                        "The result is zero."
+                    }),
+                    operands,
+                ),
+            );
+            return Ok(if sign_fill { modulus - 1 } else { num::zero() });
+        }
+    };
+
     match op {
-        mir::ShiftOp::Left => match amount.to_isize() {
-            Some(sh) if sh < 0 => value >> -sh as usize,
-            Some(sh) => value << sh as usize,
-            None => num::zero(),
-        },
-        mir::ShiftOp::Right => match amount.to_isize() {
-            Some(sh) if sh < 0 => value << -sh as usize,
-            Some(sh) => value >> sh as usize,
-            None => num::zero(),
-        },
+        mir::ShiftOp::Left => {
+            if value.bits() as usize + amount > MAX_CONST_SHIFT_WIDTH {
+                emit_const_diag(
+                    cx,
+                    mir,
+                    add_operand_notes(
+                        cx,
+                        DiagBuilder2::error(format!(
+                            "constant shift by {} produces a value wider than {} bits",
+                            amount, MAX_CONST_SHIFT_WIDTH
+                        ))
+                        .span(span),
+                        operands,
+                    ),
+                );
+                return Err(());
+            }
+            Ok(value << amount)
+        }
+        mir::ShiftOp::Right if sign_fill => {
+            let fill = ((BigInt::one() << amount) - 1) << (ty.size - amount);
+            Ok((value >> amount) | fill)
+        }
+        mir::ShiftOp::Right => Ok(value >> amount),
     }
 }
 
@@ -889,6 +2645,12 @@ fn const_comp_string<'gcx>(
 }
 
 /// Check if a node has a constant value.
+///
+/// This is a structural check that does not attempt to evaluate the node.
+/// Parameters (including `localparam`s and package parameters), genvars, enum
+/// variants, and `const` variables with a constant initializer are constant.
+/// Expressions are constant if they are literals, or if all their operands are
+/// constant.
 #[moore_derive::query]
 pub(crate) fn is_constant<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<bool> {
     let hir = cx.hir_of(node_id)?;
@@ -896,10 +2658,92 @@ pub(crate) fn is_constant<'a>(cx: &impl Context<'a>, node_id: NodeId) -> Result<
         HirNode::ValueParam(_) => true,
         HirNode::GenvarDecl(_) => true,
         HirNode::EnumVariant(_) => true,
+        HirNode::VarDecl(decl) => match decl.init {
+            Some(init) if decl.konst => cx.is_constant(init)?,
+            _ => false,
+        },
+        HirNode::Expr(expr) => is_constant_expr(cx, expr)?,
         _ => false,
     })
 }
 
+/// Check if all operands of an expression are constant.
+fn is_constant_expr<'a>(cx: &impl Context<'a>, expr: &'a hir::Expr<'a>) -> Result<bool> {
+    let all = |ids: &[NodeId]| -> Result<bool> {
+        for &id in ids {
+            if !cx.is_constant(id)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    };
+    Ok(match expr.kind {
+        hir::ExprKind::IntConst { .. }
+        | hir::ExprKind::UnsizedConst(..)
+        | hir::ExprKind::TimeConst(..)
+        | hir::ExprKind::StringConst(..) => true,
+        hir::ExprKind::Ident(..) | hir::ExprKind::Scope(..) => {
+            let binding = cx.resolve_node(expr.id, cx.default_param_env())?;
+            match cx.hir_of(binding)? {
+                // Do not look through the initializer of `const` variables
+                // here, since a self-referential initializer would otherwise
+                // recurse indefinitely. Constant evaluation reports any
+                // problems with the initializer.
+                HirNode::VarDecl(decl) => decl.konst && decl.init.is_some(),
+                _ => cx.is_constant(binding)?,
+            }
+        }
+        hir::ExprKind::Unary(_, arg)
+        | hir::ExprKind::Field(arg, _)
+        | hir::ExprKind::Cast(_, arg)
+        | hir::ExprKind::CastSign(_, arg) => cx.is_constant(arg)?,
+        hir::ExprKind::Binary(_, lhs, rhs) | hir::ExprKind::CastSize(lhs, rhs) => all(&[lhs, rhs])?,
+        hir::ExprKind::Ternary(cond, true_value, false_value) => {
+            all(&[cond, true_value, false_value])?
+        }
+        hir::ExprKind::Index(target, hir::IndexMode::One(index)) => all(&[target, index])?,
+        hir::ExprKind::Index(target, hir::IndexMode::Many(_, lhs, rhs)) => {
+            all(&[target, lhs, rhs])?
+        }
+        hir::ExprKind::Concat(repeat, ref exprs) => {
+            repeat.map(|r| cx.is_constant(r)).unwrap_or(Ok(true))? && all(exprs)?
+        }
+        hir::ExprKind::StreamConcat {
+            slice, ref exprs, ..
+        } => {
+            let slice_const = match slice {
+                Some(hir::StreamSlice::Expr(id)) => cx.is_constant(id)?,
+                _ => true,
+            };
+            slice_const && all(exprs)?
+        }
+        hir::ExprKind::PositionalPattern(ref exprs) => all(exprs)?,
+        hir::ExprKind::RepeatPattern(count, ref exprs) => cx.is_constant(count)? && all(exprs)?,
+        hir::ExprKind::NamedPattern(ref fields) => {
+            all(&fields.iter().map(|&(_, id)| id).collect::<Vec<_>>())?
+        }
+        hir::ExprKind::Inside(value, ref ranges) => {
+            let mut ids = vec![value];
+            for range in ranges {
+                match range.value {
+                    hir::InsideRange::Single(id) => ids.push(id),
+                    hir::InsideRange::Range(lo, hi) => ids.extend(lo.into_iter().chain(hi)),
+                }
+            }
+            all(&ids)?
+        }
+        hir::ExprKind::Builtin(hir::BuiltinCall::Clog2(arg))
+        | hir::ExprKind::Builtin(hir::BuiltinCall::Signed(arg))
+        | hir::ExprKind::Builtin(hir::BuiltinCall::Unsigned(arg)) => cx.is_constant(arg)?,
+        hir::ExprKind::Builtin(hir::BuiltinCall::Bits(..))
+        | hir::ExprKind::Builtin(hir::BuiltinCall::ArrayDim(..)) => true,
+        hir::ExprKind::Builtin(..)
+        | hir::ExprKind::FunctionCall(..)
+        | hir::ExprKind::Assign { .. }
+        | hir::ExprKind::Ast(..) => false,
+    })
+}
+
 /// Determine the default value of a type.
 #[moore_derive::query]
 pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType<'a>) -> Value<'a> {
@@ -926,23 +2770,31 @@ pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType
         let packed = packed;
         match packed.core {
             ty::PackedCore::IntVec(_) if packed.dims.len() <= 1 => {
-                return cx.intern_value(make_int(ty, Zero::zero()));
+                return cx.intern_int(ty, Zero::zero());
             }
             ty::PackedCore::IntAtom(ty::IntAtomType::Time) if packed.dims.is_empty() => {
                 return cx.intern_value(make_time(Zero::zero()));
             }
             ty::PackedCore::IntAtom(_) if packed.dims.is_empty() => {
-                return cx.intern_value(make_int(ty, Zero::zero()));
+                return cx.intern_int(ty, Zero::zero());
             }
             _ => (),
         }
     }
 
-    // Handle arrays.
+    // Handle enums. Per LRM 6.19 an uninitialized enum variable takes the
+    // default value of its base type, which is zero.
+    if ty.get_enum().is_some() {
+        return cx.intern_int(ty, Zero::zero());
+    }
+
+    // Handle arrays. Queues, dynamic arrays, and associative arrays start out
+    // empty.
     if let Some(dim) = ty.outermost_dim() {
-        let length = dim
-            .get_size()
-            .expect("cannot build const value of unsized array");
+        let length = match dim.get_size() {
+            Some(length) => length,
+            None => return cx.intern_value(make_aggregate(ty, vec![])),
+        };
         let elem_ty = ty.pop_dim(cx).unwrap();
         return cx.intern_value(make_array(
             ty,
@@ -960,6 +2812,7 @@ pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType
     // Handle unpacked types.
     let packed = match ty.core {
         ty::UnpackedCore::Packed(p) => p,
+        ty::UnpackedCore::String => return cx.intern_value(make_string(ty, vec![])),
         _ => panic!("cannot build const value of unpacked type `{}`", ty),
     };
 
@@ -976,10 +2829,10 @@ pub(crate) fn type_default_value<'a>(cx: &impl Context<'a>, ty: &'a UnpackedType
                 kind: ValueKind::Void,
             })
         }
-        ty::PackedCore::Enum(ref x) => return cx.type_default_value(x.base.to_unpacked(cx)),
-        ty::PackedCore::IntVec(_) | ty::PackedCore::IntAtom(_) | ty::PackedCore::Struct(_) => {
-            unreachable!("should be handled above")
-        }
+        ty::PackedCore::IntVec(_)
+        | ty::PackedCore::IntAtom(_)
+        | ty::PackedCore::Struct(_)
+        | ty::PackedCore::Enum(_) => unreachable!("should be handled above"),
         _ => panic!("cannot build const value of packed type `{}`", packed),
     }
 }
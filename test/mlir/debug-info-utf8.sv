@@ -0,0 +1,7 @@
+// RUN: moore -e A --format=mlir-native -g %s | FileCheck %s
+
+/* é */ module A;
+endmodule
+// CHECK: llhd.entity @A () -> () {
+// CHECK-NEXT: } loc([[LOC:#.+]])
+// CHECK: [[LOC]] = loc("{{.+}}/debug-info-utf8.sv":3:9)
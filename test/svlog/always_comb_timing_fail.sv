@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo (input logic a);
+    logic b;
+    always_comb @(a) b = a;
+    // CHECK-ERR: error: `always_comb` procedure cannot have a timing control
+endmodule
@@ -0,0 +1,9 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo (input logic clk, input logic rst_n, input logic d);
+    logic q, r;
+    always_ff @(posedge clk or negedge rst_n) q <= d;
+    always_ff r <= d;
+    // CHECK-ERR: error: `always_ff` procedure must begin with an event control
+endmodule
@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam int A = A + 1;
+    // CHECK-ERR: error: circular dependency in constant evaluation
+    // CHECK-ERR: = note: parameter `A` is part of the cycle
+    int x = A;
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam int A = B + 1;
+    localparam int B = A * 2;
+    // CHECK-ERR: error: circular dependency in constant evaluation
+    // CHECK-ERR: = note: parameter `A` is part of the cycle
+    // CHECK-ERR: = note: parameter `B` is part of the cycle
+    int x = A;
+endmodule
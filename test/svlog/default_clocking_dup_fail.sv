@@ -0,0 +1,11 @@
+// RUN: moore %s
+// FAIL
+module foo (input logic clk);
+    default clocking cb @(posedge clk);
+        default input #1step output #2;
+        input data;
+    endclocking
+    default clocking cb;
+endmodule
+
+// CHECK-ERR: error: multiple default clocking blocks in the same scope
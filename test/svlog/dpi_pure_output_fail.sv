@@ -0,0 +1,7 @@
+// RUN: moore %s
+// FAIL
+module foo;
+    import "DPI-C" pure function int bar(input int a, output int b);
+endmodule
+
+// CHECK-ERR: error: pure DPI function `bar` cannot have `output` arguments
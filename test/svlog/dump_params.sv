@@ -0,0 +1,10 @@
+// RUN: moore %s -e top --dump-params
+module top;
+    foo #(.W(4)) u0 ();
+endmodule
+
+module foo #(parameter int W = 1, parameter int D = 8);
+endmodule
+
+// CHECK: top.u0.W = 32'sd4 (instance override `4`)
+// CHECK: top.u0.D = 32'sd8 (default)
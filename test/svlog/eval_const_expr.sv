@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo --eval "W * 2 + D" --eval "D" -G W=5
+module foo #(parameter int W = 1);
+    localparam int D = W + 3;
+endmodule
+
+// CHECK: W * 2 + D = 32'sd18
+// CHECK: D = 32'sd8
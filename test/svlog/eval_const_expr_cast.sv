@@ -0,0 +1,5 @@
+// RUN: moore %s -e foo --eval "W" -G W=20
+module foo #(parameter logic [3:0] W = 1);
+endmodule
+
+// CHECK: W = 4'd4
@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo -W error=W0203
+// FAIL
+module foo;
+    localparam logic [3:0] a = 8'hff;
+    // CHECK-ERR: error[W0203]: truncation of `8'hff` to 4 bits ignored during constant evaluation
+    logic [3:0] x0 = a;
+endmodule
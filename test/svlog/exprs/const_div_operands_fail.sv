@@ -0,0 +1,14 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    bar #(.DIV(0)) u_bar();
+endmodule
+
+module bar #(parameter int unsigned DIV = 4);
+    localparam int unsigned WIDTH = 8;
+    localparam int unsigned W = WIDTH / DIV;
+    int x = W;
+    // CHECK-ERR: error: constant division by zero
+    // CHECK-ERR: = note: left operand evaluates to 32'd8, from parameter `WIDTH` bound here
+    // CHECK-ERR: = note: right operand evaluates to 32'd0, from parameter `DIV` bound here
+endmodule
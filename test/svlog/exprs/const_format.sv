@@ -0,0 +1,19 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    typedef struct { logic [3:0] b; bit c; } inner_t;
+    typedef struct { int a; inner_t i; } outer_t;
+
+    localparam int a = -42;
+    // CHECK-ERR: const(-42) = int, 32'shffff_ffd6
+    localparam logic [7:0] b = 8'b1x0110x1;
+    // CHECK-ERR: const(8'b1x0110x1) = logic [7:0], 8'b1x01_10x1
+    localparam logic [11:0] c = 12'hzzz;
+    // CHECK-ERR: const(12'hzzz) = logic [11:0], 12'hzzz
+    localparam outer_t d = '{a: 42, i: '{b: 4'ha, c: 1}};
+    // CHECK-ERR: const('{a: 42, i: '{b: 4'ha, c: 1}}) = outer_t, '{32'sh2a, '{4'ha, 1'h1}}
+
+    int x0 = a;
+    logic [7:0] x1 = b;
+    logic [11:0] x2 = c;
+    outer_t x3 = d;
+endmodule
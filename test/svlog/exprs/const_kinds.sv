@@ -0,0 +1,17 @@
+// RUN: moore %s -e foo -Vconsts
+package pkg;
+    parameter int P = 2;
+endpackage
+
+module foo;
+    localparam int L = 3;
+    const int C = L + 1;
+
+    logic [5:0] x0 = {L{2'b10}};
+    // CHECK-ERR: const(L) = int, 32'sh3
+    logic [3:0] x1 = {pkg::P{2'b01}};
+    // CHECK-ERR: const(pkg::P) = int, 32'sh2
+    logic [7:0] x2 = {C{2'b11}};
+    // CHECK-ERR: const(C) = int, 32'sh4
+    logic [3:0] x3 = {(L - 1){2'b10}};
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e foo -Vconst-stats
+module foo;
+    localparam int A = 1;
+    localparam int B = A + 2;
+    int x = B;
+endmodule
+
+// CHECK-ERR: Constant evaluation statistics:
+// CHECK-ERR: tombstones: 0
+// CHECK-ERR: Top modules by evaluation count:
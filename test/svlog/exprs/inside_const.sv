@@ -0,0 +1,17 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam int MODE = 9;
+    localparam bit a = MODE inside {0, 3, [8:15]};
+    // CHECK-ERR: const(MODE inside {0, 3, [8:15]}) = logic, 1'h1
+    localparam bit b = MODE inside {0, 3, [10:$]};
+    // CHECK-ERR: const(MODE inside {0, 3, [10:$]}) = logic, 1'h0
+    localparam bit c = 4'b01x1 inside {4'b0000, [4'b1000:4'b1111]};
+    // CHECK-ERR: const(4'b01x1 inside {4'b0000, [4'b1000:4'b1111]}) = logic, 1'hx
+    localparam bit d = 4'b0101 inside {4'b01?1};
+    // CHECK-ERR: const(4'b0101 inside {4'b01?1}) = logic, 1'h1
+
+    bit x0 = a;
+    bit x1 = b;
+    bit x2 = c;
+    bit x3 = d;
+endmodule
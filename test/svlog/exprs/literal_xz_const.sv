@@ -0,0 +1,25 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam logic [7:0] a = 8'b1010_xxxx;
+    // CHECK-ERR: const(8'b1010_xxxx) = logic [7:0], 8'hax
+    localparam logic [7:0] b = 8'bzzzz_0101;
+    // CHECK-ERR: const(8'bzzzz_0101) = logic [7:0], 8'hz5
+    localparam logic [11:0] c = 12'hx;
+    // CHECK-ERR: const(12'hx) = logic [11:0], 12'hxxx
+    localparam logic [11:0] d = 12'hz5;
+    // CHECK-ERR: const(12'hz5) = logic [11:0], 12'hzz5
+    localparam logic [7:0] e = 8'b1010_xxzz & 8'hf0;
+    // CHECK-ERR: const(8'b1010_xxzz & 8'hf0) = logic [7:0], 8'ha0
+    localparam logic [7:0] f = 8'b1010_xxzz & 8'hff;
+    // CHECK-ERR: const(8'b1010_xxzz & 8'hff) = logic [7:0], 8'hax
+    localparam logic [3:0] g = 4'b10xz | 4'b0011;
+    // CHECK-ERR: const(4'b10xz | 4'b0011) = logic [3:0], 4'hb
+
+    logic [7:0] x0 = a;
+    logic [7:0] x1 = b;
+    logic [11:0] x2 = c;
+    logic [11:0] x3 = d;
+    logic [7:0] x4 = e;
+    logic [7:0] x5 = f;
+    logic [3:0] x6 = g;
+endmodule
@@ -0,0 +1,9 @@
+// RUN: moore %s -e foo -Vmir-consts
+module foo;
+    localparam int A = 0 > 1 ? 8'(300) : 20;
+    int x = A;
+endmodule
+
+// CHECK-ERR: Ternary : int `0 > 1 ? 8'(300) : 20` = 32'sd20
+// CHECK-ERR: Const 32'sd300 : int `300` = 32'sd300
+// CHECK-ERR: Const 32'sd20 : int `20` = 32'sd20
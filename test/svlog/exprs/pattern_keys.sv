@@ -0,0 +1,21 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    typedef struct { int a; logic [7:0] b; } pair_t;
+
+    localparam int P [3] = '{4, 5, 6};
+    // CHECK-ERR: const('{4, 5, 6}) = int $ [3], '{32'sh4, 32'sh5, 32'sh6}
+    localparam pair_t N = '{b: 2, a: 1};
+    // CHECK-ERR: const('{b: 2, a: 1}) = pair_t, '{32'sh1, 8'h2}
+    localparam pair_t D = '{default: '1};
+    // CHECK-ERR: const('{default: '1}) = pair_t, '{32'shffff_ffff, 8'hff}
+    localparam int T [3] = '{int: 7, 1: 8};
+    // CHECK-ERR: const('{int: 7, 1: 8}) = int $ [3], '{32'sh7, 32'sh8, 32'sh7}
+    localparam pair_t A [2] = '{1: '{a: 3, b: 4}, default: '{default: 0}};
+    // CHECK-ERR: const('{1: '{a: 3, b: 4}, default: '{default: 0}}) = pair_t $ [2], '{'{32'sh0, 8'h0}, '{32'sh3, 8'h4}}
+
+    int x0 [3] = P;
+    pair_t x1 = N;
+    pair_t x2 = D;
+    int x3 [3] = T;
+    pair_t x4 [2] = A;
+endmodule
@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam int A [2] = '{shortint: 1, default: 0};
+    // CHECK-ERR: error: type key `shortint` does not match the element type `int` of the array
+    int x [2] = A;
+endmodule
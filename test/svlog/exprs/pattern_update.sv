@@ -0,0 +1,16 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    typedef struct { int addr; int data; logic [3:0] mask; } req_t;
+    localparam int BASE = 32'h1000;
+
+    localparam req_t P = '{default: 0, addr: BASE};
+    // CHECK-ERR: const('{default: 0, addr: BASE}) = req_t, '{32'sh1000, 32'sh0, 4'h0}
+    localparam req_t Q = '{int: 3, mask: 4'hf};
+    // CHECK-ERR: const('{int: 3, mask: 4'hf}) = req_t, '{32'sh3, 32'sh3, 4'hf}
+    localparam int R [4] = '{default: 7, 2: 9};
+    // CHECK-ERR: const('{default: 7, 2: 9}) = int $ [4], '{32'sh7, 32'sh7, 32'sh9, 32'sh7}
+
+    req_t x0 = P;
+    req_t x1 = Q;
+    int x2 [4] = R;
+endmodule
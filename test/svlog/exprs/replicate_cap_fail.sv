@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam int N = 1 << 24;
+    logic [7:0] x = {N{1'b1}};
+    // CHECK-ERR: error: replication count 16777216 exceeds the maximum of 1048576
+endmodule
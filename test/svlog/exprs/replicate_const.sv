@@ -0,0 +1,10 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam logic [3:0] a = { {0{1'b1}}, 4'b1010 };
+    // CHECK-ERR: const({ {0{1'b1}}, 4'b1010 }) = logic [3:0], 4'ha
+    localparam logic [7:0] b = {4{2'b10}};
+    // CHECK-ERR: const({4{2'b10}}) = logic [7:0], 8'haa
+
+    logic [3:0] x0 = a;
+    logic [7:0] x1 = b;
+endmodule
@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    int n = 3;
+    logic [5:0] x = {n{2'b10}};
+    // CHECK-ERR: error: replication count must be a constant expression
+endmodule
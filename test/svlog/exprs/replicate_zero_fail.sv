@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam logic [3:0] a = {0{1'b1}};
+    // CHECK-ERR: error: zero replication is only allowed within a concatenation
+    logic [3:0] x = a;
+endmodule
@@ -0,0 +1,25 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam longint BIG = 64'd10_000_000_000;
+
+    localparam int a = 1 << BIG;
+    // CHECK-ERR: warning[W0202]: shift amount 10000000000 exceeds the 32-bit width of the shifted value
+    // CHECK-ERR: const(1 << BIG) = int, 32'sh0
+    localparam int b = 32'h1 << 40;
+    // CHECK-ERR: warning[W0202]: shift amount 40 exceeds the 32-bit width of the shifted value
+    // CHECK-ERR: const(32'h1 << 40) = int, 32'sh0
+    localparam int c = -8 >>> 33;
+    // CHECK-ERR: warning[W0202]: shift amount 33 exceeds the 32-bit width of the shifted value
+    // CHECK-ERR: const(-8 >>> 33) = int, 32'shffff_ffff
+    localparam int d = -8 >>> 1;
+    // CHECK-ERR: const(-8 >>> 1) = int, 32'shffff_fffc
+    localparam int e = 1 << -2'sd1;
+    // CHECK-ERR: warning[W0201]: negative shift amount -1
+    // CHECK-ERR: const(1 << -2'sd1) = int, 32'sh0
+
+    int x0 = a;
+    int x1 = b;
+    int x2 = c;
+    int x3 = d;
+    int x4 = e;
+endmodule
@@ -0,0 +1,11 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam logic [2097151:0] f = 1;
+    localparam logic [2097151:0] g = f << 2000000;
+    // CHECK-ERR: error: constant shift by 2000000 produces a value wider than 1048576 bits
+    bar #(g[0]) i0();
+endmodule
+
+module bar #(parameter logic p);
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e foo -W no-W0201 -W error=W0202
+// FAIL
+module foo;
+    localparam int a = 32'h1 << 40;
+    // CHECK-ERR: error[W0202]: shift amount 40 exceeds the 32-bit width of the shifted value
+    localparam int b = 1 << -2'sd1;
+
+    int x0 = a;
+    int x1 = b;
+endmodule
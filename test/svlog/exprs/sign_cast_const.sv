@@ -0,0 +1,19 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam bit a0 = 4'b1111 < 4'b0001;
+    // CHECK-ERR: const(4'b1111 < 4'b0001) = bit, 1'h0
+    localparam bit a1 = $signed(4'b1111) < $signed(4'b0001);
+    // CHECK-ERR: const($signed(4'b1111) < $signed(4'b0001)) = bit, 1'h1
+    localparam bit b0 = -8'sd1 > 8'sd0;
+    // CHECK-ERR: const(-8'sd1 > 8'sd0) = bit, 1'h0
+    localparam bit b1 = $unsigned(-8'sd1) > 8'sd0;
+    // CHECK-ERR: const($unsigned(-8'sd1) > 8'sd0) = bit, 1'h1
+    localparam int c = $signed(4'b1111);
+    // CHECK-ERR: const($signed(4'b1111)) = int, 32'shffff_ffff
+
+    bit x0 = a0;
+    bit x1 = a1;
+    bit x2 = b0;
+    bit x3 = b1;
+    int x4 = c;
+endmodule
@@ -0,0 +1,16 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam logic [7:0] a = {<<{8'b0011_0101}};
+    // CHECK-ERR: const({<<{8'b0011_0101}}) = logic [7:0], 8'hac
+    localparam logic [31:0] b = {<<8{32'hAABBCCDD}};
+    // CHECK-ERR: const({<<8{32'hAABBCCDD}}) = logic [31:0], 32'hddcc_bbaa
+    localparam logic [15:0] c = {>>{8'hAB, 4'hC}};
+    // CHECK-ERR: const({>>{8'hAB, 4'hC}}) = logic [15:0], 16'habc0
+    localparam logic [15:0] d = {<<byte{8'h12, 8'h34}};
+    // CHECK-ERR: const({<<byte{8'h12, 8'h34}}) = logic [15:0], 16'h3412
+
+    logic [7:0] x0 = a;
+    logic [31:0] x1 = b;
+    logic [15:0] x2 = c;
+    logic [15:0] x3 = d;
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    typedef struct { int a; int b; } pair_t;
+    localparam int Z = 0;
+    localparam pair_t P = '{a: 1 / Z, b: 2};
+    localparam int B = P.b;
+    // CHECK-ERR: error: constant division by zero
+    int x = B;
+endmodule
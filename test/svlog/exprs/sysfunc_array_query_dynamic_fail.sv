@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    int q [];
+    localparam int a = $size(q);
+    // CHECK-ERR: error: `$size` of dynamic dimension `[]` is not constant
+    int x = a;
+endmodule
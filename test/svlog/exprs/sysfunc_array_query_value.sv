@@ -0,0 +1,24 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    typedef struct packed { logic [3:0] a; logic [7:0] b; } pair_t;
+    typedef pair_t [2:0] table_t;
+    localparam pair_t [2:0] TABLE = '0;
+    localparam logic [3:0][7:0] WORDS = '0;
+
+    localparam int a = $size(TABLE);
+    // CHECK-ERR: const($size(TABLE)) = int, 32'sh3
+    localparam int b = $bits(TABLE);
+    // CHECK-ERR: const($bits(TABLE)) = int, 32'sh24
+    localparam int c = $size(table_t);
+    // CHECK-ERR: const($size(table_t)) = int, 32'sh3
+    localparam int d = $dimensions(WORDS);
+    // CHECK-ERR: const($dimensions(WORDS)) = int, 32'sh2
+    localparam int e = $unpacked_dimensions(WORDS);
+    // CHECK-ERR: const($unpacked_dimensions(WORDS)) = int, 32'sh0
+
+    int x0 = a;
+    int x1 = b;
+    int x2 = c;
+    int x3 = d;
+    int x4 = e;
+endmodule
@@ -0,0 +1,14 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam int DIV = 0;
+    localparam int a = DIV != 0 ? 8 / DIV : 0;
+    // CHECK-ERR: const(DIV != 0 ? 8 / DIV : 0) = int, 32'sh0
+    localparam int b = DIV == 0 ? 1 : 8 / DIV;
+    // CHECK-ERR: const(DIV == 0 ? 1 : 8 / DIV) = int, 32'sh1
+    localparam logic [3:0] c = 1'bx ? 4'b1100 : 4'b1010;
+    // CHECK-ERR: const(1'bx ? 4'b1100 : 4'b1010) = logic [3:0], 4'b1xx0
+
+    int x0 = a;
+    int x1 = b;
+    logic [3:0] x2 = c;
+endmodule
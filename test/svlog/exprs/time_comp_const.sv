@@ -0,0 +1,19 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    timeunit 1ns;
+    parameter time T_SETUP = 2ns;
+
+    localparam bit a = 1ns == 1000ps;
+    // CHECK-ERR: const(1ns == 1000ps) = bit, 1'h1
+    localparam bit b = 1ns != 1000ps;
+    // CHECK-ERR: const(1ns != 1000ps) = bit, 1'h0
+    localparam bit c = T_SETUP > 0;
+    // CHECK-ERR: const(T_SETUP > 0) = bit, 1'h1
+    localparam bit d = T_SETUP == 2;
+    // CHECK-ERR: const(T_SETUP == 2) = bit, 1'h1
+
+    bit x0 = a;
+    bit x1 = b;
+    bit x2 = c;
+    bit x3 = d;
+endmodule
@@ -0,0 +1,27 @@
+// RUN: moore %s -e foo -e bar -Vconsts
+module foo;
+    localparam bit a = 10ns + 0.01us == 20ns;
+    // CHECK-ERR: const(10ns + 0.01us == 20ns) = bit, 1'h1
+    localparam bit b = 30ns - 10ns > 15ns;
+    // CHECK-ERR: const(30ns - 10ns > 15ns) = bit, 1'h1
+    localparam bit c = 10ns * 2 == 0.02us;
+    // CHECK-ERR: const(10ns * 2 == 0.02us) = bit, 1'h1
+    localparam bit d = 20ns / 4 == 5000ps;
+    // CHECK-ERR: const(20ns / 4 == 5000ps) = bit, 1'h1
+
+    bit x0 = a;
+    bit x1 = b;
+    bit x2 = c;
+    bit x3 = d;
+endmodule
+
+module bar;
+    timeunit 1ps;
+    localparam bit a = 5 == 5ps;
+    // CHECK-ERR: const(5 == 5ps) = bit, 1'h1
+    localparam bit b = 20ns / 10ns == 2ps;
+    // CHECK-ERR: const(20ns / 10ns == 2ps) = bit, 1'h1
+
+    bit x0 = a;
+    bit x1 = b;
+endmodule
@@ -0,0 +1,11 @@
+// RUN: moore %s -e foo -O0
+module foo;
+    typedef enum logic [3:0] { A = 3, B, C } abc_t;
+    abc_t x0;
+    abc_t x1 [2];
+    // CHECK: %0 = const i4 0
+    // CHECK: %x0 = sig i4 %0
+    // CHECK: %1 = const i4 0
+    // CHECK: %2 = [%1, %1]
+    // CHECK: %x1 = sig [2 x i4] %2
+endmodule
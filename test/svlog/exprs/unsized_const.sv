@@ -0,0 +1,19 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam logic [4:0] a = '1;
+    // CHECK-ERR: const('1) = logic [4:0], 5'h1f
+    localparam logic [63:0] b = '1;
+    // CHECK-ERR: const('1) = logic [63:0], 64'hffff_ffff_ffff_ffff
+    localparam logic [7:0] c = 'x;
+    // CHECK-ERR: const('x) = logic [7:0], 8'hxx
+    localparam logic [7:0] d = 'z;
+    // CHECK-ERR: const('z) = logic [7:0], 8'hzz
+    localparam logic [3:0] e = 6'('1);
+    // CHECK-ERR: const(6'('1)) = logic [5:0], 6'h3f
+
+    logic [4:0] x0 = a;
+    logic [63:0] x1 = b;
+    logic [7:0] x2 = c;
+    logic [7:0] x3 = d;
+    logic [3:0] x4 = e;
+endmodule
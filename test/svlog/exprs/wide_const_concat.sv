@@ -0,0 +1,14 @@
+// RUN: moore %s -e foo -Vconsts
+module foo;
+    localparam logic [1023:0] Q = {32{32'hdeadbeef}};
+    localparam logic [2047:0] H = {Q, Q};
+    localparam logic [4095:0] INIT = {H, H};
+
+    localparam logic [7:0] TOP = INIT[4095:4088];
+    // CHECK-ERR: const(INIT[4095:4088]) = logic [7:0], 8'hde
+    localparam logic [7:0] LOW = INIT[7:0];
+    // CHECK-ERR: const(INIT[7:0]) = logic [7:0], 8'hef
+
+    logic [7:0] x0 = TOP;
+    logic [7:0] x1 = LOW;
+endmodule
@@ -5,5 +5,5 @@ function myfunc2;
     input int x;
     int y;
     input int z;
-    // CHECK: warning: port after statement
+    // CHECK: warning[W0104]: port after statement
 endfunction
@@ -0,0 +1,9 @@
+// RUN: moore %s -e foo
+// Elaborates a wide generate loop, which interns a large number of small
+// integer constants for the loop indices and the comparisons on them.
+module foo;
+    for (genvar i = 0; i < 4096; i++) begin : g
+        localparam int j = i % 256;
+        localparam bit k = j < 128;
+    end
+endmodule
@@ -0,0 +1,12 @@
+// RUN: moore %s -e foo
+
+// The alternative blocks of an if-generate may share a label. See §27.5.
+module foo #(parameter int N = 4);
+    for (genvar i = 0; i < N; i++) begin : g_outer
+        if (i % 2 == 0) begin : g_inner
+            logic even;
+        end else begin : g_inner
+            logic odd;
+        end
+    end
+endmodule
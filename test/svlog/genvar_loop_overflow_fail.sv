@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo --max-genvar-iterations 100
+// FAIL
+module foo;
+    for (genvar i = 0; i < 4; i = i) begin
+        // CHECK-ERR: error: generate loop did not terminate after 100 iterations
+        // CHECK-ERR: = note: Last values of genvar `i` were 0, 0, 0, 0
+    end
+endmodule
@@ -0,0 +1,13 @@
+// RUN: moore %s -e foo
+// FAIL
+
+// See IEEE 1800-2017 §27.4.
+module foo;
+    genvar i;
+    for (i = 0; i < 2; i++) begin
+        int x = i;
+    end
+    int y;
+    always_comb y = i;
+    // CHECK-ERR: error: genvar `i` used outside of a generate loop
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e top
+// FAIL
+
+module fifo #(parameter int WIDTH = 8, parameter int DEPTH = 4);
+endmodule
+
+module top;
+    fifo #(16, .DEPTH(2)) u0 ();
+    // CHECK-ERR: error: cannot mix positional and named parameter assignments to module `fifo`
+endmodule
@@ -0,0 +1,11 @@
+// RUN: moore %s -e top
+// FAIL
+
+module fifo (input logic clk, input logic din);
+endmodule
+
+module top;
+    logic clk, din;
+    fifo u0 (clk, .din(din));
+    // CHECK-ERR: error: cannot mix positional and named port connections to module `fifo`
+endmodule
@@ -0,0 +1,17 @@
+// RUN: moore %s -e top --dump-params
+
+// Type parameter overrides are bound per instance, and value parameters of
+// that type follow the override.
+
+module top;
+    foo #(.T(int)) u0 ();
+    foo #(.T(bit [3:0])) u1 ();
+    foo u2 ();
+endmodule
+
+module foo #(type T = logic [7:0], parameter T V = 5);
+endmodule
+
+// CHECK: top.u0.V = 32'sd5 (default)
+// CHECK: top.u1.V = 4'd5 (default)
+// CHECK: top.u2.V = 8'd5 (default)
@@ -0,0 +1,24 @@
+// RUN: moore %s -e foo -O0
+
+// See IEEE 1800-2017 §25.7.
+
+module foo (bar.in x, bar.out y);
+endmodule
+
+interface bar;
+	logic [31:0] data;
+	logic valid;
+	logic ready;
+
+	function void put(logic [31:0] d);
+	endfunction
+
+	task wait_ready();
+	endtask
+
+	modport in (input data, valid, output ready, import put, task wait_ready());
+	modport out (output data, valid, input ready, export function void put(logic [31:0] d));
+endinterface
+
+// CHECK: entity @foo (i32$ %x.data, i1$ %x.valid, i1$ %y.ready) -> (i1$ %x.ready, i32$ %y.data, i1$ %y.valid) {
+// CHECK: }
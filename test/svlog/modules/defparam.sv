@@ -0,0 +1,14 @@
+// RUN: moore %s -e top
+module leaf #(parameter int WIDTH = 8);
+endmodule
+
+module mid;
+    leaf u1();
+endmodule
+
+module top;
+    mid u0();
+    defparam u0.u1.WIDTH = 16;
+    // CHECK-ERR: warning[W0106]: `defparam` is deprecated
+    // CHECK-ERR: warning[W0102]: unsupported: defparam override of `u0.u1.WIDTH`; ignored
+endmodule
@@ -0,0 +1,14 @@
+// RUN: moore %s -e top -W error=W0106 -W no-W0102
+// FAIL
+module leaf #(parameter int WIDTH = 8);
+endmodule
+
+module mid;
+    leaf u1();
+endmodule
+
+module top;
+    mid u0();
+    defparam u0.u1.WIDTH = 16;
+    // CHECK-ERR: error[W0106]: `defparam` is deprecated
+endmodule
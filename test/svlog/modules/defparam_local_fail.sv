@@ -0,0 +1,6 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo #(parameter int WIDTH = 8);
+    defparam WIDTH = 16;
+    // CHECK-ERR: error: defparam cannot override `WIDTH` in its own scope
+endmodule
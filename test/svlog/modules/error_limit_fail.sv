@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo --error-limit 2
+// FAIL
+module foo;
+    localparam int A, B, C, D;
+endmodule
+// CHECK-ERR: error: localparam `A` must have a value
+// CHECK-ERR: error: localparam `B` must have a value
+// CHECK-ERR: note: 2 additional errors not shown (raise with --error-limit)
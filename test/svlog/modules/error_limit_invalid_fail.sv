@@ -0,0 +1,5 @@
+// RUN: moore %s -e foo --error-limit many
+// FAIL
+module foo;
+endmodule
+// CHECK-ERR: error: Invalid value for '--error-limit <error-limit>': `many` is not a non-negative integer
@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+
+// See IEEE 1800-2017 §23.2.1.
+extern module foo #(parameter int N = 4) (input logic [N-1:0] a, b, output logic c);
+
+module foo #(parameter int N = 4) (input logic [N-1:0] a, input logic [N-1:0] b, output logic c);
+    assign c = a == b;
+endmodule
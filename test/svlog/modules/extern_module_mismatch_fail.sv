@@ -0,0 +1,11 @@
+// RUN: moore %s -e foo
+// FAIL
+
+// See IEEE 1800-2017 §23.2.1.
+extern module foo (input logic a, output logic b, input logic c);
+
+module foo (input logic a, input logic b);
+endmodule
+
+// CHECK-ERR: error: port `b` of module `foo` does not match its extern declaration
+// CHECK-ERR: error: port `c` of extern module `foo` missing from its definition
@@ -0,0 +1,6 @@
+// RUN: moore %s -e foo -Vconsts
+module foo #(parameter int DEPTH = 16);
+    localparam int unsigned WORDS = DEPTH / 4, HALF = WORDS / 2;
+    // CHECK-ERR: const(DEPTH / 4) = int, 32'sh4
+    int x = WORDS + HALF;
+endmodule
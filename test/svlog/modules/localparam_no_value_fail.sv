@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    localparam int unsigned A = 4, B, C = A / 2;
+    localparam type T;
+endmodule
+// CHECK-ERR: error: localparam `B` must have a value
+// CHECK-ERR: error: localparam `T` must have a value
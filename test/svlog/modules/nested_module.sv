@@ -0,0 +1,9 @@
+// RUN: moore %s
+
+// See IEEE 1800-2017 §23.4.
+module foo;
+    module bar;
+        module baz;
+        endmodule
+    endmodule
+endmodule
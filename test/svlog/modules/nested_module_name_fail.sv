@@ -0,0 +1,9 @@
+// RUN: moore %s
+// FAIL
+
+// See IEEE 1800-2017 §23.4.
+module foo;
+    module foo;
+    endmodule
+    // CHECK-ERR: error: nested module `foo` has the same name as its parent
+endmodule
@@ -0,0 +1,15 @@
+// RUN: moore %s -e fifo -Vconsts
+
+// The parameter keyword and type are inherited by subsequent entries in a
+// parameter port list. See §6.20.1 and §A.1.3 of IEEE 1800-2017.
+module fifo #(parameter WIDTH = 8, DEPTH = 16, type T = logic [WIDTH-1:0]) (
+    input T data
+);
+    localparam int SIZE = WIDTH * DEPTH;
+    // CHECK-ERR: const(WIDTH * DEPTH) = int, 32'sh80
+    localparam int TW = $bits(T);
+    // CHECK-ERR: const($bits(T)) = int, 32'sh8
+
+    int x0 = SIZE;
+    int x1 = TW;
+endmodule
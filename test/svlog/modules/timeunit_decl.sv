@@ -0,0 +1,14 @@
+// RUN: moore %s -e foo -Vconsts
+`timescale 1ps / 1ps
+
+// The timeunit declaration takes precedence over the `timescale directive.
+module foo;
+    timeunit 1ns / 1ps;
+    parameter time T = 2ns;
+    localparam bit a = T == 2;
+    // CHECK-ERR: const(T == 2) = bit, 1'h1
+
+    // Repeating a matching declaration is allowed.
+    timeunit 1ns;
+    bit x = a;
+endmodule
@@ -0,0 +1,8 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    timeunit 1ns;
+    timeprecision 1ps;
+    timeunit 10ns;
+    // CHECK-ERR: error: `timeunit` of 10ns does not match the previous declaration
+endmodule
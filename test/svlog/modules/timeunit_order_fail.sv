@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    logic x;
+    timeprecision 1ps;
+    // CHECK-ERR: error: `timeprecision` must precede all other items
+endmodule
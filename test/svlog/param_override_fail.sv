@@ -0,0 +1,13 @@
+// RUN: moore %s -e foo
+// FAIL
+module foo;
+    bar #(.D(2)) u_good();
+    bar #(.D(0)) u_bad();
+endmodule
+
+module bar #(parameter int D = 1);
+    localparam int W = 8 / D;
+    int x = W;
+    // CHECK-ERR: error: constant division by zero
+    // CHECK-ERR: = note: while elaborating instance `u_bad` with D = 0
+endmodule
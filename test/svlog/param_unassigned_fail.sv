@@ -0,0 +1,12 @@
+// RUN: moore %s -e top
+// FAIL
+module top;
+    foo #(.M(2)) u_foo();
+endmodule
+
+module foo #(parameter int N, parameter int M = 1);
+    localparam int W = N + M;
+    int x = W;
+endmodule
+// CHECK-ERR: error: parameter `N` not assigned and has no default
+// CHECK-ERR: = note: Assigned in this instance: M = 2
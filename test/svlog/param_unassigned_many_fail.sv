@@ -0,0 +1,18 @@
+// RUN: moore %s -e top
+// FAIL
+module top;
+    foo u0();
+    foo u1();
+    foo u2();
+    foo u3();
+    foo u4();
+    foo u5();
+    foo u6();
+endmodule
+
+module foo #(parameter int N);
+    int x = N;
+endmodule
+// CHECK-ERR: error: parameter `N` not assigned and has no default
+// CHECK-ERR: = note: Instantiated here:
+// CHECK-ERR: = note: ... and 2 more instantiation sites
@@ -0,0 +1,4 @@
+// RUN: moore %s
+
+// See IEEE 1800-2017 §6.19.2.
+typedef enum logic [2:0] {IDLE, RUN[2], DONE[3:4] = 5} state_t;
@@ -0,0 +1,11 @@
+// RUN: moore %s
+// FAIL
+
+package pkg;
+    localparam int A = 1;
+endpackage
+
+module foo;
+    int x = bar.pkg::A;
+    // CHECK-ERR: error: `::` cannot follow a `.` in hierarchical name `bar.pkg::A`
+endmodule
@@ -0,0 +1,9 @@
+// RUN: moore %s
+// FAIL
+
+// See IEEE 1800-2017 §6.6.4.
+module foo;
+    trireg (medium) #(1, 2, 3) a;
+    wire (small) b;
+    // CHECK-ERR: error: charge strength not allowed on `wire` net; only on `trireg`
+endmodule
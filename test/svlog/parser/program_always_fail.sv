@@ -0,0 +1,9 @@
+// RUN: moore %s
+// FAIL
+
+// See IEEE 1800-2017 §24.3.
+program tb (input logic clk);
+    logic x;
+    always @(posedge clk) x <= ~x;
+    // CHECK-ERR: error: `always` procedure not allowed in a program
+endprogram
@@ -0,0 +1,16 @@
+// RUN: moore %s
+
+// See IEEE 1800-2017 §24 and §17.
+program automatic tb #(parameter int N = 4) (input logic clk, output logic done);
+    logic [N-1:0] count;
+    initial begin
+        done = 0;
+    end
+    final begin
+        done = 1;
+    end
+endprogram : tb
+
+checker req_ack (logic req, logic ack);
+    logic seen;
+endchecker : req_ack
@@ -0,0 +1,5 @@
+// RUN: moore %s
+// FAIL
+module foo #(parameter type T [4] = logic);
+endmodule
+// CHECK: error: type parameter `T` cannot have unpacked dimensions
@@ -0,0 +1,11 @@
+// RUN: moore %s -e test -Vports
+
+// See IEEE 1800-2017 §23.2.2.3 and §23.2.2.4.
+module test (
+    input int unsigned burst = 4,
+    output logic valid = 1'b0
+);
+    // CHECK: Ports of `test`:
+    // CHECK: 0: input wire int unsigned burst
+    // CHECK: 1: output var logic valid
+endmodule
@@ -0,0 +1,10 @@
+// RUN: moore %s -e test
+// FAIL
+
+module test (
+    output wire [7:0] a = 8'h42,
+    inout wire b = 1'b0
+);
+    // CHECK-ERR: error: output port `a` cannot have a default value
+    // CHECK-ERR: error: inout port `b` cannot have a default value
+endmodule
@@ -0,0 +1,12 @@
+// RUN: moore %s -e test -Vports
+
+// Packed dimensions on the type and unpacked dimensions after the port name
+// combine into the type of the port.
+module test (
+    input logic [7:0] data [0:3],
+    output bit [1:0][3:0] q [2]
+);
+    // CHECK: Ports of `test`:
+    // CHECK: 0: input wire logic [7:0] $ [0:3] data
+    // CHECK: 1: output var bit [1:0][3:0] $ [2] q
+endmodule
@@ -0,0 +1,15 @@
+// RUN: moore %s -e test -Vports
+
+// See IEEE 1800-2017 §22.8 and §23.2.2.3.
+`default_nettype none
+module test (
+    input wire a,
+    input var logic b,
+    output logic c
+);
+    // CHECK: Ports of `test`:
+    // CHECK: 0: input wire logic a
+    // CHECK: 1: input var logic b
+    // CHECK: 2: output var logic c
+endmodule
+`default_nettype wire
@@ -0,0 +1,13 @@
+// RUN: moore %s -e test
+// FAIL
+
+// See IEEE 1800-2017 §22.8 and §23.2.2.3.
+`default_nettype none
+module test (
+    input logic a,
+    output b
+);
+    // CHECK-ERR: error: port `a` is an implicit net, but `default_nettype` is `none`
+    // CHECK-ERR: error: port `b` is an implicit net, but `default_nettype` is `none`
+endmodule
+`default_nettype wire
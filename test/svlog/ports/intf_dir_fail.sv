@@ -0,0 +1,11 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo (input bar.in x, input logic clk);
+    // CHECK-ERR: error: interface port `x` cannot have a direction
+endmodule
+
+interface bar;
+    logic a;
+    modport in (input a);
+endinterface
@@ -0,0 +1,6 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo (interface.in x, input logic clk);
+    // CHECK-ERR: error: unsupported: generic interface port `x`
+endmodule
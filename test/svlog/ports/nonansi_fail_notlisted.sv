@@ -0,0 +1,12 @@
+// RUN: moore %s -e foo -e bar
+// FAIL
+module foo(a);
+    input a;
+    output b;
+    // CHECK: error: port `b` declared in module body but not listed in its port list
+endmodule
+
+module bar;
+    input c;
+    // CHECK: error: port `c` declared in module body but not listed in its port list
+endmodule
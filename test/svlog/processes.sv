@@ -5,7 +5,7 @@ module a0;
     always x = y;
     always_comb x = y;
     always_latch x <= y;
-    always_ff x <= y;
+    always_ff @(y) x <= y;
     always @* x = y;
     always @(*) x = y;
     final x = y;
@@ -2,5 +2,5 @@
 
 module foo;
     initial $bar();
-    // CHECK-ERR: warning: unsupported: system task `$bar`; ignored
+    // CHECK-ERR: warning[W0102]: unsupported: system task `$bar`; ignored
 endmodule
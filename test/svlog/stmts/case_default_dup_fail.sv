@@ -0,0 +1,12 @@
+// RUN: moore %s
+// FAIL
+module foo;
+  int x, y;
+  always_comb unique casez (x)
+    0: y = 1;
+    default: y = 2;
+    1: y = 3;
+    default: y = 4;
+  endcase
+  // CHECK-ERR: error: multiple `default` items in case statement
+endmodule
@@ -0,0 +1,15 @@
+// RUN: moore %s -e foo -Vconsts
+
+// See IEEE 1800-2017 §7.2 and §7.3.1.
+module foo;
+    typedef struct packed { logic [7:0] a, b; logic c; } s_t;
+    typedef union packed { logic [7:0] a; bit [3:0][1:0] b; } u_t;
+
+    localparam int S = $bits(s_t);
+    // CHECK-ERR: const($bits(s_t)) = int, 32'sh11
+    localparam int U = $bits(u_t);
+    // CHECK-ERR: const($bits(u_t)) = int, 32'sh8
+
+    int x0 = S;
+    int x1 = U;
+endmodule
@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo;
+    union packed { logic [7:0] a; logic [3:0] b; } u;
+    // CHECK-ERR: error: members of packed union must have the same size, but `b` is 4 bits and `a` is 8 bits
+endmodule
@@ -0,0 +1,17 @@
+// RUN: moore %s -e foo -Vconsts
+
+// Packed and unpacked dimensions may be given by parameter expressions.
+module foo #(parameter int WIDTH = 12, parameter int DEPTH = 5);
+    typedef logic [WIDTH-1:0] word_t [0:DEPTH-1];
+
+    localparam int B = $bits(word_t);
+    // CHECK-ERR: const($bits(word_t)) = int, 32'sh3c
+    localparam int S = $size(word_t, 1);
+    // CHECK-ERR: const($size(word_t, 1)) = int, 32'sh5
+    localparam int L = $left(word_t, 2);
+    // CHECK-ERR: const($left(word_t, 2)) = int, 32'shb
+
+    int x0 = B;
+    int x1 = S;
+    int x2 = L;
+endmodule
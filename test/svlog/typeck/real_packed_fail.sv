@@ -0,0 +1,9 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo;
+    real [3:0] r;
+    // CHECK-ERR: error: unpacked type `real` cannot have packed dimensions
+    realtime signed t;
+    // CHECK-ERR: error: unpacked type `realtime` cannot be signed or unsigned
+endmodule
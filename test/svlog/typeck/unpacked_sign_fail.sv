@@ -0,0 +1,7 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo;
+    string signed s;
+    // CHECK-ERR: error: unpacked type `string` cannot be signed or unsigned
+endmodule
@@ -0,0 +1,9 @@
+// RUN: moore %s -e foo
+// FAIL
+
+module foo;
+    function void signed f();
+    endfunction
+    // CHECK-ERR: error: `void` cannot be signed, unsigned, or have packed dimensions
+    initial f();
+endmodule